@@ -0,0 +1,132 @@
+use chrono::NaiveDateTime;
+use event_log_parser::query;
+use std::{collections::HashMap, error::Error, str::FromStr};
+
+// --name value, --name=value и флаги без значения
+pub struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    pub fn parse<I>(args: I, flags: &[&str]) -> Result<Args, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(name) = arg.strip_prefix("--").or_else(|| arg.strip_prefix('-')) else {
+                positional.push(arg);
+                continue;
+            };
+            if name.is_empty() {
+                positional.extend(args.by_ref());
+                break;
+            }
+            let (name, value) = match name.split_once('=') {
+                Some((name, value)) => (name, value.to_string()),
+                None if flags.contains(&name) => (name, String::new()),
+                None => match args.next() {
+                    Some(value) => (name, value),
+                    None => return Err(format!("missing value for --{name}")),
+                },
+            };
+            options.insert(name.to_string(), value);
+        }
+        Ok(Args {
+            positional,
+            options,
+        })
+    }
+
+    pub fn positional(&self, index: usize, name: &str) -> Result<&str, String> {
+        self.positional
+            .get(index)
+            .map(String::as_str)
+            .ok_or_else(|| format!("missing argument <{name}>"))
+    }
+
+    pub fn rest(&self, index: usize) -> &[String] {
+        self.positional.get(index..).unwrap_or_default()
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    pub fn value<T>(&self, name: &str) -> Result<Option<T>, String>
+    where
+        T: FromStr,
+        T::Err: Error,
+    {
+        self.get(name)
+            .map(|value| value.parse().map_err(|e| format!("--{name}: {e}")))
+            .transpose()
+    }
+
+    // --from и --to; дата без времени задает весь день
+    pub fn range(&self) -> Result<Option<(NaiveDateTime, NaiveDateTime)>, String> {
+        let date = |name: &str| {
+            self.get(name)
+                .map(|value| {
+                    query::parse_date(value).ok_or(format!("--{name}: invalid date {value}"))
+                })
+                .transpose()
+        };
+        let (from, to) = (date("from")?, date("to")?);
+        if from.is_none() && to.is_none() {
+            return Ok(None);
+        }
+        Ok(Some((
+            from.map_or(NaiveDateTime::MIN, |(from, _)| from),
+            to.map_or(NaiveDateTime::MAX, |(_, to)| to),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let args = [
+            "dir",
+            "--lines",
+            "5",
+            "--follow",
+            "--format=json",
+            "-q",
+            "x",
+            "--",
+            "-n",
+        ];
+        let args = Args::parse(args.map(String::from), &["follow"]).unwrap();
+        assert_eq!(args.positional(0, "dir").unwrap(), "dir");
+        assert_eq!(args.rest(1), ["-n"]);
+        assert_eq!(args.value::<usize>("lines").unwrap(), Some(5));
+        assert!(args.flag("follow"));
+        assert_eq!(args.get("format"), Some("json"));
+        assert_eq!(args.get("q"), Some("x"));
+        assert!(args.value::<usize>("format").is_err());
+        assert!(Args::parse(["--top".to_string()], &[]).is_err());
+    }
+
+    #[test]
+    fn test_range() {
+        let args = ["--from", "2022-12-17", "--to=2022-12-17 22:30"];
+        let args = Args::parse(args.map(String::from), &[]).unwrap();
+        let (from, to) = args.range().unwrap().unwrap();
+        assert_eq!(from.to_string(), "2022-12-17 00:00:00");
+        assert_eq!(to.to_string(), "2022-12-17 22:30:00");
+        let args = Args::parse(["--to=yesterday".to_string()], &[]).unwrap();
+        assert!(args.range().is_err());
+        assert_eq!(Args::parse([], &[]).unwrap().range(), Ok(None));
+    }
+}
@@ -0,0 +1,14 @@
+use crate::args::Args;
+use event_log_parser::lgd::{lgd_to_lgp, lgp_to_lgd};
+use std::error::Error;
+
+pub fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+    let from = args.positional(1, "from")?;
+    let to = args.positional(2, "to")?;
+    match args.positional(0, "direction")? {
+        "lgp-to-lgd" => lgp_to_lgd(from, to)?,
+        "lgd-to-lgp" => lgd_to_lgp(from, to)?,
+        direction => return Err(format!("unknown direction: {direction}").into()),
+    }
+    Ok(())
+}
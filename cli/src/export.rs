@@ -0,0 +1,64 @@
+use crate::args::Args;
+use event_log_parser::{
+    directory::LogDirectory,
+    events::Event,
+    export::{csv, jsonl},
+    hashing::HashedIdentities,
+    query::{CompiledQuery, Query},
+};
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+pub fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+    let dir = LogDirectory::open(args.positional(0, "dir")?)?;
+    let refs = dir.references();
+    let query = match args.get("query") {
+        Some(query) => Some(Query::parse(query)?.compile(refs)),
+        None => None,
+    };
+    let out: Box<dyn Write> = match args.get("output") {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout().lock()),
+    };
+    let out = BufWriter::new(out);
+    let identities = args
+        .get("hash-secret")
+        .map(|secret| HashedIdentities::new(refs, secret.as_bytes()));
+
+    match args.get("format").unwrap_or("csv") {
+        "csv" => {
+            let mut writer = csv::Writer::new(out, refs);
+            if let Some(identities) = &identities {
+                writer = writer.hash_identities(identities);
+            }
+            export(&dir, query.as_ref(), |event| writer.write(event))?;
+            writer.flush()?;
+        }
+        "jsonl" => {
+            let mut writer = jsonl::Writer::new(out, refs);
+            if let Some(identities) = &identities {
+                writer = writer.hash_identities(identities);
+            }
+            export(&dir, query.as_ref(), |event| writer.write(event))?;
+            writer.flush()?;
+        }
+        format => return Err(format!("unknown format: {format}").into()),
+    }
+    Ok(())
+}
+
+fn export<F>(dir: &LogDirectory, query: Option<&CompiledQuery>, mut write: F) -> io::Result<()>
+where
+    F: FnMut(&Event) -> io::Result<()>,
+{
+    let mut result = Ok(());
+    dir.events(&mut |event| {
+        if result.is_ok() && query.is_none_or(|query| query.matches(&event)) {
+            result = write(&event);
+        }
+    })?;
+    result
+}
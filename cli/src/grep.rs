@@ -0,0 +1,25 @@
+use crate::{
+    args::Args,
+    output::{self, Format},
+};
+use event_log_parser::{directory::LogDirectory, query::Query};
+use std::{
+    error::Error,
+    io::{self, Write},
+};
+
+pub fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+    let dir = LogDirectory::open(args.positional(0, "dir")?)?;
+    let query = Query::parse(args.positional(1, "query")?)?.compile(dir.references());
+    let refs = dir.references();
+    let format = Format::parse(args.get("format").unwrap_or(output::DEFAULT_FORMAT))?
+        .color(output::use_color(args.get("color"))?);
+    let mut out = io::stdout().lock();
+    let mut result = Ok(());
+    dir.events(&mut |event| {
+        if result.is_ok() && query.matches(&event) {
+            result = writeln!(out, "{}", format.render(&event.resolve(refs)));
+        }
+    })?;
+    Ok(result?)
+}
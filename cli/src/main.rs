@@ -0,0 +1,82 @@
+use std::{env, io, process::ExitCode};
+
+mod args;
+mod convert;
+mod export;
+mod grep;
+mod merge;
+mod output;
+#[cfg(feature = "serve")]
+mod serve;
+mod stats;
+mod tail;
+mod validate;
+#[cfg(feature = "view")]
+mod view;
+
+const USAGE: &str = "Usage: 1c-log <command> [options]
+
+Commands:
+  stats <dir> [--top N] [--from <date>] [--to <date>] [--format table|json]
+  grep <dir> <query> [--format <format>] [--color auto|always|never]
+  export <dir> [--format csv|jsonl] [--query <query>] [--hash-secret <secret>]
+         [--output <file>]
+  tail <dir> [--lines N] [--follow] [--level <level>] [--user <name>] [--event <name>]
+       [--format <format>] [--color auto|always|never] [--interval <ms>]
+  convert lgp-to-lgd|lgd-to-lgp <from> <to>
+  validate <dir> [--repair <output dir>]
+  merge <output.lgp> <file.lgp>...
+  serve <dir> [--listen <addr>] [--interval <ms>] (requires the serve feature)
+  view <dir> [--from <date>] [--to <date>] [--level <level>] [--user <name>]
+       (requires the view feature)
+
+Query example: level >= Warning and user = \"Иванов\" and comment ~ \"deadlock\"
+Format fields: {date} {level} {event} {user} {computer} {application} {metadata}
+  {comment} {data} {data_presentation} {server} {session} {connection}";
+
+fn main() -> ExitCode {
+    let mut argv = env::args().skip(1);
+    let Some(command) = argv.next() else {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    };
+    let args = match args::Args::parse(argv, &["follow"]) {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("1c-log: {error}");
+            return ExitCode::from(2);
+        }
+    };
+    let result = match command.as_str() {
+        "stats" => stats::run(&args).map(|_| ExitCode::SUCCESS),
+        "grep" => grep::run(&args).map(|_| ExitCode::SUCCESS),
+        "export" => export::run(&args).map(|_| ExitCode::SUCCESS),
+        "tail" => tail::run(&args).map(|_| ExitCode::SUCCESS),
+        "convert" => convert::run(&args).map(|_| ExitCode::SUCCESS),
+        "validate" => validate::run(&args),
+        "merge" => merge::run(&args).map(|_| ExitCode::SUCCESS),
+        #[cfg(feature = "serve")]
+        "serve" => serve::run(&args).map(|_| ExitCode::SUCCESS),
+        #[cfg(feature = "view")]
+        "view" => view::run(&args).map(|_| ExitCode::SUCCESS),
+        "help" | "--help" | "-h" => {
+            println!("{USAGE}");
+            Ok(ExitCode::SUCCESS)
+        }
+        command => {
+            eprintln!("1c-log: unknown command {command}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    result.unwrap_or_else(|error| {
+        // Вывод оборвали, например head
+        if error
+            .downcast_ref::<io::Error>()
+            .is_some_and(|error| error.kind() == io::ErrorKind::BrokenPipe)
+        {
+            return ExitCode::SUCCESS;
+        }
+        eprintln!("1c-log: {error}");
+        ExitCode::FAILURE
+    })
+}
@@ -0,0 +1,22 @@
+use crate::args::Args;
+use event_log_parser::{events, merge::merge_files};
+use std::{error::Error, fs::File, io::BufWriter};
+
+// Объединение файлов одного журнала (с общим 1Cv8.lgf) в один файл по времени
+pub fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+    let output = args.positional(0, "output")?;
+    let files = args.rest(1);
+    if files.is_empty() {
+        return Err("missing argument <file>".into());
+    }
+    let mut writer = events::Writer::new(BufWriter::new(File::create(output)?));
+    let mut result = Ok(());
+    merge_files(files, &mut |_, event| {
+        if result.is_ok() {
+            result = writer.write(&event.to_owned());
+        }
+    })?;
+    result?;
+    writer.finish()?;
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use event_log_parser::events::{EventLogLevel, EventResolved};
+use std::io::{self, IsTerminal};
+
+pub const DEFAULT_FORMAT: &str = "{date} {level} {event} {user}: {comment}";
+
+pub fn level(level: &EventLogLevel) -> &'static str {
+    match level {
+        EventLogLevel::Error => "E",
+        EventLogLevel::Warning => "W",
+        EventLogLevel::Information => "I",
+        EventLogLevel::Note => "N",
+    }
+}
+
+pub fn parse_level(text: &str) -> Result<EventLogLevel, String> {
+    match text.to_lowercase().as_str() {
+        "error" | "e" => Ok(EventLogLevel::Error),
+        "warning" | "w" => Ok(EventLogLevel::Warning),
+        "information" | "info" | "i" => Ok(EventLogLevel::Information),
+        "note" | "n" => Ok(EventLogLevel::Note),
+        _ => Err(format!("unknown level: {text}")),
+    }
+}
+
+// Важность для сравнения: note < information < warning < error
+pub fn severity(level: &EventLogLevel) -> u8 {
+    match level {
+        EventLogLevel::Note => 0,
+        EventLogLevel::Information => 1,
+        EventLogLevel::Warning => 2,
+        EventLogLevel::Error => 3,
+    }
+}
+
+pub fn use_color(mode: Option<&str>) -> Result<bool, String> {
+    match mode.unwrap_or("auto") {
+        "auto" => Ok(io::stdout().is_terminal()),
+        "always" => Ok(true),
+        "never" => Ok(false),
+        mode => Err(format!("unknown color mode: {mode}")),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Date,
+    Level,
+    Event,
+    User,
+    Computer,
+    Application,
+    Metadata,
+    Comment,
+    Data,
+    DataPresentation,
+    Server,
+    Session,
+    Connection,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Field(Field),
+}
+
+// Строка формата вида "{date} {level} {event} {user}: {comment}"
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Format {
+    parts: Vec<Part>,
+    color: bool,
+}
+
+impl Format {
+    pub fn parse(format: &str) -> Result<Format, String> {
+        let mut parts = Vec::new();
+        let mut rest = format;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| format!("unclosed '{{' in format: {format}"))?;
+            let field = match &rest[start + 1..start + end] {
+                "date" => Field::Date,
+                "level" => Field::Level,
+                "event" => Field::Event,
+                "user" => Field::User,
+                "computer" => Field::Computer,
+                "application" => Field::Application,
+                "metadata" => Field::Metadata,
+                "comment" => Field::Comment,
+                "data" => Field::Data,
+                "data_presentation" => Field::DataPresentation,
+                "server" => Field::Server,
+                "session" => Field::Session,
+                "connection" => Field::Connection,
+                name => return Err(format!("unknown field in format: {{{name}}}")),
+            };
+            parts.push(Part::Field(field));
+            rest = &rest[start + end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+        Ok(Format {
+            parts,
+            color: false,
+        })
+    }
+
+    pub fn color(mut self, color: bool) -> Format {
+        self.color = color;
+        self
+    }
+
+    pub fn render(&self, event: &EventResolved) -> String {
+        let mut line = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => line.push_str(text),
+                Part::Field(field) => line.push_str(&match field {
+                    Field::Date => event.date().to_string(),
+                    Field::Level => level(event.log_level()).to_string(),
+                    Field::Event => event.event_name().to_string(),
+                    Field::User => event.user_name().to_string(),
+                    Field::Computer => event.computer().to_string(),
+                    Field::Application => event.application().to_string(),
+                    Field::Metadata => event.metadata_name().to_string(),
+                    Field::Comment => one_line(&event.comment()),
+                    Field::Data => one_line(event.data()),
+                    Field::DataPresentation => one_line(&event.data_presentation()),
+                    Field::Server => event.worker_server().to_string(),
+                    Field::Session => event.session().to_string(),
+                    Field::Connection => event.connection().to_string(),
+                }),
+            }
+        }
+        if !self.color {
+            return line;
+        }
+        match event.log_level() {
+            EventLogLevel::Error => format!("\x1b[31m{line}\x1b[0m"),
+            EventLogLevel::Warning => format!("\x1b[33m{line}\x1b[0m"),
+            EventLogLevel::Note => format!("\x1b[2m{line}\x1b[0m"),
+            EventLogLevel::Information => line,
+        }
+    }
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Format::parse(DEFAULT_FORMAT).expect("valid format")
+    }
+}
+
+fn one_line(text: &str) -> String {
+    text.replace("\r\n", " ").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let format = Format::parse("[{level}] {user}").unwrap();
+        assert_eq!(
+            format.parts,
+            [
+                Part::Text("[".into()),
+                Part::Field(Field::Level),
+                Part::Text("] ".into()),
+                Part::Field(Field::User)
+            ]
+        );
+        assert!(Format::parse("{unknown}").is_err());
+        assert!(Format::parse("{date").is_err());
+    }
+}
@@ -0,0 +1,438 @@
+use crate::{args::Args, output};
+use event_log_parser::{
+    directory::LogDirectory,
+    events::EventResolved,
+    export::jsonl,
+    query::{self, CompiledQuery, Query},
+    source::EventSource,
+    stats::Collector,
+    watch::Watcher,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    io::{self, Read, Write},
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, SyncSender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tiny_http::{Header, Method, Request, Response, Server, StatusCode};
+
+type Body = Box<dyn Read + Send>;
+
+// GET /events?from=&to=&level=&user=&query=&limit= - события в формате JSON Lines
+// GET /stats?from=&to=&top= - сводка по журналу
+// GET /references - справочники 1Cv8.lgf
+// GET /events/stream?level=&user=&query= - новые события как server-sent events
+pub fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+    let dir = PathBuf::from(args.positional(0, "dir")?);
+    // Проверка каталога до запуска сервера
+    LogDirectory::open(&dir)?;
+    let listen = args.get("listen").unwrap_or("127.0.0.1:8080");
+    let interval = Duration::from_millis(args.value("interval")?.unwrap_or(1000));
+    let hub = Hub::start(&dir, interval)?;
+    let server = Server::http(listen).map_err(|error| format!("{listen}: {error}"))?;
+    eprintln!("Listening on http://{listen}");
+    for request in server.incoming_requests() {
+        let dir = dir.clone();
+        let hub = hub.clone();
+        thread::spawn(move || {
+            let url = request.url().to_string();
+            if let Err(error) = handle(request, &dir, &hub) {
+                eprintln!("{url}: {error}");
+            }
+        });
+    }
+    Ok(())
+}
+
+fn handle(request: Request, dir: &Path, hub: &Hub) -> io::Result<()> {
+    let (path, params) = parse_url(request.url());
+    if *request.method() == Method::Get && path == "/events/stream" {
+        return match subscribe(dir, &params, hub) {
+            Ok(receiver) => stream(request.into_writer(), receiver),
+            Err((status, message)) => request.respond(json(
+                status,
+                serde_json::json!({ "error": message })
+                    .to_string()
+                    .into_bytes(),
+            )),
+        };
+    }
+    let result = match (request.method(), path.as_str()) {
+        (Method::Get, "/events") => events(dir, &params),
+        (Method::Get, "/stats") => stats(dir, &params),
+        (Method::Get, "/references") => references(dir),
+        (Method::Get, _) => Err((404, "not found".to_string())),
+        _ => Err((405, "method not allowed".to_string())),
+    };
+    let response = match result {
+        Ok(response) => response,
+        Err((status, message)) => json(
+            status,
+            serde_json::json!({ "error": message })
+                .to_string()
+                .into_bytes(),
+        ),
+    };
+    request.respond(response)
+}
+
+fn response(status: u16, content_type: &str, body: Body, length: Option<usize>) -> Response<Body> {
+    let header = Header::from_bytes("Content-Type", content_type).expect("valid header");
+    Response::new(StatusCode(status), vec![header], body, length, None)
+}
+
+fn json(status: u16, body: Vec<u8>) -> Response<Body> {
+    let length = body.len();
+    response(
+        status,
+        "application/json",
+        Box::new(io::Cursor::new(body)),
+        Some(length),
+    )
+}
+
+fn bad_request<E: ToString>(error: E) -> (u16, String) {
+    (400, error.to_string())
+}
+
+fn open(dir: &Path) -> Result<LogDirectory, (u16, String)> {
+    LogDirectory::open(dir).map_err(|error| (500, error.to_string()))
+}
+
+struct Filter {
+    from: Option<chrono::NaiveDateTime>,
+    to: Option<chrono::NaiveDateTime>,
+    level: Option<u8>,
+    user: Option<String>,
+    query: Option<CompiledQuery>,
+}
+
+impl Filter {
+    fn matches(&self, event: &EventResolved) -> bool {
+        self.from.is_none_or(|from| event.date() >= from)
+            && self.to.is_none_or(|to| event.date() <= to)
+            && self
+                .level
+                .is_none_or(|level| output::severity(event.log_level()) >= level)
+            && self
+                .user
+                .as_ref()
+                .is_none_or(|user| event.user_name() == user)
+            && self
+                .query
+                .as_ref()
+                .is_none_or(|query| query.matches(event.event()))
+    }
+}
+
+fn filter(dir: &LogDirectory, params: &HashMap<String, String>) -> Result<Filter, (u16, String)> {
+    let date = |name: &str| {
+        params
+            .get(name)
+            .map(|value| query::parse_date(value).ok_or(bad_request(format!("invalid {name}"))))
+            .transpose()
+    };
+    Ok(Filter {
+        from: date("from")?.map(|(from, _)| from),
+        to: date("to")?.map(|(_, to)| to),
+        level: match params.get("level") {
+            Some(level) => Some(output::severity(
+                &output::parse_level(level).map_err(bad_request)?,
+            )),
+            None => None,
+        },
+        user: params.get("user").cloned(),
+        query: match params.get("query") {
+            Some(query) => Some(
+                Query::parse(query)
+                    .map_err(bad_request)?
+                    .compile(dir.references()),
+            ),
+            None => None,
+        },
+    })
+}
+
+fn events(dir: &Path, params: &HashMap<String, String>) -> Result<Response<Body>, (u16, String)> {
+    let log = open(dir)?;
+    let filter = filter(&log, params)?;
+    let limit = match params.get("limit") {
+        Some(limit) => limit.parse().map_err(bad_request)?,
+        None => usize::MAX,
+    };
+
+    // Ответ формируется в отдельном потоке по мере чтения журнала,
+    // при отключении клиента чтение прекращается
+    let (sender, receiver) = mpsc::sync_channel(16);
+    thread::spawn(move || {
+        let mut writer = jsonl::Writer::new(ChannelWriter::new(sender), log.references());
+        let mut count = 0;
+        let mut result = Ok(());
+        let _ = log.parse_until(&mut |event| {
+            if count < limit && filter.matches(&event.resolve(log.references())) {
+                count += 1;
+                result = writer.write(&event);
+            }
+            match result.is_ok() && count < limit {
+                true => ControlFlow::Continue(()),
+                false => ControlFlow::Break(()),
+            }
+        });
+        let _ = writer.flush();
+    });
+    Ok(response(
+        200,
+        "application/x-ndjson",
+        Box::new(ChannelReader::new(receiver)),
+        None,
+    ))
+}
+
+fn stats(dir: &Path, params: &HashMap<String, String>) -> Result<Response<Body>, (u16, String)> {
+    let log = open(dir)?;
+    let filter = filter(&log, params)?;
+    let top = match params.get("top") {
+        Some(top) => top.parse().map_err(bad_request)?,
+        None => 10,
+    };
+    let mut collector = Collector::new().top(top).bucket(chrono::Duration::hours(1));
+    log.parse(&mut |event| {
+        if filter.matches(&event.resolve(log.references())) {
+            collector.push(&event);
+        }
+    })
+    .map_err(|error| (500, error.to_string()))?;
+    let body = serde_json::to_vec(&collector.report(log.references()))
+        .map_err(|error| (500, error.to_string()))?;
+    Ok(json(200, body))
+}
+
+fn references(dir: &Path) -> Result<Response<Body>, (u16, String)> {
+    let log = open(dir)?;
+    let body = serde_json::to_vec(log.references()).map_err(|error| (500, error.to_string()))?;
+    Ok(json(200, body))
+}
+
+fn subscribe(
+    dir: &Path,
+    params: &HashMap<String, String>,
+    hub: &Hub,
+) -> Result<Receiver<Vec<u8>>, (u16, String)> {
+    let filter = filter(&open(dir)?, params)?;
+    let (sender, receiver) = mpsc::sync_channel(256);
+    hub.subscribe(filter, sender);
+    Ok(receiver)
+}
+
+// Ответ пишется напрямую в соединение: буферизация chunked-ответов
+// tiny_http задержала бы события
+fn stream(mut out: Box<dyn Write + Send>, receiver: Receiver<Vec<u8>>) -> io::Result<()> {
+    out.write_all(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\
+          Cache-Control: no-cache\r\nConnection: close\r\n\r\n",
+    )?;
+    out.flush()?;
+    for message in receiver {
+        out.write_all(&message)?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+struct Subscriber {
+    filter: Filter,
+    sender: SyncSender<Vec<u8>>,
+}
+
+// Один поток следит за каталогом и рассылает новые события подписчикам
+// с учетом их фильтров. Отстающий или отключившийся клиент удаляется
+#[derive(Clone)]
+struct Hub {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+impl Hub {
+    const PING: Duration = Duration::from_secs(15);
+
+    fn start(dir: &Path, interval: Duration) -> io::Result<Hub> {
+        let hub = Hub {
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+        };
+        let mut watcher = Watcher::open(dir)?;
+        // Уже записанные события не рассылаются
+        let _ = watcher.poll(&mut |_| ControlFlow::Continue(()))?;
+        let subscribers = hub.subscribers.clone();
+        thread::spawn(move || {
+            let mut ping = Instant::now();
+            loop {
+                thread::sleep(interval);
+                let mut subscribers = subscribers.lock().expect("subscribers lock");
+                let result = watcher.poll(&mut |event| {
+                    let mut message = None;
+                    subscribers.retain(|subscriber| {
+                        if !subscriber.filter.matches(&event) {
+                            return true;
+                        }
+                        let message = message.get_or_insert_with(|| {
+                            let mut message = b"data: ".to_vec();
+                            let _ = jsonl::write_resolved(&mut message, &event);
+                            message.extend_from_slice(b"\n\n");
+                            message
+                        });
+                        subscriber.sender.try_send(message.clone()).is_ok()
+                    });
+                    ControlFlow::Continue(())
+                });
+                if let Err(error) = result {
+                    eprintln!("stream: {error}");
+                }
+                // Комментарий SSE, чтобы обнаружить закрытые соединения
+                if ping.elapsed() >= Self::PING {
+                    ping = Instant::now();
+                    subscribers.retain(|subscriber| {
+                        subscriber.sender.try_send(b": ping\n\n".to_vec()).is_ok()
+                    });
+                }
+            }
+        });
+        Ok(hub)
+    }
+
+    fn subscribe(&self, filter: Filter, sender: SyncSender<Vec<u8>>) {
+        self.subscribers
+            .lock()
+            .expect("subscribers lock")
+            .push(Subscriber { filter, sender });
+    }
+}
+
+fn parse_url(url: &str) -> (String, HashMap<String, String>) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let params = query
+        .split('&')
+        .filter(|param| !param.is_empty())
+        .map(|param| {
+            let (name, value) = param.split_once('=').unwrap_or((param, ""));
+            (decode(name), decode(value))
+        })
+        .collect();
+    (decode(path), params)
+}
+
+// Декодирование %XX и '+' в строке запроса
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[i + 1..i + 3])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// Тело ответа передается из потока чтения журнала порциями
+struct ChannelWriter {
+    sender: SyncSender<Vec<u8>>,
+    buffer: Vec<u8>,
+}
+
+impl ChannelWriter {
+    const CHUNK: usize = 64 * 1024;
+
+    fn new(sender: SyncSender<Vec<u8>>) -> ChannelWriter {
+        ChannelWriter {
+            sender,
+            buffer: Vec::with_capacity(Self::CHUNK),
+        }
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= Self::CHUNK {
+            self.flush()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.buffer, Vec::with_capacity(Self::CHUNK));
+        self.sender
+            .send(chunk)
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+}
+
+struct ChannelReader {
+    receiver: Receiver<Vec<u8>>,
+    chunk: io::Cursor<Vec<u8>>,
+}
+
+impl ChannelReader {
+    fn new(receiver: Receiver<Vec<u8>>) -> ChannelReader {
+        ChannelReader {
+            receiver,
+            chunk: io::Cursor::new(Vec::new()),
+        }
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.chunk.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+            match self.receiver.recv() {
+                Ok(chunk) => self.chunk = io::Cursor::new(chunk),
+                Err(_) => return Ok(0),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_url() {
+        let (path, params) = parse_url(
+            "/events?from=2022-12-17+22%3A16&user=%D0%98%D0%B2%D0%B0%D0%BD%D0%BE%D0%B2&x",
+        );
+        assert_eq!(path, "/events");
+        assert_eq!(params["from"], "2022-12-17 22:16");
+        assert_eq!(params["user"], "Иванов");
+        assert_eq!(params["x"], "");
+        assert_eq!(decode("100%"), "100%");
+        assert_eq!(decode("%zz"), "%zz");
+    }
+}
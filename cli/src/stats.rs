@@ -0,0 +1,67 @@
+use crate::args::Args;
+use event_log_parser::{directory::LogDirectory, stats::Collector};
+use std::{
+    error::Error,
+    io::{self, Write},
+};
+
+pub fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+    let dir = LogDirectory::open(args.positional(0, "dir")?)?;
+    let top = args.value("top")?.unwrap_or(10);
+    let mut collector = Collector::new().top(top).bucket(chrono::Duration::hours(1));
+    match args.range()? {
+        Some((from, to)) => dir.events_between(from, to, &mut |event| collector.push(&event))?,
+        None => dir.events(&mut |event| collector.push(&event))?,
+    }
+    let report = collector.report(dir.references());
+
+    let mut out = io::stdout().lock();
+    match args.get("format").unwrap_or("table") {
+        "table" => {}
+        "json" => {
+            serde_json::to_writer_pretty(&mut out, &report)?;
+            writeln!(out)?;
+            return Ok(());
+        }
+        format => return Err(format!("unknown format: {format}").into()),
+    }
+
+    writeln!(out, "Total events: {}", report.total())?;
+    if let (Some(first), Some(last)) = (report.first(), report.last()) {
+        writeln!(out, "Period: {first} - {last}")?;
+    }
+    let levels = report.levels();
+    writeln!(out, "Error: {}", levels.error())?;
+    writeln!(out, "Warning: {}", levels.warning())?;
+    writeln!(out, "Information: {}", levels.information())?;
+    writeln!(out, "Note: {}", levels.note())?;
+    writeln!(out, "Top errors:")?;
+    for error in report.top_errors() {
+        writeln!(out, "  {}: {}", error.name(), error.count())?;
+    }
+    writeln!(out, "Busiest users:")?;
+    for user in report.users().iter().take(top) {
+        let name = match user.name() {
+            "" => "<none>",
+            name => name,
+        };
+        writeln!(out, "  {name}: {}", user.count())?;
+    }
+    writeln!(out, "By hour:")?;
+    let max = report
+        .histogram()
+        .iter()
+        .map(|b| b.count())
+        .max()
+        .unwrap_or(0);
+    for bucket in report.histogram() {
+        let bar = "#".repeat((bucket.count() * 40).div_ceil(max.max(1)));
+        writeln!(
+            out,
+            "  {} {:>8} {bar}",
+            bucket.start().format("%Y-%m-%d %H:00"),
+            bucket.count()
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use crate::{
+    args::Args,
+    output::{self, Format},
+};
+use event_log_parser::{events::EventResolved, watch::Watcher};
+use std::{
+    collections::VecDeque,
+    error::Error,
+    io::{self, Write},
+    ops::ControlFlow,
+    thread,
+    time::Duration,
+};
+
+// Как journalctl -f: последние строки журнала и затем новые события
+pub fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+    let mut watcher = Watcher::open(args.positional(0, "dir")?)?;
+    let lines: usize = args.value("lines")?.unwrap_or(10);
+    let level = args.get("level").map(output::parse_level).transpose()?;
+    let user = args.get("user");
+    let event = args.get("event");
+    let format = Format::parse(args.get("format").unwrap_or(output::DEFAULT_FORMAT))?
+        .color(output::use_color(args.get("color"))?);
+    let interval = Duration::from_millis(args.value("interval")?.unwrap_or(1000));
+
+    let matches = |e: &EventResolved| {
+        level.is_none_or(|level| output::severity(e.log_level()) >= output::severity(&level))
+            && user.is_none_or(|user| e.user_name() == user)
+            && event.is_none_or(|event| e.event_name() == event)
+    };
+
+    // Первый проход читает последний файл целиком, выводятся только последние строки
+    let mut last = VecDeque::with_capacity(lines);
+    let _ = watcher.poll(&mut |event| {
+        if lines > 0 && matches(&event) {
+            if last.len() == lines {
+                last.pop_front();
+            }
+            last.push_back(format.render(&event));
+        }
+        ControlFlow::Continue(())
+    })?;
+    let mut out = io::stdout().lock();
+    for line in last {
+        writeln!(out, "{line}")?;
+    }
+
+    if !args.flag("follow") {
+        return Ok(());
+    }
+    let mut result = Ok(());
+    while result.is_ok() {
+        out.flush()?;
+        thread::sleep(interval);
+        let _ = watcher.poll(&mut |event| {
+            if matches(&event) {
+                result = writeln!(out, "{}", format.render(&event));
+            }
+            match result {
+                Ok(()) => ControlFlow::Continue(()),
+                Err(_) => ControlFlow::Break(()),
+            }
+        })?;
+    }
+    Ok(result?)
+}
@@ -0,0 +1,53 @@
+use crate::args::Args;
+use event_log_parser::{archive, directory::LogDirectory, references, validate};
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+    process::ExitCode,
+};
+
+pub fn run(args: &Args) -> Result<ExitCode, Box<dyn Error>> {
+    let dir = LogDirectory::open(args.positional(0, "dir")?)?;
+    let refs = dir.references();
+    let repair = args.get("repair").map(Path::new);
+    if let Some(repair) = repair {
+        fs::create_dir_all(repair)?;
+        let out = BufWriter::new(File::create(repair.join("1Cv8.lgf"))?);
+        references::Writer::new(out).write(refs)?.flush()?;
+    }
+
+    let mut out = io::stdout().lock();
+    let holes = validate::holes(refs);
+    for (field, id) in &holes {
+        writeln!(out, "1Cv8.lgf: empty {field} record {id}")?;
+    }
+    let mut valid = holes.is_empty();
+    for file in dir.files() {
+        let path = file.path();
+        let report = match repair {
+            Some(repair) => {
+                let name = archive::uncompressed_name(path).ok_or("invalid file name")?;
+                let out = BufWriter::new(File::create(repair.join(name))?);
+                validate::repair_file(path, refs, out)?
+            }
+            None => validate::validate_file(path, refs)?,
+        };
+        for problem in report.problems() {
+            writeln!(out, "{}: {problem}", path.display())?;
+        }
+        writeln!(
+            out,
+            "{}: {} records, {} problems",
+            path.display(),
+            report.records(),
+            report.problems().len()
+        )?;
+        valid &= report.is_valid();
+    }
+    Ok(match valid {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    })
+}
@@ -0,0 +1,483 @@
+use crate::{args::Args, output};
+use chrono::NaiveDateTime;
+use event_log_parser::{
+    archive,
+    data::Value,
+    directory::LogDirectory,
+    events::{self, Event, EventLogLevel, ParseOptions},
+    references::References,
+};
+use ratatui::{
+    crossterm::event::{self as term, KeyCode, KeyEvent, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph, Row, Table, Wrap},
+    DefaultTerminal, Frame,
+};
+use std::{error::Error, io, ops::ControlFlow, path::Path};
+
+// В памяти только краткие сведения о событии, полностью запись
+// читается по смещению при выборе строки
+struct Record {
+    file: usize,
+    offset: u64,
+    date: NaiveDateTime,
+    level: EventLogLevel,
+    user: usize,
+    event: usize,
+    metadata: usize,
+    comment: String,
+}
+
+pub fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+    let dir = LogDirectory::open(args.positional(0, "dir")?)?;
+    let level = args.get("level").map(output::parse_level).transpose()?;
+    let user = match args.get("user") {
+        Some(name) => Some(
+            dir.references()
+                .user_id_by_name(name)
+                .ok_or_else(|| format!("unknown user: {name}"))?,
+        ),
+        None => None,
+    };
+    let records = load(&dir, args.range()?)?;
+    let mut view = View {
+        dir,
+        records,
+        visible: Vec::new(),
+        selected: 0,
+        top: 0,
+        height: 0,
+        level,
+        user,
+        search: String::new(),
+        input: None,
+        detail: true,
+        loaded: None,
+    };
+    view.apply_filters();
+    view.selected = view.visible.len().saturating_sub(1);
+
+    let mut terminal = ratatui::init();
+    let result = view.run(&mut terminal);
+    ratatui::restore();
+    Ok(result?)
+}
+
+fn load(
+    dir: &LogDirectory,
+    range: Option<(NaiveDateTime, NaiveDateTime)>,
+) -> io::Result<Vec<Record>> {
+    let mut records = Vec::new();
+    for (num, file) in dir.files().iter().enumerate() {
+        if range.is_some_and(|(from, to)| !file.overlaps(from, to)) {
+            continue;
+        }
+        let mut push = |event: Event| {
+            if range.is_none_or(|(from, to)| event.date() >= from && event.date() <= to) {
+                records.push(Record {
+                    file: num,
+                    offset: event.offset(),
+                    date: event.date(),
+                    level: *event.log_level(),
+                    user: event.user_id(),
+                    event: event.event_id(),
+                    metadata: event.metadata_id(),
+                    comment: event.comment().replace(['\r', '\n'], " "),
+                });
+            }
+            ControlFlow::Continue(())
+        };
+        // Для несжатых файлов начало интервала ищется по индексу
+        if archive::is_compressed(file.path()) {
+            events::parse_reader(archive::open(file.path())?, &mut |event| {
+                let _ = push(event);
+            })?;
+        } else {
+            let mut options = ParseOptions::new();
+            if let Some((from, to)) = range {
+                options = options.range(from, to);
+            }
+            events::parse_with_options(file.path(), &mut options, &mut push)?;
+        }
+    }
+    records.sort_by_key(|record| record.date);
+    Ok(records)
+}
+
+fn read_event<T, F>(path: &Path, offset: u64, action: &mut F) -> io::Result<Option<T>>
+where
+    F: FnMut(Event) -> T,
+{
+    let mut found = None;
+    if archive::is_compressed(path) {
+        events::parse_reader(archive::open(path)?, &mut |event| {
+            if found.is_none() && event.offset() == offset {
+                found = Some(action(event));
+            }
+        })?;
+    } else {
+        events::parse_from_offset(path, offset, &mut |event| {
+            found = Some(action(event));
+            ControlFlow::Break(())
+        })?;
+    }
+    Ok(found)
+}
+
+struct View {
+    dir: LogDirectory,
+    records: Vec<Record>,
+    visible: Vec<usize>,
+    selected: usize,
+    top: usize,
+    height: usize,
+    level: Option<EventLogLevel>,
+    user: Option<usize>,
+    search: String,
+    input: Option<String>,
+    detail: bool,
+    loaded: Option<(usize, Vec<String>)>,
+}
+
+impl View {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let term::Event::Key(key) = term::read()? {
+                if key.kind == KeyEventKind::Press && self.key(key).is_break() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn key(&mut self, key: KeyEvent) -> ControlFlow<()> {
+        if let Some(input) = &mut self.input {
+            match key.code {
+                KeyCode::Enter => {
+                    self.search = self.input.take().unwrap_or_default();
+                }
+                KeyCode::Esc => self.input = None,
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(ch) => {
+                    input.push(ch);
+                    // Поиск по мере ввода начинается с текущей строки
+                    let text = input.to_lowercase();
+                    self.find(&text, self.selected, true);
+                }
+                _ => {}
+            }
+            return ControlFlow::Continue(());
+        }
+        let page = self.height.max(1);
+        match key.code {
+            KeyCode::Char('q') => return ControlFlow::Break(()),
+            KeyCode::Esc => {
+                self.search.clear();
+                self.level = None;
+                self.user = None;
+                self.apply_filters();
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.select(self.selected.saturating_add(1)),
+            KeyCode::Up | KeyCode::Char('k') => self.select(self.selected.saturating_sub(1)),
+            KeyCode::PageDown | KeyCode::Char(' ') => {
+                self.select(self.selected.saturating_add(page))
+            }
+            KeyCode::PageUp => self.select(self.selected.saturating_sub(page)),
+            KeyCode::Home | KeyCode::Char('g') => self.select(0),
+            KeyCode::End | KeyCode::Char('G') => self.select(usize::MAX),
+            KeyCode::Enter => self.detail = !self.detail,
+            KeyCode::Char('/') => self.input = Some(String::new()),
+            KeyCode::Char('n') => {
+                let text = self.search.to_lowercase();
+                self.find(&text, self.selected + 1, true);
+            }
+            KeyCode::Char('N') => {
+                let text = self.search.to_lowercase();
+                if let Some(start) = self.selected.checked_sub(1) {
+                    self.find(&text, start, false);
+                }
+            }
+            KeyCode::Char('l') => {
+                self.level = match self.level {
+                    None => Some(EventLogLevel::Information),
+                    Some(EventLogLevel::Information) => Some(EventLogLevel::Warning),
+                    Some(EventLogLevel::Warning) => Some(EventLogLevel::Error),
+                    Some(_) => None,
+                };
+                self.apply_filters();
+            }
+            KeyCode::Char('u') => {
+                self.user = match self.user {
+                    Some(_) => None,
+                    None => self.current().map(|record| record.user),
+                };
+                self.apply_filters();
+            }
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn current(&self) -> Option<&Record> {
+        self.visible.get(self.selected).map(|&i| &self.records[i])
+    }
+
+    fn select(&mut self, selected: usize) {
+        self.selected = selected.min(self.visible.len().saturating_sub(1));
+    }
+
+    // Выбранная строка сохраняется, если она осталась после фильтра
+    fn apply_filters(&mut self) {
+        let current = self.visible.get(self.selected).copied();
+        self.visible = (0..self.records.len())
+            .filter(|&i| {
+                let record = &self.records[i];
+                self.level
+                    .is_none_or(|level| output::severity(&record.level) >= output::severity(&level))
+                    && self.user.is_none_or(|user| record.user == user)
+            })
+            .collect();
+        let selected = current.map_or(usize::MAX, |current| {
+            self.visible.partition_point(|&i| i < current)
+        });
+        self.select(selected);
+    }
+
+    fn matches(&self, record: &Record, text: &str) -> bool {
+        let refs = self.dir.references();
+        let name = |names: &[String], id: usize| {
+            names
+                .get(id)
+                .is_some_and(|name| name.to_lowercase().contains(text))
+        };
+        record.comment.to_lowercase().contains(text)
+            || name(refs.events(), record.event)
+            || refs
+                .users()
+                .get(record.user)
+                .is_some_and(|user| user.name().to_lowercase().contains(text))
+            || refs
+                .metadata()
+                .get(record.metadata)
+                .is_some_and(|metadata| metadata.name().to_lowercase().contains(text))
+    }
+
+    fn find(&mut self, text: &str, start: usize, forward: bool) {
+        if text.is_empty() {
+            return;
+        }
+        let found = match forward {
+            true => (start..self.visible.len())
+                .find(|&i| self.matches(&self.records[self.visible[i]], text)),
+            false => (0..=start.min(self.visible.len().saturating_sub(1)))
+                .rev()
+                .find(|&i| self.matches(&self.records[self.visible[i]], text)),
+        };
+        if let Some(found) = found {
+            self.select(found);
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let detail = if self.detail { 14 } else { 0 };
+        let [list, details, status] = Layout::vertical([
+            Constraint::Min(3),
+            Constraint::Length(detail),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        // Строится только видимая часть таблицы
+        self.height = list.height.saturating_sub(2) as usize;
+        if self.selected < self.top {
+            self.top = self.selected;
+        } else if self.selected >= self.top + self.height {
+            self.top = self.selected + 1 - self.height;
+        }
+        let refs = self.dir.references();
+        let end = (self.top + self.height).min(self.visible.len());
+        let rows = self.visible[self.top..end]
+            .iter()
+            .enumerate()
+            .map(|(i, &num)| {
+                let record = &self.records[num];
+                let mut style = level_style(record.level);
+                if self.top + i == self.selected {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                Row::new([
+                    record.date.to_string(),
+                    output::level(&record.level).to_string(),
+                    refs.events().get(record.event).cloned().unwrap_or_default(),
+                    refs.users()
+                        .get(record.user)
+                        .map(|user| user.name().to_string())
+                        .unwrap_or_default(),
+                    record.comment.clone(),
+                ])
+                .style(style)
+            });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(19),
+                Constraint::Length(1),
+                Constraint::Length(32),
+                Constraint::Length(20),
+                Constraint::Fill(1),
+            ],
+        )
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            " {} ({} of {}) ",
+            self.dir.path().display(),
+            self.visible.len(),
+            self.records.len()
+        )));
+        frame.render_widget(table, list);
+
+        if self.detail {
+            let lines = self.detail_lines();
+            let paragraph = Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::ALL));
+            frame.render_widget(paragraph, details);
+        }
+
+        let status_line = match &self.input {
+            Some(input) => format!("/{input}"),
+            None => {
+                let mut status = String::from(
+                    "q quit  / search  n/N next/prev  l level  u user  Enter details  Esc reset",
+                );
+                if let Some(level) = &self.level {
+                    status.push_str(&format!("  [level >= {}]", output::level(level)));
+                }
+                if self.user.is_some() {
+                    status.push_str("  [user]");
+                }
+                if !self.search.is_empty() {
+                    status.push_str(&format!("  [/{}]", self.search));
+                }
+                status
+            }
+        };
+        frame.render_widget(Paragraph::new(status_line), status);
+    }
+
+    fn detail_lines(&mut self) -> Vec<Line<'static>> {
+        let Some(&num) = self.visible.get(self.selected) else {
+            return Vec::new();
+        };
+        if self
+            .loaded
+            .as_ref()
+            .is_none_or(|(loaded, _)| *loaded != num)
+        {
+            let record = &self.records[num];
+            let path = self.dir.files()[record.file].path();
+            let lines = read_event(path, record.offset, &mut |event| {
+                detail(&event, self.dir.references())
+            });
+            let lines = match lines {
+                Ok(Some(lines)) => lines,
+                Ok(None) => vec!["event not found".to_string()],
+                Err(error) => vec![error.to_string()],
+            };
+            self.loaded = Some((num, lines));
+        }
+        let (_, lines) = self.loaded.as_ref().expect("loaded event");
+        lines.iter().cloned().map(Line::from).collect()
+    }
+}
+
+fn detail(event: &Event, refs: &References) -> Vec<String> {
+    let event = event.resolve(refs);
+    let mut lines = vec![
+        format!("Date: {}  Level: {:?}", event.date(), event.log_level()),
+        format!("Event: {}", event.event_name()),
+        format!(
+            "User: {}  Computer: {}  Application: {}",
+            event.user_name(),
+            event.computer(),
+            event.application()
+        ),
+        format!(
+            "Session: {}  Connection: {}  Server: {}",
+            event.session(),
+            event.connection(),
+            event.worker_server()
+        ),
+        format!(
+            "Transaction: {:?} {}",
+            event.transaction_status(),
+            event.transaction_data()
+        ),
+        format!("Metadata: {}", event.metadata_name()),
+        format!("Comment: {}", event.comment()),
+        format!("Data presentation: {}", event.data_presentation()),
+        "Data:".to_string(),
+    ];
+    match Value::parse(event.data()) {
+        Some(value) => value_lines(&value, 1, &mut lines),
+        None => lines.push(format!("  {}", event.data())),
+    }
+    lines
+}
+
+fn level_style(level: EventLogLevel) -> Style {
+    match level {
+        EventLogLevel::Error => Style::default().fg(Color::Red),
+        EventLogLevel::Warning => Style::default().fg(Color::Yellow),
+        EventLogLevel::Information => Style::default(),
+        EventLogLevel::Note => Style::default().fg(Color::DarkGray),
+    }
+}
+
+// Разобранное значение данных события с отступами по уровню вложенности
+fn value_lines(value: &Value, depth: usize, lines: &mut Vec<String>) {
+    let indent = "  ".repeat(depth);
+    match value {
+        Value::String(s) => lines.push(format!("{indent}\"{s}\"")),
+        Value::Number(n) => lines.push(format!("{indent}{n}")),
+        Value::Uuid(id) => lines.push(format!("{indent}{id}")),
+        Value::Ref(kind, id) => lines.push(format!("{indent}ref {kind}:{id}")),
+        Value::Raw(raw) => lines.push(format!("{indent}{raw}")),
+        Value::List(list) => {
+            lines.push(format!("{indent}{{"));
+            for value in list {
+                value_lines(value, depth + 1, lines);
+            }
+            lines.push(format!("{indent}}}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_lines() {
+        let value = Value::parse(r#"{"R",{"S","Иванов"},1}"#).unwrap();
+        let mut lines = Vec::new();
+        value_lines(&value, 0, &mut lines);
+        assert_eq!(
+            lines,
+            [
+                "{",
+                "  \"R\"",
+                "  {",
+                "    \"S\"",
+                "    \"Иванов\"",
+                "  }",
+                "  1",
+                "}"
+            ]
+        );
+    }
+}
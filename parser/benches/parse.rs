@@ -0,0 +1,52 @@
+// cargo bench -p event-log-parser
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use event_log_parser::record::parse_buffer;
+use std::{hint::black_box, ops::ControlFlow};
+
+fn test_log() -> Vec<u8> {
+    std::fs::read(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../test-log/20221212000000.lgp"
+    ))
+    .expect("test log")
+}
+
+// Записи с длинными комментариями и экранированными кавычками
+fn comments() -> Vec<u8> {
+    let comment =
+        r#"Ошибка при вызове метода контекста (Записать): ""Документ.Реализация"", строка 42; "#
+            .repeat(20);
+    let mut data = Vec::new();
+    for i in 0..2000 {
+        data.extend_from_slice(
+            format!(
+                "{{20221217221504,N,\r\n{{0,0}},1,1,1,1,{i},E,\"{comment}\",0,\r\n\
+                 {{\"S\",\"{comment}\"}},\"{comment}\",0,0,0,2,0,\r\n{{0}}\r\n}},\r\n"
+            )
+            .as_bytes(),
+        );
+    }
+    data
+}
+
+fn parse_all(data: &[u8]) -> usize {
+    let mut count = 0;
+    let _ = parse_buffer(data, 0, &mut |event| {
+        black_box(event);
+        count += 1;
+        ControlFlow::Continue(())
+    });
+    count
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_buffer");
+    for (name, data) in [("test_log", test_log()), ("comments", comments())] {
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        group.bench_function(name, |b| b.iter(|| parse_all(black_box(&data))));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
@@ -0,0 +1,169 @@
+// Обертка C API (include/event_log_parser.h) для .NET 6+.
+// Библиотека: cargo rustc -p event-log-parser --release --features ffi --crate-type cdylib,
+// результат (event_log_parser.dll / libevent_log_parser.so) кладется рядом с приложением.
+using System;
+using System.Collections;
+using System.Collections.Generic;
+using System.IO;
+using System.Runtime.InteropServices;
+
+namespace EventLogParser
+{
+    public enum EventLogLevel
+    {
+        Information = 0,
+        Note = 1,
+        Warning = 2,
+        Error = 3,
+    }
+
+    public enum TransactionStatus
+    {
+        NotApplicable = 0,
+        Committed = 1,
+        Unfinished = 2,
+        RolledBack = 3,
+    }
+
+    public sealed record LogEvent(
+        DateTime Date,
+        EventLogLevel Level,
+        TransactionStatus TransactionStatus,
+        string TransactionData,
+        string User,
+        string UserId,
+        string Computer,
+        string Application,
+        string Event,
+        string Comment,
+        string Metadata,
+        string MetadataId,
+        string Data,
+        string DataPresentation,
+        string WorkerServer,
+        uint Port,
+        uint SyncPort,
+        ulong Session,
+        ulong Connection);
+
+    internal static class Native
+    {
+        private const string Library = "event_log_parser";
+
+        public const int Ok = 0;
+        public const int End = 1;
+
+        // Порядок и типы полей совпадают с ElpEvent из event_log_parser.h
+        [StructLayout(LayoutKind.Sequential)]
+        public struct ElpEvent
+        {
+            public long Date;
+            public int Level;
+            public int TransactionStatus;
+            public ulong Session;
+            public ulong Connection;
+            public uint Port;
+            public uint SyncPort;
+            public IntPtr User;
+            public IntPtr UserId;
+            public IntPtr Computer;
+            public IntPtr Application;
+            public IntPtr Event;
+            public IntPtr Comment;
+            public IntPtr Metadata;
+            public IntPtr MetadataId;
+            public IntPtr Data;
+            public IntPtr DataPresentation;
+            public IntPtr WorkerServer;
+            public IntPtr TransactionData;
+        }
+
+        [DllImport(Library, EntryPoint = "elp_open_dir")]
+        public static extern int OpenDir(byte[] path, out IntPtr log);
+
+        [DllImport(Library, EntryPoint = "elp_close")]
+        public static extern void Close(IntPtr log);
+
+        [DllImport(Library, EntryPoint = "elp_read_event")]
+        public static extern int ReadEvent(IntPtr log, out ElpEvent ev);
+
+        [DllImport(Library, EntryPoint = "elp_last_error")]
+        public static extern IntPtr LastError(IntPtr log);
+
+        public static byte[] Utf8(string value)
+        {
+            var bytes = System.Text.Encoding.UTF8.GetBytes(value);
+            Array.Resize(ref bytes, bytes.Length + 1);
+            return bytes;
+        }
+
+        public static string Text(IntPtr value) =>
+            Marshal.PtrToStringUTF8(value) ?? string.Empty;
+    }
+
+    /// <summary>Журнал регистрации из каталога с 1Cv8.lgf, события читаются один раз по порядку.</summary>
+    public sealed class EventLog : IEnumerable<LogEvent>, IDisposable
+    {
+        private IntPtr log;
+
+        public EventLog(string path)
+        {
+            if (Native.OpenDir(Native.Utf8(path), out log) != Native.Ok)
+            {
+                throw new IOException($"cannot open event log: {path}");
+            }
+        }
+
+        public IEnumerator<LogEvent> GetEnumerator()
+        {
+            if (log == IntPtr.Zero)
+            {
+                throw new ObjectDisposedException(nameof(EventLog));
+            }
+            while (true)
+            {
+                var code = Native.ReadEvent(log, out var ev);
+                if (code == Native.End)
+                {
+                    yield break;
+                }
+                if (code != Native.Ok)
+                {
+                    throw new IOException(Native.Text(Native.LastError(log)));
+                }
+                // Строки копируются сразу, указатели живут до следующего чтения
+                yield return new LogEvent(
+                    DateTime.SpecifyKind(DateTime.UnixEpoch.AddSeconds(ev.Date), DateTimeKind.Unspecified),
+                    (EventLogLevel)ev.Level,
+                    (TransactionStatus)ev.TransactionStatus,
+                    Native.Text(ev.TransactionData),
+                    Native.Text(ev.User),
+                    Native.Text(ev.UserId),
+                    Native.Text(ev.Computer),
+                    Native.Text(ev.Application),
+                    Native.Text(ev.Event),
+                    Native.Text(ev.Comment),
+                    Native.Text(ev.Metadata),
+                    Native.Text(ev.MetadataId),
+                    Native.Text(ev.Data),
+                    Native.Text(ev.DataPresentation),
+                    Native.Text(ev.WorkerServer),
+                    ev.Port,
+                    ev.SyncPort,
+                    ev.Session,
+                    ev.Connection);
+            }
+        }
+
+        IEnumerator IEnumerable.GetEnumerator() => GetEnumerator();
+
+        public void Dispose()
+        {
+            if (log != IntPtr.Zero)
+            {
+                Native.Close(log);
+                log = IntPtr.Zero;
+            }
+        }
+    }
+}
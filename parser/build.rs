@@ -0,0 +1,9 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("vendored protoc");
+        std::env::set_var("PROTOC", protoc);
+        println!("cargo:rerun-if-changed=proto/event_log.proto");
+        tonic_prost_build::compile_protos("proto/event_log.proto").expect("compile proto");
+    }
+}
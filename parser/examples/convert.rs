@@ -0,0 +1,16 @@
+use std::{env, io};
+
+use event_log_parser::lgd::{lgd_to_lgp, lgp_to_lgd};
+
+fn main() -> io::Result<()> {
+    let args: Vec<_> = env::args().skip(1).collect();
+    match args.as_slice() {
+        [command, from, to] if command == "lgp-to-lgd" => lgp_to_lgd(from, to),
+        [command, from, to] if command == "lgd-to-lgp" => lgd_to_lgp(from, to),
+        _ => {
+            println!("Usage: convert lgp-to-lgd /path/to/log/dir /path/to/1Cv8.lgd");
+            println!("       convert lgd-to-lgp /path/to/1Cv8.lgd /path/to/log/dir");
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use std::{
+    env,
+    io::{self, BufWriter},
+};
+
+use event_log_parser::{directory::LogDirectory, export::jsonl::Writer};
+
+fn main() -> io::Result<()> {
+    let Some(dir_name) = env::args().nth(1) else {
+        println!("Usage: export-json /path/to/log/dir > events.jsonl");
+        return Ok(());
+    };
+
+    let dir = LogDirectory::open(dir_name)?;
+    let refs = dir.references();
+    let mut writer = Writer::new(BufWriter::new(io::stdout().lock()), refs);
+
+    let mut result = Ok(());
+    dir.events(&mut |event| {
+        if result.is_ok() {
+            result = writer.write(&event);
+        }
+    })?;
+    result?;
+    writer.flush()
+}
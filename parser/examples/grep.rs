@@ -0,0 +1,29 @@
+use std::{env, io};
+
+use event_log_parser::directory::LogDirectory;
+
+fn main() -> io::Result<()> {
+    let (Some(dir_name), Some(pattern)) = (env::args().nth(1), env::args().nth(2)) else {
+        println!("Usage: grep /path/to/log/dir pattern");
+        return Ok(());
+    };
+
+    let dir = LogDirectory::open(dir_name)?;
+    let refs = dir.references();
+
+    dir.events(&mut |event| {
+        let event = event.resolve(refs);
+        let comment = event.comment();
+        if comment.contains(&pattern) {
+            println!(
+                "{} {} {}: {}",
+                event.date(),
+                event.event_name(),
+                event.user_name(),
+                comment
+            );
+        }
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use std::{env, error::Error, net::SocketAddr};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let Some(dir) = args.next() else {
+        println!("Usage: grpc_server /path/to/log/dir [127.0.0.1:50051]");
+        return Ok(());
+    };
+    let addr: SocketAddr = args
+        .next()
+        .as_deref()
+        .unwrap_or("127.0.0.1:50051")
+        .parse()?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(event_log_parser::grpc::serve(dir, addr))?;
+
+    Ok(())
+}
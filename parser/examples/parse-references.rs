@@ -4,7 +4,7 @@ use event_log_parser::references::References;
 
 fn main() -> io::Result<()> {
     let Some(file_name) = env::args().nth(1) else {
-        println!("Usage: parse-events /path/to/file/1Cv8.lgf");
+        println!("Usage: parse-references /path/to/file/1Cv8.lgf");
         return Ok(());
     };
     let now = Instant::now();
@@ -0,0 +1,36 @@
+use std::{env, io, path::Path};
+
+use chrono::NaiveDateTime;
+use event_log_parser::{events, references::References};
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 5 {
+        println!("Usage: range-query /path/to/log/dir /path/to/file/*.lgp \"2022-12-17 22:00:00\" \"2022-12-17 23:00:00\"");
+        return Ok(());
+    }
+
+    let parse_date = |s: &str| {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    };
+    let from = parse_date(&args[3])?;
+    let to = parse_date(&args[4])?;
+
+    let mut refs = References::default();
+    refs.parse(Path::new(&args[1]).join("1Cv8.lgf"))?;
+
+    let mut count = 0;
+    events::parse_range(&args[2], from, to, &mut |event| {
+        println!(
+            "{} {} {}",
+            event.date(),
+            event.event(&refs),
+            event.user(&refs).name()
+        );
+        count += 1;
+    })?;
+    println!("count: {count}");
+
+    Ok(())
+}
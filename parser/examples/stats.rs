@@ -0,0 +1,58 @@
+use std::{env, fs::metadata, io, time::Instant};
+
+use event_log_parser::{directory::LogDirectory, stats::Collector};
+
+fn main() -> io::Result<()> {
+    let now = Instant::now();
+
+    let Some(dir_name) = env::args().nth(1) else {
+        println!("Usage: stats /path/to/log/dir");
+        return Ok(());
+    };
+
+    let dir = LogDirectory::open(dir_name)?;
+    let refs = dir.references();
+
+    let mut total_log_size = 0;
+    for file in dir.files() {
+        total_log_size += metadata(file.path())?.len();
+    }
+
+    let mut collector = Collector::new().top(10);
+    dir.events(&mut |event| collector.push(&event))?;
+    let report = collector.report(refs);
+
+    println!(
+        "duration: {} ms",
+        (now.elapsed().as_nanos() as f64) / 1_000_000f64
+    );
+    println!(
+        "Total log size: {:.3} Mb",
+        total_log_size as f64 / 1024f64 / 1024f64
+    );
+    println!("Total Events: {}", report.total());
+
+    println!("==========");
+    let levels = report.levels();
+    println!("LogLevel.Error: {}", levels.error());
+    println!("LogLevel.Warning: {}", levels.warning());
+    println!("LogLevel.Information: {}", levels.information());
+    println!("LogLevel.Note: {}", levels.note());
+
+    println!("==========");
+    let count = |name| {
+        let event = report.events().iter().find(|x| x.name() == name);
+        event.map_or(0, |x| x.count())
+    };
+    println!("Session.Start: {}", count("_$Session$_.Start"));
+    println!("Data.New: {}", count("_$Data$_.New"));
+    println!("Data.Update: {}", count("_$Data$_.Update"));
+
+    println!("==========");
+    println!("Top 10 errors:");
+    for error in report.top_errors() {
+        println!("  {}: {}", error.name(), error.count());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use std::{env, io, ops::ControlFlow, time::Duration};
+
+use event_log_parser::watch;
+
+fn main() -> io::Result<()> {
+    let Some(dir_name) = env::args().nth(1) else {
+        println!("Usage: tail /path/to/log/dir");
+        return Ok(());
+    };
+
+    watch::watch(dir_name, Duration::from_secs(1), &mut |event| {
+        println!(
+            "{} {:?} {} {}: {}",
+            event.date(),
+            event.log_level(),
+            event.event_name(),
+            event.user_name(),
+            event.comment()
+        );
+        ControlFlow::Continue(())
+    })
+}
@@ -0,0 +1,85 @@
+/*
+ * C API парсера журнала регистрации 1С (feature "ffi").
+ * Сборка: cargo rustc -p event-log-parser --release --features ffi --crate-type cdylib
+ */
+#ifndef EVENT_LOG_PARSER_H
+#define EVENT_LOG_PARSER_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define ELP_OK 0
+#define ELP_END 1
+#define ELP_INVALID_ARGUMENT (-1)
+#define ELP_IO_ERROR (-2)
+#define ELP_NO_EVENT (-3)
+
+#define ELP_FIELD_USER 0
+#define ELP_FIELD_COMPUTER 1
+#define ELP_FIELD_APPLICATION 2
+#define ELP_FIELD_EVENT 3
+#define ELP_FIELD_COMMENT 4
+#define ELP_FIELD_METADATA 5
+#define ELP_FIELD_DATA 6
+#define ELP_FIELD_DATA_PRESENTATION 7
+#define ELP_FIELD_WORKER_SERVER 8
+#define ELP_FIELD_TRANSACTION_DATA 9
+#define ELP_FIELD_DATE 10
+#define ELP_FIELD_USER_ID 11
+#define ELP_FIELD_METADATA_ID 12
+
+typedef struct ElpLog ElpLog;
+
+/* Событие целиком, строки UTF-8 действительны до следующего чтения */
+typedef struct ElpEvent {
+    int64_t date;
+    int level;
+    int transaction_status;
+    uint64_t session;
+    uint64_t connection;
+    uint32_t port;
+    uint32_t sync_port;
+    const char *user;
+    const char *user_id;
+    const char *computer;
+    const char *application;
+    const char *event;
+    const char *comment;
+    const char *metadata;
+    const char *metadata_id;
+    const char *data;
+    const char *data_presentation;
+    const char *worker_server;
+    const char *transaction_data;
+} ElpEvent;
+
+/* Каталог журнала с 1Cv8.lgf, путь в UTF-8 */
+int elp_open_dir(const char *path, ElpLog **log);
+void elp_close(ElpLog *log);
+
+/* ELP_OK - есть событие, ELP_END - конец журнала, иначе код ошибки */
+int elp_next_event(ElpLog *log);
+/* Следующее событие со всеми полями за один вызов, коды как у elp_next_event */
+int elp_read_event(ElpLog *log, ElpEvent *event);
+
+/* Строка UTF-8 действительна до следующего elp_next_event, NULL если события нет */
+const char *elp_event_field(ElpLog *log, int field);
+/* Секунды от 1970-01-01 без учета часового пояса */
+int elp_event_date(const ElpLog *log, int64_t *date);
+/* 0 - информация, 1 - примечание, 2 - предупреждение, 3 - ошибка */
+int elp_event_level(const ElpLog *log);
+/* 0 - нет транзакции, 1 - зафиксирована, 2 - не завершена, 3 - отменена */
+int elp_event_transaction_status(const ElpLog *log);
+uint64_t elp_event_session(const ElpLog *log);
+uint64_t elp_event_connection(const ElpLog *log);
+
+const char *elp_last_error(const ElpLog *log);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
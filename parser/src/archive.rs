@@ -0,0 +1,87 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+// Расширения сжатых файлов журнала: 20221212000000.lgp.gz, 1Cv8.lgf.zst
+pub const EXTENSIONS: &[&str] = &[
+    #[cfg(feature = "gzip")]
+    "gz",
+    #[cfg(feature = "zstd")]
+    "zst",
+];
+
+pub fn is_compressed<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| EXTENSIONS.contains(&ext))
+}
+
+// Имя файла без расширения сжатия: 20221212000000.lgp.gz -> 20221212000000.lgp
+pub fn uncompressed_name(path: &Path) -> Option<&str> {
+    let name = path.file_name()?.to_str()?;
+    match is_compressed(path) {
+        true => name.rsplit_once('.').map(|(name, _)| name),
+        false => Some(name),
+    }
+}
+
+pub fn decompress<'a, R: Read + 'a>(reader: R, extension: &str) -> io::Result<Box<dyn Read + 'a>> {
+    match extension {
+        #[cfg(feature = "gzip")]
+        "gz" => Ok(Box::new(flate2::read::MultiGzDecoder::new(reader))),
+        #[cfg(feature = "zstd")]
+        "zst" => Ok(Box::new(zstd::Decoder::new(reader)?)),
+        _ => {
+            drop(reader);
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Unsupported compression: {extension}"),
+            ))
+        }
+    }
+}
+
+pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Box<dyn Read>> {
+    let path = path.as_ref();
+    let file = File::open(path)?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if is_compressed(path) => decompress(file, ext),
+        _ => Ok(Box::new(file)),
+    }
+}
+
+#[cfg(feature = "zip")]
+pub struct ZipLog {
+    archive: zip::ZipArchive<File>,
+}
+
+#[cfg(feature = "zip")]
+impl ZipLog {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<ZipLog> {
+        let archive = zip::ZipArchive::new(File::open(path)?).map_err(io::Error::other)?;
+        Ok(ZipLog { archive })
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.archive.file_names()
+    }
+
+    pub fn read<T, F>(&mut self, name: &str, action: F) -> io::Result<T>
+    where
+        F: FnOnce(&mut dyn Read) -> io::Result<T>,
+    {
+        let mut file = self.archive.by_name(name).map_err(|error| match error {
+            zip::result::ZipError::FileNotFound => {
+                io::Error::new(io::ErrorKind::NotFound, format!("{name} not found"))
+            }
+            error => io::Error::other(error),
+        })?;
+        match Path::new(name).extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if is_compressed(name) => action(&mut decompress(file, ext)?),
+            _ => action(&mut file),
+        }
+    }
+}
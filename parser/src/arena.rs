@@ -0,0 +1,159 @@
+// Пачка событий, строки которых лежат в одном общем буфере: вместо пяти String
+// на событие - дописывание в конец буфера, который очищается целиком
+use crate::{
+    events::{Event, EventLogLevel, TransactionStatus},
+    parser::{LazyStr, LogStr},
+};
+use chrono::NaiveDateTime;
+use std::{cmp::Ordering, ops::Range};
+
+#[derive(Clone, Debug)]
+struct Record {
+    date: NaiveDateTime,
+    transaction_status: TransactionStatus,
+    log_level: EventLogLevel,
+    ids: [usize; 11],
+    strings: [Range<usize>; 5],
+    offset: u64,
+    end_offset: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct EventArena {
+    text: String,
+    records: Vec<Record>,
+}
+
+impl EventArena {
+    pub fn new() -> EventArena {
+        EventArena::default()
+    }
+
+    // Заранее выделить место под events событий и bytes байт строк
+    pub fn with_capacity(events: usize, bytes: usize) -> EventArena {
+        EventArena {
+            text: String::with_capacity(bytes),
+            records: Vec::with_capacity(events),
+        }
+    }
+
+    pub fn push(&mut self, event: &Event) {
+        let mut push = |str: &str| {
+            let start = self.text.len();
+            self.text.push_str(str);
+            start..self.text.len()
+        };
+        let strings = [
+            push(event.transaction_data()),
+            push(&event.comment()),
+            push(event.data()),
+            push(&event.data_presentation()),
+            push(event.unknown2()),
+        ];
+        self.records.push(Record {
+            date: event.date(),
+            transaction_status: *event.transaction_status(),
+            log_level: *event.log_level(),
+            ids: [
+                event.user_id(),
+                event.computer_id(),
+                event.application_id(),
+                event.connection(),
+                event.event_id(),
+                event.metadata_id(),
+                event.worker_server_id(),
+                event.port_id(),
+                event.sync_port_id(),
+                event.session(),
+                event.unknown1(),
+            ],
+            strings,
+            offset: event.offset(),
+            end_offset: event.end_offset(),
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    // Байт, занятых строками
+    pub fn bytes(&self) -> usize {
+        self.text.len()
+    }
+
+    // Выделенная память сохраняется для следующей пачки
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.records.clear();
+    }
+
+    pub fn get(&self, index: usize) -> Option<Event<'_>> {
+        self.records
+            .get(index)
+            .map(|record| event(&self.text, record))
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = Event<'_>> {
+        self.records.iter().map(|record| event(&self.text, record))
+    }
+
+    // Строки не перемещаются, переставляются только записи
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&Event, &Event) -> Ordering,
+    {
+        let text = &self.text;
+        self.records
+            .sort_by(|a, b| compare(&event(text, a), &event(text, b)));
+    }
+
+    pub fn sort_by_date(&mut self) {
+        self.records.sort_by_key(|record| record.date);
+    }
+
+    pub fn append(&mut self, other: &mut EventArena) {
+        let shift = self.text.len();
+        self.text.push_str(&other.text);
+        self.records
+            .extend(other.records.drain(..).map(|mut record| {
+                for range in &mut record.strings {
+                    *range = range.start + shift..range.end + shift;
+                }
+                record
+            }));
+        other.text.clear();
+    }
+}
+
+fn event<'a>(text: &'a str, record: &Record) -> Event<'a> {
+    let string = |i: usize| &text[record.strings[i].clone()];
+    Event {
+        date: record.date,
+        transaction_status: record.transaction_status,
+        transaction_data: string(0),
+        user_id: record.ids[0],
+        computer_id: record.ids[1],
+        application_id: record.ids[2],
+        connection: record.ids[3],
+        event_id: record.ids[4],
+        log_level: record.log_level,
+        comment: LogStr::new(string(1).as_bytes(), false),
+        metadata_id: record.ids[5],
+        data: LazyStr::decoded(string(2)),
+        data_presentation: LogStr::new(string(3).as_bytes(), false),
+        worker_server_id: record.ids[6],
+        port_id: record.ids[7],
+        sync_port_id: record.ids[8],
+        session: record.ids[9],
+        unknown1: record.ids[10],
+        unknown2: string(4),
+        offset: record.offset,
+        end_offset: record.end_offset,
+        raw: &[],
+    }
+}
@@ -0,0 +1,231 @@
+use crate::{events::Event, known_events::KnownEvent, references::References};
+use chrono::{Duration, NaiveDateTime};
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Finding {
+    FailedAuthenticationBurst {
+        user: String,
+        computer: String,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        attempts: usize,
+    },
+    UserModified {
+        date: NaiveDateTime,
+        author: String,
+        action: String,
+        properties: Vec<String>,
+    },
+    AccessDenied {
+        date: NaiveDateTime,
+        user: String,
+        right: Option<String>,
+    },
+    ExternalProcessor {
+        date: NaiveDateTime,
+        user: String,
+        computer: String,
+        name: String,
+    },
+}
+
+impl Finding {
+    pub fn date(&self) -> NaiveDateTime {
+        match self {
+            Finding::FailedAuthenticationBurst { start, .. } => *start,
+            Finding::UserModified { date, .. }
+            | Finding::AccessDenied { date, .. }
+            | Finding::ExternalProcessor { date, .. } => *date,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AuditReport {
+    findings: Vec<Finding>,
+}
+
+impl AuditReport {
+    pub fn findings(&self) -> &[Finding] {
+        &self.findings
+    }
+
+    pub fn failed_authentication_bursts(&self) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(|x| matches!(x, Finding::FailedAuthenticationBurst { .. }))
+    }
+
+    pub fn user_modifications(&self) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(|x| matches!(x, Finding::UserModified { .. }))
+    }
+
+    pub fn access_denied(&self) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(|x| matches!(x, Finding::AccessDenied { .. }))
+    }
+
+    pub fn external_processors(&self) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(|x| matches!(x, Finding::ExternalProcessor { .. }))
+    }
+}
+
+struct Burst {
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    attempts: usize,
+}
+
+pub struct Auditor<'refs> {
+    refs: &'refs References,
+    threshold: usize,
+    window: Duration,
+    external_events: Vec<String>,
+    failures: HashMap<(String, String), (VecDeque<NaiveDateTime>, Option<Burst>)>,
+    findings: Vec<Finding>,
+}
+
+impl<'refs> Auditor<'refs> {
+    pub fn new(refs: &'refs References) -> Auditor<'refs> {
+        Auditor {
+            refs,
+            threshold: 5,
+            window: Duration::minutes(5),
+            external_events: vec![
+                "_$Session$_.ExternalDataProcessorConnect".to_string(),
+                "_$Session$_.ExternalReportConnect".to_string(),
+            ],
+            failures: HashMap::new(),
+            findings: Vec::new(),
+        }
+    }
+
+    pub fn failed_authentication(mut self, threshold: usize, window: Duration) -> Auditor<'refs> {
+        if threshold == 0 || window <= Duration::zero() {
+            panic!("Invalid burst: threshold {threshold}, window {window}");
+        }
+        self.threshold = threshold;
+        self.window = window;
+        self
+    }
+
+    pub fn external_events<I, S>(mut self, names: I) -> Auditor<'refs>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.external_events = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn push(&mut self, event: &Event) {
+        let refs = self.refs;
+        let date = event.date();
+        let user = || {
+            refs.users()
+                .get(event.user_id())
+                .map_or("", |user| user.name())
+                .to_string()
+        };
+        let computer = || {
+            refs.computers()
+                .get(event.computer_id())
+                .cloned()
+                .unwrap_or_default()
+        };
+
+        let name = refs.events().get(event.event_id()).map_or("", |x| x);
+        if self.external_events.iter().any(|x| x == name) {
+            self.findings.push(Finding::ExternalProcessor {
+                date,
+                user: user(),
+                computer: computer(),
+                name: event.data_presentation().into_owned(),
+            });
+            return;
+        }
+
+        match KnownEvent::decode(event, refs) {
+            Some(KnownEvent::AuthenticationError(credentials)) => {
+                let name = credentials.user().or(credentials.os_user()).unwrap_or("");
+                self.failure(name.to_string(), computer(), date);
+            }
+            Some(KnownEvent::User { action, properties }) => {
+                self.findings.push(Finding::UserModified {
+                    date,
+                    author: user(),
+                    action,
+                    properties,
+                });
+            }
+            Some(KnownEvent::AccessDenied { right }) => {
+                self.findings.push(Finding::AccessDenied {
+                    date,
+                    user: user(),
+                    right,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    pub fn finish(mut self) -> AuditReport {
+        for ((user, computer), (_, burst)) in self.failures.drain() {
+            if let Some(burst) = burst {
+                self.findings.push(burst_finding(user, computer, burst));
+            }
+        }
+        self.findings.sort_by_key(Finding::date);
+        AuditReport {
+            findings: self.findings,
+        }
+    }
+
+    // Серия: не меньше threshold неудачных попыток в пределах window
+    fn failure(&mut self, user: String, computer: String, date: NaiveDateTime) {
+        let key = (user, computer);
+        let (dates, burst) = self.failures.entry(key.clone()).or_default();
+        while dates.front().is_some_and(|x| *x < date - self.window) {
+            dates.pop_front();
+        }
+        dates.push_back(date);
+
+        match burst {
+            Some(current) if date - current.end <= self.window => {
+                current.end = date;
+                current.attempts += 1;
+            }
+            _ => {
+                if let Some(ended) = burst.take() {
+                    let (user, computer) = key.clone();
+                    self.findings.push(burst_finding(user, computer, ended));
+                }
+                if dates.len() >= self.threshold {
+                    *burst = Some(Burst {
+                        start: dates[0],
+                        end: date,
+                        attempts: dates.len(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn burst_finding(user: String, computer: String, burst: Burst) -> Finding {
+    Finding::FailedAuthenticationBurst {
+        user,
+        computer,
+        start: burst.start,
+        end: burst.end,
+        attempts: burst.attempts,
+    }
+}
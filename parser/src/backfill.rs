@@ -0,0 +1,101 @@
+// Загрузка журнала с переходом к отслеживанию. Закрытые файлы *.lgp разбираются
+// параллельно в пуле потоков rayon, одновременно текущий файл читается с начала.
+// Граница передачи - конец последней полной записи текущего файла при первом опросе:
+// записи до нее относятся к истории, дописанные после - к отслеживанию,
+// поэтому каждое событие передается ровно один раз.
+use crate::{
+    events::{self, EventResolved},
+    watch::Watcher,
+};
+use rayon::prelude::*;
+use std::{
+    io,
+    ops::ControlFlow,
+    panic,
+    path::{Path, PathBuf},
+    thread::{self, ScopedJoinHandle},
+    time::Duration,
+};
+
+pub struct Backfill {
+    dir: PathBuf,
+    interval: Duration,
+    grace: Option<Duration>,
+}
+
+impl Backfill {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Backfill {
+        Backfill {
+            dir: dir.as_ref().to_path_buf(),
+            interval: Duration::from_secs(1),
+            grace: None,
+        }
+    }
+
+    /// Период опроса текущего файла, по умолчанию 1 секунда
+    pub fn interval(mut self, interval: Duration) -> Backfill {
+        self.interval = interval;
+        self
+    }
+
+    /// Сколько дочитывать предыдущий файл после ротации, см. `Watcher::grace`
+    pub fn grace(mut self, grace: Duration) -> Backfill {
+        self.grace = Some(grace);
+        self
+    }
+
+    /// `history` вызывается из нескольких потоков для событий до границы передачи,
+    /// порядок событий между файлами не сохраняется. `live` вызывается в текущем
+    /// потоке для дописанных событий, в том числе пока история еще загружается;
+    /// разбор продолжается до `ControlFlow::Break`.
+    pub fn run<H, L>(&self, history: &H, live: &mut L) -> io::Result<()>
+    where
+        H: Fn(EventResolved) + Sync,
+        L: FnMut(EventResolved) -> ControlFlow<()>,
+    {
+        let mut watcher = Watcher::open(&self.dir)?;
+        if let Some(grace) = self.grace {
+            watcher = watcher.grace(grace);
+        }
+        let refs = watcher.snapshot();
+        let current = watcher.current_file().map(Path::to_path_buf);
+        let closed: Vec<_> = watcher
+            .log_files()?
+            .into_iter()
+            .filter(|file| Some(file) != current.as_ref())
+            .collect();
+
+        thread::scope(|scope| {
+            let mut backfill = Some(scope.spawn(|| {
+                closed.par_iter().try_for_each(|file| {
+                    events::parse(file, &mut |event| history(event.resolve(&refs)))
+                })
+            }));
+
+            // Первый опрос текущего файла задает границу передачи
+            let _ = watcher.poll(&mut |event| {
+                history(event);
+                ControlFlow::Continue(())
+            })?;
+            loop {
+                if backfill.as_ref().is_some_and(|x| x.is_finished()) {
+                    join(backfill.take())?;
+                }
+                if watcher.poll(live)?.is_break() {
+                    break;
+                }
+                thread::sleep(self.interval);
+            }
+            join(backfill)
+        })
+    }
+}
+
+fn join(handle: Option<ScopedJoinHandle<io::Result<()>>>) -> io::Result<()> {
+    match handle {
+        Some(handle) => handle
+            .join()
+            .unwrap_or_else(|error| panic::resume_unwind(error)),
+        None => Ok(()),
+    }
+}
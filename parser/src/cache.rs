@@ -0,0 +1,350 @@
+use crate::{
+    directory::LogDirectory,
+    events::{Event, EventLogLevel, TransactionStatus},
+    parser::{LazyStr, LogStr},
+};
+use chrono::DateTime;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+use uuid::Uuid;
+
+const MAGIC: &[u8; 8] = b"LGPCACHE";
+const VERSION: u64 = 1;
+
+// Файл журнала, из которого построен кэш
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Source {
+    path: PathBuf,
+    size: u64,
+    modified: u64,
+}
+
+impl Source {
+    fn stat(path: &Path) -> io::Result<Source> {
+        let meta = fs::metadata(path)?;
+        let modified = meta
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |x| x.as_nanos() as u64);
+        Ok(Source {
+            path: path.to_path_buf(),
+            size: meta.len(),
+            modified,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+// Строки хранятся один раз, запись ссылается на них по номеру
+#[derive(Clone, Debug)]
+struct Record {
+    date: i64,
+    transaction_status: u8,
+    log_level: u8,
+    ids: [usize; 11],
+    strings: [u32; 5],
+    offset: u64,
+    end_offset: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Cache {
+    sources: Vec<Source>,
+    strings: Vec<String>,
+    records: Vec<Record>,
+}
+
+impl Cache {
+    pub fn build(dir: &LogDirectory) -> io::Result<Cache> {
+        let mut cache = Cache {
+            sources: sources(dir)?,
+            ..Default::default()
+        };
+        let mut strings = HashMap::new();
+        dir.events(&mut |event| {
+            let mut intern = |s: &str| match strings.get(s) {
+                Some(num) => *num,
+                None => {
+                    let num = cache.strings.len() as u32;
+                    cache.strings.push(s.to_string());
+                    strings.insert(s.to_string(), num);
+                    num
+                }
+            };
+            let strings = [
+                intern(event.transaction_data()),
+                intern(&event.comment()),
+                intern(event.data()),
+                intern(&event.data_presentation()),
+                intern(event.unknown2()),
+            ];
+            cache.records.push(Record {
+                date: event.date().and_utc().timestamp(),
+                transaction_status: match event.transaction_status() {
+                    TransactionStatus::Committed => 0,
+                    TransactionStatus::Unfinished => 1,
+                    TransactionStatus::NotApplicable => 2,
+                    TransactionStatus::RolledBack => 3,
+                },
+                log_level: match event.log_level() {
+                    EventLogLevel::Information => 0,
+                    EventLogLevel::Warning => 1,
+                    EventLogLevel::Error => 2,
+                    EventLogLevel::Note => 3,
+                },
+                ids: [
+                    event.user_id(),
+                    event.computer_id(),
+                    event.application_id(),
+                    event.connection(),
+                    event.event_id(),
+                    event.metadata_id(),
+                    event.worker_server_id(),
+                    event.port_id(),
+                    event.sync_port_id(),
+                    event.session(),
+                    event.unknown1(),
+                ],
+                strings,
+                offset: event.offset(),
+                end_offset: event.end_offset(),
+            });
+        })?;
+        Ok(cache)
+    }
+
+    // Кэш с диска, если файлы журнала не менялись, иначе разбор каталога заново
+    pub fn open<P: AsRef<Path>>(dir: &LogDirectory, path: P) -> io::Result<Cache> {
+        match Cache::load(&path) {
+            Ok(cache) if cache.is_valid(dir) => return Ok(cache),
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound || e.kind() == ErrorKind::InvalidData => {}
+            Err(e) => return Err(e),
+        }
+        let cache = Cache::build(dir)?;
+        cache.save(path)?;
+        Ok(cache)
+    }
+
+    pub fn is_valid(&self, dir: &LogDirectory) -> bool {
+        sources(dir).is_ok_and(|sources| sources == self.sources)
+    }
+
+    pub fn sources(&self) -> &[Source] {
+        &self.sources
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = Event<'_>> {
+        self.records.iter().map(|record| self.event(record))
+    }
+
+    fn event<'a>(&'a self, record: &Record) -> Event<'a> {
+        let string = |i: usize| self.strings[record.strings[i] as usize].as_str();
+        let date = DateTime::from_timestamp(record.date, 0).unwrap_or_default();
+        Event {
+            date: date.naive_utc(),
+            transaction_status: match record.transaction_status {
+                0 => TransactionStatus::Committed,
+                1 => TransactionStatus::Unfinished,
+                2 => TransactionStatus::NotApplicable,
+                _ => TransactionStatus::RolledBack,
+            },
+            transaction_data: string(0),
+            user_id: record.ids[0],
+            computer_id: record.ids[1],
+            application_id: record.ids[2],
+            connection: record.ids[3],
+            event_id: record.ids[4],
+            log_level: match record.log_level {
+                0 => EventLogLevel::Information,
+                1 => EventLogLevel::Warning,
+                2 => EventLogLevel::Error,
+                _ => EventLogLevel::Note,
+            },
+            comment: LogStr::new(string(1).as_bytes(), false),
+            metadata_id: record.ids[5],
+            data: LazyStr::decoded(string(2)),
+            data_presentation: LogStr::new(string(3).as_bytes(), false),
+            worker_server_id: record.ids[6],
+            port_id: record.ids[7],
+            sync_port_id: record.ids[8],
+            session: record.ids[9],
+            unknown1: record.ids[10],
+            unknown2: string(4),
+            offset: record.offset,
+            end_offset: record.end_offset,
+            raw: &[],
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut out = MAGIC.to_vec();
+        write_varint(&mut out, VERSION);
+        write_varint(&mut out, self.sources.len() as u64);
+        for source in &self.sources {
+            write_bytes(&mut out, source.path.to_string_lossy().as_bytes());
+            write_varint(&mut out, source.size);
+            write_varint(&mut out, source.modified);
+        }
+        write_varint(&mut out, self.strings.len() as u64);
+        for s in &self.strings {
+            write_bytes(&mut out, s.as_bytes());
+        }
+        write_varint(&mut out, self.records.len() as u64);
+        for record in &self.records {
+            write_varint(&mut out, record.date as u64);
+            out.push(record.transaction_status);
+            out.push(record.log_level);
+            for id in record.ids {
+                write_varint(&mut out, id as u64);
+            }
+            for num in record.strings {
+                write_varint(&mut out, num as u64);
+            }
+            write_varint(&mut out, record.offset);
+            write_varint(&mut out, record.end_offset);
+        }
+        fs::write(path, out)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Cache> {
+        let data = fs::read(path)?;
+        let mut input = Input {
+            data: data.strip_prefix(MAGIC).ok_or_else(invalid)?,
+        };
+        if input.varint()? != VERSION {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "Unsupported cache version",
+            ));
+        }
+        let mut cache = Cache::default();
+        for _ in 0..input.varint()? {
+            cache.sources.push(Source {
+                path: PathBuf::from(input.string()?),
+                size: input.varint()?,
+                modified: input.varint()?,
+            });
+        }
+        for _ in 0..input.varint()? {
+            cache.strings.push(input.string()?);
+        }
+        for _ in 0..input.varint()? {
+            let date = input.varint()? as i64;
+            let transaction_status = input.byte()?;
+            let log_level = input.byte()?;
+            let mut ids = [0; 11];
+            for id in &mut ids {
+                *id = input.varint()? as usize;
+            }
+            let mut strings = [0; 5];
+            for num in &mut strings {
+                *num = input.varint()? as u32;
+                if *num as usize >= cache.strings.len() {
+                    return Err(invalid());
+                }
+            }
+            cache.records.push(Record {
+                date,
+                transaction_status,
+                log_level,
+                ids,
+                strings,
+                offset: input.varint()?,
+                end_offset: input.varint()?,
+            });
+        }
+        Ok(cache)
+    }
+}
+
+// Для zip-архива источником считается сам архив
+fn sources(dir: &LogDirectory) -> io::Result<Vec<Source>> {
+    if dir.path().is_file() {
+        return Ok(vec![Source::stat(dir.path())?]);
+    }
+    dir.files()
+        .iter()
+        .map(|file| Source::stat(file.path()))
+        .collect()
+}
+
+pub(crate) fn invalid() -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, "Invalid cache file")
+}
+
+pub(crate) fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+pub(crate) fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+pub(crate) struct Input<'a> {
+    pub(crate) data: &'a [u8],
+}
+
+impl Input<'_> {
+    pub(crate) fn byte(&mut self) -> io::Result<u8> {
+        let (byte, rest) = self.data.split_first().ok_or_else(invalid)?;
+        self.data = rest;
+        Ok(*byte)
+    }
+
+    pub(crate) fn varint(&mut self) -> io::Result<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte < 0x80 {
+                return Ok(value);
+            }
+        }
+        Err(invalid())
+    }
+
+    pub(crate) fn string(&mut self) -> io::Result<String> {
+        let len = self.varint()? as usize;
+        if len > self.data.len() {
+            return Err(invalid());
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        String::from_utf8(bytes.to_vec()).map_err(|_| invalid())
+    }
+
+    pub(crate) fn uuid(&mut self) -> io::Result<Uuid> {
+        if self.data.len() < 16 {
+            return Err(invalid());
+        }
+        let (bytes, rest) = self.data.split_at(16);
+        self.data = rest;
+        Uuid::from_slice(bytes).map_err(|_| invalid())
+    }
+}
@@ -0,0 +1,235 @@
+// Выгрузка конфигурации 1С в файлы (Конфигуратор: Выгрузить конфигурацию в файлы):
+// по файлу на объект, например Documents/ЗаказКлиента.xml. Из выгрузки берутся
+// вид, имя и синоним объекта, ими дополняются записи Metadata из 1Cv8.lgf.
+use crate::references::{Metadata, References};
+use quick_xml::{escape::resolve_predefined_entity, events::Event as XmlEvent, Reader};
+use std::{collections::HashMap, fs, io, path::Path, str::FromStr, sync::OnceLock};
+use uuid::Uuid;
+
+// Вид объекта в выгрузке и в полном имени объекта
+const KINDS: &[(&str, &str)] = &[
+    ("AccountingRegister", "РегистрБухгалтерии"),
+    ("AccumulationRegister", "РегистрНакопления"),
+    ("BusinessProcess", "БизнесПроцесс"),
+    ("CalculationRegister", "РегистрРасчета"),
+    ("Catalog", "Справочник"),
+    ("ChartOfAccounts", "ПланСчетов"),
+    ("ChartOfCalculationTypes", "ПланВидовРасчета"),
+    ("ChartOfCharacteristicTypes", "ПланВидовХарактеристик"),
+    ("CommonModule", "ОбщийМодуль"),
+    ("Constant", "Константа"),
+    ("DataProcessor", "Обработка"),
+    ("Document", "Документ"),
+    ("DocumentJournal", "ЖурналДокументов"),
+    ("Enum", "Перечисление"),
+    ("ExchangePlan", "ПланОбмена"),
+    ("ExternalDataSource", "ВнешнийИсточникДанных"),
+    ("FilterCriterion", "КритерийОтбора"),
+    ("InformationRegister", "РегистрСведений"),
+    ("Report", "Отчет"),
+    ("Role", "Роль"),
+    ("ScheduledJob", "РегламентноеЗадание"),
+    ("Sequence", "Последовательность"),
+    ("SettingsStorage", "ХранилищеНастроек"),
+    ("Subsystem", "Подсистема"),
+    ("Task", "Задача"),
+];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Object {
+    id: Uuid,
+    kind: String,
+    name: String,
+    synonym: String,
+}
+
+impl Object {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Вид объекта как в выгрузке: Catalog, Document, InformationRegister...
+    pub fn kind(&self) -> &str {
+        self.kind.as_ref()
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    /// Синоним на русском языке, а если его нет - на первом указанном
+    pub fn synonym(&self) -> &str {
+        self.synonym.as_ref()
+    }
+
+    /// Имя как в 1Cv8.lgf, например "Документ.ЗаказКлиента"
+    pub fn full_name(&self) -> String {
+        let kind = KINDS
+            .iter()
+            .find(|(kind, _)| *kind == self.kind)
+            .map_or(self.kind.as_str(), |(_, name)| name);
+        format!("{kind}.{}", self.name)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Configuration {
+    objects: Vec<Object>,
+    by_id: HashMap<Uuid, usize>,
+    by_name: HashMap<String, usize>,
+}
+
+impl Configuration {
+    /// Все файлы *.xml каталога выгрузки, включая вложенные
+    pub fn load<P: AsRef<Path>>(dir: P) -> io::Result<Configuration> {
+        let mut config = Configuration::default();
+        let mut dirs = vec![dir.as_ref().to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else if path.extension().is_some_and(|ext| ext == "xml") {
+                    config.add_object(&fs::read(&path)?)?;
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// Файл одного объекта выгрузки; false, если это не описание объекта
+    pub fn add_object(&mut self, xml: &[u8]) -> io::Result<bool> {
+        let Some(object) = parse_object(xml)? else {
+            return Ok(false);
+        };
+        let num = self.objects.len();
+        self.by_id.entry(object.id).or_insert(num);
+        self.by_name.entry(object.full_name()).or_insert(num);
+        self.objects.push(object);
+        Ok(true)
+    }
+
+    pub fn objects(&self) -> &[Object] {
+        self.objects.as_ref()
+    }
+
+    /// Объект по идентификатору записи Metadata, а если он не найден - по имени
+    pub fn object(&self, metadata: &Metadata) -> Option<&Object> {
+        self.by_id
+            .get(&metadata.id())
+            .or_else(|| self.by_name.get(metadata.name()))
+            .map(|&num| &self.objects[num])
+    }
+
+    /// Заполняет вид и синоним записей Metadata, а пустое имя - полным именем
+    /// объекта. Возвращает число найденных в выгрузке записей
+    pub fn enrich(&self, refs: &mut References) -> usize {
+        refs.lookup = OnceLock::new();
+        let mut count = 0;
+        for metadata in &mut refs.metadata {
+            let Some(object) = self.object(metadata) else {
+                continue;
+            };
+            metadata.kind = object.kind.clone();
+            metadata.synonym = object.synonym.clone();
+            if metadata.name.is_empty() {
+                metadata.name = object.full_name();
+            }
+            count += 1;
+        }
+        count
+    }
+}
+
+// Уровни вложенности: MetaDataObject / объект / Properties / свойство / v8:item / v8:lang
+fn parse_object(xml: &[u8]) -> io::Result<Option<Object>> {
+    let mut reader = Reader::from_reader(xml);
+    let mut buf = Vec::new();
+    let mut path: Vec<Vec<u8>> = Vec::new();
+    let mut object: Option<Object> = None;
+    let (mut lang, mut content) = (String::new(), String::new());
+
+    loop {
+        let event = reader.read_event_into(&mut buf).map_err(io::Error::other)?;
+        match event {
+            XmlEvent::Start(start) => {
+                let name = start.local_name().as_ref().to_vec();
+                match path.len() {
+                    0 if name != b"MetaDataObject" => return Ok(None),
+                    1 => {
+                        let id = start
+                            .attributes()
+                            .flatten()
+                            .find(|attr| attr.key.local_name().as_ref() == b"uuid")
+                            .and_then(|attr| Uuid::from_str(&attr.unescape_value().ok()?).ok())
+                            .unwrap_or_default();
+                        object = Some(Object {
+                            id,
+                            kind: String::from_utf8_lossy(&name).into_owned(),
+                            ..Default::default()
+                        });
+                    }
+                    _ => {}
+                }
+                path.push(name);
+            }
+            XmlEvent::End(_) => {
+                if is(&path, &[b"Properties"]) {
+                    break;
+                }
+                if is(&path, &[b"Properties", b"Synonym", b"item"]) {
+                    let synonym = &mut object.as_mut().expect("object").synonym;
+                    if synonym.is_empty() || lang == "ru" {
+                        *synonym = std::mem::take(&mut content);
+                    }
+                    lang.clear();
+                    content.clear();
+                }
+                path.pop();
+            }
+            XmlEvent::Text(text) => {
+                if let Some(value) = field(&path, &mut object, &mut lang, &mut content) {
+                    value.push_str(&text.xml_content().map_err(io::Error::other)?);
+                }
+            }
+            XmlEvent::GeneralRef(reference) => {
+                if let Some(value) = field(&path, &mut object, &mut lang, &mut content) {
+                    match reference.resolve_char_ref().map_err(io::Error::other)? {
+                        Some(ch) => value.push(ch),
+                        None => {
+                            let name = reference.decode().map_err(io::Error::other)?;
+                            value.push_str(resolve_predefined_entity(&name).unwrap_or_default());
+                        }
+                    }
+                }
+            }
+            XmlEvent::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(object.filter(|object| !object.name.is_empty()))
+}
+
+// Путь внутри объекта, начиная с уровня Properties
+fn is(path: &[Vec<u8>], tail: &[&[u8]]) -> bool {
+    path.len() == tail.len() + 2 && path[2..].iter().zip(tail).all(|(x, y)| x == y)
+}
+
+fn field<'a>(
+    path: &[Vec<u8>],
+    object: &'a mut Option<Object>,
+    lang: &'a mut String,
+    content: &'a mut String,
+) -> Option<&'a mut String> {
+    if is(path, &[b"Properties", b"Name"]) {
+        object.as_mut().map(|object| &mut object.name)
+    } else if is(path, &[b"Properties", b"Synonym", b"item", b"lang"]) {
+        Some(lang)
+    } else if is(path, &[b"Properties", b"Synonym", b"item", b"content"]) {
+        Some(content)
+    } else {
+        None
+    }
+}
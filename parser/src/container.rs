@@ -0,0 +1,314 @@
+use crate::{
+    events::{self, Event, ParseOptions},
+    references::{self, References},
+    source::EventSource,
+};
+use chrono::{DateTime, NaiveDateTime};
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+    ops::ControlFlow,
+    path::Path,
+    sync::{Mutex, PoisonError},
+};
+
+const MAGIC: &[u8; 8] = b"1CLOGZS1";
+const INDEX_ENTRY_LEN: usize = 36;
+const FOOTER_LEN: usize = 8 * 3 + 4 + MAGIC.len();
+
+// Блок событий, сжатый отдельным кадром zstd
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chunk {
+    offset: u64,
+    len: u64,
+    events: u32,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+}
+
+impl Chunk {
+    pub fn events(&self) -> u32 {
+        self.events
+    }
+
+    pub fn start(&self) -> NaiveDateTime {
+        self.start
+    }
+
+    pub fn end(&self) -> NaiveDateTime {
+        self.end
+    }
+
+    pub fn compressed_len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn overlaps(&self, from: NaiveDateTime, to: NaiveDateTime) -> bool {
+        self.start <= to && self.end >= from
+    }
+}
+
+// Файл: MAGIC, блоки событий, справочники, индекс блоков, концевик
+pub struct Writer<W: Write> {
+    out: W,
+    refs: Vec<u8>,
+    position: u64,
+    level: i32,
+    chunk_size: u32,
+    current: events::Writer<Vec<u8>>,
+    count: u32,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    chunks: Vec<Chunk>,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(out: W, refs: &References) -> io::Result<Writer<W>> {
+        let refs = references::Writer::new(Vec::new()).write(refs)?;
+        Ok(Writer {
+            out,
+            refs,
+            position: 0,
+            level: 9,
+            chunk_size: 10_000,
+            current: events::Writer::new(Vec::new()),
+            count: 0,
+            start: NaiveDateTime::MAX,
+            end: NaiveDateTime::MIN,
+            chunks: Vec::new(),
+        })
+    }
+
+    pub fn level(mut self, level: i32) -> Writer<W> {
+        self.level = level;
+        self
+    }
+
+    pub fn chunk_size(mut self, chunk_size: u32) -> Writer<W> {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    pub fn write(&mut self, event: &Event) -> io::Result<()> {
+        self.current.write(&event.to_owned())?;
+        self.count += 1;
+        self.start = self.start.min(event.date());
+        self.end = self.end.max(event.date());
+        if self.count >= self.chunk_size {
+            self.write_chunk()?;
+        }
+        Ok(())
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        if self.position == 0 {
+            self.out.write_all(MAGIC)?;
+            self.position = MAGIC.len() as u64;
+        }
+        Ok(())
+    }
+
+    fn write_all(&mut self, data: &[u8]) -> io::Result<()> {
+        self.start()?;
+        self.out.write_all(data)?;
+        self.position += data.len() as u64;
+        Ok(())
+    }
+
+    fn write_chunk(&mut self) -> io::Result<()> {
+        if self.count == 0 {
+            return Ok(());
+        }
+        let current = std::mem::replace(&mut self.current, events::Writer::new(Vec::new()));
+        let data = zstd::encode_all(current.finish()?.as_slice(), self.level)?;
+        self.start()?;
+        let chunk = Chunk {
+            offset: self.position,
+            len: data.len() as u64,
+            events: self.count,
+            start: self.start,
+            end: self.end,
+        };
+        self.write_all(&data)?;
+        self.chunks.push(chunk);
+        self.count = 0;
+        self.start = NaiveDateTime::MAX;
+        self.end = NaiveDateTime::MIN;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_chunk()?;
+        self.start()?;
+
+        let refs_offset = self.position;
+        let refs = zstd::encode_all(self.refs.as_slice(), self.level)?;
+        self.write_all(&refs)?;
+
+        let index_offset = self.position;
+        let mut index = Vec::with_capacity(self.chunks.len() * INDEX_ENTRY_LEN + FOOTER_LEN);
+        for chunk in &self.chunks {
+            index.extend_from_slice(&chunk.offset.to_le_bytes());
+            index.extend_from_slice(&chunk.len.to_le_bytes());
+            index.extend_from_slice(&chunk.events.to_le_bytes());
+            index.extend_from_slice(&chunk.start.and_utc().timestamp().to_le_bytes());
+            index.extend_from_slice(&chunk.end.and_utc().timestamp().to_le_bytes());
+        }
+        index.extend_from_slice(&refs_offset.to_le_bytes());
+        index.extend_from_slice(&(refs.len() as u64).to_le_bytes());
+        index.extend_from_slice(&index_offset.to_le_bytes());
+        index.extend_from_slice(&(self.chunks.len() as u32).to_le_bytes());
+        index.extend_from_slice(MAGIC);
+        self.write_all(&index)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+pub struct Reader<R = File> {
+    reader: Mutex<R>,
+    refs: References,
+    chunks: Vec<Chunk>,
+}
+
+impl Reader<File> {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Reader<File>> {
+        Reader::new(File::open(path)?)
+    }
+}
+
+impl<R: Read + Seek> Reader<R> {
+    pub fn new(mut reader: R) -> io::Result<Reader<R>> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let mut footer = [0u8; FOOTER_LEN];
+        reader.seek(SeekFrom::End(-(FOOTER_LEN as i64)))?;
+        reader.read_exact(&mut footer)?;
+        if &footer[FOOTER_LEN - MAGIC.len()..] != MAGIC {
+            return Err(invalid("Not an event log container"));
+        }
+        let u64_at = |buf: &[u8], i: usize| u64::from_le_bytes(buf[i..i + 8].try_into().unwrap());
+        let u32_at = |buf: &[u8], i: usize| u32::from_le_bytes(buf[i..i + 4].try_into().unwrap());
+        let refs_offset = u64_at(&footer, 0);
+        let refs_len = u64_at(&footer, 8);
+        let index_offset = u64_at(&footer, 16);
+        let count = u32_at(&footer, 24) as usize;
+
+        let mut index = vec![0u8; count * INDEX_ENTRY_LEN];
+        reader.seek(SeekFrom::Start(index_offset))?;
+        reader.read_exact(&mut index)?;
+        let date = |buf: &[u8], i: usize| {
+            DateTime::from_timestamp(u64_at(buf, i) as i64, 0)
+                .map(|date| date.naive_utc())
+                .ok_or_else(|| invalid("Invalid chunk date"))
+        };
+        let mut chunks = Vec::with_capacity(count);
+        for entry in index.chunks_exact(INDEX_ENTRY_LEN) {
+            chunks.push(Chunk {
+                offset: u64_at(entry, 0),
+                len: u64_at(entry, 8),
+                events: u32_at(entry, 16),
+                start: date(entry, 20)?,
+                end: date(entry, 28)?,
+            });
+        }
+
+        let mut refs = References::default();
+        refs.parse_reader(zstd::Decoder::new(
+            read_at(&mut reader, refs_offset, refs_len)?.as_slice(),
+        )?)?;
+
+        Ok(Reader {
+            reader: Mutex::new(reader),
+            refs,
+            chunks,
+        })
+    }
+
+    pub fn references(&self) -> &References {
+        &self.refs
+    }
+
+    pub fn chunks(&self) -> &[Chunk] {
+        &self.chunks
+    }
+
+    fn read_chunk<F>(
+        &self,
+        chunk: &Chunk,
+        options: &mut ParseOptions,
+        action: &mut F,
+    ) -> io::Result<()>
+    where
+        F: FnMut(Event) -> ControlFlow<()>,
+    {
+        let data = {
+            let mut reader = self.reader.lock().unwrap_or_else(PoisonError::into_inner);
+            read_at(&mut *reader, chunk.offset, chunk.len)?
+        };
+        let data = zstd::decode_all(data.as_slice())?;
+        events::read_events(data.as_slice(), 0, options, action)?;
+        Ok(())
+    }
+
+    pub fn events<F>(&self, action: &mut F) -> io::Result<()>
+    where
+        F: FnMut(Event),
+    {
+        for chunk in &self.chunks {
+            self.read_chunk(chunk, &mut ParseOptions::default(), &mut |event| {
+                action(event);
+                ControlFlow::Continue(())
+            })?;
+        }
+        Ok(())
+    }
+
+    // Распаковываются только блоки, пересекающиеся с интервалом
+    pub fn events_between<F>(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        action: &mut F,
+    ) -> io::Result<()>
+    where
+        F: FnMut(Event),
+    {
+        for chunk in self.chunks.iter().filter(|chunk| chunk.overlaps(from, to)) {
+            let mut options = ParseOptions::new().range(from, to);
+            self.read_chunk(chunk, &mut options, &mut |event| {
+                action(event);
+                ControlFlow::Continue(())
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> EventSource for Reader<R> {
+    fn references(&self) -> &References {
+        &self.refs
+    }
+
+    fn parse_until(&self, action: &mut dyn FnMut(Event) -> ControlFlow<()>) -> io::Result<()> {
+        let mut stopped = false;
+        for chunk in &self.chunks {
+            self.read_chunk(chunk, &mut ParseOptions::default(), &mut |event| {
+                let result = action(event);
+                stopped = result.is_break();
+                result
+            })?;
+            if stopped {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+    let mut data = vec![0u8; len as usize];
+    reader.seek(SeekFrom::Start(offset))?;
+    reader.read_exact(&mut data)?;
+    Ok(data)
+}
@@ -0,0 +1,228 @@
+use alloc::{borrow::Cow, format, vec::Vec};
+use core::str::FromStr;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value<'a> {
+    String(Cow<'a, str>),
+    Number(&'a str),
+    Uuid(Uuid),
+    Ref(u32, Uuid),
+    Raw(&'a str),
+    List(Vec<Value<'a>>),
+}
+
+impl<'a> Value<'a> {
+    pub fn parse(s: &'a str) -> Option<Value<'a>> {
+        let mut parser = ValueParser { s, pos: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos != s.len() {
+            return None;
+        }
+        Some(value)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Number(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Value<'a>]> {
+        match self {
+            Value::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Value<'a>> {
+        self.as_list()?.get(index)
+    }
+}
+
+struct ValueParser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> ValueParser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.s.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|ch| ch.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn value(&mut self) -> Option<Value<'a>> {
+        self.skip_whitespace();
+        match self.peek()? {
+            b'{' => self.list(),
+            b'"' => self.string(),
+            _ => self.token(),
+        }
+    }
+
+    fn list(&mut self) -> Option<Value<'a>> {
+        self.pos += 1;
+        let mut items = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek()? == b'}' {
+            self.pos += 1;
+            return Some(Value::List(items));
+        }
+
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            let ch = self.peek()?;
+            self.pos += 1;
+            match ch {
+                b',' => continue,
+                b'}' => break,
+                _ => return None,
+            }
+        }
+        Some(Value::List(items))
+    }
+
+    fn string(&mut self) -> Option<Value<'a>> {
+        let bytes = self.s.as_bytes();
+        let start = self.pos + 1;
+        let mut need_replace_quotes = false;
+
+        let mut i = start;
+        loop {
+            i += memchr::memchr(b'"', &bytes[i..])?;
+            if bytes.get(i + 1) == Some(&b'"') {
+                need_replace_quotes = true;
+                i += 2;
+            } else {
+                break;
+            }
+        }
+        self.pos = i + 1;
+
+        let s = &self.s[start..i];
+        Some(Value::String(match need_replace_quotes {
+            true => Cow::Owned(s.replace(r#""""#, r#"""#)),
+            _ => Cow::Borrowed(s),
+        }))
+    }
+
+    fn token(&mut self) -> Option<Value<'a>> {
+        let bytes = self.s.as_bytes();
+        let start = self.pos;
+        let len = memchr::memchr2(b',', b'}', &bytes[start..]).unwrap_or(bytes.len() - start);
+        self.pos = start + len;
+
+        let token = self.s[start..self.pos].trim();
+        if token.is_empty() {
+            return None;
+        }
+        Some(classify(token))
+    }
+}
+
+fn classify(token: &str) -> Value<'_> {
+    let digits = token.strip_prefix('-').unwrap_or(token);
+    if !digits.is_empty()
+        && digits.bytes().all(|ch| ch.is_ascii_digit() || ch == b'.')
+        && digits.bytes().filter(|&ch| ch == b'.').count() <= 1
+    {
+        return Value::Number(token);
+    }
+
+    if let Some((type_id, hex)) = token.split_once(':') {
+        if let (Ok(type_id), Some(id)) = (type_id.parse(), parse_ref_uuid(hex)) {
+            return Value::Ref(type_id, id);
+        }
+    }
+
+    match Uuid::from_str(token) {
+        Ok(id) => Value::Uuid(id),
+        Err(_) => Value::Raw(token),
+    }
+}
+
+// Ссылка хранится как 32 hex-символа с переставленными группами UUID
+fn parse_ref_uuid(hex: &str) -> Option<Uuid> {
+    if hex.len() != 32 || !hex.bytes().all(|ch| ch.is_ascii_hexdigit()) {
+        return None;
+    }
+    let s = format!(
+        "{}-{}-{}-{}-{}",
+        &hex[24..32],
+        &hex[20..24],
+        &hex[16..20],
+        &hex[0..4],
+        &hex[4..16]
+    );
+    Uuid::from_str(&s).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_parse_list() {
+        let value = Value::parse(
+            "{\"P\",\r\n{6,\r\n{\"S\",\"\"},\r\n{\"S\",\"COMPUTER1\\user1\"}\r\n}\r\n}",
+        )
+        .unwrap();
+        assert_eq!(value.get(0).unwrap().as_str(), Some("P"));
+
+        let list = value.get(1).unwrap();
+        assert_eq!(list.get(0).unwrap().as_i64(), Some(6));
+        assert_eq!(
+            list.get(2).unwrap().get(1).unwrap().as_str(),
+            Some("COMPUTER1\\user1")
+        );
+    }
+
+    #[test]
+    fn test_parse_string() {
+        let value = Value::parse(r#"{"S","a""b",""}"#).unwrap();
+        assert_eq!(value.get(1).unwrap().as_str(), Some(r#"a"b"#));
+        assert_eq!(value.get(2).unwrap().as_str(), Some(""));
+    }
+
+    #[test]
+    fn test_parse_ref() {
+        let value = Value::parse(r#"{"R",174:8781b06ebf31a92f11e876186beff5a9}"#).unwrap();
+        assert_eq!(
+            value.get(1),
+            Some(&Value::Ref(
+                174,
+                Uuid::from_str("6beff5a9-7618-11e8-8781-b06ebf31a92f").unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        assert_eq!(Value::parse("{}"), Some(Value::List(vec![])));
+        assert_eq!(Value::parse("{1,"), None);
+    }
+}
@@ -0,0 +1,367 @@
+use crate::{
+    archive,
+    events::{self, Event, ParseOptions},
+    index::{Index, IndexBuilder, Key},
+    references::References,
+    source::EventSource,
+};
+use chrono::NaiveDateTime;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "zip")]
+use std::sync::{Mutex, PoisonError};
+use std::{
+    fs::read_dir,
+    io,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+pub struct LogFile {
+    path: PathBuf,
+    start: Option<NaiveDateTime>,
+    end: Option<NaiveDateTime>,
+}
+
+impl LogFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn start(&self) -> Option<NaiveDateTime> {
+        self.start
+    }
+
+    pub fn end(&self) -> Option<NaiveDateTime> {
+        self.end
+    }
+
+    pub fn overlaps(&self, from: NaiveDateTime, to: NaiveDateTime) -> bool {
+        self.start.is_none_or(|start| start <= to) && self.end.is_none_or(|end| end > from)
+    }
+}
+
+pub struct LogDirectory {
+    path: PathBuf,
+    refs: Arc<References>,
+    files: Vec<LogFile>,
+    #[cfg(feature = "zip")]
+    zip: Option<Mutex<archive::ZipLog>>,
+}
+
+impl LogDirectory {
+    // Каталог журнала, в том числе со сжатыми файлами, или zip-архив каталога
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<LogDirectory> {
+        let path = dir.as_ref().to_path_buf();
+
+        #[cfg(feature = "zip")]
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "zip") {
+            return LogDirectory::open_zip(path);
+        }
+
+        let lgf = std::iter::once("1Cv8.lgf".to_string())
+            .chain(
+                archive::EXTENSIONS
+                    .iter()
+                    .map(|ext| format!("1Cv8.lgf.{ext}")),
+            )
+            .map(|name| path.join(name))
+            .find(|lgf| lgf.exists())
+            .unwrap_or_else(|| path.join("1Cv8.lgf"));
+        let mut refs = References::default();
+        refs.parse_reader(archive::open(lgf)?)?;
+
+        let mut files = Vec::new();
+        for entry in read_dir(&path)? {
+            files.extend(log_file(entry?.path()));
+        }
+
+        Ok(LogDirectory {
+            path,
+            refs: Arc::new(refs),
+            files: sort_files(files),
+            #[cfg(feature = "zip")]
+            zip: None,
+        })
+    }
+
+    // Файлы журнала берутся из того же каталога архива, где лежит 1Cv8.lgf
+    #[cfg(feature = "zip")]
+    fn open_zip(path: PathBuf) -> io::Result<LogDirectory> {
+        let mut zip = archive::ZipLog::open(&path)?;
+        let lgf = zip
+            .names()
+            .filter(|name| archive::uncompressed_name(Path::new(name)) == Some("1Cv8.lgf"))
+            .min_by_key(|name| name.len())
+            .map(str::to_string)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "1Cv8.lgf not found"))?;
+        let mut refs = References::default();
+        zip.read(&lgf, |reader| refs.parse_reader(reader))?;
+
+        let parent = Path::new(&lgf).parent().map(Path::to_path_buf);
+        let files = zip
+            .names()
+            .map(PathBuf::from)
+            .filter(|name| name.parent().map(Path::to_path_buf) == parent)
+            .filter_map(log_file)
+            .collect();
+
+        Ok(LogDirectory {
+            path,
+            refs: Arc::new(refs),
+            files: sort_files(files),
+            zip: Some(Mutex::new(zip)),
+        })
+    }
+
+    fn read_file<F>(
+        &self,
+        file: &LogFile,
+        options: &mut ParseOptions,
+        action: &mut F,
+    ) -> io::Result<()>
+    where
+        F: FnMut(Event) -> ControlFlow<()>,
+    {
+        #[cfg(feature = "zip")]
+        if let Some(zip) = &self.zip {
+            let name = file.path.to_string_lossy();
+            let mut zip = zip.lock().unwrap_or_else(PoisonError::into_inner);
+            return zip.read(&name, |reader| {
+                events::read_events(reader, 0, options, action).map(drop)
+            });
+        }
+        match archive::is_compressed(&file.path) {
+            true => events::read_events(archive::open(&file.path)?, 0, options, action)?,
+            false => events::parse_with_options(&file.path, options, action)?,
+        };
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn references(&self) -> &References {
+        &self.refs
+    }
+
+    pub fn snapshot(&self) -> Arc<References> {
+        self.refs.clone()
+    }
+
+    pub fn files(&self) -> &[LogFile] {
+        self.files.as_ref()
+    }
+
+    pub fn events<F>(&self, action: &mut F) -> io::Result<()>
+    where
+        F: FnMut(Event),
+    {
+        for file in &self.files {
+            self.read_file(file, &mut ParseOptions::default(), &mut |event| {
+                action(event);
+                ControlFlow::Continue(())
+            })?;
+        }
+        Ok(())
+    }
+
+    pub fn events_between<F>(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        action: &mut F,
+    ) -> io::Result<()>
+    where
+        F: FnMut(Event),
+    {
+        for file in self.files.iter().filter(|file| file.overlaps(from, to)) {
+            let mut options = ParseOptions::new().range(from, to);
+            self.read_file(file, &mut options, &mut |event| {
+                action(event);
+                ControlFlow::Continue(())
+            })?;
+        }
+        Ok(())
+    }
+
+    // Разбор до ControlFlow::Break; при заданном периоде читаются только
+    // пересекающиеся с ним файлы
+    pub(crate) fn events_until<F>(
+        &self,
+        range: Option<(NaiveDateTime, NaiveDateTime)>,
+        action: &mut F,
+    ) -> io::Result<()>
+    where
+        F: FnMut(Event) -> ControlFlow<()>,
+    {
+        for file in &self.files {
+            let mut options = match range {
+                Some((from, to)) if !file.overlaps(from, to) => continue,
+                Some((from, to)) => ParseOptions::new().range(from, to),
+                None => ParseOptions::default(),
+            };
+            let mut flow = ControlFlow::Continue(());
+            self.read_file(file, &mut options, &mut |event| {
+                flow = action(event);
+                flow
+            })?;
+            if flow.is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Файлы разбираются параллельно в пуле потоков rayon, `action` вызывается
+    /// из нескольких потоков, порядок событий между файлами не сохраняется.
+    /// Файлы из zip-архива читаются по очереди.
+    #[cfg(feature = "parallel")]
+    pub fn par_events<F>(&self, action: &F) -> io::Result<()>
+    where
+        F: Fn(Event) + Sync,
+    {
+        self.files.par_iter().try_for_each(|file| {
+            self.read_file(file, &mut ParseOptions::default(), &mut |event| {
+                action(event);
+                ControlFlow::Continue(())
+            })
+        })
+    }
+
+    /// Свертка событий каждого файла в отдельный результат, начиная с `init()`.
+    /// Результаты возвращаются в порядке файлов для последующего объединения.
+    #[cfg(feature = "parallel")]
+    pub fn par_fold<T, I, F>(&self, init: I, fold: F) -> io::Result<Vec<T>>
+    where
+        T: Send,
+        I: Fn() -> T + Sync,
+        F: Fn(&mut T, Event) + Sync,
+    {
+        self.files
+            .par_iter()
+            .map(|file| {
+                let mut result = init();
+                self.read_file(file, &mut ParseOptions::default(), &mut |event| {
+                    fold(&mut result, event);
+                    ControlFlow::Continue(())
+                })?;
+                Ok(result)
+            })
+            .collect()
+    }
+
+    // Файлы, в которых по фильтрам Блума заведомо нет нужных значений, не читаются
+    pub fn events_matching<F>(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        keys: &[Key],
+        action: &mut F,
+    ) -> io::Result<()>
+    where
+        F: FnMut(Event),
+    {
+        for file in self.files.iter().filter(|file| file.overlaps(from, to)) {
+            if self
+                .index(file)
+                .is_some_and(|index| !keys.iter().all(|key| index.may_contain(*key)))
+            {
+                continue;
+            }
+            let mut options = ParseOptions::new().range(from, to);
+            self.read_file(file, &mut options, &mut |event| {
+                if keys.iter().all(|key| key.matches(&event)) {
+                    action(event);
+                }
+                ControlFlow::Continue(())
+            })?;
+        }
+        Ok(())
+    }
+
+    // Индексы строятся только для несжатых файлов в каталоге
+    pub fn create_indexes(&self, builder: &IndexBuilder) -> io::Result<()> {
+        for file in &self.files {
+            if self.is_plain(file) {
+                builder.create(&file.path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn is_plain(&self, file: &LogFile) -> bool {
+        #[cfg(feature = "zip")]
+        if self.zip.is_some() {
+            return false;
+        }
+        !archive::is_compressed(&file.path)
+    }
+
+    fn index(&self, file: &LogFile) -> Option<Index> {
+        match self.is_plain(file) {
+            true => Index::for_file(&file.path).ok().flatten(),
+            false => None,
+        }
+    }
+}
+
+impl EventSource for LogDirectory {
+    fn references(&self) -> &References {
+        &self.refs
+    }
+
+    fn parse_until(&self, action: &mut dyn FnMut(Event) -> ControlFlow<()>) -> io::Result<()> {
+        self.events_until(None, &mut |event| action(event))
+    }
+}
+
+fn log_file(path: PathBuf) -> Option<LogFile> {
+    let stem = archive::uncompressed_name(&path)?.strip_suffix(".lgp")?;
+    let start = NaiveDateTime::parse_from_str(stem, "%Y%m%d%H%M%S").ok();
+    Some(LogFile {
+        path,
+        start,
+        end: None,
+    })
+}
+
+fn sort_files(mut files: Vec<LogFile>) -> Vec<LogFile> {
+    files.sort_by(|a, b| (a.start, &a.path).cmp(&(b.start, &b.path)));
+
+    // Файл содержит события до начала следующего файла
+    for i in 1..files.len() {
+        if files[i - 1].start.is_some() {
+            files[i - 1].end = files[i].start;
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn date(d: u32, h: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2022, 12, d)
+            .unwrap()
+            .and_hms_opt(h, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let file = LogFile {
+            path: PathBuf::from("20221212000000.lgp"),
+            start: Some(date(12, 0)),
+            end: Some(date(13, 0)),
+        };
+        assert!(file.overlaps(date(11, 0), date(12, 0)));
+        assert!(file.overlaps(date(12, 10), date(12, 11)));
+        assert!(!file.overlaps(date(13, 0), date(14, 0)));
+        assert!(!file.overlaps(date(10, 0), date(11, 0)));
+    }
+}
@@ -0,0 +1,27 @@
+use std::borrow::Cow;
+
+pub use encoding_rs::{Encoding, UTF_8, WINDOWS_1251};
+
+// Без явно заданной кодировки: UTF-8, а если не получилось - windows-1251
+pub(crate) fn decode<'a>(bytes: &'a [u8], encoding: Option<&'static Encoding>) -> Cow<'a, str> {
+    match encoding {
+        Some(encoding) => encoding.decode_without_bom_handling(bytes).0,
+        None => match std::str::from_utf8(bytes) {
+            Ok(s) => Cow::Borrowed(s),
+            Err(_) => WINDOWS_1251.decode_without_bom_handling(bytes).0,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let cp1251 = b"\xcf\xf0\xe8\xe2\xe5\xf2";
+        assert_eq!(decode(cp1251, None), "Привет");
+        assert_eq!(decode("Привет".as_bytes(), None), "Привет");
+        assert_eq!(decode(cp1251, Some(WINDOWS_1251)), "Привет");
+    }
+}
@@ -0,0 +1,111 @@
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    Date,
+    TransactionStatus,
+    LogLevel,
+    Number,
+    Uuid,
+    String,
+    Object,
+    ReferenceType,
+    DataSeparation,
+    Header,
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Field::Date => "date",
+            Field::TransactionStatus => "transaction status",
+            Field::LogLevel => "log level",
+            Field::Number => "number",
+            Field::Uuid => "uuid",
+            Field::String => "string",
+            Field::Object => "object",
+            Field::ReferenceType => "reference type",
+            Field::DataSeparation => "data separation",
+            Field::Header => "header",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    Incomplete,
+    InvalidFormat {
+        offset: u64,
+        field: Field,
+        expected: &'static str,
+    },
+}
+
+impl ParseError {
+    pub(crate) fn invalid(position: usize, field: Field, expected: &'static str) -> ParseError {
+        ParseError::InvalidFormat {
+            offset: position as u64,
+            field,
+            expected,
+        }
+    }
+
+    // Смещение внутри буфера -> смещение в файле
+    pub(crate) fn shift(self, base: u64) -> ParseError {
+        match self {
+            ParseError::InvalidFormat {
+                offset,
+                field,
+                expected,
+            } => ParseError::InvalidFormat {
+                offset: base + offset,
+                field,
+                expected,
+            },
+            error => error,
+        }
+    }
+
+    pub fn is_incomplete(&self) -> bool {
+        matches!(self, ParseError::Incomplete)
+    }
+
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            ParseError::InvalidFormat { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    pub fn field(&self) -> Option<Field> {
+        match self {
+            ParseError::InvalidFormat { field, .. } => Some(*field),
+            _ => None,
+        }
+    }
+
+    pub fn expected(&self) -> Option<&'static str> {
+        match self {
+            ParseError::InvalidFormat { expected, .. } => Some(expected),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Incomplete => write!(f, "unexpected end of data"),
+            ParseError::InvalidFormat {
+                offset,
+                field,
+                expected,
+            } => write!(f, "invalid {field} at offset {offset}: expected {expected}"),
+        }
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+pub type ParseResult<T> = Result<T, ParseError>;
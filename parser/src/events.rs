@@ -1,48 +1,266 @@
 use crate::{
-    parser::{LogStr, Parser},
-    references::{Metadata, References, User},
+    data::Value,
+    error::ParseError,
+    filter::CompiledFilter,
+    follow::Follower,
+    index::Index,
+    parser::{LazyStr, LogStr, Parser},
+    reader::{Buffer, ChunkReader, BUFFER_SIZE},
+    record::{
+        parse_raw_record, parse_record, parse_record_date, parse_record_in_range, skip_invalid,
+        RecordHooks,
+    },
+    references::{
+        write_header, write_str, DataSeparation, Metadata, References, ResolveRefs, User,
+        NO_METADATA, NO_USER,
+    },
+    stats::LevelCounts,
 };
-use chrono::{NaiveDate, NaiveDateTime};
-use std::{borrow::Cow, io, path::Path};
-use std::{fs::File, io::Read};
-
-pub enum TransactionStatus {
-    Unfinished,
-    NotApplicable,
-    Committed,
-    RolledBack,
-}
-
-pub enum EventLogLevel {
-    Error,
-    Information,
-    Note,
-    Warning,
-}
-
-pub struct Event<'a> {
-    date: NaiveDateTime,
-    transaction_status: TransactionStatus,
-    transaction_data: &'a str,
-    user_id: usize,
-    computer_id: usize,
-    application_id: usize,
-    connection: usize,
-    event_id: usize,
-    log_level: EventLogLevel,
-    comment: LogStr<'a>,
-    metadata_id: usize,
-    data: &'a str,
-    data_presentation: LogStr<'a>,
-    worker_server_id: usize,
-    port_id: usize,
-    sync_port_id: usize,
-    session: usize,
-    unknown1: usize,
-    unknown2: &'a str,
-}
+use chrono::NaiveDateTime;
+use std::io::{Read, Write};
+use std::{borrow::Cow, io, ops::ControlFlow, path::Path, sync::Arc, thread, time};
+use uuid::Uuid;
+
+#[cfg(feature = "parallel")]
+pub use crate::parallel::parse_parallel;
+pub use crate::record::{Event, EventLogLevel, Projection, TransactionInfo, TransactionStatus};
+#[cfg(feature = "tokio")]
+pub use crate::stream::stream;
 
+// Имена и значения по справочникам 1Cv8.lgf
 impl<'a> Event<'a> {
+    pub fn user<'refs, R: ResolveRefs + ?Sized>(&self, refs: &'refs R) -> &'refs User {
+        refs.user(self.user_id).unwrap_or(&NO_USER)
+    }
+
+    pub fn computer<'refs, R: ResolveRefs + ?Sized>(&self, refs: &'refs R) -> &'refs str {
+        refs.computer(self.computer_id).unwrap_or_default()
+    }
+
+    pub fn application<'refs, R: ResolveRefs + ?Sized>(&self, refs: &'refs R) -> &'refs str {
+        refs.application(self.application_id).unwrap_or_default()
+    }
+
+    pub fn event<'refs, R: ResolveRefs + ?Sized>(&self, refs: &'refs R) -> &'refs str {
+        refs.event(self.event_id).unwrap_or_default()
+    }
+
+    pub fn metadata<'refs, R: ResolveRefs + ?Sized>(&self, refs: &'refs R) -> &'refs Metadata {
+        refs.metadata(self.metadata_id).unwrap_or(&NO_METADATA)
+    }
+
+    pub fn worker_server<'refs, R: ResolveRefs + ?Sized>(&self, refs: &'refs R) -> &'refs str {
+        refs.worker_server(self.worker_server_id)
+            .unwrap_or_default()
+    }
+
+    pub fn port<R: ResolveRefs + ?Sized>(&self, refs: &R) -> u32 {
+        refs.port(self.port_id).unwrap_or_default()
+    }
+
+    pub fn sync_port<R: ResolveRefs + ?Sized>(&self, refs: &R) -> u32 {
+        refs.sync_port(self.sync_port_id).unwrap_or_default()
+    }
+
+    /// Разделители данных сеанса и их значения; номера вне справочников пропускаются
+    pub fn data_separation<'refs>(
+        &self,
+        refs: &'refs References,
+    ) -> Vec<(&'refs DataSeparation, &'refs str)> {
+        data_separation(self.data_separation_ids(), refs)
+    }
+
+    pub fn resolve<'b>(&'b self, refs: &'b References) -> EventResolved<'b> {
+        EventResolved { event: self, refs }
+    }
+
+    /// Как `resolve`, но со своей реализацией справочников
+    pub fn resolve_with<'b, R: ResolveRefs + ?Sized>(
+        &'b self,
+        refs: &'b R,
+    ) -> EventResolved<'b, R> {
+        EventResolved { event: self, refs }
+    }
+
+    pub fn to_owned(&self) -> EventOwned {
+        EventOwned {
+            date: self.date,
+            transaction_status: self.transaction_status,
+            transaction_data: self.transaction_data.into(),
+            user_id: self.user_id,
+            computer_id: self.computer_id,
+            application_id: self.application_id,
+            connection: self.connection,
+            event_id: self.event_id,
+            log_level: self.log_level,
+            comment: self.comment().into(),
+            metadata_id: self.metadata_id,
+            data: self.data().into(),
+            data_presentation: self.data_presentation().into(),
+            worker_server_id: self.worker_server_id,
+            port_id: self.port_id,
+            sync_port_id: self.sync_port_id,
+            session: self.session,
+            unknown1: self.unknown1,
+            unknown2: self.unknown2.into(),
+            offset: self.offset,
+            end_offset: self.end_offset,
+        }
+    }
+}
+
+fn data_separation(
+    ids: impl Iterator<Item = (usize, usize)>,
+    refs: &References,
+) -> Vec<(&DataSeparation, &str)> {
+    ids.filter_map(|(separation, value)| {
+        let separation = refs.data_separation().get(separation)?;
+        Some((separation, separation.values().get(value)?.as_str()))
+    })
+    .collect()
+}
+
+pub struct EventResolved<'a, R: ResolveRefs + ?Sized = References> {
+    event: &'a Event<'a>,
+    refs: &'a R,
+}
+
+impl<'a, R: ResolveRefs + ?Sized> EventResolved<'a, R> {
+    pub fn event(&self) -> &Event<'a> {
+        self.event
+    }
+
+    pub fn date(&self) -> NaiveDateTime {
+        self.event.date
+    }
+
+    pub fn transaction_status(&self) -> &TransactionStatus {
+        &self.event.transaction_status
+    }
+
+    pub fn transaction_data(&self) -> &'a str {
+        self.event.transaction_data
+    }
+
+    pub fn user(&self) -> &'a User {
+        self.event.user(self.refs)
+    }
+
+    pub fn user_name(&self) -> &'a str {
+        self.user().name()
+    }
+
+    pub fn computer(&self) -> &'a str {
+        self.event.computer(self.refs)
+    }
+
+    pub fn application(&self) -> &'a str {
+        self.event.application(self.refs)
+    }
+
+    pub fn connection(&self) -> usize {
+        self.event.connection
+    }
+
+    pub fn event_name(&self) -> &'a str {
+        self.event.event(self.refs)
+    }
+
+    pub fn log_level(&self) -> &EventLogLevel {
+        &self.event.log_level
+    }
+
+    pub fn comment(&self) -> Cow<'a, str> {
+        self.event.comment()
+    }
+
+    pub fn metadata(&self) -> &'a Metadata {
+        self.event.metadata(self.refs)
+    }
+
+    pub fn metadata_name(&self) -> &'a str {
+        self.metadata().name()
+    }
+
+    pub fn data(&self) -> &'a str {
+        self.event.data()
+    }
+
+    pub fn data_presentation(&self) -> Cow<'a, str> {
+        self.event.data_presentation()
+    }
+
+    pub fn worker_server(&self) -> &'a str {
+        self.event.worker_server(self.refs)
+    }
+
+    pub fn port(&self) -> u32 {
+        self.event.port(self.refs)
+    }
+
+    pub fn sync_port(&self) -> u32 {
+        self.event.sync_port(self.refs)
+    }
+
+    pub fn session(&self) -> usize {
+        self.event.session
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EventOwned {
+    pub(crate) date: NaiveDateTime,
+    pub(crate) transaction_status: TransactionStatus,
+    pub(crate) transaction_data: Arc<str>,
+    pub(crate) user_id: usize,
+    pub(crate) computer_id: usize,
+    pub(crate) application_id: usize,
+    pub(crate) connection: usize,
+    pub(crate) event_id: usize,
+    pub(crate) log_level: EventLogLevel,
+    pub(crate) comment: Arc<str>,
+    pub(crate) metadata_id: usize,
+    pub(crate) data: Arc<str>,
+    pub(crate) data_presentation: Arc<str>,
+    pub(crate) worker_server_id: usize,
+    pub(crate) port_id: usize,
+    pub(crate) sync_port_id: usize,
+    pub(crate) session: usize,
+    pub(crate) unknown1: usize,
+    pub(crate) unknown2: Arc<str>,
+    pub(crate) offset: u64,
+    pub(crate) end_offset: u64,
+}
+
+impl EventOwned {
+    pub(crate) fn as_event(&self) -> Event<'_> {
+        Event {
+            date: self.date,
+            transaction_status: self.transaction_status,
+            transaction_data: &self.transaction_data,
+            user_id: self.user_id,
+            computer_id: self.computer_id,
+            application_id: self.application_id,
+            connection: self.connection,
+            event_id: self.event_id,
+            log_level: self.log_level,
+            comment: LogStr::new(self.comment.as_bytes(), false),
+            metadata_id: self.metadata_id,
+            data: LazyStr::decoded(&self.data),
+            data_presentation: LogStr::new(self.data_presentation.as_bytes(), false),
+            worker_server_id: self.worker_server_id,
+            port_id: self.port_id,
+            sync_port_id: self.sync_port_id,
+            session: self.session,
+            unknown1: self.unknown1,
+            unknown2: &self.unknown2,
+            offset: self.offset,
+            end_offset: self.end_offset,
+            raw: &[],
+        }
+    }
+
     pub fn date(&self) -> NaiveDateTime {
         self.date
     }
@@ -52,31 +270,35 @@ impl<'a> Event<'a> {
     }
 
     pub fn transaction_data(&self) -> &str {
-        self.transaction_data
+        &self.transaction_data
+    }
+
+    pub fn transaction(&self) -> Option<TransactionInfo> {
+        TransactionInfo::parse(&self.transaction_data)
     }
 
     pub fn user_id(&self) -> usize {
         self.user_id
     }
 
-    pub fn user<'refs>(&self, refs: &'refs References) -> &'refs User {
-        &refs.users()[self.user_id]
+    pub fn user<'refs, R: ResolveRefs + ?Sized>(&self, refs: &'refs R) -> &'refs User {
+        refs.user(self.user_id).unwrap_or(&NO_USER)
     }
 
     pub fn computer_id(&self) -> usize {
         self.computer_id
     }
 
-    pub fn computer<'refs>(&self, refs: &'refs References) -> &'refs str {
-        &refs.computers()[self.computer_id]
+    pub fn computer<'refs, R: ResolveRefs + ?Sized>(&self, refs: &'refs R) -> &'refs str {
+        refs.computer(self.computer_id).unwrap_or_default()
     }
 
     pub fn application_id(&self) -> usize {
         self.application_id
     }
 
-    pub fn application<'refs>(&self, refs: &'refs References) -> &'refs str {
-        &refs.applications()[self.application_id]
+    pub fn application<'refs, R: ResolveRefs + ?Sized>(&self, refs: &'refs R) -> &'refs str {
+        refs.application(self.application_id).unwrap_or_default()
     }
 
     pub fn connection(&self) -> usize {
@@ -87,56 +309,69 @@ impl<'a> Event<'a> {
         self.event_id
     }
 
-    pub fn event<'refs>(&self, refs: &'refs References) -> &'refs str {
-        &refs.events()[self.event_id]
+    pub fn event<'refs, R: ResolveRefs + ?Sized>(&self, refs: &'refs R) -> &'refs str {
+        refs.event(self.event_id).unwrap_or_default()
     }
 
     pub fn log_level(&self) -> &EventLogLevel {
         &self.log_level
     }
 
-    pub fn comment(&self) -> Cow<'a, str> {
-        self.comment.str()
+    pub fn comment(&self) -> &str {
+        &self.comment
     }
 
     pub fn metadata_id(&self) -> usize {
         self.metadata_id
     }
 
-    pub fn metadata<'refs>(&self, refs: &'refs References) -> &'refs Metadata {
-        &refs.metadata()[self.metadata_id]
+    pub fn metadata<'refs, R: ResolveRefs + ?Sized>(&self, refs: &'refs R) -> &'refs Metadata {
+        refs.metadata(self.metadata_id).unwrap_or(&NO_METADATA)
     }
 
     pub fn data(&self) -> &str {
-        self.data
+        &self.data
     }
 
-    pub fn data_presentation(&self) -> Cow<'a, str> {
-        self.data_presentation.str()
+    pub fn data_value(&self) -> Option<Value<'_>> {
+        Value::parse(&self.data)
+    }
+
+    pub fn data_presentation(&self) -> &str {
+        &self.data_presentation
     }
 
     pub fn worker_server_id(&self) -> usize {
         self.worker_server_id
     }
 
-    pub fn worker_server<'refs>(&self, refs: &'refs References) -> &'refs str {
-        &refs.worker_servers()[self.worker_server_id]
+    pub fn worker_server<'refs, R: ResolveRefs + ?Sized>(&self, refs: &'refs R) -> &'refs str {
+        refs.worker_server(self.worker_server_id)
+            .unwrap_or_default()
     }
 
     pub fn port_id(&self) -> usize {
         self.port_id
     }
 
-    pub fn port(&self, refs: &References) -> u32 {
-        refs.ports()[self.port_id]
+    pub fn port<R: ResolveRefs + ?Sized>(&self, refs: &R) -> u32 {
+        refs.port(self.port_id).unwrap_or_default()
     }
 
     pub fn sync_port_id(&self) -> usize {
         self.sync_port_id
     }
 
-    pub fn sync_port(&self, refs: &References) -> u32 {
-        refs.sync_ports()[self.sync_port_id]
+    pub fn sync_port<R: ResolveRefs + ?Sized>(&self, refs: &R) -> u32 {
+        refs.sync_port(self.sync_port_id).unwrap_or_default()
+    }
+
+    /// Разделители данных сеанса и их значения; номера вне справочников пропускаются
+    pub fn data_separation<'refs>(
+        &self,
+        refs: &'refs References,
+    ) -> Vec<(&'refs DataSeparation, &'refs str)> {
+        data_separation(self.data_separation_ids(), refs)
     }
 
     pub fn session(&self) -> usize {
@@ -148,7 +383,252 @@ impl<'a> Event<'a> {
     }
 
     pub fn unknown2(&self) -> &str {
-        self.unknown2
+        &self.unknown2
+    }
+
+    pub fn data_separation_ids(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        crate::record::separation_ids(&self.unknown2)
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn end_offset(&self) -> u64 {
+        self.end_offset
+    }
+}
+
+pub struct Writer<W: Write> {
+    out: W,
+    id: Uuid,
+    started: bool,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(out: W) -> Writer<W> {
+        Writer {
+            out,
+            id: Uuid::nil(),
+            started: false,
+        }
+    }
+
+    pub fn id(mut self, id: Uuid) -> Writer<W> {
+        self.id = id;
+        self
+    }
+
+    fn start(&mut self) -> io::Result<bool> {
+        if self.started {
+            return Ok(false);
+        }
+        self.started = true;
+        write_header(&mut self.out, self.id)?;
+        Ok(true)
+    }
+
+    pub fn write(&mut self, event: &EventOwned) -> io::Result<()> {
+        if !self.start()? {
+            self.out.write_all(b",\r\n")?;
+        }
+        let out = &mut self.out;
+        write!(out, "{{{},", event.date.format("%Y%m%d%H%M%S"))?;
+        let status = match event.transaction_status {
+            TransactionStatus::RolledBack => 'R',
+            TransactionStatus::NotApplicable => 'N',
+            TransactionStatus::Unfinished => 'U',
+            TransactionStatus::Committed => 'C',
+        };
+        write!(
+            out,
+            "{status},\r\n{},{},{},{},{},{},",
+            event.transaction_data.trim_end(),
+            event.user_id,
+            event.computer_id,
+            event.application_id,
+            event.connection,
+            event.event_id
+        )?;
+        let level = match event.log_level {
+            EventLogLevel::Error => 'E',
+            EventLogLevel::Information => 'I',
+            EventLogLevel::Note => 'N',
+            EventLogLevel::Warning => 'W',
+        };
+        write!(out, "{level},")?;
+        write_str(out, &event.comment)?;
+        let data = event.data.trim_end();
+        write!(out, ",{},\r\n{data},", event.metadata_id)?;
+        write_str(out, &event.data_presentation)?;
+        write!(
+            out,
+            ",{},{},{},{},{},\r\n{}\r\n}}",
+            event.worker_server_id,
+            event.port_id,
+            event.sync_port_id,
+            event.session,
+            event.unknown1,
+            event.unknown2.trim_end()
+        )
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.start()?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseSummary {
+    records: usize,
+    bytes: u64,
+    skipped: usize,
+    last_offset: u64,
+}
+
+impl ParseSummary {
+    pub fn records(&self) -> usize {
+        self.records
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    pub fn last_offset(&self) -> u64 {
+        self.last_offset
+    }
+
+    fn record(&mut self, event: &Event) {
+        self.records += 1;
+        self.last_offset = event.end_offset;
+    }
+}
+
+type InvalidRecordHook<'a> = Box<dyn FnMut(&[u8], u64, &ParseError) + 'a>;
+
+#[derive(Default)]
+pub struct ParseOptions<'a> {
+    range: Option<(NaiveDateTime, NaiveDateTime)>,
+    buffer_size: Option<usize>,
+    projection: Projection,
+    on_invalid: Option<InvalidRecordHook<'a>>,
+    filter: Option<CompiledFilter>,
+    #[cfg(feature = "encoding")]
+    encoding: Option<&'static crate::encoding::Encoding>,
+}
+
+impl<'a> ParseOptions<'a> {
+    pub fn new() -> ParseOptions<'a> {
+        ParseOptions::default()
+    }
+
+    pub fn range(mut self, from: NaiveDateTime, to: NaiveDateTime) -> ParseOptions<'a> {
+        self.range = Some((from, to));
+        self
+    }
+
+    /// Начальный размер буфера чтения, по умолчанию 1 МБ; растет под запись большего размера
+    pub fn buffer_size(mut self, size: usize) -> ParseOptions<'a> {
+        self.buffer_size = Some(size);
+        self
+    }
+
+    /// Разбираются только указанные поля; с фильтром поля разбираются все
+    pub fn projection(mut self, projection: Projection) -> ParseOptions<'a> {
+        self.projection = projection;
+        self
+    }
+
+    pub fn on_invalid<F>(mut self, on_invalid: F) -> ParseOptions<'a>
+    where
+        F: FnMut(&[u8], u64, &ParseError) + 'a,
+    {
+        self.on_invalid = Some(Box::new(on_invalid));
+        self
+    }
+
+    pub fn filter(mut self, filter: CompiledFilter) -> ParseOptions<'a> {
+        self.filter = Some(filter);
+        self
+    }
+
+    #[cfg(feature = "encoding")]
+    pub fn encoding(mut self, encoding: &'static crate::encoding::Encoding) -> ParseOptions<'a> {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    fn invalid(
+        &mut self,
+        summary: &mut ParseSummary,
+        record: &[u8],
+        offset: u64,
+        error: ParseError,
+    ) {
+        summary.skipped += 1;
+        if let Some(on_invalid) = &mut self.on_invalid {
+            // Начало записи и конец без разделителя
+            let start = memchr::memchr(b'{', record).unwrap_or(0);
+            let end = record
+                .iter()
+                .rposition(|ch| !ch.is_ascii_whitespace() && *ch != b',')
+                .map_or(start, |i| i + 1);
+            on_invalid(&record[start..end], offset + start as u64, &error);
+        }
+    }
+}
+
+impl RecordHooks for ParseOptions<'_> {
+    fn matches_date(&self, date: NaiveDateTime) -> bool {
+        self.range
+            .is_none_or(|(from, to)| date >= from && date <= to)
+            && self
+                .filter
+                .as_ref()
+                .is_none_or(|filter| filter.matches_date(date))
+    }
+
+    #[cfg(feature = "encoding")]
+    fn log_str<'b>(&self, str: LogStr<'b>) -> LogStr<'b> {
+        str.with_encoding(self.encoding)
+    }
+
+    #[cfg(feature = "regex")]
+    fn matches_comment(&self, comment: &LogStr) -> bool {
+        self.filter
+            .as_ref()
+            .is_none_or(|filter| filter.matches_comment(comment.raw()))
+    }
+
+    #[cfg(feature = "regex")]
+    fn matches_data_presentation(&self, data_presentation: &LogStr) -> bool {
+        self.filter
+            .as_ref()
+            .is_none_or(|filter| filter.matches_data_presentation(data_presentation.raw()))
+    }
+
+    fn matches_fields(&self, event: &Event) -> bool {
+        self.filter
+            .as_ref()
+            .is_none_or(|filter| filter.matches_fields(event))
+    }
+
+    fn projection(&self) -> Projection {
+        match self.filter {
+            Some(_) => Projection::ALL,
+            None => self.projection,
+        }
     }
 }
 
@@ -157,132 +637,558 @@ where
     F: FnMut(Event),
     P: AsRef<Path>,
 {
-    let mut reader = File::open(file_name)?;
+    parse_until(file_name, &mut |event| {
+        action(event);
+        ControlFlow::Continue(())
+    })
+}
+
+pub fn parse_until<F, P>(file_name: P, action: &mut F) -> io::Result<()>
+where
+    F: FnMut(Event) -> ControlFlow<()>,
+    P: AsRef<Path>,
+{
+    read_events(
+        open_file(file_name, 0)?,
+        0,
+        &mut ParseOptions::default(),
+        action,
+    )?;
+    Ok(())
+}
+
+pub fn parse_with_report<F, P>(file_name: P, action: &mut F) -> io::Result<ParseSummary>
+where
+    F: FnMut(Event),
+    P: AsRef<Path>,
+{
+    parse_with_options(file_name, &mut ParseOptions::default(), &mut |event| {
+        action(event);
+        ControlFlow::Continue(())
+    })
+}
 
-    let mut buffer = Box::new([0u8; 1024 * 1024]);
-    let mut offset = 0usize;
+pub fn parse_with_options<F, P>(
+    file_name: P,
+    options: &mut ParseOptions,
+    action: &mut F,
+) -> io::Result<ParseSummary>
+where
+    F: FnMut(Event) -> ControlFlow<()>,
+    P: AsRef<Path>,
+{
+    let offset = start_offset(&file_name, options);
+    read_events(open_file(file_name, offset)?, offset, options, action)
+}
 
-    loop {
-        let len = reader.read(&mut buffer[offset..])?;
-        if len == 0 {
-            break;
+// С сохраненным индексом чтение начинается ближе к началу интервала
+fn start_offset<P: AsRef<Path>>(file_name: P, options: &ParseOptions) -> u64 {
+    match options.range {
+        Some((from, _)) => Index::for_file(&file_name)
+            .ok()
+            .flatten()
+            .map_or(0, |index| index.offset(from)),
+        None => 0,
+    }
+}
+
+/// События передаются пачками: все записи, разобранные из одного заполнения буфера.
+pub fn parse_batches<F, P>(file_name: P, action: &mut F) -> io::Result<()>
+where
+    F: FnMut(&[Event]),
+    P: AsRef<Path>,
+{
+    parse_batches_with_options(file_name, &mut ParseOptions::default(), &mut |batch| {
+        action(batch);
+        ControlFlow::Continue(())
+    })?;
+    Ok(())
+}
+
+pub fn parse_batches_with_options<F, P>(
+    file_name: P,
+    options: &mut ParseOptions,
+    action: &mut F,
+) -> io::Result<ParseSummary>
+where
+    F: FnMut(&[Event]) -> ControlFlow<()>,
+    P: AsRef<Path>,
+{
+    let offset = start_offset(&file_name, options);
+    read_batches(open_file(file_name, offset)?, offset, options, action)
+}
+
+#[cfg(feature = "mmap")]
+pub fn parse_mmap<F, P>(file_name: P, action: &mut F) -> io::Result<()>
+where
+    F: FnMut(Event),
+    P: AsRef<Path>,
+{
+    parse_mmap_with_options(file_name, &mut ParseOptions::default(), &mut |event| {
+        action(event);
+        ControlFlow::Continue(())
+    })?;
+    Ok(())
+}
+
+// Файл целиком как один буфер, без цикла чтения и копирования остатка
+#[cfg(feature = "mmap")]
+#[allow(unsafe_code)]
+pub fn parse_mmap_with_options<F, P>(
+    file_name: P,
+    options: &mut ParseOptions,
+    action: &mut F,
+) -> io::Result<ParseSummary>
+where
+    F: FnMut(Event) -> ControlFlow<()>,
+    P: AsRef<Path>,
+{
+    let file = std::fs::File::open(file_name)?;
+    // Файл не должен усекаться во время разбора, дописывание в конец безопасно
+    let map = unsafe { memmap2::Mmap::map(&file)? };
+    #[cfg(unix)]
+    let _ = map.advise(memmap2::Advice::Sequential);
+
+    let mut summary = ParseSummary::default();
+    if let ControlFlow::Continue(read) = parse_buffer(&map, 0, options, &mut summary, action) {
+        invalid_tail(&map[read..], read as u64, options, &mut summary);
+    }
+    summary.bytes = map.len() as u64;
+    Ok(summary)
+}
+
+pub fn parse_from_offset<F, P>(file_name: P, offset: u64, action: &mut F) -> io::Result<()>
+where
+    F: FnMut(Event) -> ControlFlow<()>,
+    P: AsRef<Path>,
+{
+    read_events(
+        open_file(file_name, offset)?,
+        offset,
+        &mut ParseOptions::default(),
+        action,
+    )?;
+    Ok(())
+}
+
+#[cfg(all(feature = "uring", target_os = "linux"))]
+pub(crate) use crate::uring::open_file;
+
+// Файл для последовательного чтения с позиции offset
+#[cfg(not(all(feature = "uring", target_os = "linux")))]
+pub(crate) fn open_file<P: AsRef<Path>>(file_name: P, offset: u64) -> io::Result<std::fs::File> {
+    use std::io::{Seek, SeekFrom};
+
+    let mut file = std::fs::File::open(file_name)?;
+    file.seek(SeekFrom::Start(offset))?;
+    Ok(file)
+}
+
+/// Записи без разбора полей: смещение записи в файле и ее байты от '{' до '}'.
+/// Испорченные записи пропускаются.
+pub fn parse_raw_records<F, P>(file_name: P, action: &mut F) -> io::Result<()>
+where
+    F: FnMut(u64, &[u8]) -> ControlFlow<()>,
+    P: AsRef<Path>,
+{
+    let mut offset = 0;
+    let mut reader = ChunkReader::new(open_file(file_name, 0)?);
+    reader.read(&mut |buffer| {
+        let mut parser = Parser::new(buffer);
+        loop {
+            let position = parser.position();
+            match parse_raw_record(&mut parser) {
+                Ok(raw) => {
+                    let start = parser.position() - raw.len();
+                    action(offset + start as u64, raw)?;
+                }
+                Err(error) => {
+                    if error.is_incomplete() || skip_invalid(&mut parser, position).is_err() {
+                        offset += position as u64;
+                        return ControlFlow::Continue(position);
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Количество записей без разбора: ищутся строки вида "{YYYYMMDDhhmmss,".
+/// Строка такого вида внутри текста события тоже будет посчитана.
+pub fn count<P: AsRef<Path>>(file_name: P) -> io::Result<usize> {
+    let mut count = 0;
+    scan_records(open_file(file_name, 0)?, false, &mut |_| count += 1)?;
+    Ok(count)
+}
+
+/// Количество записей по уровням, уровень читается по позиции в записи без ее разбора.
+pub fn count_by_level<P: AsRef<Path>>(file_name: P) -> io::Result<LevelCounts> {
+    let mut counts = LevelCounts::default();
+    scan_records(open_file(file_name, 0)?, true, &mut |level| {
+        if let Some(level) = level {
+            counts.add(level);
         }
-        let len = len + offset;
-        let read = parse_buffer(&buffer[0..len], action);
+    })?;
+    Ok(counts)
+}
 
-        if read == 0 {
-            panic!("buffer too small")
+fn scan_records<R, F>(reader: R, with_level: bool, action: &mut F) -> io::Result<()>
+where
+    R: Read,
+    F: FnMut(Option<EventLogLevel>),
+{
+    let mut reader = ChunkReader::new(reader);
+    reader.read(&mut |buffer| {
+        for i in memchr::memmem::find_iter(buffer, b"\n{") {
+            match scan_record(&buffer[i + 1..], with_level) {
+                ScanResult::Record(level) => action(level),
+                ScanResult::Invalid => {}
+                ScanResult::Incomplete => return ControlFlow::Continue(i),
+            }
         }
+        // Последний '\n' может оказаться перед '{' из следующей части
+        ControlFlow::Continue(buffer.len().saturating_sub(1))
+    })
+}
 
-        for i in read..len {
-            buffer[i - read] = buffer[i];
+enum ScanResult {
+    Record(Option<EventLogLevel>),
+    Invalid,
+    Incomplete,
+}
+
+fn scan_record(record: &[u8], with_level: bool) -> ScanResult {
+    if record.len() < 16 {
+        return ScanResult::Incomplete;
+    }
+    if !record[1..15].iter().all(u8::is_ascii_digit) || record[15] != b',' {
+        return ScanResult::Invalid;
+    }
+    if !with_level {
+        return ScanResult::Record(None);
+    }
+
+    // Уровень - после статуса, данных транзакции {..} и пяти чисел
+    let level = memchr::memchr(b'}', &record[16..]).and_then(|i| {
+        let rest = &record[16 + i + 1..];
+        let position = memchr::memchr_iter(b',', rest).nth(5)? + 1;
+        match rest.get(position..position + 2)? {
+            [b'E', b','] => Some(EventLogLevel::Error),
+            [b'I', b','] => Some(EventLogLevel::Information),
+            [b'N', b','] => Some(EventLogLevel::Note),
+            [b'W', b','] => Some(EventLogLevel::Warning),
+            _ => None,
         }
-        offset = len - read;
+    });
+    if let Some(level) = level {
+        return ScanResult::Record(Some(level));
+    }
+    match parse_record(&mut Parser::new(record), 0) {
+        Ok(event) => ScanResult::Record(Some(event.log_level)),
+        Err(error) if error.is_incomplete() => ScanResult::Incomplete,
+        Err(_) => ScanResult::Invalid,
     }
+}
+
+pub fn parse_reader<F, R>(reader: R, action: &mut F) -> io::Result<()>
+where
+    F: FnMut(Event),
+    R: Read,
+{
+    read_events(reader, 0, &mut ParseOptions::default(), &mut |event| {
+        action(event);
+        ControlFlow::Continue(())
+    })?;
+    Ok(())
+}
 
+pub fn follow<F, P>(file_name: P, interval: time::Duration, action: &mut F) -> io::Result<()>
+where
+    F: FnMut(Event) -> ControlFlow<()>,
+    P: AsRef<Path>,
+{
+    let mut follower = Follower::open(file_name)?;
+    while follower.poll(action)?.is_continue() {
+        thread::sleep(interval);
+    }
     Ok(())
 }
 
-fn parse_buffer<F>(buffer: &[u8], action: &mut F) -> usize
+pub fn parse_range<F, P>(
+    file_name: P,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    action: &mut F,
+) -> io::Result<()>
 where
     F: FnMut(Event),
+    P: AsRef<Path>,
 {
-    let mut parser = Parser::new(buffer);
-    loop {
-        let position = parser.position();
-        match parse_record(&mut parser) {
-            Some(event) => action(event),
-            None => return position,
+    let mut options = ParseOptions::new().range(from, to);
+    parse_with_options(file_name, &mut options, &mut |event| {
+        action(event);
+        ControlFlow::Continue(())
+    })?;
+    Ok(())
+}
+
+pub struct EventReader<R> {
+    reader: R,
+    buffer: Buffer,
+    offset: u64,
+    // Конец потока, остаток буфера - неполная запись
+    eof: bool,
+}
+
+impl<R: Read> EventReader<R> {
+    pub fn new(reader: R) -> EventReader<R> {
+        EventReader {
+            reader,
+            buffer: Buffer::with_capacity(BUFFER_SIZE),
+            offset: 0,
+            eof: false,
+        }
+    }
+
+    pub fn peek_date(&mut self) -> io::Result<Option<NaiveDateTime>> {
+        if self.eof {
+            return Ok(None);
+        }
+        loop {
+            let mut parser = Parser::new(self.buffer.data());
+            match parse_record_date(&mut parser) {
+                Ok(date) => return Ok(Some(date)),
+                Err(ParseError::InvalidFormat { .. }) => {
+                    if self.skip_invalid() {
+                        continue;
+                    }
+                }
+                Err(ParseError::Incomplete) => {}
+            }
+            if self.buffer.fill(&mut self.reader)? == 0 {
+                return Ok(None);
+            }
+        }
+    }
+
+    pub fn read_next<F>(&mut self, action: &mut F) -> io::Result<bool>
+    where
+        F: FnMut(Event),
+    {
+        if self.eof {
+            return Ok(false);
+        }
+        loop {
+            let mut parser = Parser::new(self.buffer.data());
+            let error = match parse_record(&mut parser, self.offset) {
+                Ok(event) => {
+                    let position = parser.position();
+                    action(event);
+                    self.buffer.consume(position);
+                    self.offset += position as u64;
+                    return Ok(true);
+                }
+                Err(error) => error,
+            };
+            // Дата следующей записи может быть меньше, чем у пропущенной:
+            // вызывающий должен снова запросить peek_date
+            if !error.is_incomplete() && self.skip_invalid() {
+                return Ok(true);
+            }
+            if self.buffer.fill(&mut self.reader)? == 0 {
+                self.eof = true;
+                return Ok(false);
+            }
         }
     }
+
+    fn skip_invalid(&mut self) -> bool {
+        let mut parser = Parser::new(self.buffer.data());
+        if skip_invalid(&mut parser, 0).is_err() {
+            return false;
+        }
+        let position = parser.position();
+        self.buffer.consume(position);
+        self.offset += position as u64;
+        true
+    }
 }
 
-fn parse_record<'a>(parser: &'a mut Parser) -> Option<Event<'a>> {
-    while parser.next()? != b'{' {}
-
-    let date = parse_datetime(parser)?;
-    let transaction_status = parse_transaction_status(parser)?;
-    let transaction_data = parser.parse_object()?;
-    let user_id = parser.parse_usize()?;
-    let computer_id = parser.parse_usize()?;
-    let application_id = parser.parse_usize()?;
-    let connection = parser.parse_usize()?;
-    let event_id = parser.parse_usize()?;
-    let log_level = parse_log_level(parser)?;
-    let comment = parser.parse_str()?;
-    let metadata_id = parser.parse_usize()?;
-    let data = parser.parse_object()?;
-    let data_presentation = parser.parse_str()?;
-    let worker_server_id = parser.parse_usize()?;
-    let port_id = parser.parse_usize()?;
-    let sync_port_id = parser.parse_usize()?;
-    let session = parser.parse_usize()?;
-    let unknown1 = parser.parse_usize()?;
-    let unknown2 = parser.parse_object()?;
-
-    Some(Event {
-        date,
-        transaction_status,
-        transaction_data,
-        user_id,
-        computer_id,
-        application_id,
-        connection,
-        event_id,
-        log_level,
-        comment,
-        metadata_id,
-        data,
-        data_presentation,
-        worker_server_id,
-        port_id,
-        sync_port_id,
-        session,
-        unknown1,
-        unknown2,
-    })
+pub(crate) fn read_events<F, R>(
+    reader: R,
+    offset: u64,
+    options: &mut ParseOptions,
+    action: &mut F,
+) -> io::Result<ParseSummary>
+where
+    F: FnMut(Event) -> ControlFlow<()>,
+    R: Read,
+{
+    let mut offset = offset;
+    let mut summary = ParseSummary::default();
+    let mut reader = ChunkReader::with_capacity(reader, options.buffer_size.unwrap_or(BUFFER_SIZE));
+    let mut flow = ControlFlow::Continue(());
+    reader.read(&mut |buffer| {
+        let result = parse_buffer(buffer, offset, options, &mut summary, action);
+        match result {
+            ControlFlow::Continue(read) => offset += read as u64,
+            ControlFlow::Break(()) => flow = ControlFlow::Break(()),
+        }
+        result
+    })?;
+
+    if flow.is_continue() {
+        invalid_tail(reader.leftover(), offset, options, &mut summary);
+    }
+    summary.bytes = reader.total();
+    Ok(summary)
 }
 
-fn parse_datetime(parser: &mut Parser) -> Option<NaiveDateTime> {
-    fn next2(parser: &mut Parser) -> Option<u32> {
-        Some((parser.next()? - b'0') as u32 * 10 + (parser.next()? - b'0') as u32)
+pub(crate) fn read_batches<F, R>(
+    reader: R,
+    offset: u64,
+    options: &mut ParseOptions,
+    action: &mut F,
+) -> io::Result<ParseSummary>
+where
+    F: FnMut(&[Event]) -> ControlFlow<()>,
+    R: Read,
+{
+    let mut offset = offset;
+    let mut summary = ParseSummary::default();
+    let mut reader = ChunkReader::with_capacity(reader, options.buffer_size.unwrap_or(BUFFER_SIZE));
+    let mut flow = ControlFlow::Continue(());
+    reader.read(&mut |buffer| {
+        let mut batch = Vec::new();
+        let result = parse_buffer(buffer, offset, options, &mut summary, &mut |event| {
+            batch.push(event);
+            ControlFlow::Continue(())
+        });
+        if !batch.is_empty() {
+            flow = action(&batch);
+        }
+        match (result, flow) {
+            (ControlFlow::Continue(read), ControlFlow::Continue(())) => {
+                offset += read as u64;
+                ControlFlow::Continue(read)
+            }
+            _ => ControlFlow::Break(()),
+        }
+    })?;
+
+    if flow.is_continue() {
+        invalid_tail(reader.leftover(), offset, options, &mut summary);
     }
+    summary.bytes = reader.total();
+    Ok(summary)
+}
 
-    let year = next2(parser)? * 100 + next2(parser)?;
-    let month = next2(parser)?;
-    let day = next2(parser)?;
-    let hour = next2(parser)?;
-    let min = next2(parser)?;
-    let sec = next2(parser)?;
-    parser.skip(1)?;
+// Оборванная запись в конце файла
+fn invalid_tail(
+    leftover: &[u8],
+    offset: u64,
+    options: &mut ParseOptions,
+    summary: &mut ParseSummary,
+) {
+    if leftover.iter().any(|ch| !ch.is_ascii_whitespace()) {
+        let error = match parse_record(&mut Parser::new(leftover), offset) {
+            Err(error) => error.shift(offset),
+            Ok(_) => ParseError::Incomplete,
+        };
+        options.invalid(summary, leftover, offset, error);
+    }
+}
 
-    let date = NaiveDate::from_ymd_opt(year as i32, month, day)
-        .expect("Invalid file format")
-        .and_hms_opt(hour, min, sec)
-        .expect("Invalid file format");
-    Some(date)
+pub(crate) fn parse_buffer<'a, F>(
+    buffer: &'a [u8],
+    offset: u64,
+    options: &mut ParseOptions,
+    summary: &mut ParseSummary,
+    action: &mut F,
+) -> ControlFlow<(), usize>
+where
+    F: FnMut(Event<'a>) -> ControlFlow<()>,
+{
+    let mut parser = Parser::new(buffer);
+    loop {
+        let position = parser.position();
+        match parse_record_in_range(&mut parser, offset, options) {
+            Ok(Some(event)) => {
+                summary.record(&event);
+                action(event)?
+            }
+            Ok(None) => {}
+            Err(ParseError::Incomplete) => return ControlFlow::Continue(position),
+            Err(error) => {
+                if skip_invalid(&mut parser, position).is_err() {
+                    return ControlFlow::Continue(position);
+                }
+                let record = &buffer[position..parser.position()];
+                options.invalid(
+                    summary,
+                    record,
+                    offset + position as u64,
+                    error.shift(offset),
+                );
+            }
+        }
+    }
 }
 
-fn parse_transaction_status(parser: &mut Parser) -> Option<TransactionStatus> {
-    let ch = parser.next()?;
-    parser.skip(1)?;
-    Some(match ch {
-        b'R' => TransactionStatus::RolledBack,
-        b'N' => TransactionStatus::NotApplicable,
-        b'U' => TransactionStatus::Unfinished,
-        b'C' => TransactionStatus::Committed,
-        _ => panic!("Unknown transaction status: {ch}"),
-    })
+#[cfg(feature = "serde")]
+impl serde::Serialize for Event<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut s = serializer.serialize_struct("Event", 21)?;
+        s.serialize_field("date", &self.date)?;
+        s.serialize_field("transaction_status", &self.transaction_status)?;
+        s.serialize_field("transaction_data", self.transaction_data)?;
+        s.serialize_field("user_id", &self.user_id)?;
+        s.serialize_field("computer_id", &self.computer_id)?;
+        s.serialize_field("application_id", &self.application_id)?;
+        s.serialize_field("connection", &self.connection)?;
+        s.serialize_field("event_id", &self.event_id)?;
+        s.serialize_field("log_level", &self.log_level)?;
+        s.serialize_field("comment", &self.comment())?;
+        s.serialize_field("metadata_id", &self.metadata_id)?;
+        s.serialize_field("data", self.data())?;
+        s.serialize_field("data_presentation", &self.data_presentation())?;
+        s.serialize_field("worker_server_id", &self.worker_server_id)?;
+        s.serialize_field("port_id", &self.port_id)?;
+        s.serialize_field("sync_port_id", &self.sync_port_id)?;
+        s.serialize_field("session", &self.session)?;
+        s.serialize_field("unknown1", &self.unknown1)?;
+        s.serialize_field("unknown2", self.unknown2)?;
+        s.serialize_field("offset", &self.offset)?;
+        s.serialize_field("end_offset", &self.end_offset)?;
+        s.end()
+    }
 }
 
-fn parse_log_level(parser: &mut Parser) -> Option<EventLogLevel> {
-    let ch = parser.next()?;
-    parser.skip(1)?;
-    Some(match ch {
-        b'E' => EventLogLevel::Error,
-        b'I' => EventLogLevel::Information,
-        b'N' => EventLogLevel::Note,
-        b'W' => EventLogLevel::Warning,
-        _ => panic!("Unknown log level: {ch}"),
-    })
+#[cfg(feature = "serde")]
+impl<R: ResolveRefs + ?Sized> serde::Serialize for EventResolved<'_, R> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut s = serializer.serialize_struct("EventResolved", 17)?;
+        s.serialize_field("date", &self.date())?;
+        s.serialize_field("transaction_status", self.transaction_status())?;
+        s.serialize_field("transaction_data", self.event.transaction_data)?;
+        s.serialize_field("user", self.user_name())?;
+        s.serialize_field("computer", self.computer())?;
+        s.serialize_field("application", self.application())?;
+        s.serialize_field("connection", &self.connection())?;
+        s.serialize_field("event", self.event_name())?;
+        s.serialize_field("log_level", self.log_level())?;
+        s.serialize_field("comment", &self.comment())?;
+        s.serialize_field("metadata", self.metadata_name())?;
+        s.serialize_field("data", self.data())?;
+        s.serialize_field("data_presentation", &self.data_presentation())?;
+        s.serialize_field("worker_server", self.worker_server())?;
+        s.serialize_field("port", &self.port())?;
+        s.serialize_field("sync_port", &self.sync_port())?;
+        s.serialize_field("session", &self.session())?;
+        s.end()
+    }
 }
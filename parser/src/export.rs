@@ -0,0 +1,161 @@
+use crate::{events::EventResolved, references::ResolveRefs};
+use chrono::NaiveDateTime;
+use std::{borrow::Cow, fmt, str::FromStr};
+
+#[cfg(feature = "csv")]
+pub mod csv;
+#[cfg(feature = "datafusion")]
+pub mod datafusion;
+#[cfg(feature = "gelf")]
+pub mod gelf;
+#[cfg(feature = "jsonl")]
+pub mod jsonl;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "loki")]
+pub mod loki;
+#[cfg(feature = "otlp")]
+pub mod otlp;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod partition;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "xml")]
+pub mod xml;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Column {
+    Date,
+    TransactionStatus,
+    TransactionData,
+    User,
+    Computer,
+    Application,
+    Connection,
+    Event,
+    LogLevel,
+    Comment,
+    Metadata,
+    Data,
+    DataPresentation,
+    WorkerServer,
+    Port,
+    SyncPort,
+    Session,
+}
+
+impl Column {
+    pub const ALL: [Column; 17] = [
+        Column::Date,
+        Column::TransactionStatus,
+        Column::TransactionData,
+        Column::User,
+        Column::Computer,
+        Column::Application,
+        Column::Connection,
+        Column::Event,
+        Column::LogLevel,
+        Column::Comment,
+        Column::Metadata,
+        Column::Data,
+        Column::DataPresentation,
+        Column::WorkerServer,
+        Column::Port,
+        Column::SyncPort,
+        Column::Session,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::Date => "date",
+            Column::TransactionStatus => "transaction_status",
+            Column::TransactionData => "transaction_data",
+            Column::User => "user",
+            Column::Computer => "computer",
+            Column::Application => "application",
+            Column::Connection => "connection",
+            Column::Event => "event",
+            Column::LogLevel => "log_level",
+            Column::Comment => "comment",
+            Column::Metadata => "metadata",
+            Column::Data => "data",
+            Column::DataPresentation => "data_presentation",
+            Column::WorkerServer => "worker_server",
+            Column::Port => "port",
+            Column::SyncPort => "sync_port",
+            Column::Session => "session",
+        }
+    }
+
+    pub fn value<'a, R: ResolveRefs + ?Sized>(&self, event: &EventResolved<'a, R>) -> Cell<'a> {
+        match self {
+            Column::Date => Cell::Date(event.date()),
+            Column::TransactionStatus => {
+                Cell::Str(format!("{:?}", event.transaction_status()).into())
+            }
+            Column::TransactionData => Cell::Str(event.transaction_data().into()),
+            Column::User => Cell::Str(event.user_name().into()),
+            Column::Computer => Cell::Str(event.computer().into()),
+            Column::Application => Cell::Str(event.application().into()),
+            Column::Connection => Cell::Number(event.connection() as u64),
+            Column::Event => Cell::Str(event.event_name().into()),
+            Column::LogLevel => Cell::Str(format!("{:?}", event.log_level()).into()),
+            Column::Comment => Cell::Str(event.comment()),
+            Column::Metadata => Cell::Str(event.metadata_name().into()),
+            Column::Data => Cell::Str(event.data().into()),
+            Column::DataPresentation => Cell::Str(event.data_presentation()),
+            Column::WorkerServer => Cell::Str(event.worker_server().into()),
+            Column::Port => Cell::Number(event.port() as u64),
+            Column::SyncPort => Cell::Number(event.sync_port() as u64),
+            Column::Session => Cell::Number(event.session() as u64),
+        }
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Column {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Column::ALL
+            .into_iter()
+            .find(|column| column.name() == s)
+            .ok_or_else(|| format!("unknown column: {s}"))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cell<'a> {
+    Str(Cow<'a, str>),
+    Number(u64),
+    Date(NaiveDateTime),
+}
+
+impl fmt::Display for Cell<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cell::Str(s) => f.write_str(s),
+            Cell::Number(n) => write!(f, "{n}"),
+            Cell::Date(date) => write!(f, "{}", date.format("%Y-%m-%dT%H:%M:%S")),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Cell<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Cell::Str(s) => serializer.serialize_str(s),
+            Cell::Number(n) => serializer.serialize_u64(*n),
+            Cell::Date(date) => date.serialize(serializer),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use super::Column;
+#[cfg(feature = "hashing")]
+use crate::hashing::HashedIdentities;
+use crate::{events::Event, references::References};
+use std::io::{self, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Header {
+    None,
+    Names,
+    Localized,
+}
+
+pub struct Writer<'refs, W: Write> {
+    out: Option<W>,
+    writer: Option<csv::Writer<W>>,
+    refs: &'refs References,
+    columns: Vec<Column>,
+    header: Header,
+    delimiter: u8,
+    bom: bool,
+}
+
+impl<'refs, W: Write> Writer<'refs, W> {
+    pub fn new(out: W, refs: &'refs References) -> Writer<'refs, W> {
+        Writer {
+            out: Some(out),
+            writer: None,
+            refs,
+            columns: Column::ALL.to_vec(),
+            header: Header::Names,
+            delimiter: b',',
+            bom: false,
+        }
+    }
+
+    pub fn columns(mut self, columns: &[Column]) -> Writer<'refs, W> {
+        self.columns = columns.to_vec();
+        self
+    }
+
+    pub fn header(mut self, header: Header) -> Writer<'refs, W> {
+        self.header = header;
+        self
+    }
+
+    pub fn delimiter(mut self, delimiter: u8) -> Writer<'refs, W> {
+        self.delimiter = delimiter;
+        self
+    }
+
+    // Без BOM Excel открывает UTF-8 в кодировке системы
+    pub fn bom(mut self, bom: bool) -> Writer<'refs, W> {
+        self.bom = bom;
+        self
+    }
+
+    #[cfg(feature = "hashing")]
+    pub fn hash_identities(mut self, identities: &'refs HashedIdentities) -> Writer<'refs, W> {
+        self.refs = identities.references();
+        self
+    }
+
+    pub fn write(&mut self, event: &Event) -> io::Result<()> {
+        let event = event.resolve(self.refs);
+        self.start()?;
+        let writer = self.writer.as_mut().unwrap();
+        for column in &self.columns {
+            writer.write_field(column.value(&event).to_string())?;
+        }
+        writer.write_record(None::<&[u8]>)?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.start()?.flush()
+    }
+
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.start()?;
+        let writer = self.writer.take().unwrap();
+        writer.into_inner().map_err(|e| e.into_error())
+    }
+
+    fn start(&mut self) -> io::Result<&mut csv::Writer<W>> {
+        if let Some(mut out) = self.out.take() {
+            if self.bom {
+                out.write_all(b"\xEF\xBB\xBF")?;
+            }
+            let mut writer = csv::WriterBuilder::new()
+                .delimiter(self.delimiter)
+                .from_writer(out);
+            match self.header {
+                Header::None => {}
+                Header::Names => writer.write_record(self.columns.iter().map(Column::name))?,
+                Header::Localized => writer.write_record(self.columns.iter().map(localized))?,
+            }
+            self.writer = Some(writer);
+        }
+        Ok(self.writer.as_mut().unwrap())
+    }
+}
+
+// Заголовки как при выгрузке журнала из конфигуратора
+fn localized(column: &Column) -> &'static str {
+    match column {
+        Column::Date => "Дата",
+        Column::TransactionStatus => "Статус транзакции",
+        Column::TransactionData => "Транзакция",
+        Column::User => "Пользователь",
+        Column::Computer => "Компьютер",
+        Column::Application => "Приложение",
+        Column::Connection => "Соединение",
+        Column::Event => "Событие",
+        Column::LogLevel => "Уровень",
+        Column::Comment => "Комментарий",
+        Column::Metadata => "Метаданные",
+        Column::Data => "Данные",
+        Column::DataPresentation => "Представление данных",
+        Column::WorkerServer => "Рабочий сервер",
+        Column::Port => "Основной IP порт",
+        Column::SyncPort => "Вспомогательный IP порт",
+        Column::Session => "Сеанс",
+    }
+}
@@ -0,0 +1,240 @@
+// Каталог журнала как таблица DataFusion: ctx.register_table("log", ...) и SQL.
+// Проекция, предел и отбор по дате передаются в Scan, остальные условия
+// применяет DataFusion
+use super::parquet::{schema, Scan};
+use crate::directory::LogDirectory;
+use ::datafusion::{
+    catalog::{Session, TableProvider},
+    common::{project_schema, Result, ScalarValue},
+    datasource::TableType,
+    execution::{SendableRecordBatchStream, TaskContext},
+    logical_expr::{BinaryExpr, Expr, Operator, TableProviderFilterPushDown},
+    physical_expr::EquivalenceProperties,
+    physical_plan::{
+        execution_plan::{Boundedness, EmissionType},
+        stream::RecordBatchReceiverStreamBuilder,
+        DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, PlanProperties,
+    },
+};
+use arrow_schema::SchemaRef;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, TimeDelta};
+use std::{any::Any, fmt, ops::ControlFlow, sync::Arc};
+
+pub struct LogTable {
+    dir: Arc<LogDirectory>,
+}
+
+impl LogTable {
+    pub fn new(dir: LogDirectory) -> LogTable {
+        LogTable { dir: Arc::new(dir) }
+    }
+}
+
+impl fmt::Debug for LogTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LogTable")
+            .field("path", &self.dir.path())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl TableProvider for LogTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        schema()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> Result<Vec<TableProviderFilterPushDown>> {
+        Ok(filters
+            .iter()
+            .map(|filter| match date_bound(filter) {
+                Some(_) => TableProviderFilterPushDown::Exact,
+                None => TableProviderFilterPushDown::Unsupported,
+            })
+            .collect())
+    }
+
+    async fn scan(
+        &self,
+        _state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let mut range: Option<(NaiveDateTime, NaiveDateTime)> = None;
+        for (from, to) in filters.iter().filter_map(date_bound) {
+            let (min, max) = range.get_or_insert((NaiveDateTime::MIN, NaiveDateTime::MAX));
+            *min = from.max(*min);
+            *max = to.min(*max);
+        }
+        let schema = project_schema(&schema(), projection)?;
+        Ok(Arc::new(LogExec {
+            dir: self.dir.clone(),
+            projection: projection.cloned(),
+            range,
+            limit,
+            properties: PlanProperties::new(
+                EquivalenceProperties::new(schema),
+                Partitioning::UnknownPartitioning(1),
+                EmissionType::Incremental,
+                Boundedness::Bounded,
+            ),
+        }))
+    }
+}
+
+// Условие по дате вида date >= TIMESTAMP '...' как границы периода Scan::range.
+// Дата хранится с точностью до секунды, поэтому строгие сравнения тоже точные
+fn date_bound(expr: &Expr) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    let Expr::BinaryExpr(BinaryExpr { left, op, right }) = expr else {
+        return None;
+    };
+    let (column, value, op) = match (left.as_ref(), right.as_ref()) {
+        (Expr::Column(column), Expr::Literal(value, _)) => (column, value, *op),
+        (Expr::Literal(value, _), Expr::Column(column)) => (column, value, op.swap()?),
+        _ => return None,
+    };
+    let ScalarValue::TimestampSecond(Some(seconds), None) = value else {
+        return None;
+    };
+    if column.name != "date" {
+        return None;
+    }
+    let date = DateTime::from_timestamp(*seconds, 0)?.naive_utc();
+    let second = TimeDelta::seconds(1);
+    match op {
+        Operator::Eq => Some((date, date)),
+        Operator::Gt => Some((date.checked_add_signed(second)?, NaiveDateTime::MAX)),
+        Operator::GtEq => Some((date, NaiveDateTime::MAX)),
+        Operator::Lt => Some((NaiveDateTime::MIN, date.checked_sub_signed(second)?)),
+        Operator::LtEq => Some((NaiveDateTime::MIN, date)),
+        _ => None,
+    }
+}
+
+struct LogExec {
+    dir: Arc<LogDirectory>,
+    projection: Option<Vec<usize>>,
+    range: Option<(NaiveDateTime, NaiveDateTime)>,
+    limit: Option<usize>,
+    properties: PlanProperties,
+}
+
+impl fmt::Debug for LogExec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LogExec")
+            .field("path", &self.dir.path())
+            .field("projection", &self.projection)
+            .field("range", &self.range)
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+impl DisplayAs for LogExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LogExec: path={}", self.dir.path().display())?;
+        if let Some((from, to)) = self.range {
+            write!(f, ", range={from}..={to}")?;
+        }
+        if let Some(limit) = self.limit {
+            write!(f, ", limit={limit}")?;
+        }
+        Ok(())
+    }
+}
+
+impl ExecutionPlan for LogExec {
+    fn name(&self) -> &str {
+        "LogExec"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        Vec::new()
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    // Разбор файлов идет в отдельном потоке, пачки передаются через канал
+    fn execute(
+        &self,
+        _partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let mut builder = RecordBatchReceiverStreamBuilder::new(self.schema(), 2);
+        let tx = builder.tx();
+        let dir = self.dir.clone();
+        let projection = self.projection.clone();
+        let (range, limit) = (self.range, self.limit);
+        let batch_size = context.session_config().batch_size();
+        builder.spawn_blocking(move || {
+            let mut scan = Scan::new(&dir).batch_size(batch_size);
+            if let Some(columns) = projection {
+                scan = scan.projection(columns);
+            }
+            if let Some((from, to)) = range {
+                scan = scan.range(from, to);
+            }
+            if let Some(limit) = limit {
+                scan = scan.limit(limit);
+            }
+            // Получатель закрыт: запрос отменен или предел достигнут выше по плану
+            scan.run(&mut |batch| match tx.blocking_send(Ok(batch)) {
+                Ok(()) => ControlFlow::Continue(()),
+                Err(_) => ControlFlow::Break(()),
+            })?;
+            Ok(())
+        });
+        Ok(builder.build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::datafusion::logical_expr::{col, lit};
+    use chrono::Timelike;
+
+    #[test]
+    fn test_date_bound_overflow() {
+        let timestamp = |date: NaiveDateTime| {
+            lit(ScalarValue::TimestampSecond(
+                Some(date.and_utc().timestamp()),
+                None,
+            ))
+        };
+        let max = NaiveDateTime::MAX.with_nanosecond(0).unwrap();
+        let min = NaiveDateTime::MIN;
+        assert_eq!(date_bound(&col("date").gt(timestamp(max))), None);
+        assert_eq!(date_bound(&col("date").lt(timestamp(min))), None);
+        assert_eq!(
+            date_bound(&col("date").lt_eq(timestamp(max))),
+            Some((NaiveDateTime::MIN, max))
+        );
+    }
+}
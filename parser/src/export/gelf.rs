@@ -0,0 +1,182 @@
+use super::Column;
+#[cfg(feature = "hashing")]
+use crate::hashing::HashedIdentities;
+use crate::{
+    events::{Event, EventLogLevel, EventResolved},
+    references::References,
+};
+use serde::ser::{SerializeMap, Serializer};
+use std::{
+    io::{self, Write},
+    net::{TcpStream, ToSocketAddrs, UdpSocket},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const MAGIC: [u8; 2] = [0x1e, 0x0f];
+const MAX_CHUNKS: usize = 128;
+
+enum Transport {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+}
+
+pub struct Writer<'refs> {
+    transport: Transport,
+    refs: &'refs References,
+    host: String,
+    chunk_size: usize,
+    message_id: u64,
+}
+
+impl<'refs> Writer<'refs> {
+    pub fn udp<A: ToSocketAddrs>(addr: A, refs: &'refs References) -> io::Result<Writer<'refs>> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+        socket.connect(addr)?;
+        Ok(Writer::new(Transport::Udp(socket), refs))
+    }
+
+    pub fn tcp<A: ToSocketAddrs>(addr: A, refs: &'refs References) -> io::Result<Writer<'refs>> {
+        Ok(Writer::new(Transport::Tcp(TcpStream::connect(addr)?), refs))
+    }
+
+    fn new(transport: Transport, refs: &'refs References) -> Writer<'refs> {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |x| x.as_nanos() as u64);
+        Writer {
+            transport,
+            refs,
+            host: "1c".to_string(),
+            chunk_size: 8192,
+            message_id: seed,
+        }
+    }
+
+    pub fn host<S: Into<String>>(mut self, host: S) -> Writer<'refs> {
+        self.host = host.into();
+        self
+    }
+
+    // Размер датаграммы вместе с 12 байтами заголовка чанка
+    pub fn chunk_size(mut self, chunk_size: usize) -> Writer<'refs> {
+        if chunk_size <= 12 {
+            panic!("Invalid chunk size: {chunk_size}");
+        }
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    #[cfg(feature = "hashing")]
+    pub fn hash_identities(mut self, identities: &'refs HashedIdentities) -> Writer<'refs> {
+        self.refs = identities.references();
+        self
+    }
+
+    pub fn write(&mut self, event: &Event) -> io::Result<()> {
+        let message = message(&event.resolve(self.refs), &self.host)?;
+        match &mut self.transport {
+            Transport::Udp(socket) => {
+                self.message_id = self.message_id.wrapping_add(1);
+                for chunk in chunks(&message, self.message_id, self.chunk_size)? {
+                    socket.send(&chunk)?;
+                }
+                Ok(())
+            }
+            Transport::Tcp(stream) => {
+                stream.write_all(&message)?;
+                stream.write_all(&[0])
+            }
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.transport {
+            Transport::Udp(_) => Ok(()),
+            Transport::Tcp(stream) => stream.flush(),
+        }
+    }
+}
+
+// Уровни syslog
+fn level(level: &EventLogLevel) -> u8 {
+    match level {
+        EventLogLevel::Error => 3,
+        EventLogLevel::Warning => 4,
+        EventLogLevel::Note => 5,
+        EventLogLevel::Information => 6,
+    }
+}
+
+// Дата журнала без часового пояса передается как UTC
+pub fn message(event: &EventResolved, host: &str) -> io::Result<Vec<u8>> {
+    let comment = event.comment();
+    let short_message = match comment.lines().next() {
+        Some(line) if !line.is_empty() => line,
+        _ => event.event_name(),
+    };
+
+    let mut out = Vec::new();
+    let mut serializer = serde_json::Serializer::new(&mut out);
+    let mut map = serializer.serialize_map(None)?;
+    map.serialize_entry("version", "1.1")?;
+    map.serialize_entry("host", host)?;
+    map.serialize_entry("short_message", short_message)?;
+    if comment.len() > short_message.len() {
+        map.serialize_entry("full_message", &comment)?;
+    }
+    map.serialize_entry("timestamp", &event.date().and_utc().timestamp())?;
+    map.serialize_entry("level", &level(event.log_level()))?;
+    for column in Column::ALL {
+        if column != Column::Date && column != Column::Comment {
+            map.serialize_entry(&format!("_{column}"), &column.value(event))?;
+        }
+    }
+    map.end()?;
+    Ok(out)
+}
+
+pub(crate) fn chunks(message: &[u8], id: u64, chunk_size: usize) -> io::Result<Vec<Vec<u8>>> {
+    if message.len() <= chunk_size {
+        return Ok(vec![message.to_vec()]);
+    }
+    let parts: Vec<_> = message.chunks(chunk_size - 12).collect();
+    if parts.len() > MAX_CHUNKS {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("GELF message too large: {} bytes", message.len()),
+        ));
+    }
+    Ok(parts
+        .iter()
+        .enumerate()
+        .map(|(num, part)| {
+            let mut chunk = Vec::with_capacity(part.len() + 12);
+            chunk.extend_from_slice(&MAGIC);
+            chunk.extend_from_slice(&id.to_be_bytes());
+            chunk.push(num as u8);
+            chunk.push(parts.len() as u8);
+            chunk.extend_from_slice(part);
+            chunk
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks() {
+        let message = vec![b'x'; 100];
+        assert_eq!(chunks(&message, 1, 100).unwrap(), vec![message.clone()]);
+
+        let result = chunks(&message, 0x0102, 62).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0][..12], [0x1e, 0x0f, 0, 0, 0, 0, 0, 0, 1, 2, 0, 2]);
+        assert_eq!(result[0].len(), 62);
+        assert_eq!(result[1][10..12], [1, 2]);
+        assert_eq!(result[1].len(), 12 + 50);
+
+        assert!(chunks(&vec![0; 129 * 10], 1, 22).is_err());
+    }
+}
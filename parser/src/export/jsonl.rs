@@ -0,0 +1,73 @@
+use super::Column;
+#[cfg(feature = "hashing")]
+use crate::hashing::HashedIdentities;
+use crate::{
+    events::{Event, EventResolved},
+    references::{References, ResolveRefs},
+};
+use serde::ser::{SerializeMap, Serializer};
+use std::io::{self, Write};
+
+pub struct Writer<'refs, W: Write> {
+    out: W,
+    refs: &'refs References,
+    columns: Vec<Column>,
+}
+
+impl<'refs, W: Write> Writer<'refs, W> {
+    pub fn new(out: W, refs: &'refs References) -> Writer<'refs, W> {
+        Writer {
+            out,
+            refs,
+            columns: Column::ALL.to_vec(),
+        }
+    }
+
+    pub fn columns(mut self, columns: &[Column]) -> Writer<'refs, W> {
+        self.columns = columns.to_vec();
+        self
+    }
+
+    #[cfg(feature = "hashing")]
+    pub fn hash_identities(mut self, identities: &'refs HashedIdentities) -> Writer<'refs, W> {
+        self.refs = identities.references();
+        self
+    }
+
+    pub fn write(&mut self, event: &Event) -> io::Result<()> {
+        let event = event.resolve(self.refs);
+        let mut serializer = serde_json::Serializer::new(&mut self.out);
+        write_object(&mut serializer, &self.columns, &event)?;
+        self.out.write_all(b"\n")
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+fn write_object<W: Write, R: ResolveRefs + ?Sized>(
+    serializer: &mut serde_json::Serializer<W>,
+    columns: &[Column],
+    event: &EventResolved<R>,
+) -> serde_json::Result<()> {
+    let mut map = serializer.serialize_map(Some(columns.len()))?;
+    for column in columns {
+        map.serialize_entry(column.name(), &column.value(event))?;
+    }
+    map.end()
+}
+
+// Одно событие JSON-объектом со всеми колонками, без перевода строки
+pub fn write_resolved<W: Write, R: ResolveRefs + ?Sized>(
+    out: W,
+    event: &EventResolved<R>,
+) -> io::Result<()> {
+    let mut serializer = serde_json::Serializer::new(out);
+    write_object(&mut serializer, &Column::ALL, event)?;
+    Ok(())
+}
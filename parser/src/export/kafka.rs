@@ -0,0 +1,134 @@
+#[cfg(feature = "hashing")]
+use crate::hashing::HashedIdentities;
+use crate::{events::Event, references::References};
+use rdkafka::{
+    error::{KafkaError, KafkaResult, RDKafkaErrorCode},
+    producer::{BaseProducer, BaseRecord, DeliveryResult, Producer as _, ProducerContext},
+    ClientConfig, ClientContext,
+};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    Session,
+    User,
+}
+
+#[derive(Default)]
+pub struct DeliveryContext {
+    delivered: AtomicUsize,
+    failed: AtomicUsize,
+    last_error: Mutex<Option<KafkaError>>,
+}
+
+impl DeliveryContext {
+    pub fn delivered(&self) -> usize {
+        self.delivered.load(Ordering::Relaxed)
+    }
+
+    pub fn failed(&self) -> usize {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    pub fn last_error(&self) -> Option<KafkaError> {
+        self.last_error.lock().unwrap().clone()
+    }
+}
+
+impl ClientContext for DeliveryContext {}
+
+impl ProducerContext for DeliveryContext {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, result: &DeliveryResult<'_>, _: Self::DeliveryOpaque) {
+        match result {
+            Ok(_) => {
+                self.delivered.fetch_add(1, Ordering::Relaxed);
+            }
+            Err((error, _)) => {
+                self.failed.fetch_add(1, Ordering::Relaxed);
+                *self.last_error.lock().unwrap() = Some(error.clone());
+            }
+        }
+    }
+}
+
+pub struct Producer<'refs> {
+    producer: BaseProducer<DeliveryContext>,
+    topic: String,
+    refs: &'refs References,
+    key: Key,
+}
+
+impl<'refs> Producer<'refs> {
+    pub fn new(
+        brokers: &str,
+        topic: &str,
+        refs: &'refs References,
+    ) -> KafkaResult<Producer<'refs>> {
+        let mut config = ClientConfig::new();
+        config.set("bootstrap.servers", brokers);
+        Producer::from_config(&config, topic, refs)
+    }
+
+    pub fn from_config(
+        config: &ClientConfig,
+        topic: &str,
+        refs: &'refs References,
+    ) -> KafkaResult<Producer<'refs>> {
+        Ok(Producer {
+            producer: config.create_with_context(DeliveryContext::default())?,
+            topic: topic.to_string(),
+            refs,
+            key: Key::Session,
+        })
+    }
+
+    pub fn key(mut self, key: Key) -> Producer<'refs> {
+        self.key = key;
+        self
+    }
+
+    #[cfg(feature = "hashing")]
+    pub fn hash_identities(mut self, identities: &'refs HashedIdentities) -> Producer<'refs> {
+        self.refs = identities.references();
+        self
+    }
+
+    pub fn send(&mut self, event: &Event) -> KafkaResult<()> {
+        let key = match self.key {
+            Key::Session => event.session().to_string(),
+            Key::User => event.user(self.refs).id().to_string(),
+        };
+        let payload = serde_json::to_vec(&event.resolve(self.refs))
+            .map_err(|_| KafkaError::MessageProduction(RDKafkaErrorCode::InvalidMessage))?;
+        let mut record = BaseRecord::to(&self.topic).key(&key).payload(&payload);
+        loop {
+            match self.producer.send(record) {
+                Ok(()) => break,
+                // Очередь librdkafka заполнена: ждем отчетов о доставке
+                Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), returned)) => {
+                    record = returned;
+                    self.producer.poll(Duration::from_millis(100));
+                }
+                Err((error, _)) => return Err(error),
+            }
+        }
+        self.producer.poll(Duration::ZERO);
+        Ok(())
+    }
+
+    pub fn flush(&mut self, timeout: Duration) -> KafkaResult<()> {
+        self.producer.flush(timeout)
+    }
+
+    pub fn delivery(&self) -> &DeliveryContext {
+        self.producer.context()
+    }
+}
@@ -0,0 +1,193 @@
+#[cfg(feature = "hashing")]
+use crate::hashing::HashedIdentities;
+use crate::{events::Event, references::References};
+use serde::ser::{SerializeMap, Serializer};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io, thread,
+    time::Duration,
+};
+
+type Labels = [String; 4];
+
+pub struct Writer<'refs> {
+    url: String,
+    refs: &'refs References,
+    infobase: String,
+    batch_size: usize,
+    retries: usize,
+    backoff: Duration,
+    streams: BTreeMap<Labels, Vec<(i64, String)>>,
+    pending: usize,
+    last: HashMap<Labels, i64>,
+}
+
+impl<'refs> Writer<'refs> {
+    pub fn new<S: Into<String>>(url: S, refs: &'refs References) -> Writer<'refs> {
+        let url = url.into();
+        let url = match url.ends_with("/loki/api/v1/push") {
+            true => url,
+            false => format!("{}/loki/api/v1/push", url.trim_end_matches('/')),
+        };
+        Writer {
+            url,
+            refs,
+            infobase: String::new(),
+            batch_size: 1000,
+            retries: 5,
+            backoff: Duration::from_millis(500),
+            streams: BTreeMap::new(),
+            pending: 0,
+            last: HashMap::new(),
+        }
+    }
+
+    pub fn infobase<S: Into<String>>(mut self, infobase: S) -> Writer<'refs> {
+        self.infobase = infobase.into();
+        self
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> Writer<'refs> {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn retries(mut self, retries: usize, backoff: Duration) -> Writer<'refs> {
+        self.retries = retries;
+        self.backoff = backoff;
+        self
+    }
+
+    #[cfg(feature = "hashing")]
+    pub fn hash_identities(mut self, identities: &'refs HashedIdentities) -> Writer<'refs> {
+        self.refs = identities.references();
+        self
+    }
+
+    pub fn write(&mut self, event: &Event) -> io::Result<()> {
+        let event = event.resolve(self.refs);
+        let labels = [
+            self.infobase.clone(),
+            format!("{:?}", event.log_level()),
+            event.event_name().to_string(),
+            event.user_name().to_string(),
+        ];
+        let timestamp = event
+            .date()
+            .and_utc()
+            .timestamp_nanos_opt()
+            .unwrap_or_default();
+        let line = serde_json::to_string(&event)?;
+        self.streams
+            .entry(labels)
+            .or_default()
+            .push((timestamp, line));
+        self.pending += 1;
+        if self.pending >= self.batch_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        if self.pending == 0 {
+            return Ok(());
+        }
+        // Внутри потока Loki принимает записи только по возрастанию времени
+        for (labels, values) in &mut self.streams {
+            values.sort_by_key(|(timestamp, _)| *timestamp);
+            let last = self.last.entry(labels.clone()).or_insert(i64::MIN);
+            for (timestamp, _) in values.iter_mut() {
+                *timestamp = (*timestamp).max(*last);
+                *last = *timestamp;
+            }
+        }
+        let body = self.body()?;
+        self.push(&body)?;
+        self.streams.clear();
+        self.pending = 0;
+        Ok(())
+    }
+
+    fn body(&self) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut serializer = serde_json::Serializer::new(&mut out);
+        let mut root = serializer.serialize_map(Some(1))?;
+        root.serialize_entry("streams", &Streams(&self.streams))?;
+        root.end()?;
+        Ok(out)
+    }
+
+    fn push(&self, body: &[u8]) -> io::Result<()> {
+        let mut backoff = self.backoff;
+        let mut attempt = 0;
+        loop {
+            let result = ureq::post(&self.url)
+                .set("Content-Type", "application/json")
+                .send_bytes(body);
+            let delay = match result {
+                Ok(_) => return Ok(()),
+                Err(ureq::Error::Status(code, response))
+                    if (code == 429 || code >= 500) && attempt < self.retries =>
+                {
+                    let retry_after = response.header("Retry-After");
+                    let retry_after = retry_after.and_then(|x| x.parse().ok());
+                    retry_after.map_or(backoff, Duration::from_secs)
+                }
+                Err(ureq::Error::Status(code, response)) => {
+                    let text = response.into_string().unwrap_or_default();
+                    return Err(io::Error::other(format!("Loki error {code}: {text}")));
+                }
+                Err(error) => return Err(io::Error::other(error)),
+            };
+            thread::sleep(delay);
+            backoff *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+impl Drop for Writer<'_> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+struct Streams<'a>(&'a BTreeMap<Labels, Vec<(i64, String)>>);
+
+impl serde::Serialize for Streams<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for (labels, values) in self.0 {
+            seq.serialize_element(&Stream(labels, values))?;
+        }
+        seq.end()
+    }
+}
+
+struct Stream<'a>(&'a Labels, &'a [(i64, String)]);
+
+impl serde::Serialize for Stream<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let [infobase, level, event, user] = self.0;
+        let mut labels = BTreeMap::new();
+        if !infobase.is_empty() {
+            labels.insert("infobase", infobase);
+        }
+        labels.insert("level", level);
+        labels.insert("event", event);
+        labels.insert("user", user);
+
+        let values: Vec<_> = self
+            .1
+            .iter()
+            .map(|(timestamp, line)| (timestamp.to_string(), line))
+            .collect();
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("stream", &labels)?;
+        map.serialize_entry("values", &values)?;
+        map.end()
+    }
+}
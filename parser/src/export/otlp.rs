@@ -0,0 +1,169 @@
+use super::{Cell, Column};
+#[cfg(feature = "hashing")]
+use crate::hashing::HashedIdentities;
+use crate::{
+    events::{Event, EventLogLevel, EventResolved},
+    references::References,
+};
+use serde_json::{json, Value};
+use std::io;
+
+pub struct Writer<'refs> {
+    url: String,
+    refs: &'refs References,
+    service: String,
+    infobase: String,
+    server: String,
+    batch_size: usize,
+    records: Vec<Value>,
+}
+
+impl<'refs> Writer<'refs> {
+    pub fn new<S: Into<String>>(url: S, refs: &'refs References) -> Writer<'refs> {
+        let url = url.into();
+        let url = match url.ends_with("/v1/logs") {
+            true => url,
+            false => format!("{}/v1/logs", url.trim_end_matches('/')),
+        };
+        Writer {
+            url,
+            refs,
+            service: "1c".to_string(),
+            infobase: String::new(),
+            server: String::new(),
+            batch_size: 1000,
+            records: Vec::new(),
+        }
+    }
+
+    pub fn service<S: Into<String>>(mut self, service: S) -> Writer<'refs> {
+        self.service = service.into();
+        self
+    }
+
+    pub fn infobase<S: Into<String>>(mut self, infobase: S) -> Writer<'refs> {
+        self.infobase = infobase.into();
+        self
+    }
+
+    pub fn server<S: Into<String>>(mut self, server: S) -> Writer<'refs> {
+        self.server = server.into();
+        self
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> Writer<'refs> {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    #[cfg(feature = "hashing")]
+    pub fn hash_identities(mut self, identities: &'refs HashedIdentities) -> Writer<'refs> {
+        self.refs = identities.references();
+        self
+    }
+
+    pub fn write(&mut self, event: &Event) -> io::Result<()> {
+        self.records.push(log_record(&event.resolve(self.refs)));
+        if self.records.len() >= self.batch_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        if self.records.is_empty() {
+            return Ok(());
+        }
+        let body = serde_json::to_vec(&self.body())?;
+        match ureq::post(&self.url)
+            .set("Content-Type", "application/json")
+            .send_bytes(&body)
+        {
+            Ok(_) => {}
+            Err(ureq::Error::Status(code, response)) => {
+                let text = response.into_string().unwrap_or_default();
+                return Err(io::Error::other(format!("OTLP error {code}: {text}")));
+            }
+            Err(error) => return Err(io::Error::other(error)),
+        }
+        self.records.clear();
+        Ok(())
+    }
+
+    fn body(&self) -> Value {
+        let mut resource = vec![attribute(
+            "service.name",
+            json!({ "stringValue": self.service }),
+        )];
+        if !self.infobase.is_empty() {
+            resource.push(attribute(
+                "infobase.name",
+                json!({ "stringValue": self.infobase }),
+            ));
+        }
+        if !self.server.is_empty() {
+            resource.push(attribute(
+                "host.name",
+                json!({ "stringValue": self.server }),
+            ));
+        }
+        json!({
+            "resourceLogs": [{
+                "resource": { "attributes": resource },
+                "scopeLogs": [{
+                    "scope": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+                    "logRecords": self.records,
+                }],
+            }],
+        })
+    }
+}
+
+impl Drop for Writer<'_> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+// SeverityNumber из спецификации OpenTelemetry
+pub fn severity(level: &EventLogLevel) -> (u8, &'static str) {
+    match level {
+        EventLogLevel::Error => (17, "ERROR"),
+        EventLogLevel::Warning => (13, "WARN"),
+        EventLogLevel::Information => (9, "INFO"),
+        EventLogLevel::Note => (5, "DEBUG"),
+    }
+}
+
+fn attribute(key: &str, value: Value) -> Value {
+    json!({ "key": key, "value": value })
+}
+
+// Дата журнала без часового пояса передается как UTC
+pub fn log_record(event: &EventResolved) -> Value {
+    let (number, text) = severity(event.log_level());
+    let timestamp = event
+        .date()
+        .and_utc()
+        .timestamp_nanos_opt()
+        .unwrap_or_default();
+    let attributes: Vec<_> = Column::ALL
+        .into_iter()
+        .filter(|column| !matches!(column, Column::Date | Column::Comment | Column::LogLevel))
+        .map(|column| {
+            let value = match column.value(event) {
+                // int64 в OTLP/JSON кодируется строкой
+                Cell::Number(n) => json!({ "intValue": n.to_string() }),
+                cell => json!({ "stringValue": cell.to_string() }),
+            };
+            attribute(&format!("1c.{column}"), value)
+        })
+        .collect();
+    json!({
+        "timeUnixNano": timestamp.to_string(),
+        "severityNumber": number,
+        "severityText": text,
+        "body": { "stringValue": event.comment() },
+        "attributes": attributes,
+    })
+}
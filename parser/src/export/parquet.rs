@@ -0,0 +1,424 @@
+use super::partition::{Granularity, Planner, Volumes};
+#[cfg(feature = "hashing")]
+use crate::hashing::HashedIdentities;
+use crate::{directory::LogDirectory, events::Event, query::CompiledQuery, references::References};
+use arrow_array::{
+    builder::{StringBuilder, StringDictionaryBuilder, TimestampSecondBuilder, UInt64Builder},
+    types::Int32Type,
+    ArrayRef, RecordBatch,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::NaiveDateTime;
+use parquet::{
+    arrow::ArrowWriter, basic::Compression, errors::Result, file::properties::WriterProperties,
+};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io,
+    io::Write,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+pub fn schema() -> SchemaRef {
+    let dictionary = || DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+    Arc::new(Schema::new(vec![
+        Field::new("date", DataType::Timestamp(TimeUnit::Second, None), false),
+        Field::new("transaction_status", dictionary(), false),
+        Field::new("transaction_data", DataType::Utf8, false),
+        Field::new("user", dictionary(), false),
+        Field::new("computer", dictionary(), false),
+        Field::new("application", dictionary(), false),
+        Field::new("connection", DataType::UInt64, false),
+        Field::new("event", dictionary(), false),
+        Field::new("log_level", dictionary(), false),
+        Field::new("comment", DataType::Utf8, false),
+        Field::new("metadata", dictionary(), false),
+        Field::new("data", DataType::Utf8, false),
+        Field::new("data_presentation", DataType::Utf8, false),
+        Field::new("worker_server", dictionary(), false),
+        Field::new("port", DataType::UInt64, false),
+        Field::new("sync_port", DataType::UInt64, false),
+        Field::new("session", DataType::UInt64, false),
+    ]))
+}
+
+pub struct BatchBuilder<'refs> {
+    refs: &'refs References,
+    len: usize,
+    date: TimestampSecondBuilder,
+    transaction_status: StringDictionaryBuilder<Int32Type>,
+    transaction_data: StringBuilder,
+    user: StringDictionaryBuilder<Int32Type>,
+    computer: StringDictionaryBuilder<Int32Type>,
+    application: StringDictionaryBuilder<Int32Type>,
+    connection: UInt64Builder,
+    event: StringDictionaryBuilder<Int32Type>,
+    log_level: StringDictionaryBuilder<Int32Type>,
+    comment: StringBuilder,
+    metadata: StringDictionaryBuilder<Int32Type>,
+    data: StringBuilder,
+    data_presentation: StringBuilder,
+    worker_server: StringDictionaryBuilder<Int32Type>,
+    port: UInt64Builder,
+    sync_port: UInt64Builder,
+    session: UInt64Builder,
+}
+
+impl<'refs> BatchBuilder<'refs> {
+    pub fn new(refs: &'refs References) -> BatchBuilder<'refs> {
+        BatchBuilder {
+            refs,
+            len: 0,
+            date: TimestampSecondBuilder::new(),
+            transaction_status: StringDictionaryBuilder::new(),
+            transaction_data: StringBuilder::new(),
+            user: StringDictionaryBuilder::new(),
+            computer: StringDictionaryBuilder::new(),
+            application: StringDictionaryBuilder::new(),
+            connection: UInt64Builder::new(),
+            event: StringDictionaryBuilder::new(),
+            log_level: StringDictionaryBuilder::new(),
+            comment: StringBuilder::new(),
+            metadata: StringDictionaryBuilder::new(),
+            data: StringBuilder::new(),
+            data_presentation: StringBuilder::new(),
+            worker_server: StringDictionaryBuilder::new(),
+            port: UInt64Builder::new(),
+            sync_port: UInt64Builder::new(),
+            session: UInt64Builder::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push(&mut self, event: &Event) {
+        let event = event.resolve(self.refs);
+        self.len += 1;
+        self.date.append_value(event.date().and_utc().timestamp());
+        self.transaction_status
+            .append_value(format!("{:?}", event.transaction_status()));
+        self.transaction_data.append_value(event.transaction_data());
+        self.user.append_value(event.user_name());
+        self.computer.append_value(event.computer());
+        self.application.append_value(event.application());
+        self.connection.append_value(event.connection() as u64);
+        self.event.append_value(event.event_name());
+        self.log_level
+            .append_value(format!("{:?}", event.log_level()));
+        self.comment.append_value(event.comment());
+        self.metadata.append_value(event.metadata_name());
+        self.data.append_value(event.data());
+        self.data_presentation
+            .append_value(event.data_presentation());
+        self.worker_server.append_value(event.worker_server());
+        self.port.append_value(event.port() as u64);
+        self.sync_port.append_value(event.sync_port() as u64);
+        self.session.append_value(event.session() as u64);
+    }
+
+    pub fn finish(&mut self) -> RecordBatch {
+        self.len = 0;
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.date.finish()),
+            Arc::new(self.transaction_status.finish()),
+            Arc::new(self.transaction_data.finish()),
+            Arc::new(self.user.finish()),
+            Arc::new(self.computer.finish()),
+            Arc::new(self.application.finish()),
+            Arc::new(self.connection.finish()),
+            Arc::new(self.event.finish()),
+            Arc::new(self.log_level.finish()),
+            Arc::new(self.comment.finish()),
+            Arc::new(self.metadata.finish()),
+            Arc::new(self.data.finish()),
+            Arc::new(self.data_presentation.finish()),
+            Arc::new(self.worker_server.finish()),
+            Arc::new(self.port.finish()),
+            Arc::new(self.sync_port.finish()),
+            Arc::new(self.session.finish()),
+        ];
+        RecordBatch::try_new(schema(), columns).expect("columns match schema")
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct WriterOptions {
+    batch_size: usize,
+    row_group_size: usize,
+    compression: Compression,
+}
+
+impl Default for WriterOptions {
+    fn default() -> Self {
+        WriterOptions {
+            batch_size: 8192,
+            row_group_size: 1024 * 1024,
+            compression: Compression::SNAPPY,
+        }
+    }
+}
+
+impl WriterOptions {
+    pub fn new() -> WriterOptions {
+        WriterOptions::default()
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> WriterOptions {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn row_group_size(mut self, row_group_size: usize) -> WriterOptions {
+        self.row_group_size = row_group_size.max(1);
+        self
+    }
+
+    pub fn compression(mut self, compression: Compression) -> WriterOptions {
+        self.compression = compression;
+        self
+    }
+}
+
+pub struct Writer<'refs, W: Write + Send> {
+    writer: ArrowWriter<W>,
+    builder: BatchBuilder<'refs>,
+    batch_size: usize,
+}
+
+impl<'refs, W: Write + Send> Writer<'refs, W> {
+    pub fn new(
+        out: W,
+        refs: &'refs References,
+        options: WriterOptions,
+    ) -> Result<Writer<'refs, W>> {
+        let properties = WriterProperties::builder()
+            .set_max_row_group_size(options.row_group_size)
+            .set_compression(options.compression)
+            .build();
+        Ok(Writer {
+            writer: ArrowWriter::try_new(out, schema(), Some(properties))?,
+            builder: BatchBuilder::new(refs),
+            batch_size: options.batch_size,
+        })
+    }
+
+    #[cfg(feature = "hashing")]
+    pub fn hash_identities(mut self, identities: &'refs HashedIdentities) -> Writer<'refs, W> {
+        self.builder.refs = identities.references();
+        self
+    }
+
+    pub fn write(&mut self, event: &Event) -> Result<()> {
+        self.builder.push(event);
+        if self.builder.len() >= self.batch_size {
+            self.writer.write(&self.builder.finish())?;
+        }
+        Ok(())
+    }
+
+    pub fn close(mut self) -> Result<W> {
+        if !self.builder.is_empty() {
+            self.writer.write(&self.builder.finish())?;
+        }
+        self.writer.into_inner()
+    }
+}
+
+// Каталог Parquet с партициями по дате: <dir>/year=2022/month=12/part-0.parquet.
+// Событие из уже закрытой партиции попадает в следующий файл этой партиции
+pub struct PartitionedWriter<'refs> {
+    dir: PathBuf,
+    refs: &'refs References,
+    granularity: Granularity,
+    options: WriterOptions,
+    current: Option<(NaiveDateTime, Writer<'refs, File>)>,
+    parts: HashMap<NaiveDateTime, usize>,
+    files: Vec<PathBuf>,
+}
+
+impl<'refs> PartitionedWriter<'refs> {
+    pub fn new<P: AsRef<Path>>(
+        dir: P,
+        refs: &'refs References,
+        granularity: Granularity,
+        options: WriterOptions,
+    ) -> PartitionedWriter<'refs> {
+        PartitionedWriter {
+            dir: dir.as_ref().to_path_buf(),
+            refs,
+            granularity,
+            options,
+            current: None,
+            parts: HashMap::new(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Размер партиций выбирает `planner` по объему выгружаемых событий
+    pub fn planned<P: AsRef<Path>>(
+        dir: P,
+        refs: &'refs References,
+        planner: &Planner,
+        volumes: &Volumes,
+        options: WriterOptions,
+    ) -> PartitionedWriter<'refs> {
+        PartitionedWriter::new(dir, refs, planner.plan(volumes), options)
+    }
+
+    #[cfg(feature = "hashing")]
+    pub fn hash_identities(
+        mut self,
+        identities: &'refs HashedIdentities,
+    ) -> PartitionedWriter<'refs> {
+        self.refs = identities.references();
+        self
+    }
+
+    pub fn granularity(&self) -> Granularity {
+        self.granularity
+    }
+
+    pub fn write(&mut self, event: &Event) -> Result<()> {
+        let start = self.granularity.start(event.date());
+        if self
+            .current
+            .as_ref()
+            .is_none_or(|(current, _)| *current != start)
+        {
+            self.close_current()?;
+            let part = self.parts.entry(start).or_default();
+            let dir = self.dir.join(self.granularity.path(start));
+            let path = dir.join(format!("part-{part}.parquet"));
+            *part += 1;
+            fs::create_dir_all(&dir)?;
+            let writer = Writer::new(File::create(&path)?, self.refs, self.options)?;
+            self.current = Some((start, writer));
+            self.files.push(path);
+        }
+        let (_, writer) = self.current.as_mut().expect("partition writer");
+        writer.write(event)
+    }
+
+    fn close_current(&mut self) -> Result<()> {
+        if let Some((_, writer)) = self.current.take() {
+            writer.close()?;
+        }
+        Ok(())
+    }
+
+    /// Записанные файлы в порядке создания
+    pub fn close(mut self) -> Result<Vec<PathBuf>> {
+        self.close_current()?;
+        Ok(self.files)
+    }
+}
+
+// Чтение каталога пачками Arrow с проекцией колонок, отбором и пределом.
+// На нем построена таблица DataFusion в export::datafusion
+pub struct Scan<'a> {
+    dir: &'a LogDirectory,
+    projection: Option<Vec<usize>>,
+    range: Option<(NaiveDateTime, NaiveDateTime)>,
+    filter: Option<CompiledQuery>,
+    limit: Option<usize>,
+    batch_size: usize,
+}
+
+impl<'a> Scan<'a> {
+    pub fn new(dir: &'a LogDirectory) -> Scan<'a> {
+        Scan {
+            dir,
+            projection: None,
+            range: None,
+            filter: None,
+            limit: None,
+            batch_size: 8192,
+        }
+    }
+
+    pub fn projection(mut self, columns: Vec<usize>) -> Scan<'a> {
+        self.projection = Some(columns);
+        self
+    }
+
+    pub fn range(mut self, from: NaiveDateTime, to: NaiveDateTime) -> Scan<'a> {
+        self.range = Some((from, to));
+        self
+    }
+
+    pub fn filter(mut self, filter: CompiledQuery) -> Scan<'a> {
+        self.filter = Some(filter);
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Scan<'a> {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> Scan<'a> {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        match &self.projection {
+            Some(columns) => Arc::new(schema().project(columns).expect("valid projection")),
+            None => schema(),
+        }
+    }
+
+    /// Чтение прекращается по `ControlFlow::Break` из `action` или по достижении предела
+    pub fn run<F>(&self, action: &mut F) -> io::Result<()>
+    where
+        F: FnMut(RecordBatch) -> ControlFlow<()>,
+    {
+        let project = |batch: RecordBatch| match &self.projection {
+            Some(columns) => batch.project(columns).map_err(io::Error::other),
+            None => Ok(batch),
+        };
+        let mut builder = BatchBuilder::new(self.dir.references());
+        let mut rows = 0;
+        let mut result = Ok(());
+        let mut push = |event: Event| {
+            if self
+                .filter
+                .as_ref()
+                .is_some_and(|filter| !filter.matches(&event))
+            {
+                return ControlFlow::Continue(());
+            }
+            builder.push(&event);
+            rows += 1;
+            if builder.len() >= self.batch_size {
+                match project(builder.finish()) {
+                    Ok(batch) => action(batch)?,
+                    Err(error) => {
+                        result = Err(error);
+                        return ControlFlow::Break(());
+                    }
+                }
+            }
+            match self.limit.is_some_and(|limit| rows >= limit) {
+                true => ControlFlow::Break(()),
+                false => ControlFlow::Continue(()),
+            }
+        };
+        self.dir.events_until(self.range, &mut push)?;
+        result?;
+        // После Break из action пачка уже передана и builder пуст
+        if !builder.is_empty() {
+            let _ = action(project(builder.finish())?);
+        }
+        Ok(())
+    }
+}
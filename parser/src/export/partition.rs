@@ -0,0 +1,218 @@
+// Выбор размера партиций для выгрузки в партиционированные хранилища (каталоги
+// Parquet и т.п.) по объему событий: без тысяч мелких файлов и без партиций
+// в несколько гигабайт. Объем оценивается по записям *.lgp без разбора полей.
+// Партиции по выбранному размеру пишет parquet::PartitionedWriter
+use crate::{events, parser::Parser, record::parse_record_date};
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use std::{collections::BTreeMap, io, ops::ControlFlow, path::Path};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Granularity {
+    Hour,
+    Day,
+    Month,
+}
+
+impl Granularity {
+    /// Начало партиции, в которую попадает `date`
+    pub fn start(self, date: NaiveDateTime) -> NaiveDateTime {
+        let day = date.date();
+        let day = match self {
+            Granularity::Month => day.with_day(1).unwrap_or(day),
+            _ => day,
+        };
+        let hour = match self {
+            Granularity::Hour => date.hour(),
+            _ => 0,
+        };
+        day.and_hms_opt(hour, 0, 0).unwrap_or(date)
+    }
+
+    /// Путь партиции в стиле Hive, например "year=2022/month=12/day=17"
+    pub fn path(self, date: NaiveDateTime) -> String {
+        let mut path = format!("year={}/month={:02}", date.year(), date.month());
+        if self != Granularity::Month {
+            path += &format!("/day={:02}", date.day());
+        }
+        if self == Granularity::Hour {
+            path += &format!("/hour={:02}", date.hour());
+        }
+        path
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Volume {
+    events: usize,
+    bytes: u64,
+}
+
+impl Volume {
+    pub fn events(&self) -> usize {
+        self.events
+    }
+
+    /// Размер исходных записей
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    fn add(&mut self, other: Volume) {
+        self.events += other.events;
+        self.bytes += other.bytes;
+    }
+}
+
+/// Число и размер записей по часам
+#[derive(Clone, Debug, Default)]
+pub struct Volumes {
+    hours: BTreeMap<NaiveDateTime, Volume>,
+}
+
+impl Volumes {
+    pub fn new() -> Volumes {
+        Volumes::default()
+    }
+
+    /// Добавляет записи файла *.lgp; поля записей, кроме даты, не разбираются
+    pub fn scan<P: AsRef<Path>>(&mut self, file_name: P) -> io::Result<()> {
+        events::parse_raw_records(file_name, &mut |_, raw| {
+            if let Ok(date) = parse_record_date(&mut Parser::new(raw)) {
+                self.add(date, raw.len() as u64);
+            }
+            ControlFlow::Continue(())
+        })
+    }
+
+    pub fn add(&mut self, date: NaiveDateTime, bytes: u64) {
+        let hour = Granularity::Hour.start(date);
+        self.hours
+            .entry(hour)
+            .or_default()
+            .add(Volume { events: 1, bytes });
+    }
+
+    pub fn merge(&mut self, other: &Volumes) {
+        for (&hour, &volume) in &other.hours {
+            self.hours.entry(hour).or_default().add(volume);
+        }
+    }
+
+    pub fn total(&self) -> Volume {
+        let mut total = Volume::default();
+        self.hours.values().for_each(|&volume| total.add(volume));
+        total
+    }
+
+    /// Объемы партиций в порядке их начала
+    pub fn partitions(&self, granularity: Granularity) -> Vec<(NaiveDateTime, Volume)> {
+        let mut partitions = BTreeMap::<NaiveDateTime, Volume>::new();
+        for (&hour, &volume) in &self.hours {
+            partitions
+                .entry(granularity.start(hour))
+                .or_default()
+                .add(volume);
+        }
+        partitions.into_iter().collect()
+    }
+}
+
+pub struct Planner {
+    max_bytes: u64,
+    ratio: f64,
+}
+
+impl Default for Planner {
+    fn default() -> Self {
+        Planner {
+            max_bytes: 512 * 1024 * 1024,
+            ratio: 0.2,
+        }
+    }
+}
+
+impl Planner {
+    pub fn new() -> Planner {
+        Planner::default()
+    }
+
+    /// Наибольший допустимый размер партиции, по умолчанию 512 МБ
+    pub fn max_bytes(mut self, max_bytes: u64) -> Planner {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Отношение размера выгрузки к размеру исходных записей, по умолчанию 0.2
+    /// (Parquet со сжатием)
+    pub fn ratio(mut self, ratio: f64) -> Planner {
+        self.ratio = ratio;
+        self
+    }
+
+    /// Самые крупные партиции, в которых оценка размера не превышает `max_bytes`:
+    /// меньше всего файлов. Если не помещаются и часы, выбираются часы.
+    pub fn plan(&self, volumes: &Volumes) -> Granularity {
+        [Granularity::Month, Granularity::Day]
+            .into_iter()
+            .find(|&granularity| {
+                volumes
+                    .partitions(granularity)
+                    .iter()
+                    .all(|(_, volume)| self.estimate(*volume) <= self.max_bytes)
+            })
+            .unwrap_or(Granularity::Hour)
+    }
+
+    /// Оценка размера партиции в выгрузке
+    pub fn estimate(&self, volume: Volume) -> u64 {
+        (volume.bytes as f64 * self.ratio) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn date(d: u32, h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2022, 12, d)
+            .unwrap()
+            .and_hms_opt(h, m, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_granularity() {
+        let value = date(17, 22, 42);
+        assert_eq!(Granularity::Hour.start(value), date(17, 22, 0));
+        assert_eq!(Granularity::Day.start(value), date(17, 0, 0));
+        assert_eq!(Granularity::Month.start(value), date(1, 0, 0));
+        assert_eq!(
+            Granularity::Hour.path(value),
+            "year=2022/month=12/day=17/hour=22"
+        );
+        assert_eq!(Granularity::Month.path(value), "year=2022/month=12");
+    }
+
+    #[test]
+    fn test_plan() {
+        let mut volumes = Volumes::new();
+        for hour in 0..24 {
+            volumes.add(date(17, hour, 5), 100);
+            volumes.add(date(18, hour, 5), 100);
+        }
+        assert_eq!(volumes.partitions(Granularity::Day).len(), 2);
+        assert_eq!(volumes.total().events(), 48);
+
+        let plan = |max_bytes| {
+            Planner::new()
+                .ratio(1.0)
+                .max_bytes(max_bytes)
+                .plan(&volumes)
+        };
+        assert_eq!(plan(10_000), Granularity::Month);
+        assert_eq!(plan(2_400), Granularity::Day);
+        assert_eq!(plan(150), Granularity::Hour);
+        assert_eq!(plan(50), Granularity::Hour);
+    }
+}
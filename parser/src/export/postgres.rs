@@ -0,0 +1,195 @@
+use super::{Cell, Column};
+#[cfg(feature = "hashing")]
+use crate::hashing::HashedIdentities;
+use crate::{events::Event, references::References};
+use chrono::NaiveDate;
+use std::io::{self, Write};
+
+const SIGNATURE: &[u8] = b"PGCOPY\n\xff\r\n\0";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Binary,
+}
+
+pub fn sql_type(column: Column) -> &'static str {
+    match column {
+        Column::Date => "timestamp",
+        Column::Connection | Column::Port | Column::SyncPort | Column::Session => "bigint",
+        _ => "text",
+    }
+}
+
+pub fn create_table(table: &str, columns: &[Column]) -> String {
+    let columns: Vec<_> = columns
+        .iter()
+        .map(|column| format!("{column} {} NOT NULL", sql_type(*column)))
+        .collect();
+    format!("CREATE TABLE {table} ({})", columns.join(", "))
+}
+
+pub fn copy_statement(table: &str, columns: &[Column], format: Format) -> String {
+    let columns: Vec<_> = columns.iter().map(Column::name).collect();
+    let format = match format {
+        Format::Text => "text",
+        Format::Binary => "binary",
+    };
+    format!(
+        "COPY {table} ({}) FROM STDIN (FORMAT {format})",
+        columns.join(", ")
+    )
+}
+
+// Поток для COPY ... FROM STDIN, out может быть и CopyInWriter клиента postgres
+pub struct Writer<'refs, W: Write> {
+    out: W,
+    refs: &'refs References,
+    columns: Vec<Column>,
+    format: Format,
+    started: bool,
+    buffer: Vec<u8>,
+}
+
+impl<'refs, W: Write> Writer<'refs, W> {
+    pub fn new(out: W, refs: &'refs References) -> Writer<'refs, W> {
+        Writer {
+            out,
+            refs,
+            columns: Column::ALL.to_vec(),
+            format: Format::Text,
+            started: false,
+            buffer: Vec::new(),
+        }
+    }
+
+    pub fn columns(mut self, columns: &[Column]) -> Writer<'refs, W> {
+        self.columns = columns.to_vec();
+        self
+    }
+
+    pub fn format(mut self, format: Format) -> Writer<'refs, W> {
+        self.format = format;
+        self
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        if !self.started {
+            self.started = true;
+            if self.format == Format::Binary {
+                self.out.write_all(SIGNATURE)?;
+                self.out.write_all(&0i32.to_be_bytes())?;
+                self.out.write_all(&0i32.to_be_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "hashing")]
+    pub fn hash_identities(mut self, identities: &'refs HashedIdentities) -> Writer<'refs, W> {
+        self.refs = identities.references();
+        self
+    }
+
+    pub fn write(&mut self, event: &Event) -> io::Result<()> {
+        self.start()?;
+        let event = event.resolve(self.refs);
+        self.buffer.clear();
+        match self.format {
+            Format::Text => {
+                for (i, column) in self.columns.iter().enumerate() {
+                    if i > 0 {
+                        self.buffer.push(b'\t');
+                    }
+                    write_text(&mut self.buffer, &column.value(&event));
+                }
+                self.buffer.push(b'\n');
+            }
+            Format::Binary => {
+                let count = self.columns.len() as i16;
+                self.buffer.extend_from_slice(&count.to_be_bytes());
+                for column in &self.columns {
+                    write_binary(&mut self.buffer, &column.value(&event));
+                }
+            }
+        }
+        self.out.write_all(&self.buffer)
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.start()?;
+        if self.format == Format::Binary {
+            self.out.write_all(&(-1i16).to_be_bytes())?;
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+fn write_text(out: &mut Vec<u8>, cell: &Cell) {
+    match cell {
+        Cell::Str(s) => {
+            for b in s.bytes() {
+                match b {
+                    b'\\' => out.extend_from_slice(b"\\\\"),
+                    b'\t' => out.extend_from_slice(b"\\t"),
+                    b'\n' => out.extend_from_slice(b"\\n"),
+                    b'\r' => out.extend_from_slice(b"\\r"),
+                    b => out.push(b),
+                }
+            }
+        }
+        Cell::Number(n) => {
+            let _ = write!(out, "{n}");
+        }
+        Cell::Date(date) => {
+            let _ = write!(out, "{}", date.format("%Y-%m-%d %H:%M:%S"));
+        }
+    }
+}
+
+// timestamp в бинарном формате: микросекунды от 2000-01-01
+fn write_binary(out: &mut Vec<u8>, cell: &Cell) {
+    match cell {
+        Cell::Str(s) => {
+            out.extend_from_slice(&(s.len() as i32).to_be_bytes());
+            out.extend_from_slice(s.as_bytes());
+        }
+        Cell::Number(n) => {
+            out.extend_from_slice(&8i32.to_be_bytes());
+            out.extend_from_slice(&(*n as i64).to_be_bytes());
+        }
+        Cell::Date(date) => {
+            let epoch = NaiveDate::from_ymd_opt(2000, 1, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap();
+            let micros = (*date - epoch).num_microseconds().unwrap_or_default();
+            out.extend_from_slice(&8i32.to_be_bytes());
+            out.extend_from_slice(&micros.to_be_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_text() {
+        let mut out = Vec::new();
+        write_text(&mut out, &Cell::Str("a\tb\\c\nd".into()));
+        assert_eq!(out, b"a\\tb\\\\c\\nd");
+    }
+
+    #[test]
+    fn test_write_binary() {
+        let date = NaiveDate::from_ymd_opt(2000, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 1)
+            .unwrap();
+        let mut out = Vec::new();
+        write_binary(&mut out, &Cell::Date(date));
+        assert_eq!(out, [0, 0, 0, 8, 0, 0, 0, 0, 0, 0x0f, 0x42, 0x40]);
+    }
+}
@@ -0,0 +1,152 @@
+#[cfg(feature = "hashing")]
+use crate::hashing::HashedIdentities;
+use crate::{
+    events::Event,
+    references::{References, User},
+};
+use rusqlite::{params, Connection, Result};
+use std::path::Path;
+
+const SCHEMA: &str = "
+    CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, uuid TEXT NOT NULL);
+    CREATE TABLE computers (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+    CREATE TABLE applications (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+    CREATE TABLE events (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+    CREATE TABLE metadata (id INTEGER PRIMARY KEY, name TEXT NOT NULL, uuid TEXT NOT NULL);
+    CREATE TABLE worker_servers (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+    CREATE TABLE ports (id INTEGER PRIMARY KEY, port INTEGER NOT NULL);
+    CREATE TABLE sync_ports (id INTEGER PRIMARY KEY, port INTEGER NOT NULL);
+    CREATE TABLE event_log (
+        id INTEGER PRIMARY KEY,
+        date TEXT NOT NULL,
+        transaction_status TEXT NOT NULL,
+        transaction_data TEXT NOT NULL,
+        user_id INTEGER NOT NULL,
+        computer_id INTEGER NOT NULL,
+        application_id INTEGER NOT NULL,
+        connection INTEGER NOT NULL,
+        event_id INTEGER NOT NULL,
+        log_level TEXT NOT NULL,
+        comment TEXT NOT NULL,
+        metadata_id INTEGER NOT NULL,
+        data TEXT NOT NULL,
+        data_presentation TEXT NOT NULL,
+        worker_server_id INTEGER NOT NULL,
+        port_id INTEGER NOT NULL,
+        sync_port_id INTEGER NOT NULL,
+        session INTEGER NOT NULL
+    );
+";
+
+// Индексы строятся после загрузки, так вставка заметно быстрее
+const INDEXES: &str = "
+    CREATE INDEX event_log_date ON event_log (date);
+    CREATE INDEX event_log_user ON event_log (user_id, date);
+    CREATE INDEX event_log_event ON event_log (event_id, date);
+";
+
+const INSERT: &str = "INSERT INTO event_log VALUES \
+    (NULL, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)";
+
+pub struct Writer {
+    connection: Connection,
+}
+
+impl Writer {
+    pub fn create<P: AsRef<Path>>(path: P, refs: &References) -> Result<Writer> {
+        Writer::from_connection(Connection::open(path)?, refs)
+    }
+
+    pub fn from_connection(connection: Connection, refs: &References) -> Result<Writer> {
+        connection.execute_batch(SCHEMA)?;
+        connection.execute_batch("BEGIN")?;
+        let writer = Writer { connection };
+        writer.write_references(refs)?;
+        Ok(writer)
+    }
+
+    // Пользователи и компьютеры заменяются хэшами из identities
+    #[cfg(feature = "hashing")]
+    pub fn hash_identities(self, identities: &HashedIdentities) -> Result<Writer> {
+        self.connection
+            .execute_batch("DELETE FROM users; DELETE FROM computers;")?;
+        self.write_users(identities.users())?;
+        self.write_names("computers", identities.computers())?;
+        Ok(self)
+    }
+
+    fn write_users(&self, users: &[User]) -> Result<()> {
+        let mut stmt = self
+            .connection
+            .prepare("INSERT INTO users VALUES (?1, ?2, ?3)")?;
+        for (id, user) in users.iter().enumerate() {
+            stmt.execute(params![id, user.name(), user.id().to_string()])?;
+        }
+        Ok(())
+    }
+
+    fn write_names(&self, table: &str, names: &[String]) -> Result<()> {
+        let sql = format!("INSERT INTO {table} VALUES (?1, ?2)");
+        let mut stmt = self.connection.prepare(&sql)?;
+        for (id, name) in names.iter().enumerate() {
+            stmt.execute(params![id, name])?;
+        }
+        Ok(())
+    }
+
+    fn write_references(&self, refs: &References) -> Result<()> {
+        self.write_users(refs.users())?;
+        let mut stmt = self
+            .connection
+            .prepare("INSERT INTO metadata VALUES (?1, ?2, ?3)")?;
+        for (id, metadata) in refs.metadata().iter().enumerate() {
+            stmt.execute(params![id, metadata.name(), metadata.id().to_string()])?;
+        }
+        for (table, names) in [
+            ("computers", refs.computers()),
+            ("applications", refs.applications()),
+            ("events", refs.events()),
+            ("worker_servers", refs.worker_servers()),
+        ] {
+            self.write_names(table, names)?;
+        }
+        for (table, ports) in [("ports", refs.ports()), ("sync_ports", refs.sync_ports())] {
+            let sql = format!("INSERT INTO {table} VALUES (?1, ?2)");
+            let mut stmt = self.connection.prepare(&sql)?;
+            for (id, port) in ports.iter().enumerate() {
+                stmt.execute(params![id, port])?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write(&mut self, event: &Event) -> Result<()> {
+        let mut stmt = self.connection.prepare_cached(INSERT)?;
+        stmt.execute(params![
+            event.date().format("%Y-%m-%d %H:%M:%S").to_string(),
+            format!("{:?}", event.transaction_status()),
+            event.transaction_data(),
+            event.user_id(),
+            event.computer_id(),
+            event.application_id(),
+            event.connection(),
+            event.event_id(),
+            format!("{:?}", event.log_level()),
+            event.comment(),
+            event.metadata_id(),
+            event.data(),
+            event.data_presentation(),
+            event.worker_server_id(),
+            event.port_id(),
+            event.sync_port_id(),
+            event.session(),
+        ])?;
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<Connection> {
+        self.connection.execute_batch(INDEXES)?;
+        self.connection.execute_batch("COMMIT")?;
+        Ok(self.connection)
+    }
+}
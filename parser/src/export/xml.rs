@@ -0,0 +1,156 @@
+#[cfg(feature = "hashing")]
+use crate::hashing::HashedIdentities;
+use crate::{
+    data::Value,
+    events::{Event, EventLogLevel, EventResolved, TransactionStatus},
+    references::References,
+};
+use quick_xml::escape::escape;
+use std::io::{self, Write};
+
+const HEADER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+    <v8e:EventLog xmlns:v8e=\"http://v8.1c.ru/eventLog\" \
+    xmlns:xs=\"http://www.w3.org/2001/XMLSchema\" \
+    xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\">\n";
+
+// Формат выгрузки журнала регистрации, который читает xml::XmlReader и сама 1С
+pub struct Writer<'refs, W: Write> {
+    out: W,
+    refs: &'refs References,
+    started: bool,
+}
+
+impl<'refs, W: Write> Writer<'refs, W> {
+    pub fn new(out: W, refs: &'refs References) -> Writer<'refs, W> {
+        Writer {
+            out,
+            refs,
+            started: false,
+        }
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        if !self.started {
+            self.started = true;
+            self.out.write_all(HEADER.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "hashing")]
+    pub fn hash_identities(mut self, identities: &'refs HashedIdentities) -> Writer<'refs, W> {
+        self.refs = identities.references();
+        self
+    }
+
+    pub fn write(&mut self, event: &Event) -> io::Result<()> {
+        self.start()?;
+        let event = event.resolve(self.refs);
+        let out = &mut self.out;
+        out.write_all(b"\t<v8e:Event>\n")?;
+        element(out, "Level", level(event.log_level()))?;
+        element(
+            out,
+            "Date",
+            &event.date().format("%Y-%m-%dT%H:%M:%S").to_string(),
+        )?;
+        element(out, "ApplicationName", event.application())?;
+        element(out, "Event", event.event_name())?;
+        let user = event.user();
+        match user.id().is_nil() {
+            true => element(out, "User", "")?,
+            false => element(out, "User", &user.id().to_string())?,
+        }
+        element(out, "UserName", user.name())?;
+        element(out, "Computer", event.computer())?;
+        element(out, "Metadata", event.metadata_name())?;
+        element(out, "MetadataPresentation", event.metadata_name())?;
+        element(out, "Comment", &event.comment())?;
+        data(out, &event)?;
+        element(out, "DataPresentation", &event.data_presentation())?;
+        element(
+            out,
+            "TransactionStatus",
+            transaction_status(event.transaction_status()),
+        )?;
+        let transaction = event.event().transaction().map(|info| {
+            let start = info.start().format("%Y-%m-%dT%H:%M:%S");
+            format!("{start} ({})", info.number())
+        });
+        element(out, "TransactionID", transaction.as_deref().unwrap_or(""))?;
+        element(out, "Connection", &event.connection().to_string())?;
+        element(out, "Session", &event.session().to_string())?;
+        element(out, "ServerName", event.worker_server())?;
+        element(out, "Port", &event.port().to_string())?;
+        element(out, "SyncPort", &event.sync_port().to_string())?;
+        out.write_all(b"\t</v8e:Event>\n")
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.start()?;
+        self.out.write_all(b"</v8e:EventLog>\n")?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+fn element<W: Write>(out: &mut W, name: &str, value: &str) -> io::Result<()> {
+    match value.is_empty() {
+        true => writeln!(out, "\t\t<v8e:{name}/>"),
+        false => writeln!(out, "\t\t<v8e:{name}>{}</v8e:{name}>", text(value)),
+    }
+}
+
+// Без &#13; парсер XML превратит \r\n в \n
+fn text(value: &str) -> String {
+    escape(value).replace('\r', "&#13;")
+}
+
+// Простые типы переносятся как есть, ссылки и составные значения - как пустые
+fn data<W: Write>(out: &mut W, event: &EventResolved) -> io::Result<()> {
+    let value = event.event().data_value();
+    let (kind, value) = match value.as_ref().and_then(Value::as_list) {
+        Some([Value::String(tag), value]) => match (tag.as_ref(), value) {
+            ("S", Value::String(s)) => ("xs:string", s.to_string()),
+            ("N", Value::Number(n)) => ("xs:decimal", n.to_string()),
+            ("B", Value::Number(n)) => ("xs:boolean", (*n == "1").to_string()),
+            ("D", Value::Number(d)) if d.len() == 14 => {
+                let date = format!(
+                    "{}-{}-{}T{}:{}:{}",
+                    &d[0..4],
+                    &d[4..6],
+                    &d[6..8],
+                    &d[8..10],
+                    &d[10..12],
+                    &d[12..14]
+                );
+                ("xs:dateTime", date)
+            }
+            _ => return writeln!(out, "\t\t<v8e:Data xsi:nil=\"true\"/>"),
+        },
+        _ => return writeln!(out, "\t\t<v8e:Data xsi:nil=\"true\"/>"),
+    };
+    writeln!(
+        out,
+        "\t\t<v8e:Data xsi:type=\"{kind}\">{}</v8e:Data>",
+        text(&value)
+    )
+}
+
+fn level(level: &EventLogLevel) -> &'static str {
+    match level {
+        EventLogLevel::Error => "Error",
+        EventLogLevel::Warning => "Warning",
+        EventLogLevel::Information => "Information",
+        EventLogLevel::Note => "Note",
+    }
+}
+
+fn transaction_status(status: &TransactionStatus) -> &'static str {
+    match status {
+        TransactionStatus::Unfinished => "Unfinished",
+        TransactionStatus::NotApplicable => "NotApplicable",
+        TransactionStatus::Committed => "Committed",
+        TransactionStatus::RolledBack => "RolledBack",
+    }
+}
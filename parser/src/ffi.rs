@@ -0,0 +1,367 @@
+// C ABI для встраивания парсера в C/C++/C# инструменты, заголовок include/event_log_parser.h,
+// обертка для .NET - bindings/dotnet/EventLogParser.cs.
+// Динамическая библиотека: cargo rustc -p event-log-parser --release --features ffi --crate-type cdylib
+use crate::{
+    directory::LogDirectory,
+    events::{EventLogLevel, EventOwned, TransactionStatus},
+    references::References,
+    source::EventSource,
+};
+use std::{
+    ffi::{c_char, c_int, CStr, CString},
+    ops::ControlFlow,
+    ptr,
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+pub const ELP_OK: c_int = 0;
+pub const ELP_END: c_int = 1;
+pub const ELP_INVALID_ARGUMENT: c_int = -1;
+pub const ELP_IO_ERROR: c_int = -2;
+pub const ELP_NO_EVENT: c_int = -3;
+
+pub const ELP_FIELD_USER: c_int = 0;
+pub const ELP_FIELD_COMPUTER: c_int = 1;
+pub const ELP_FIELD_APPLICATION: c_int = 2;
+pub const ELP_FIELD_EVENT: c_int = 3;
+pub const ELP_FIELD_COMMENT: c_int = 4;
+pub const ELP_FIELD_METADATA: c_int = 5;
+pub const ELP_FIELD_DATA: c_int = 6;
+pub const ELP_FIELD_DATA_PRESENTATION: c_int = 7;
+pub const ELP_FIELD_WORKER_SERVER: c_int = 8;
+pub const ELP_FIELD_TRANSACTION_DATA: c_int = 9;
+pub const ELP_FIELD_DATE: c_int = 10;
+pub const ELP_FIELD_USER_ID: c_int = 11;
+pub const ELP_FIELD_METADATA_ID: c_int = 12;
+
+/// Событие целиком за один вызов `elp_read_event`, строки UTF-8 как в `elp_event_field`.
+#[repr(C)]
+pub struct ElpEvent {
+    pub date: i64,
+    pub level: c_int,
+    pub transaction_status: c_int,
+    pub session: u64,
+    pub connection: u64,
+    pub port: u32,
+    pub sync_port: u32,
+    pub user: *const c_char,
+    pub user_id: *const c_char,
+    pub computer: *const c_char,
+    pub application: *const c_char,
+    pub event: *const c_char,
+    pub comment: *const c_char,
+    pub metadata: *const c_char,
+    pub metadata_id: *const c_char,
+    pub data: *const c_char,
+    pub data_presentation: *const c_char,
+    pub worker_server: *const c_char,
+    pub transaction_data: *const c_char,
+}
+
+// События читаются в отдельном потоке, C-код забирает их по одному
+pub struct ElpLog {
+    refs: References,
+    events: Receiver<Result<EventOwned, String>>,
+    current: Option<EventOwned>,
+    // Строки полей текущего события живут до следующего elp_next_event
+    strings: Vec<CString>,
+    error: Option<CString>,
+}
+
+impl ElpLog {
+    fn set_error(&mut self, error: String) {
+        self.error = CString::new(error.replace('\0', " ")).ok();
+    }
+
+    fn field(&self, field: c_int) -> Option<String> {
+        let event = self.current.as_ref()?;
+        let refs = &self.refs;
+        let name = |names: &[String], id: usize| names.get(id).cloned().unwrap_or_default();
+        Some(match field {
+            ELP_FIELD_USER => refs
+                .users()
+                .get(event.user_id())
+                .map(|user| user.name().to_string())
+                .unwrap_or_default(),
+            ELP_FIELD_COMPUTER => name(refs.computers(), event.computer_id()),
+            ELP_FIELD_APPLICATION => name(refs.applications(), event.application_id()),
+            ELP_FIELD_EVENT => name(refs.events(), event.event_id()),
+            ELP_FIELD_COMMENT => event.comment().to_string(),
+            ELP_FIELD_METADATA => refs
+                .metadata()
+                .get(event.metadata_id())
+                .map(|metadata| metadata.name().to_string())
+                .unwrap_or_default(),
+            ELP_FIELD_DATA => event.data().to_string(),
+            ELP_FIELD_DATA_PRESENTATION => event.data_presentation().to_string(),
+            ELP_FIELD_WORKER_SERVER => name(refs.worker_servers(), event.worker_server_id()),
+            ELP_FIELD_TRANSACTION_DATA => event.transaction_data().to_string(),
+            ELP_FIELD_DATE => event.date().format("%Y-%m-%dT%H:%M:%S").to_string(),
+            ELP_FIELD_USER_ID => refs
+                .users()
+                .get(event.user_id())
+                .map(|user| user.id().to_string())
+                .unwrap_or_default(),
+            ELP_FIELD_METADATA_ID => refs
+                .metadata()
+                .get(event.metadata_id())
+                .map(|metadata| metadata.id().to_string())
+                .unwrap_or_default(),
+            _ => return None,
+        })
+    }
+
+    fn text(&mut self, field: c_int) -> *const c_char {
+        let Some(value) = self.field(field) else {
+            return ptr::null();
+        };
+        let Ok(value) = CString::new(value.replace('\0', " ")) else {
+            return ptr::null();
+        };
+        // Буфер CString не перемещается вместе с вектором
+        let text = value.as_ptr();
+        self.strings.push(value);
+        text
+    }
+
+    fn event(&mut self) -> Option<ElpEvent> {
+        let event = self.current.as_ref()?;
+        let (date, level, transaction_status) = (
+            event.date().and_utc().timestamp(),
+            level_code(event.log_level()),
+            transaction_status_code(event.transaction_status()),
+        );
+        let (session, connection, port, sync_port) = (
+            event.session() as u64,
+            event.connection() as u64,
+            self.refs
+                .ports()
+                .get(event.port_id())
+                .copied()
+                .unwrap_or_default(),
+            self.refs
+                .sync_ports()
+                .get(event.sync_port_id())
+                .copied()
+                .unwrap_or_default(),
+        );
+        Some(ElpEvent {
+            date,
+            level,
+            transaction_status,
+            session,
+            connection,
+            port,
+            sync_port,
+            user: self.text(ELP_FIELD_USER),
+            user_id: self.text(ELP_FIELD_USER_ID),
+            computer: self.text(ELP_FIELD_COMPUTER),
+            application: self.text(ELP_FIELD_APPLICATION),
+            event: self.text(ELP_FIELD_EVENT),
+            comment: self.text(ELP_FIELD_COMMENT),
+            metadata: self.text(ELP_FIELD_METADATA),
+            metadata_id: self.text(ELP_FIELD_METADATA_ID),
+            data: self.text(ELP_FIELD_DATA),
+            data_presentation: self.text(ELP_FIELD_DATA_PRESENTATION),
+            worker_server: self.text(ELP_FIELD_WORKER_SERVER),
+            transaction_data: self.text(ELP_FIELD_TRANSACTION_DATA),
+        })
+    }
+}
+
+fn level_code(level: &EventLogLevel) -> c_int {
+    match level {
+        EventLogLevel::Information => 0,
+        EventLogLevel::Note => 1,
+        EventLogLevel::Warning => 2,
+        EventLogLevel::Error => 3,
+    }
+}
+
+fn transaction_status_code(status: &TransactionStatus) -> c_int {
+    match status {
+        TransactionStatus::NotApplicable => 0,
+        TransactionStatus::Committed => 1,
+        TransactionStatus::Unfinished => 2,
+        TransactionStatus::RolledBack => 3,
+    }
+}
+
+/// # Safety
+/// `path` - строка UTF-8 с завершающим нулем, `log` - указатель для результата.
+#[no_mangle]
+pub unsafe extern "C" fn elp_open_dir(path: *const c_char, log: *mut *mut ElpLog) -> c_int {
+    if path.is_null() || log.is_null() {
+        return ELP_INVALID_ARGUMENT;
+    }
+    *log = ptr::null_mut();
+    let Ok(path) = CStr::from_ptr(path).to_str() else {
+        return ELP_INVALID_ARGUMENT;
+    };
+    let dir = match LogDirectory::open(path) {
+        Ok(dir) => dir,
+        Err(_) => return ELP_IO_ERROR,
+    };
+    let refs = dir.references().clone();
+    let (sender, events) = mpsc::sync_channel(1024);
+    thread::spawn(move || {
+        let result = dir.parse_until(&mut |event| match sender.send(Ok(event.to_owned())) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(_) => ControlFlow::Break(()),
+        });
+        if let Err(error) = result {
+            let _ = sender.send(Err(error.to_string()));
+        }
+    });
+    *log = Box::into_raw(Box::new(ElpLog {
+        refs,
+        events,
+        current: None,
+        strings: Vec::new(),
+        error: None,
+    }));
+    ELP_OK
+}
+
+/// # Safety
+/// `log` - результат `elp_open_dir` или NULL, после вызова не используется.
+#[no_mangle]
+pub unsafe extern "C" fn elp_close(log: *mut ElpLog) {
+    if !log.is_null() {
+        drop(Box::from_raw(log));
+    }
+}
+
+/// Следующее событие: ELP_OK, ELP_END в конце журнала или ELP_IO_ERROR.
+///
+/// # Safety
+/// `log` - результат `elp_open_dir`.
+#[no_mangle]
+pub unsafe extern "C" fn elp_next_event(log: *mut ElpLog) -> c_int {
+    let Some(log) = log.as_mut() else {
+        return ELP_INVALID_ARGUMENT;
+    };
+    log.strings.clear();
+    log.current = None;
+    match log.events.recv() {
+        Ok(Ok(event)) => {
+            log.current = Some(event);
+            ELP_OK
+        }
+        Ok(Err(error)) => {
+            log.set_error(error);
+            ELP_IO_ERROR
+        }
+        Err(_) => ELP_END,
+    }
+}
+
+/// Текстовое поле текущего события (ELP_FIELD_*), NULL если события нет.
+/// Строка действительна до следующего вызова `elp_next_event`.
+///
+/// # Safety
+/// `log` - результат `elp_open_dir`.
+#[no_mangle]
+pub unsafe extern "C" fn elp_event_field(log: *mut ElpLog, field: c_int) -> *const c_char {
+    match log.as_mut() {
+        Some(log) => log.text(field),
+        None => ptr::null(),
+    }
+}
+
+/// Следующее событие со всеми полями: коды возврата как у `elp_next_event`.
+/// Строки действительны до следующего вызова `elp_next_event` или `elp_read_event`.
+///
+/// # Safety
+/// `log` - результат `elp_open_dir`, `event` - указатель для результата.
+#[no_mangle]
+pub unsafe extern "C" fn elp_read_event(log: *mut ElpLog, event: *mut ElpEvent) -> c_int {
+    if event.is_null() {
+        return ELP_INVALID_ARGUMENT;
+    }
+    let code = elp_next_event(log);
+    if code != ELP_OK {
+        return code;
+    }
+    match log.as_mut().and_then(|log| log.event()) {
+        Some(value) => {
+            event.write(value);
+            ELP_OK
+        }
+        None => ELP_NO_EVENT,
+    }
+}
+
+/// Дата события в секундах от 1970-01-01 без учета часового пояса.
+///
+/// # Safety
+/// `log` - результат `elp_open_dir`, `date` - указатель для результата.
+#[no_mangle]
+pub unsafe extern "C" fn elp_event_date(log: *const ElpLog, date: *mut i64) -> c_int {
+    let (Some(log), false) = (log.as_ref(), date.is_null()) else {
+        return ELP_INVALID_ARGUMENT;
+    };
+    let Some(event) = &log.current else {
+        return ELP_NO_EVENT;
+    };
+    *date = event.date().and_utc().timestamp();
+    ELP_OK
+}
+
+/// Уровень: 0 - информация, 1 - примечание, 2 - предупреждение, 3 - ошибка; -3 без события.
+///
+/// # Safety
+/// `log` - результат `elp_open_dir`.
+#[no_mangle]
+pub unsafe extern "C" fn elp_event_level(log: *const ElpLog) -> c_int {
+    match log.as_ref().and_then(|log| log.current.as_ref()) {
+        Some(event) => level_code(event.log_level()),
+        None => ELP_NO_EVENT,
+    }
+}
+
+/// Статус транзакции: 0 - нет, 1 - зафиксирована, 2 - не завершена, 3 - отменена; -3 без события.
+///
+/// # Safety
+/// `log` - результат `elp_open_dir`.
+#[no_mangle]
+pub unsafe extern "C" fn elp_event_transaction_status(log: *const ElpLog) -> c_int {
+    match log.as_ref().and_then(|log| log.current.as_ref()) {
+        Some(event) => transaction_status_code(event.transaction_status()),
+        None => ELP_NO_EVENT,
+    }
+}
+
+/// Номер сеанса текущего события, 0 без события.
+///
+/// # Safety
+/// `log` - результат `elp_open_dir`.
+#[no_mangle]
+pub unsafe extern "C" fn elp_event_session(log: *const ElpLog) -> u64 {
+    log.as_ref()
+        .and_then(|log| log.current.as_ref())
+        .map_or(0, |event| event.session() as u64)
+}
+
+/// Номер соединения текущего события, 0 без события.
+///
+/// # Safety
+/// `log` - результат `elp_open_dir`.
+#[no_mangle]
+pub unsafe extern "C" fn elp_event_connection(log: *const ElpLog) -> u64 {
+    log.as_ref()
+        .and_then(|log| log.current.as_ref())
+        .map_or(0, |event| event.connection() as u64)
+}
+
+/// Текст последней ошибки или NULL.
+///
+/// # Safety
+/// `log` - результат `elp_open_dir`.
+#[no_mangle]
+pub unsafe extern "C" fn elp_last_error(log: *const ElpLog) -> *const c_char {
+    log.as_ref()
+        .and_then(|log| log.error.as_ref())
+        .map_or(ptr::null(), |error| error.as_ptr())
+}
@@ -0,0 +1,197 @@
+use crate::{
+    events::{Event, EventLogLevel},
+    references::References,
+};
+use chrono::NaiveDateTime;
+use uuid::Uuid;
+
+#[cfg(feature = "regex")]
+pub use regex::bytes::Regex;
+
+#[derive(Clone, Debug, Default)]
+pub struct EventFilter {
+    levels: Option<Vec<EventLogLevel>>,
+    events: Option<Vec<String>>,
+    users: Option<Vec<String>>,
+    computers: Option<Vec<String>>,
+    metadata: Option<Vec<Uuid>>,
+    comment: Option<String>,
+    #[cfg(feature = "regex")]
+    comment_regex: Option<Regex>,
+    #[cfg(feature = "regex")]
+    data_presentation_regex: Option<Regex>,
+    range: Option<(NaiveDateTime, NaiveDateTime)>,
+}
+
+impl EventFilter {
+    pub fn new() -> EventFilter {
+        EventFilter::default()
+    }
+
+    pub fn levels<I>(mut self, levels: I) -> EventFilter
+    where
+        I: IntoIterator<Item = EventLogLevel>,
+    {
+        self.levels = Some(levels.into_iter().collect());
+        self
+    }
+
+    pub fn events<I, S>(mut self, names: I) -> EventFilter
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.events = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn users<I, S>(mut self, names: I) -> EventFilter
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.users = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn computers<I, S>(mut self, names: I) -> EventFilter
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.computers = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn metadata<I>(mut self, ids: I) -> EventFilter
+    where
+        I: IntoIterator<Item = Uuid>,
+    {
+        self.metadata = Some(ids.into_iter().collect());
+        self
+    }
+
+    pub fn comment_contains<S: Into<String>>(mut self, text: S) -> EventFilter {
+        self.comment = Some(text.into());
+        self
+    }
+
+    // Проверяется по байтам из файла: кавычки в них удвоены
+    #[cfg(feature = "regex")]
+    pub fn comment_regex(mut self, regex: Regex) -> EventFilter {
+        self.comment_regex = Some(regex);
+        self
+    }
+
+    #[cfg(feature = "regex")]
+    pub fn data_presentation_regex(mut self, regex: Regex) -> EventFilter {
+        self.data_presentation_regex = Some(regex);
+        self
+    }
+
+    pub fn range(mut self, from: NaiveDateTime, to: NaiveDateTime) -> EventFilter {
+        self.range = Some((from, to));
+        self
+    }
+
+    pub fn compile(&self, refs: &References) -> CompiledFilter {
+        CompiledFilter {
+            levels: self.levels.clone(),
+            events: self
+                .events
+                .as_ref()
+                .map(|names| ids(refs.events(), |x| names.iter().any(|n| n == x))),
+            users: self
+                .users
+                .as_ref()
+                .map(|names| ids(refs.users(), |x| names.iter().any(|n| n == x.name()))),
+            computers: self
+                .computers
+                .as_ref()
+                .map(|names| ids(refs.computers(), |x| names.iter().any(|n| n == x))),
+            metadata: self
+                .metadata
+                .as_ref()
+                .map(|uuids| ids(refs.metadata(), |x| uuids.contains(&x.id()))),
+            comment: self.comment.clone(),
+            #[cfg(feature = "regex")]
+            comment_regex: self.comment_regex.clone(),
+            #[cfg(feature = "regex")]
+            data_presentation_regex: self.data_presentation_regex.clone(),
+            range: self.range,
+        }
+    }
+}
+
+// Имена сопоставляются со всеми номерами, т.к. в справочниках бывают повторы
+fn ids<T, F>(values: &[T], matches: F) -> Vec<bool>
+where
+    F: Fn(&T) -> bool,
+{
+    values.iter().map(matches).collect()
+}
+
+fn contains(ids: &Option<Vec<bool>>, id: usize) -> bool {
+    ids.as_ref()
+        .is_none_or(|ids| ids.get(id).copied().unwrap_or(false))
+}
+
+#[derive(Clone, Debug)]
+pub struct CompiledFilter {
+    levels: Option<Vec<EventLogLevel>>,
+    events: Option<Vec<bool>>,
+    users: Option<Vec<bool>>,
+    computers: Option<Vec<bool>>,
+    metadata: Option<Vec<bool>>,
+    comment: Option<String>,
+    #[cfg(feature = "regex")]
+    comment_regex: Option<Regex>,
+    #[cfg(feature = "regex")]
+    data_presentation_regex: Option<Regex>,
+    range: Option<(NaiveDateTime, NaiveDateTime)>,
+}
+
+impl CompiledFilter {
+    pub fn matches(&self, event: &Event) -> bool {
+        #[cfg(feature = "regex")]
+        if !self.matches_comment(event.comment.raw())
+            || !self.matches_data_presentation(event.data_presentation.raw())
+        {
+            return false;
+        }
+        self.matches_date(event.date()) && self.matches_fields(event)
+    }
+
+    pub(crate) fn matches_fields(&self, event: &Event) -> bool {
+        self.levels
+            .as_ref()
+            .is_none_or(|levels| levels.contains(event.log_level()))
+            && contains(&self.events, event.event_id())
+            && contains(&self.users, event.user_id())
+            && contains(&self.computers, event.computer_id())
+            && contains(&self.metadata, event.metadata_id())
+            && self
+                .comment
+                .as_ref()
+                .is_none_or(|text| event.comment().contains(text.as_str()))
+    }
+
+    pub(crate) fn matches_date(&self, date: NaiveDateTime) -> bool {
+        self.range
+            .is_none_or(|(from, to)| date >= from && date <= to)
+    }
+
+    #[cfg(feature = "regex")]
+    pub(crate) fn matches_comment(&self, raw: &[u8]) -> bool {
+        self.comment_regex
+            .as_ref()
+            .is_none_or(|regex| regex.is_match(raw))
+    }
+
+    #[cfg(feature = "regex")]
+    pub(crate) fn matches_data_presentation(&self, raw: &[u8]) -> bool {
+        self.data_presentation_regex
+            .as_ref()
+            .is_none_or(|regex| regex.is_match(raw))
+    }
+}
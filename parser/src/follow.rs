@@ -0,0 +1,110 @@
+use crate::{
+    events::{parse_buffer, Event, ParseOptions, ParseSummary},
+    reader::ChunkReader,
+};
+use std::{
+    fs::File,
+    io::{self, Seek, SeekFrom},
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+};
+
+pub struct Follower {
+    path: PathBuf,
+    reader: ChunkReader<File>,
+    offset: u64,
+}
+
+impl Follower {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Follower> {
+        Follower::open_at(path, 0)
+    }
+
+    pub fn open_at<P: AsRef<Path>>(path: P, offset: u64) -> io::Result<Follower> {
+        let path = path.as_ref().to_path_buf();
+        let reader = ChunkReader::new(File::open(&path)?);
+        Ok(Follower {
+            path,
+            reader,
+            offset,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn poll<F>(&mut self, action: &mut F) -> io::Result<ControlFlow<()>>
+    where
+        F: FnMut(Event) -> ControlFlow<()>,
+    {
+        let file = self.reader.get_mut();
+        if file.metadata()?.len() < self.offset {
+            // Файл был усечен или пересоздан
+            *file = File::open(&self.path)?;
+            self.offset = 0;
+        }
+        file.seek(SeekFrom::Start(self.offset))?;
+
+        let mut offset = self.offset;
+        let mut options = ParseOptions::default();
+        let mut summary = ParseSummary::default();
+        let mut flow = ControlFlow::Continue(());
+        self.reader.read(&mut |buffer| {
+            let result = parse_buffer(buffer, offset, &mut options, &mut summary, action);
+            match result {
+                ControlFlow::Continue(read) => offset += read as u64,
+                ControlFlow::Break(()) => {
+                    offset = summary.last_offset();
+                    flow = ControlFlow::Break(());
+                }
+            }
+            result
+        })?;
+        self.offset = offset;
+
+        Ok(flow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const RECORD: &[u8] =
+        b"{20221217221504,N,\r\n{0,0},1,1,1,1,1,I,\"\",0,\r\n{\"U\"},\"\",0,0,0,2,0,\r\n{0}\r\n}";
+
+    #[test]
+    fn test_follow() {
+        let path = std::env::temp_dir().join("event-log-parser-test-follow.lgp");
+        let mut file = File::create(&path).unwrap();
+        file.write_all(RECORD).unwrap();
+
+        let mut total_events = 0;
+        let mut follower = Follower::open(&path).unwrap();
+        let mut action = |_: Event| {
+            total_events += 1;
+            ControlFlow::Continue(())
+        };
+
+        assert!(follower.poll(&mut action).unwrap().is_continue());
+        assert_eq!(follower.offset(), RECORD.len() as u64);
+
+        file.write_all(b",\r\n").unwrap();
+        file.write_all(&RECORD[..20]).unwrap();
+        assert!(follower.poll(&mut action).unwrap().is_continue());
+        assert_eq!(follower.offset(), RECORD.len() as u64);
+
+        file.write_all(&RECORD[20..]).unwrap();
+        assert!(follower.poll(&mut action).unwrap().is_continue());
+        assert_eq!(follower.offset(), RECORD.len() as u64 * 2 + 3);
+
+        assert_eq!(total_events, 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{
+    directory::LogDirectory,
+    events::{EventLogLevel, EventResolved, TransactionStatus},
+    query::{self, Query},
+    references::References,
+    source::EventSource,
+    watch::Watcher,
+};
+use chrono::NaiveDateTime;
+use std::{
+    io,
+    net::SocketAddr,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+pub mod proto {
+    tonic::include_proto!("event_log");
+}
+
+use proto::event_log_server::{EventLog, EventLogServer};
+
+pub type EventStream = ReceiverStream<Result<proto::Event, Status>>;
+
+pub struct EventLogService {
+    dir: PathBuf,
+    interval: Duration,
+}
+
+impl EventLogService {
+    pub fn new<P: AsRef<Path>>(dir: P) -> EventLogService {
+        EventLogService {
+            dir: dir.as_ref().to_path_buf(),
+            interval: Duration::from_secs(1),
+        }
+    }
+
+    // Период опроса каталога для StreamEvents
+    pub fn interval(mut self, interval: Duration) -> EventLogService {
+        self.interval = interval;
+        self
+    }
+
+    pub fn into_server(self) -> EventLogServer<EventLogService> {
+        EventLogServer::new(self)
+    }
+
+    fn open(&self) -> Result<LogDirectory, Status> {
+        LogDirectory::open(&self.dir).map_err(io_status)
+    }
+}
+
+pub async fn serve<P: AsRef<Path>>(
+    dir: P,
+    addr: SocketAddr,
+) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(EventLogService::new(dir).into_server())
+        .serve(addr)
+        .await
+}
+
+#[tonic::async_trait]
+impl EventLog for EventLogService {
+    type ListEventsStream = EventStream;
+    type StreamEventsStream = EventStream;
+
+    async fn list_events(
+        &self,
+        request: Request<proto::ListEventsRequest>,
+    ) -> Result<Response<EventStream>, Status> {
+        let request = request.into_inner();
+        let from = date(&request.from)?.map(|(from, _)| from);
+        let to = date(&request.to)?.map(|(_, to)| to);
+        let limit = match request.limit {
+            0 => usize::MAX,
+            limit => limit as usize,
+        };
+        let dir = self.open()?;
+        let query = compile(&request.query, dir.references())?;
+
+        // Журнал читается в отдельном потоке, пока клиент принимает события
+        let (sender, receiver) = mpsc::channel(256);
+        thread::spawn(move || {
+            let refs = dir.references();
+            let mut count = 0;
+            let result = dir.parse_until(&mut |event| {
+                let matches = from.is_none_or(|from| event.date() >= from)
+                    && to.is_none_or(|to| event.date() <= to)
+                    && query.as_ref().is_none_or(|query| query.matches(&event));
+                if matches {
+                    count += 1;
+                    if sender
+                        .blocking_send(Ok(event_proto(&event.resolve(refs))))
+                        .is_err()
+                    {
+                        return ControlFlow::Break(());
+                    }
+                }
+                match count < limit {
+                    true => ControlFlow::Continue(()),
+                    false => ControlFlow::Break(()),
+                }
+            });
+            if let Err(error) = result {
+                let _ = sender.blocking_send(Err(io_status(error)));
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    async fn stream_events(
+        &self,
+        request: Request<proto::StreamEventsRequest>,
+    ) -> Result<Response<EventStream>, Status> {
+        let request = request.into_inner();
+        let mut watcher = Watcher::open(&self.dir).map_err(io_status)?;
+        let query = compile(&request.query, watcher.references())?;
+        let interval = self.interval;
+
+        let (sender, receiver) = mpsc::channel(256);
+        thread::spawn(move || {
+            // Уже записанные события пропускаются
+            let mut skip = true;
+            while !sender.is_closed() {
+                let result = watcher.poll(&mut |event| {
+                    if skip
+                        || query
+                            .as_ref()
+                            .is_some_and(|query| !query.matches(event.event()))
+                    {
+                        return ControlFlow::Continue(());
+                    }
+                    match sender.blocking_send(Ok(event_proto(&event))) {
+                        Ok(()) => ControlFlow::Continue(()),
+                        Err(_) => ControlFlow::Break(()),
+                    }
+                });
+                if let Err(error) = result {
+                    let _ = sender.blocking_send(Err(io_status(error)));
+                    return;
+                }
+                skip = false;
+                thread::sleep(interval);
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    async fn get_references(
+        &self,
+        _request: Request<proto::GetReferencesRequest>,
+    ) -> Result<Response<proto::References>, Status> {
+        let dir = self.open()?;
+        Ok(Response::new(references_proto(dir.references())))
+    }
+}
+
+fn io_status(error: io::Error) -> Status {
+    match error.kind() {
+        io::ErrorKind::NotFound => Status::not_found(error.to_string()),
+        _ => Status::internal(error.to_string()),
+    }
+}
+
+fn date(text: &str) -> Result<Option<(NaiveDateTime, NaiveDateTime)>, Status> {
+    match text {
+        "" => Ok(None),
+        text => query::parse_date(text)
+            .map(Some)
+            .ok_or_else(|| Status::invalid_argument(format!("invalid date: {text}"))),
+    }
+}
+
+fn compile(text: &str, refs: &References) -> Result<Option<query::CompiledQuery>, Status> {
+    match text {
+        "" => Ok(None),
+        text => Query::parse(text)
+            .map(|query| Some(query.compile(refs)))
+            .map_err(|error| Status::invalid_argument(error.to_string())),
+    }
+}
+
+pub fn event_proto(event: &EventResolved) -> proto::Event {
+    let level = match event.log_level() {
+        EventLogLevel::Information => proto::Level::Information,
+        EventLogLevel::Note => proto::Level::Note,
+        EventLogLevel::Warning => proto::Level::Warning,
+        EventLogLevel::Error => proto::Level::Error,
+    };
+    let transaction_status = match event.transaction_status() {
+        TransactionStatus::NotApplicable => proto::TransactionStatus::NotApplicable,
+        TransactionStatus::Committed => proto::TransactionStatus::Committed,
+        TransactionStatus::Unfinished => proto::TransactionStatus::Unfinished,
+        TransactionStatus::RolledBack => proto::TransactionStatus::RolledBack,
+    };
+    proto::Event {
+        date: event.date().format("%Y-%m-%dT%H:%M:%S").to_string(),
+        transaction_status: transaction_status.into(),
+        transaction_data: event.transaction_data().to_string(),
+        user: event.user_name().to_string(),
+        user_id: event.user().id().to_string(),
+        computer: event.computer().to_string(),
+        application: event.application().to_string(),
+        connection: event.connection() as u64,
+        event: event.event_name().to_string(),
+        level: level.into(),
+        comment: event.comment().into_owned(),
+        metadata: event.metadata_name().to_string(),
+        metadata_id: event.metadata().id().to_string(),
+        data: event.data().to_string(),
+        data_presentation: event.data_presentation().into_owned(),
+        worker_server: event.worker_server().to_string(),
+        port: event.port(),
+        sync_port: event.sync_port(),
+        session: event.session() as u64,
+    }
+}
+
+pub fn references_proto(refs: &References) -> proto::References {
+    proto::References {
+        users: refs
+            .users()
+            .iter()
+            .map(|user| proto::User {
+                id: user.id().to_string(),
+                name: user.name().to_string(),
+            })
+            .collect(),
+        computers: refs.computers().to_vec(),
+        applications: refs.applications().to_vec(),
+        events: refs.events().to_vec(),
+        metadata: refs
+            .metadata()
+            .iter()
+            .map(|metadata| proto::Metadata {
+                id: metadata.id().to_string(),
+                name: metadata.name().to_string(),
+            })
+            .collect(),
+        worker_servers: refs.worker_servers().to_vec(),
+        ports: refs.ports().to_vec(),
+        sync_ports: refs.sync_ports().to_vec(),
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{
+    events::Event,
+    references::{Placeholder, References, User},
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::{fmt::Write, sync::OnceLock};
+use uuid::Builder;
+
+pub fn hash(secret: &[u8], value: &str) -> String {
+    hex(&mac(secret, value))
+}
+
+fn mac(secret: &[u8], value: &str) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(value.as_bytes());
+    mac.finalize().into_bytes().into()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        write!(result, "{b:02x}").unwrap();
+    }
+    result
+}
+
+/// Что хэшируется для пользователя
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UserKey {
+    /// Идентификатор пользователя, хэш не меняется при переименовании
+    #[default]
+    Id,
+    Name,
+}
+
+// Копия справочников, в которой пользователи и компьютеры заменены хэшами.
+// Пустые записи и номера вне справочника получают пустое значение, чтобы
+// не соединяться между собой по хэшу пустой строки
+pub struct HashedIdentities {
+    refs: References,
+}
+
+impl HashedIdentities {
+    pub fn new(refs: &References, secret: &[u8]) -> HashedIdentities {
+        HashedIdentities::with_key(refs, secret, UserKey::default())
+    }
+
+    pub fn with_key(refs: &References, secret: &[u8], key: UserKey) -> HashedIdentities {
+        let mut refs = refs.clone();
+        refs.lookup = OnceLock::new();
+        for user in &mut refs.users {
+            if user.is_placeholder() {
+                continue;
+            }
+            let mac = match key {
+                UserKey::Id => mac(secret, &user.id.to_string()),
+                UserKey::Name => mac(secret, &user.name),
+            };
+            // Идентификатор из того же хэша, чтобы исходный не попал в выгрузку
+            user.id = Builder::from_custom_bytes(mac[..16].try_into().unwrap()).into_uuid();
+            user.name = hex(&mac);
+        }
+        for computer in &mut refs.computers {
+            if !computer.is_placeholder() {
+                *computer = hash(secret, computer);
+            }
+        }
+        HashedIdentities { refs }
+    }
+
+    /// Справочники для выгрузок: параметр hash_identities у Writer
+    pub fn references(&self) -> &References {
+        &self.refs
+    }
+
+    pub fn users(&self) -> &[User] {
+        self.refs.users()
+    }
+
+    pub fn computers(&self) -> &[String] {
+        self.refs.computers()
+    }
+
+    pub fn user(&self, event: &Event) -> &str {
+        self.refs
+            .users()
+            .get(event.user_id())
+            .map_or("", |user| user.name())
+    }
+
+    pub fn computer(&self, event: &Event) -> &str {
+        self.refs
+            .computers()
+            .get(event.computer_id())
+            .map_or("", String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(
+            hash(b"Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
@@ -0,0 +1,365 @@
+use crate::events::{self, Event};
+use chrono::{DateTime, NaiveDateTime, Timelike};
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+const MAGIC: &[u8; 8] = b"LGPIDX01";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    Minute,
+    Records(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    User(usize),
+    Event(usize),
+    Metadata(usize),
+}
+
+impl Key {
+    pub fn matches(&self, event: &Event) -> bool {
+        match *self {
+            Key::User(id) => event.user_id() == id,
+            Key::Event(id) => event.event_id() == id,
+            Key::Metadata(id) => event.metadata_id() == id,
+        }
+    }
+}
+
+// Фильтр Блума по номерам из справочников: ложные срабатывания около 1%
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bloom {
+    hashes: u64,
+    bits: Vec<u64>,
+}
+
+impl Bloom {
+    pub fn new(items: usize) -> Bloom {
+        Bloom {
+            hashes: 7,
+            bits: vec![0; (items * 10).div_ceil(64).max(1)],
+        }
+    }
+
+    fn positions(&self, value: u64) -> impl Iterator<Item = usize> {
+        let h1 = mix(value);
+        let h2 = mix(h1) | 1;
+        let len = self.bits.len() as u64 * 64;
+        (0..self.hashes).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % len) as usize)
+    }
+
+    pub fn insert(&mut self, value: usize) {
+        for pos in self.positions(value as u64) {
+            self.bits[pos / 64] |= 1 << (pos % 64);
+        }
+    }
+
+    pub fn contains(&self, value: usize) -> bool {
+        self.positions(value as u64)
+            .all(|pos| self.bits[pos / 64] & (1 << (pos % 64)) != 0)
+    }
+
+    fn from_set(set: &HashSet<usize>) -> Bloom {
+        let mut bloom = Bloom::new(set.len());
+        set.iter().for_each(|value| bloom.insert(*value));
+        bloom
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Filters {
+    users: Bloom,
+    events: Bloom,
+    metadata: Bloom,
+}
+
+impl Filters {
+    pub fn users(&self) -> &Bloom {
+        &self.users
+    }
+
+    pub fn events(&self) -> &Bloom {
+        &self.events
+    }
+
+    pub fn metadata(&self) -> &Bloom {
+        &self.metadata
+    }
+
+    pub fn may_contain(&self, key: Key) -> bool {
+        match key {
+            Key::User(id) => self.users.contains(id),
+            Key::Event(id) => self.events.contains(id),
+            Key::Metadata(id) => self.metadata.contains(id),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexBuilder {
+    step: Step,
+    filters: bool,
+}
+
+impl Default for IndexBuilder {
+    fn default() -> Self {
+        IndexBuilder {
+            step: Step::Minute,
+            filters: false,
+        }
+    }
+}
+
+impl IndexBuilder {
+    pub fn new() -> IndexBuilder {
+        IndexBuilder::default()
+    }
+
+    pub fn step(mut self, step: Step) -> IndexBuilder {
+        self.step = step;
+        self
+    }
+
+    pub fn filters(mut self, filters: bool) -> IndexBuilder {
+        self.filters = filters;
+        self
+    }
+
+    pub fn build<P: AsRef<Path>>(&self, path: P) -> io::Result<Index> {
+        let path = path.as_ref();
+        let size = fs::metadata(path)?.len();
+        let mut entries = Vec::new();
+        let mut max: Option<NaiveDateTime> = None;
+        let mut count = 0;
+        let mut sets: [HashSet<usize>; 3] = Default::default();
+        events::parse(path, &mut |event| {
+            if event.end_offset() > size {
+                return;
+            }
+            let date = event.date();
+            if let Some(before) = max {
+                let boundary = match self.step {
+                    Step::Minute => minute(date) > minute(before),
+                    Step::Records(n) => count % n.max(1) == 0,
+                };
+                if boundary {
+                    entries.push(Entry {
+                        before,
+                        offset: event.offset(),
+                    });
+                }
+            }
+            max = max.max(Some(date));
+            count += 1;
+            if self.filters {
+                sets[0].insert(event.user_id());
+                sets[1].insert(event.event_id());
+                sets[2].insert(event.metadata_id());
+            }
+        })?;
+        let filters = self.filters.then(|| Filters {
+            users: Bloom::from_set(&sets[0]),
+            events: Bloom::from_set(&sets[1]),
+            metadata: Bloom::from_set(&sets[2]),
+        });
+        Ok(Index {
+            size,
+            entries,
+            filters,
+        })
+    }
+
+    // Построить индекс и сохранить его рядом с файлом журнала
+    pub fn create<P: AsRef<Path>>(&self, path: P) -> io::Result<Index> {
+        let index = self.build(&path)?;
+        index.save(sidecar(path))?;
+        Ok(index)
+    }
+}
+
+// Все события файла до offset имеют дату не позже before
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Entry {
+    before: NaiveDateTime,
+    offset: u64,
+}
+
+impl Entry {
+    pub fn before(&self) -> NaiveDateTime {
+        self.before
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Index {
+    size: u64,
+    entries: Vec<Entry>,
+    filters: Option<Filters>,
+}
+
+impl Index {
+    pub fn build<P: AsRef<Path>>(path: P) -> io::Result<Index> {
+        IndexBuilder::new().build(path)
+    }
+
+    pub fn build_with<P: AsRef<Path>>(path: P, step: Step) -> io::Result<Index> {
+        IndexBuilder::new().step(step).build(path)
+    }
+
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Index> {
+        IndexBuilder::new().create(path)
+    }
+
+    // Индекс из сохраненного файла, если он подходит к текущему файлу журнала.
+    // Журнал только дописывается, поэтому индекс остается верным, пока файл не стал меньше
+    pub fn for_file<P: AsRef<Path>>(path: P) -> io::Result<Option<Index>> {
+        let index = match Index::load(sidecar(&path)) {
+            Ok(index) => index,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let size = fs::metadata(path)?.len();
+        Ok((index.size <= size).then_some(index))
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn filters(&self) -> Option<&Filters> {
+        self.filters.as_ref()
+    }
+
+    // Без фильтров Блума файл может содержать что угодно
+    pub fn may_contain(&self, key: Key) -> bool {
+        self.filters
+            .as_ref()
+            .is_none_or(|filters| filters.may_contain(key))
+    }
+
+    // Смещение, с которого можно читать события начиная с from
+    pub fn offset(&self, from: NaiveDateTime) -> u64 {
+        match self.entries.partition_point(|entry| entry.before < from) {
+            0 => 0,
+            i => self.entries[i - 1].offset,
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut data = Vec::with_capacity(MAGIC.len() + 16 + self.entries.len() * 16);
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&self.size.to_le_bytes());
+        data.extend_from_slice(&(self.entries.len() as u64).to_le_bytes());
+        for entry in &self.entries {
+            data.extend_from_slice(&entry.before.and_utc().timestamp().to_le_bytes());
+            data.extend_from_slice(&entry.offset.to_le_bytes());
+        }
+        if let Some(filters) = &self.filters {
+            for bloom in [&filters.users, &filters.events, &filters.metadata] {
+                data.extend_from_slice(&bloom.hashes.to_le_bytes());
+                data.extend_from_slice(&(bloom.bits.len() as u64).to_le_bytes());
+                bloom
+                    .bits
+                    .iter()
+                    .for_each(|bits| data.extend_from_slice(&bits.to_le_bytes()));
+            }
+        }
+        fs::write(path, data)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Index> {
+        let invalid = || io::Error::new(ErrorKind::InvalidData, "Invalid index file");
+        let data = fs::read(path)?;
+        let data = data.strip_prefix(MAGIC).ok_or_else(invalid)?;
+        let mut values = data
+            .chunks_exact(8)
+            .map(|x| u64::from_le_bytes(x.try_into().unwrap()));
+        let size = values.next().ok_or_else(invalid)?;
+        let len = values.next().ok_or_else(invalid)? as usize;
+        if data.len() < 16 + len * 16 {
+            return Err(invalid());
+        }
+        let mut entries = Vec::with_capacity(len);
+        for _ in 0..len {
+            let (Some(before), Some(offset)) = (values.next(), values.next()) else {
+                return Err(invalid());
+            };
+            let before = DateTime::from_timestamp(before as i64, 0).ok_or_else(invalid)?;
+            entries.push(Entry {
+                before: before.naive_utc(),
+                offset,
+            });
+        }
+        let mut bloom = || -> io::Result<Bloom> {
+            let hashes = values.next().ok_or_else(invalid)?;
+            let len = values.next().ok_or_else(invalid)? as usize;
+            let bits: Vec<_> = values.by_ref().take(len).collect();
+            match bits.len() == len && len > 0 {
+                true => Ok(Bloom { hashes, bits }),
+                false => Err(invalid()),
+            }
+        };
+        let filters = match data.len() > 16 + len * 16 {
+            true => Some(Filters {
+                users: bloom()?,
+                events: bloom()?,
+                metadata: bloom()?,
+            }),
+            false => None,
+        };
+        Ok(Index {
+            size,
+            entries,
+            filters,
+        })
+    }
+}
+
+// 20221212000000.lgp -> 20221212000000.lgp.idx
+pub fn sidecar<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut path = path.as_ref().as_os_str().to_owned();
+    path.push(".idx");
+    PathBuf::from(path)
+}
+
+// splitmix64
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+fn minute(date: NaiveDateTime) -> NaiveDateTime {
+    date.with_second(0).unwrap_or(date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bloom() {
+        let mut bloom = Bloom::new(100);
+        (0..100).for_each(|value| bloom.insert(value * 3));
+        assert!((0..100).all(|value| bloom.contains(value * 3)));
+        let false_positives = (0..1000)
+            .filter(|value| bloom.contains(1000 + value))
+            .count();
+        assert!(false_positives < 50);
+    }
+}
@@ -0,0 +1,110 @@
+// Общие копии повторяющихся строк для EventOwned: одинаковые комментарии и данные
+// многих событий хранятся один раз.
+use crate::events::{Event, EventOwned};
+use std::{collections::HashSet, sync::Arc};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct InternStats {
+    strings: usize,
+    bytes: usize,
+    lookups: usize,
+    hits: usize,
+    saved_bytes: usize,
+}
+
+impl InternStats {
+    // Различных строк в интернере
+    pub fn strings(&self) -> usize {
+        self.strings
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn lookups(&self) -> usize {
+        self.lookups
+    }
+
+    // Строки, для которых нашлась готовая копия
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    pub fn saved_bytes(&self) -> usize {
+        self.saved_bytes
+    }
+
+    // Доля повторов среди всех запрошенных строк
+    pub fn dedup_ratio(&self) -> f64 {
+        match self.lookups {
+            0 => 0.0,
+            lookups => self.hits as f64 / lookups as f64,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Interner {
+    strings: HashSet<Arc<str>>,
+    stats: InternStats,
+}
+
+impl Interner {
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+
+    pub fn intern(&mut self, str: &str) -> Arc<str> {
+        self.stats.lookups += 1;
+        if let Some(shared) = self.strings.get(str) {
+            self.stats.hits += 1;
+            self.stats.saved_bytes += str.len();
+            return shared.clone();
+        }
+        let shared: Arc<str> = Arc::from(str);
+        self.strings.insert(shared.clone());
+        self.stats.strings += 1;
+        self.stats.bytes += str.len();
+        shared
+    }
+
+    // Как Event::to_owned, но строковые поля берутся из интернера
+    pub fn to_owned(&mut self, event: &Event) -> EventOwned {
+        EventOwned {
+            date: event.date,
+            transaction_status: event.transaction_status,
+            transaction_data: self.intern(event.transaction_data),
+            user_id: event.user_id,
+            computer_id: event.computer_id,
+            application_id: event.application_id,
+            connection: event.connection,
+            event_id: event.event_id,
+            log_level: event.log_level,
+            comment: self.intern(&event.comment()),
+            metadata_id: event.metadata_id,
+            data: self.intern(event.data()),
+            data_presentation: self.intern(&event.data_presentation()),
+            worker_server_id: event.worker_server_id,
+            port_id: event.port_id,
+            sync_port_id: event.sync_port_id,
+            session: event.session,
+            unknown1: event.unknown1,
+            unknown2: self.intern(event.unknown2),
+            offset: event.offset,
+            end_offset: event.end_offset,
+        }
+    }
+
+    pub fn stats(&self) -> &InternStats {
+        &self.stats
+    }
+
+    // Освободить строки, на которые не осталось ссылок из событий
+    pub fn shrink(&mut self) {
+        self.strings.retain(|str| Arc::strong_count(str) > 1);
+        self.stats.strings = self.strings.len();
+        self.stats.bytes = self.strings.iter().map(|str| str.len()).sum();
+    }
+}
@@ -0,0 +1,207 @@
+use crate::{data::Value, events::Event, references::References};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Credentials {
+    method: Option<i64>,
+    user: Option<String>,
+    os_user: Option<String>,
+}
+
+impl Credentials {
+    pub fn method(&self) -> Option<i64> {
+        self.method
+    }
+
+    pub fn user(&self) -> Option<&str> {
+        self.user.as_deref()
+    }
+
+    pub fn os_user(&self) -> Option<&str> {
+        self.os_user.as_deref()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum DataObject {
+    Undefined,
+    Ref(u32, Uuid),
+    Name(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum KnownEvent {
+    Authentication(Credentials),
+    AuthenticationError(Credentials),
+    SessionStart,
+    SessionFinish,
+    AccessDenied {
+        right: Option<String>,
+    },
+    Access {
+        action: String,
+        values: Vec<String>,
+    },
+    Data {
+        action: String,
+        object: DataObject,
+    },
+    User {
+        action: String,
+        properties: Vec<String>,
+    },
+    Job {
+        action: String,
+        name: Option<String>,
+    },
+    Transaction {
+        action: String,
+    },
+}
+
+impl KnownEvent {
+    pub fn decode(event: &Event, refs: &References) -> Option<KnownEvent> {
+        let name = refs.events().get(event.event_id())?;
+        KnownEvent::decode_data(name, event.data())
+    }
+
+    pub fn decode_data(name: &str, data: &str) -> Option<KnownEvent> {
+        let (group, action) = name.strip_prefix("_$")?.split_once("$_.")?;
+        let value = Value::parse(data)?;
+
+        Some(match (group, action) {
+            ("Session", "Authentication") => KnownEvent::Authentication(credentials(&value)),
+            ("Session", "AuthenticationError") => {
+                KnownEvent::AuthenticationError(credentials(&value))
+            }
+            ("Session", "Start") => KnownEvent::SessionStart,
+            ("Session", "Finish") => KnownEvent::SessionFinish,
+            ("Access", "AccessDenied") => KnownEvent::AccessDenied {
+                right: strings(&value).into_iter().next(),
+            },
+            ("Access", _) => KnownEvent::Access {
+                action: action.to_string(),
+                values: strings(&value),
+            },
+            ("Data", _) => KnownEvent::Data {
+                action: action.to_string(),
+                object: data_object(&value),
+            },
+            ("User", _) => KnownEvent::User {
+                action: action.to_string(),
+                properties: strings(&value),
+            },
+            ("Job", _) => KnownEvent::Job {
+                action: action.to_string(),
+                name: strings(&value).into_iter().next(),
+            },
+            ("Transaction", _) => KnownEvent::Transaction {
+                action: action.to_string(),
+            },
+            _ => return None,
+        })
+    }
+}
+
+fn kind<'a>(value: &'a Value) -> Option<&'a str> {
+    value.get(0)?.as_str()
+}
+
+// Строки из значений вида {"S","..."}, в том числе внутри структуры {"P",{N,...}}
+fn strings(value: &Value) -> Vec<String> {
+    let mut result = Vec::new();
+    collect_strings(value, &mut result);
+    result
+}
+
+fn collect_strings(value: &Value, result: &mut Vec<String>) {
+    let Some(list) = value.as_list() else {
+        return;
+    };
+    match (kind(value), list.get(1)) {
+        (Some("S"), Some(Value::String(s))) => result.push(s.to_string()),
+        _ => list.iter().for_each(|x| collect_strings(x, result)),
+    }
+}
+
+// {"P",{6,{"S",user},{"S",os_user}}}, при ошибке входа по ОС только {"P",{1,{"S",os_user}}}
+fn credentials(value: &Value) -> Credentials {
+    let method = value.get(1).and_then(|x| x.get(0)).and_then(Value::as_i64);
+    let mut strings = strings(value).into_iter();
+    let (user, os_user) = match (strings.next(), strings.next()) {
+        (Some(user), Some(os_user)) => (Some(user), Some(os_user)),
+        (Some(s), None) if s.contains('\\') => (None, Some(s)),
+        (user, _) => (user, None),
+    };
+    Credentials {
+        method,
+        user: user.filter(|x| !x.is_empty()),
+        os_user: os_user.filter(|x| !x.is_empty()),
+    }
+}
+
+fn data_object(value: &Value) -> DataObject {
+    match (kind(value), value.get(1)) {
+        (Some("R"), Some(Value::Ref(type_id, id))) => DataObject::Ref(*type_id, *id),
+        (Some("S"), Some(Value::String(s))) => DataObject::Name(s.to_string()),
+        _ => DataObject::Undefined,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_authentication() {
+        let event = KnownEvent::decode_data(
+            "_$Session$_.Authentication",
+            "{\"P\",\r\n{6,\r\n{\"S\",\"Admin\"},\r\n{\"S\",\"COMPUTER1\\user1\"}\r\n}\r\n}",
+        );
+        let Some(KnownEvent::Authentication(credentials)) = event else {
+            panic!("{event:?}");
+        };
+        assert_eq!(credentials.method(), Some(6));
+        assert_eq!(credentials.user(), Some("Admin"));
+        assert_eq!(credentials.os_user(), Some("COMPUTER1\\user1"));
+
+        let event = KnownEvent::decode_data(
+            "_$Session$_.AuthenticationError",
+            r#"{"P",{1,{"S","COMPUTER1\user1"}}}"#,
+        );
+        let Some(KnownEvent::AuthenticationError(credentials)) = event else {
+            panic!("{event:?}");
+        };
+        assert_eq!(credentials.user(), None);
+        assert_eq!(credentials.os_user(), Some("COMPUTER1\\user1"));
+    }
+
+    #[test]
+    fn test_data() {
+        assert_eq!(
+            KnownEvent::decode_data(
+                "_$Data$_.Post",
+                r#"{"R",174:8781b06ebf31a92f11e876186beff5a9}"#
+            ),
+            Some(KnownEvent::Data {
+                action: "Post".to_string(),
+                object: DataObject::Ref(
+                    174,
+                    Uuid::from_str("6beff5a9-7618-11e8-8781-b06ebf31a92f").unwrap()
+                ),
+            })
+        );
+        assert_eq!(
+            KnownEvent::decode_data("_$Job$_.Start", r#"{"S","Job"}"#),
+            Some(KnownEvent::Job {
+                action: "Start".to_string(),
+                name: Some("Job".to_string()),
+            })
+        );
+        assert_eq!(KnownEvent::decode_data("Custom event", r#"{"U"}"#), None);
+    }
+}
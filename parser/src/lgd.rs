@@ -0,0 +1,502 @@
+use crate::{
+    directory::LogDirectory,
+    events::{self, Event, EventLogLevel, TransactionStatus},
+    parser::{LazyStr, LogStr},
+    record::{datetime_to_ticks, ticks_to_datetime},
+    references::{self, add_ref, Metadata, References, User},
+};
+use chrono::NaiveDate;
+use rusqlite::{params, types::ValueRef, Connection, OpenFlags, Row};
+use std::{fs::File, io, io::BufWriter, ops::ControlFlow, path::Path, str::FromStr};
+use uuid::Uuid;
+
+pub struct LgdReader {
+    connection: Connection,
+}
+
+impl LgdReader {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<LgdReader> {
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        let connection = Connection::open_with_flags(path, flags).map_err(io::Error::other)?;
+        Ok(LgdReader { connection })
+    }
+
+    pub fn references(&self) -> io::Result<References> {
+        let mut refs = References::default();
+
+        self.read_codes(
+            "SELECT code, name, uuid FROM UserCodes",
+            &mut refs.users,
+            |row| {
+                Ok(User {
+                    id: parse_uuid(row.get_ref(2)?),
+                    name: row.get(1)?,
+                })
+            },
+        )?;
+        self.read_codes(
+            "SELECT code, name FROM ComputerCodes",
+            &mut refs.computers,
+            |row| row.get(1),
+        )?;
+        self.read_codes(
+            "SELECT code, name FROM AppCodes",
+            &mut refs.applications,
+            |row| row.get(1),
+        )?;
+        self.read_codes(
+            "SELECT code, name FROM EventCodes",
+            &mut refs.events,
+            |row| row.get(1),
+        )?;
+        self.read_codes(
+            "SELECT code, name, uuid FROM MetadataCodes",
+            &mut refs.metadata,
+            |row| {
+                Ok(Metadata::new(
+                    parse_uuid(row.get_ref(2)?),
+                    row.get::<_, String>(1)?,
+                ))
+            },
+        )?;
+        self.read_codes(
+            "SELECT code, name FROM WorkServerCodes",
+            &mut refs.worker_servers,
+            |row| row.get(1),
+        )?;
+        self.read_codes(
+            "SELECT code, name FROM PrimaryPortCodes",
+            &mut refs.ports,
+            |row| row.get(1),
+        )?;
+        self.read_codes(
+            "SELECT code, name FROM SecondaryPortCodes",
+            &mut refs.sync_ports,
+            |row| row.get(1),
+        )?;
+
+        Ok(refs)
+    }
+
+    pub fn parse<F>(&self, action: &mut F) -> io::Result<()>
+    where
+        F: FnMut(Event),
+    {
+        self.parse_until(&mut |event| {
+            action(event);
+            ControlFlow::Continue(())
+        })
+    }
+
+    pub fn parse_until<F>(&self, action: &mut F) -> io::Result<()>
+    where
+        F: FnMut(Event) -> ControlFlow<()>,
+    {
+        let mut stmt = self
+            .connection
+            .prepare(
+                "SELECT date, transactionStatus, transactionDate, transactionID, userCode, \
+                 computerCode, appCode, connectID, eventCode, severity, comment, metadataCodes, \
+                 data, dataPresentation, workServerCode, primaryPortCode, secondaryPortCode, \
+                 session FROM EventLog ORDER BY rowID",
+            )
+            .map_err(io::Error::other)?;
+        let mut rows = stmt.query([]).map_err(io::Error::other)?;
+
+        while let Some(row) = rows.next().map_err(io::Error::other)? {
+            let record = Record::read(row).map_err(io::Error::other)?;
+            if action(record.event()?).is_break() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_codes<T, F>(&self, sql: &str, vec: &mut Vec<T>, map: F) -> io::Result<()>
+    where
+        T: Default,
+        F: Fn(&Row) -> rusqlite::Result<T>,
+    {
+        let mut stmt = self.connection.prepare(sql).map_err(io::Error::other)?;
+        let mut rows = stmt.query([]).map_err(io::Error::other)?;
+        while let Some(row) = rows.next().map_err(io::Error::other)? {
+            let code: i64 = row.get(0).map_err(io::Error::other)?;
+            let value = map(row).map_err(io::Error::other)?;
+            add_ref(vec, value, code as usize);
+        }
+        Ok(())
+    }
+}
+
+struct Record {
+    date: i64,
+    transaction_status: i64,
+    user_id: i64,
+    computer_id: i64,
+    application_id: i64,
+    connection: i64,
+    event_id: i64,
+    severity: i64,
+    comment: String,
+    metadata_id: i64,
+    data: String,
+    data_presentation: String,
+    worker_server_id: i64,
+    port_id: i64,
+    sync_port_id: i64,
+    session: i64,
+    transaction_data: String,
+}
+
+impl Record {
+    fn read(row: &Row) -> rusqlite::Result<Record> {
+        let transaction_date: i64 = row.get(2)?;
+        let transaction_id: i64 = row.get(3)?;
+        Ok(Record {
+            date: row.get(0)?,
+            transaction_status: row.get(1)?,
+            user_id: row.get(4)?,
+            computer_id: row.get(5)?,
+            application_id: row.get(6)?,
+            connection: row.get(7)?,
+            event_id: row.get(8)?,
+            severity: row.get(9)?,
+            comment: row.get::<_, Option<String>>(10)?.unwrap_or_default(),
+            metadata_id: parse_code(row.get_ref(11)?),
+            data: row.get::<_, Option<String>>(12)?.unwrap_or_default(),
+            data_presentation: row.get::<_, Option<String>>(13)?.unwrap_or_default(),
+            worker_server_id: row.get(14)?,
+            port_id: row.get(15)?,
+            sync_port_id: row.get(16)?,
+            session: row.get(17)?,
+            transaction_data: format!("{{{transaction_date:x},{transaction_id:x}}}"),
+        })
+    }
+
+    fn event(&self) -> io::Result<Event<'_>> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let date = ticks_to_datetime(self.date)
+            .ok_or_else(|| invalid(format!("Invalid date: {}", self.date)))?;
+        let transaction_status = match self.transaction_status {
+            0 => TransactionStatus::Committed,
+            1 => TransactionStatus::Unfinished,
+            2 => TransactionStatus::NotApplicable,
+            3 => TransactionStatus::RolledBack,
+            s => return Err(invalid(format!("Unknown transaction status: {s}"))),
+        };
+        let log_level = match self.severity {
+            1 => EventLogLevel::Information,
+            2 => EventLogLevel::Warning,
+            3 => EventLogLevel::Error,
+            4 => EventLogLevel::Note,
+            s => return Err(invalid(format!("Unknown log level: {s}"))),
+        };
+
+        Ok(Event {
+            date,
+            transaction_status,
+            transaction_data: &self.transaction_data,
+            user_id: self.user_id as usize,
+            computer_id: self.computer_id as usize,
+            application_id: self.application_id as usize,
+            connection: self.connection as usize,
+            event_id: self.event_id as usize,
+            log_level,
+            comment: LogStr::new(self.comment.as_bytes(), false),
+            metadata_id: self.metadata_id as usize,
+            data: LazyStr::decoded(&self.data),
+            data_presentation: LogStr::new(self.data_presentation.as_bytes(), false),
+            worker_server_id: self.worker_server_id as usize,
+            port_id: self.port_id as usize,
+            sync_port_id: self.sync_port_id as usize,
+            session: self.session as usize,
+            unknown1: 0,
+            unknown2: "{0}",
+            offset: 0,
+            end_offset: 0,
+            raw: &[],
+        })
+    }
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE UserCodes (code INTEGER PRIMARY KEY, name TEXT, uuid TEXT);
+    CREATE TABLE ComputerCodes (code INTEGER PRIMARY KEY, name TEXT);
+    CREATE TABLE AppCodes (code INTEGER PRIMARY KEY, name TEXT);
+    CREATE TABLE EventCodes (code INTEGER PRIMARY KEY, name TEXT);
+    CREATE TABLE MetadataCodes (code INTEGER PRIMARY KEY, name TEXT, uuid TEXT);
+    CREATE TABLE WorkServerCodes (code INTEGER PRIMARY KEY, name TEXT);
+    CREATE TABLE PrimaryPortCodes (code INTEGER PRIMARY KEY, name INTEGER);
+    CREATE TABLE SecondaryPortCodes (code INTEGER PRIMARY KEY, name INTEGER);
+    CREATE TABLE EventLog (rowID INTEGER PRIMARY KEY, severity INTEGER,
+        date INTEGER, connectID INTEGER, session INTEGER,
+        transactionStatus INTEGER, transactionDate INTEGER, transactionID INTEGER,
+        userCode INTEGER, computerCode INTEGER, appCode INTEGER, eventCode INTEGER,
+        comment TEXT, metadataCodes TEXT, sessionDataSplitCode INTEGER,
+        dataType INTEGER, data TEXT, dataPresentation TEXT, workServerCode INTEGER,
+        primaryPortCode INTEGER, secondaryPortCode INTEGER);
+";
+
+pub struct LgdWriter {
+    connection: Connection,
+}
+
+impl LgdWriter {
+    pub fn create<P: AsRef<Path>>(path: P, refs: &References) -> io::Result<LgdWriter> {
+        let connection = Connection::open(path).map_err(io::Error::other)?;
+        let writer = LgdWriter { connection };
+        writer.init(refs).map_err(io::Error::other)?;
+        Ok(writer)
+    }
+
+    fn init(&self, refs: &References) -> rusqlite::Result<()> {
+        self.connection.execute_batch(SCHEMA)?;
+        self.connection.execute_batch("BEGIN")?;
+        let mut stmt = self
+            .connection
+            .prepare("INSERT INTO UserCodes VALUES (?1, ?2, ?3)")?;
+        for (code, user) in refs.users().iter().enumerate().skip(1) {
+            stmt.execute(params![code, user.name(), user.id().to_string()])?;
+        }
+        let mut stmt = self
+            .connection
+            .prepare("INSERT INTO MetadataCodes VALUES (?1, ?2, ?3)")?;
+        for (code, metadata) in refs.metadata().iter().enumerate().skip(1) {
+            stmt.execute(params![code, metadata.name(), metadata.id().to_string()])?;
+        }
+        for (table, names) in [
+            ("ComputerCodes", refs.computers()),
+            ("AppCodes", refs.applications()),
+            ("EventCodes", refs.events()),
+            ("WorkServerCodes", refs.worker_servers()),
+        ] {
+            let mut stmt = self
+                .connection
+                .prepare(&format!("INSERT INTO {table} VALUES (?1, ?2)"))?;
+            for (code, name) in names.iter().enumerate().skip(1) {
+                stmt.execute(params![code, name])?;
+            }
+        }
+        for (table, ports) in [
+            ("PrimaryPortCodes", refs.ports()),
+            ("SecondaryPortCodes", refs.sync_ports()),
+        ] {
+            let mut stmt = self
+                .connection
+                .prepare(&format!("INSERT INTO {table} VALUES (?1, ?2)"))?;
+            for (code, port) in ports.iter().enumerate().skip(1) {
+                stmt.execute(params![code, port])?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write(&mut self, event: &Event) -> io::Result<()> {
+        self.insert(event).map_err(io::Error::other)
+    }
+
+    fn insert(&mut self, event: &Event) -> rusqlite::Result<()> {
+        let transaction_status = match event.transaction_status() {
+            TransactionStatus::Committed => 0,
+            TransactionStatus::Unfinished => 1,
+            TransactionStatus::NotApplicable => 2,
+            TransactionStatus::RolledBack => 3,
+        };
+        let severity = match event.log_level() {
+            EventLogLevel::Information => 1,
+            EventLogLevel::Warning => 2,
+            EventLogLevel::Error => 3,
+            EventLogLevel::Note => 4,
+        };
+        // {дата начала транзакции,номер} в шестнадцатеричном виде
+        let (transaction_date, transaction_id) = event
+            .transaction_data()
+            .trim_matches(['{', '}'])
+            .split_once(',')
+            .map(|(date, id)| {
+                let date = i64::from_str_radix(date.trim(), 16).unwrap_or_default();
+                let id = i64::from_str_radix(id.trim(), 16).unwrap_or_default();
+                (date, id)
+            })
+            .unwrap_or_default();
+
+        let mut stmt = self.connection.prepare_cached(
+            "INSERT INTO EventLog VALUES (NULL, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, \
+             ?12, ?13, 0, 0, ?14, ?15, ?16, ?17, ?18)",
+        )?;
+        stmt.execute(params![
+            severity,
+            datetime_to_ticks(event.date()),
+            event.connection(),
+            event.session(),
+            transaction_status,
+            transaction_date,
+            transaction_id,
+            event.user_id(),
+            event.computer_id(),
+            event.application_id(),
+            event.event_id(),
+            event.comment(),
+            event.metadata_id().to_string(),
+            event.data().trim_end(),
+            event.data_presentation(),
+            event.worker_server_id(),
+            event.port_id(),
+            event.sync_port_id(),
+        ])?;
+        Ok(())
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        self.connection
+            .execute_batch("COMMIT")
+            .map_err(io::Error::other)
+    }
+}
+
+pub fn lgp_to_lgd<P, Q>(dir: P, lgd: Q) -> io::Result<()>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let dir = LogDirectory::open(dir)?;
+    let mut writer = LgdWriter::create(lgd, dir.references())?;
+    let mut result = Ok(());
+    dir.events(&mut |event| {
+        if result.is_ok() {
+            result = writer.write(&event);
+        }
+    })?;
+    result?;
+    writer.finish()
+}
+
+// События раскладываются по файлам за сутки, как при настройке "По дням"
+pub fn lgd_to_lgp<P, Q>(lgd: P, dir: Q) -> io::Result<()>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let dir = dir.as_ref();
+    let reader = LgdReader::open(lgd)?;
+    let refs = reader.references()?;
+    let lgf = BufWriter::new(File::create(dir.join("1Cv8.lgf"))?);
+    references::Writer::new(lgf).write(&refs)?;
+
+    let mut current = None;
+    let mut result = Ok(());
+    reader.parse_until(&mut |event| {
+        result = write_daily(&mut current, dir, &event);
+        match result {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(_) => ControlFlow::Break(()),
+        }
+    })?;
+    result?;
+    if let Some((_, writer)) = current {
+        writer.finish()?;
+    }
+    Ok(())
+}
+
+type DailyWriter = (NaiveDate, events::Writer<BufWriter<File>>);
+
+fn write_daily(current: &mut Option<DailyWriter>, dir: &Path, event: &Event) -> io::Result<()> {
+    let day = event.date().date();
+    if current.as_ref().is_none_or(|(current, _)| *current != day) {
+        if let Some((_, writer)) = current.take() {
+            writer.finish()?;
+        }
+        let name = format!("{}000000.lgp", day.format("%Y%m%d"));
+        let file = BufWriter::new(File::create(dir.join(name))?);
+        *current = Some((day, events::Writer::new(file)));
+    }
+    let (_, writer) = current.as_mut().expect("writer is open");
+    writer.write(&event.to_owned())
+}
+
+fn parse_uuid(value: ValueRef) -> Uuid {
+    match value {
+        ValueRef::Text(s) => std::str::from_utf8(s)
+            .ok()
+            .and_then(|s| Uuid::from_str(s).ok())
+            .unwrap_or_default(),
+        _ => Uuid::default(),
+    }
+}
+
+fn parse_code(value: ValueRef) -> i64 {
+    match value {
+        ValueRef::Integer(code) => code,
+        ValueRef::Text(s) => std::str::from_utf8(s)
+            .ok()
+            .and_then(|s| s.split(',').next()?.trim().parse().ok())
+            .unwrap_or_default(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lgd() {
+        let path = std::env::temp_dir().join("event-log-parser-test.lgd");
+        let _ = std::fs::remove_file(&path);
+
+        let connection = Connection::open(&path).unwrap();
+        connection
+            .execute_batch(
+                r#"
+                CREATE TABLE UserCodes (code INTEGER PRIMARY KEY, name TEXT, uuid TEXT);
+                CREATE TABLE ComputerCodes (code INTEGER PRIMARY KEY, name TEXT);
+                CREATE TABLE AppCodes (code INTEGER PRIMARY KEY, name TEXT);
+                CREATE TABLE EventCodes (code INTEGER PRIMARY KEY, name TEXT);
+                CREATE TABLE MetadataCodes (code INTEGER PRIMARY KEY, name TEXT, uuid TEXT);
+                CREATE TABLE WorkServerCodes (code INTEGER PRIMARY KEY, name TEXT);
+                CREATE TABLE PrimaryPortCodes (code INTEGER PRIMARY KEY, name INTEGER);
+                CREATE TABLE SecondaryPortCodes (code INTEGER PRIMARY KEY, name INTEGER);
+                CREATE TABLE EventLog (rowID INTEGER PRIMARY KEY, severity INTEGER,
+                    date INTEGER, connectID INTEGER, session INTEGER,
+                    transactionStatus INTEGER, transactionDate INTEGER, transactionID INTEGER,
+                    userCode INTEGER, computerCode INTEGER, appCode INTEGER, eventCode INTEGER,
+                    comment TEXT, metadataCodes TEXT, sessionDataSplitCode INTEGER,
+                    dataType INTEGER, data TEXT, dataPresentation TEXT, workServerCode INTEGER,
+                    primaryPortCode INTEGER, secondaryPortCode INTEGER);
+
+                INSERT INTO UserCodes VALUES (1, 'Executor', 'd303f30c-9e76-412f-95d2-3c3622e6b6e1');
+                INSERT INTO ComputerCodes VALUES (1, 'computer1');
+                INSERT INTO AppCodes VALUES (1, 'Designer');
+                INSERT INTO EventCodes VALUES (1, '_$Session$_.Start');
+                INSERT INTO EventLog VALUES (1, 3, 638069137710000, 5, 7, 2, 0, 0,
+                    1, 1, 1, 1, 'Ошибка', '', 0, 0, '{"U"}', '', 0, 0, 0);
+                "#,
+            )
+            .unwrap();
+        drop(connection);
+
+        let reader = LgdReader::open(&path).unwrap();
+        let refs = reader.references().unwrap();
+        assert_eq!(refs.users()[1].name(), "Executor");
+
+        let mut total_events = 0;
+        reader
+            .parse(&mut |event| {
+                assert_eq!(event.user(&refs).name(), "Executor");
+                assert_eq!(event.event(&refs), "_$Session$_.Start");
+                assert_eq!(event.log_level(), &EventLogLevel::Error);
+                assert_eq!(event.comment(), "Ошибка");
+                assert_eq!(event.transaction(), None);
+                assert_eq!(event.session(), 7);
+                assert_eq!(event.date().to_string(), "2022-12-17 22:42:51");
+                total_events += 1;
+            })
+            .unwrap();
+        assert_eq!(total_events, 1);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
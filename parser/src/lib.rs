@@ -1,3 +1,105 @@
+// Без feature "std" доступно только ядро разбора: parser, record, data, error
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(
+    all(
+        feature = "safe-parser",
+        not(feature = "ffi"),
+        not(feature = "mmap"),
+        not(feature = "uring")
+    ),
+    forbid(unsafe_code)
+)]
+// unsafe разрешен только в модуле ffi, events::parse_mmap_with_options и uring
+#![cfg_attr(
+    all(
+        feature = "safe-parser",
+        any(feature = "ffi", feature = "mmap", feature = "uring")
+    ),
+    deny(unsafe_code)
+)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod archive;
+#[cfg(feature = "std")]
+pub mod arena;
+#[cfg(feature = "std")]
+pub mod audit;
+#[cfg(feature = "parallel")]
+pub mod backfill;
+#[cfg(feature = "std")]
+pub mod cache;
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(feature = "zstd")]
+pub mod container;
+pub mod data;
+#[cfg(feature = "std")]
+pub mod directory;
+#[cfg(feature = "encoding")]
+pub mod encoding;
+pub mod error;
+#[cfg(feature = "std")]
 pub mod events;
-mod parser;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "ffi")]
+#[allow(unsafe_code)]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod filter;
+#[cfg(feature = "std")]
+pub mod follow;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "hashing")]
+pub mod hashing;
+#[cfg(feature = "std")]
+pub mod index;
+#[cfg(feature = "std")]
+pub mod intern;
+#[cfg(feature = "std")]
+pub mod known_events;
+#[cfg(feature = "lgd")]
+pub mod lgd;
+#[cfg(feature = "std")]
+pub mod merge;
+#[cfg(feature = "prometheus")]
+pub mod metrics;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod parser;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "std")]
+pub mod query;
+#[cfg(feature = "std")]
+mod reader;
+pub mod record;
+#[cfg(feature = "std")]
+pub mod redact;
+#[cfg(feature = "std")]
 pub mod references;
+#[cfg(feature = "std")]
+pub mod sessions;
+#[cfg(feature = "std")]
+pub mod source;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "tokio")]
+mod stream;
+#[cfg(feature = "std")]
+pub mod transactions;
+#[cfg(all(feature = "uring", target_os = "linux"))]
+mod uring;
+#[cfg(feature = "std")]
+pub mod validate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "std")]
+pub mod watch;
+#[cfg(feature = "std")]
+pub mod window;
+#[cfg(feature = "xml")]
+pub mod xml;
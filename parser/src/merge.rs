@@ -0,0 +1,185 @@
+use crate::{
+    events::{Event, EventOwned, EventReader},
+    references::{Conflict, Merge, References, Tables},
+};
+use std::{cmp::Reverse, collections::BinaryHeap, fmt::Write, fs::File, io, io::Read, path::Path};
+
+pub fn merge<F, R>(readers: Vec<R>, action: &mut F) -> io::Result<()>
+where
+    F: FnMut(usize, Event),
+    R: Read,
+{
+    let mut readers: Vec<_> = readers.into_iter().map(EventReader::new).collect();
+
+    let mut heap = BinaryHeap::new();
+    for (i, reader) in readers.iter_mut().enumerate() {
+        if let Some(date) = reader.peek_date()? {
+            heap.push(Reverse((date, i)));
+        }
+    }
+
+    while let Some(Reverse((_, i))) = heap.pop() {
+        let reader = &mut readers[i];
+        if !reader.read_next(&mut |event| action(i, event))? {
+            continue;
+        }
+        if let Some(date) = reader.peek_date()? {
+            heap.push(Reverse((date, i)));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn merge_files<F, P>(files: &[P], action: &mut F) -> io::Result<()>
+where
+    F: FnMut(&Path, Event),
+    P: AsRef<Path>,
+{
+    let readers = files
+        .iter()
+        .map(File::open)
+        .collect::<io::Result<Vec<_>>>()?;
+    merge(readers, &mut |i, event| action(files[i].as_ref(), event))
+}
+
+// Порядок совпадает с RefMapper::ids
+const TABLES: [Tables; 8] = [
+    Tables::USERS,
+    Tables::COMPUTERS,
+    Tables::APPLICATIONS,
+    Tables::EVENTS,
+    Tables::METADATA,
+    Tables::WORKER_SERVERS,
+    Tables::PORTS,
+    Tables::SYNC_PORTS,
+];
+
+/// Перенумерация событий журнала со справочниками `source` в номера справочников `target`,
+/// например при объединении журналов нескольких рабочих серверов
+pub struct RefMapper {
+    merge: Merge,
+}
+
+impl RefMapper {
+    /// Недостающие в `target` записи `source` дописываются в `target`
+    pub fn new(source: &References, target: &mut References) -> RefMapper {
+        RefMapper {
+            merge: target.merge(source),
+        }
+    }
+
+    pub fn conflicts(&self) -> &[Conflict] {
+        self.merge.conflicts()
+    }
+
+    /// Номера, которых нет в справочниках `source`, не меняются.
+    /// Значения разделителей в `unknown2` ссылаются на исходную запись и не меняются:
+    /// для них нужен `map_owned`
+    pub fn map<'a>(&self, mut event: Event<'a>) -> Event<'a> {
+        self.ids([
+            &mut event.user_id,
+            &mut event.computer_id,
+            &mut event.application_id,
+            &mut event.event_id,
+            &mut event.metadata_id,
+            &mut event.worker_server_id,
+            &mut event.port_id,
+            &mut event.sync_port_id,
+        ]);
+        event
+    }
+
+    pub fn map_owned(&self, event: &mut EventOwned) {
+        self.ids([
+            &mut event.user_id,
+            &mut event.computer_id,
+            &mut event.application_id,
+            &mut event.event_id,
+            &mut event.metadata_id,
+            &mut event.worker_server_id,
+            &mut event.port_id,
+            &mut event.sync_port_id,
+        ]);
+        let ids: Vec<_> = event.data_separation_ids().collect();
+        if !ids.is_empty() {
+            event.unknown2 = self.separation(&ids).into();
+        }
+    }
+
+    // Последнее поле записи: {количество,разделитель,значение,...}
+    fn separation(&self, ids: &[(usize, usize)]) -> String {
+        let mut result = format!("{{{}", ids.len());
+        for &(separation, value) in ids {
+            let (separation, value) = self
+                .merge
+                .map_separation(separation, value)
+                .unwrap_or((separation, value));
+            let _ = write!(result, ",{separation},{value}");
+        }
+        result.push('}');
+        result
+    }
+
+    fn ids(&self, ids: [&mut usize; 8]) {
+        for (table, id) in TABLES.into_iter().zip(ids) {
+            if let Some(num) = self.merge.map(table, *id) {
+                *id = num;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let a = b"{20221217221504,N,\r\n{0,0},1,1,1,1,1,I,\"a1\",0,\r\n{\"U\"},\"\",0,0,0,2,0,\r\n{0}\r\n},\r\n\
+                  {20221217221510,N,\r\n{0,0},1,1,1,1,1,I,\"a2\",0,\r\n{\"U\"},\"\",0,0,0,2,0,\r\n{0}\r\n}";
+        let b = b"{20221217221505,N,\r\n{0,0},1,1,1,1,1,I,\"b1\",0,\r\n{\"U\"},\"\",0,0,0,2,0,\r\n{0}\r\n},\r\n\
+                  {20221217221509,N,\r\n{0,0},1,1,1,1,1,I,\"b2\",0,\r\n{\"U\"},\"\",0,0,0,2,0,\r\n{0}\r\n}";
+
+        let mut result = Vec::new();
+        merge(vec![&a[..], &b[..]], &mut |i, event| {
+            result.push((i, event.comment().into_owned()))
+        })
+        .unwrap();
+
+        assert_eq!(
+            result,
+            vec![
+                (0, "a1".to_string()),
+                (1, "b1".to_string()),
+                (1, "b2".to_string()),
+                (0, "a2".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_invalid() {
+        let a = b"{20221217221504,N,\r\n{0,0},1,1,1,1,1,I,\"a1\",0,\r\n{\"U\"},\"\",0,0,0,2,0,\r\n{0}\r\n},\r\n\
+                  {20221217221506,N,\r\n{0,0},x,1,1,1,1,I,\"bad\",0,\r\n{\"U\"},\"\",0,0,0,2,0,\r\n{0}\r\n},\r\n\
+                  {20221217221510,N,\r\n{0,0},1,1,1,1,1,I,\"a2\",0,\r\n{\"U\"},\"\",0,0,0,2,0,\r\n{0}\r\n}";
+        let b = b"{20221217221505,N,\r\n{0,0},1,1,1,1,1,I,\"b1\",0,\r\n{\"U\"},\"\",0,0,0,2,0,\r\n{0}\r\n},\r\n\
+                  {20221217221509,N,\r\n{0,0},1,1,1,1,1,I,\"b2\",0,\r\n{\"U\"},\"\",0,0,0,2,0,\r\n{0}\r\n}";
+
+        let mut result = Vec::new();
+        merge(vec![&a[..], &b[..]], &mut |i, event| {
+            result.push((i, event.comment().into_owned()))
+        })
+        .unwrap();
+
+        assert_eq!(
+            result,
+            vec![
+                (0, "a1".to_string()),
+                (1, "b1".to_string()),
+                (1, "b2".to_string()),
+                (0, "a2".to_string())
+            ]
+        );
+    }
+}
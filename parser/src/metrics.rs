@@ -0,0 +1,153 @@
+use crate::{
+    events::{EventLogLevel, EventResolved},
+    watch,
+};
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    ops::ControlFlow,
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+#[derive(Default)]
+struct Counters {
+    levels: [u64; 4],
+    errors: BTreeMap<String, u64>,
+    sessions: u64,
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    counters: Mutex<Counters>,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics::default()
+    }
+
+    pub fn record(&self, event: &EventResolved) {
+        let mut counters = self.counters.lock().unwrap();
+        counters.levels[level_index(event.log_level())] += 1;
+        if *event.log_level() == EventLogLevel::Error {
+            *counters
+                .errors
+                .entry(event.event_name().to_string())
+                .or_default() += 1;
+        }
+        if event.event_name() == "_$Session$_.Start" {
+            counters.sessions += 1;
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let counters = self.counters.lock().unwrap();
+        let mut out = String::new();
+
+        out.push_str("# HELP eventlog_events_total Events by level.\n");
+        out.push_str("# TYPE eventlog_events_total counter\n");
+        for (level, count) in LEVELS.iter().zip(counters.levels) {
+            let _ = writeln!(out, "eventlog_events_total{{level=\"{level}\"}} {count}");
+        }
+
+        out.push_str("# HELP eventlog_errors_total Error events by event name.\n");
+        out.push_str("# TYPE eventlog_errors_total counter\n");
+        for (event, count) in &counters.errors {
+            let event = escape(event);
+            let _ = writeln!(out, "eventlog_errors_total{{event=\"{event}\"}} {count}");
+        }
+
+        out.push_str("# HELP eventlog_sessions_started_total Started sessions.\n");
+        out.push_str("# TYPE eventlog_sessions_started_total counter\n");
+        let _ = writeln!(out, "eventlog_sessions_started_total {}", counters.sessions);
+        out
+    }
+}
+
+const LEVELS: [&str; 4] = ["error", "warning", "information", "note"];
+
+fn level_index(level: &EventLogLevel) -> usize {
+    match level {
+        EventLogLevel::Error => 0,
+        EventLogLevel::Warning => 1,
+        EventLogLevel::Information => 2,
+        EventLogLevel::Note => 3,
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// Отдает /metrics в отдельном потоке, возвращает фактический адрес
+pub fn serve<A: ToSocketAddrs>(addr: A, metrics: Arc<Metrics>) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let addr = listener.local_addr()?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = respond(stream, &metrics);
+        }
+    });
+    Ok(addr)
+}
+
+// Соединения обслуживаются по очереди: клиент, не приславший запрос,
+// не должен задерживать остальных
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn respond(mut stream: TcpStream, metrics: &Metrics) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+    }
+
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = match path {
+        "/metrics" => ("200 OK", metrics.render()),
+        _ => ("404 Not Found", String::new()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+pub fn watch<P, A>(dir: P, addr: A, interval: Duration) -> io::Result<()>
+where
+    P: AsRef<Path>,
+    A: ToSocketAddrs,
+{
+    let metrics = Arc::new(Metrics::new());
+    serve(addr, metrics.clone())?;
+    watch::watch(dir, interval, &mut |event| {
+        metrics.record(&event);
+        ControlFlow::Continue(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
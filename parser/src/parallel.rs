@@ -0,0 +1,168 @@
+// Разбор одного большого файла *.lgp по частям в пуле потоков rayon.
+// Запись относится к части, в которой находится ее начало; часть, кроме первой,
+// начинается с первой записи после своей границы.
+use crate::events::{open_file, read_events, Event, ParseOptions};
+use rayon::prelude::*;
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+};
+
+const CHUNK_SIZE: u64 = 64 * 1024 * 1024;
+const SCAN_SIZE: usize = 64 * 1024;
+
+pub struct ParallelParser {
+    path: PathBuf,
+    chunk_size: u64,
+}
+
+impl ParallelParser {
+    pub fn new<P: AsRef<Path>>(path: P) -> ParallelParser {
+        ParallelParser {
+            path: path.as_ref().to_path_buf(),
+            chunk_size: CHUNK_SIZE,
+        }
+    }
+
+    pub fn chunk_size(mut self, chunk_size: u64) -> ParallelParser {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// События в произвольном порядке, `action` вызывается из нескольких потоков;
+    /// порядок в файле восстанавливается по `Event::offset`.
+    pub fn for_each<F>(&self, action: &F) -> io::Result<()>
+    where
+        F: Fn(Event) + Sync,
+    {
+        (0..self.chunks()?)
+            .into_par_iter()
+            .try_for_each(|index| self.parse_chunk(index, &mut |event| action(event)))
+    }
+
+    /// `map` выполняется параллельно, результаты передаются в `sink` в порядке файла.
+    /// В памяти одновременно находятся результаты не более чем 2 частей на поток.
+    pub fn map_ordered<T, M, S>(&self, map: &M, sink: &mut S) -> io::Result<()>
+    where
+        T: Send,
+        M: Fn(Event) -> Option<T> + Sync,
+        S: FnMut(T),
+    {
+        let chunks = self.chunks()?;
+        let window = rayon::current_num_threads() as u64 * 2;
+        let mut first = 0;
+        while first < chunks {
+            let last = (first + window).min(chunks);
+            let results = (first..last)
+                .into_par_iter()
+                .map(|index| {
+                    let mut items = Vec::new();
+                    self.parse_chunk(index, &mut |event| items.extend(map(event)))?;
+                    Ok(items)
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+            results.into_iter().flatten().for_each(&mut *sink);
+            first = last;
+        }
+        Ok(())
+    }
+
+    fn chunks(&self) -> io::Result<u64> {
+        let len = std::fs::metadata(&self.path)?.len();
+        Ok(len.div_ceil(self.chunk_size).max(1))
+    }
+
+    // Часть без начала записи пуста: ее диапазон разбирает предыдущая часть
+    fn parse_chunk<F>(&self, index: u64, action: &mut F) -> io::Result<()>
+    where
+        F: FnMut(Event),
+    {
+        let start = self.boundary(index)?;
+        let end = self.boundary(index + 1)?;
+        if start >= end {
+            return Ok(());
+        }
+        let mut options =
+            ParseOptions::new().buffer_size(self.chunk_size.min(1024 * 1024) as usize);
+        read_events(
+            open_file(&self.path, start)?,
+            start,
+            &mut options,
+            &mut |event| {
+                if event.offset() >= end {
+                    return ControlFlow::Break(());
+                }
+                action(event);
+                ControlFlow::Continue(())
+            },
+        )?;
+        Ok(())
+    }
+
+    // Первая запись, начинающаяся не раньше границы части, или конец файла
+    fn boundary(&self, index: u64) -> io::Result<u64> {
+        if index == 0 {
+            return Ok(0);
+        }
+        let mut file = File::open(&self.path)?;
+        let len = file.metadata()?.len();
+        let from = index * self.chunk_size;
+        if from >= len {
+            return Ok(len);
+        }
+        Ok(find_record_start(&mut file, from)?.unwrap_or(len))
+    }
+}
+
+pub fn parse_parallel<F, P>(path: P, action: &F) -> io::Result<()>
+where
+    F: Fn(Event) + Sync,
+    P: AsRef<Path>,
+{
+    ParallelParser::new(path).for_each(action)
+}
+
+// Начало записи - "{YYYYMMDDhhmmss," после разделителя "},\r\n" или "},\n" предыдущей записи
+const SEPARATOR: &[u8] = b"\n{";
+const RECORD_START: usize = 16;
+const LOOKBEHIND: usize = 4;
+
+// `bytes` начинается с '{', `before` - предшествующие байты
+fn is_record_start(before: &[u8], bytes: &[u8]) -> bool {
+    let before = before.strip_suffix(b"\n").unwrap_or(before);
+    let before = before.strip_suffix(b"\r").unwrap_or(before);
+    before.ends_with(b"},")
+        && bytes.len() >= RECORD_START
+        && bytes[0] == b'{'
+        && bytes[1..15].iter().all(u8::is_ascii_digit)
+        && bytes[15] == b','
+}
+
+fn find_record_start(file: &mut File, from: u64) -> io::Result<Option<u64>> {
+    let mut position = from.saturating_sub(LOOKBEHIND as u64);
+    file.seek(SeekFrom::Start(position))?;
+    let mut buffer = vec![0u8; SCAN_SIZE];
+    let mut len = 0;
+    loop {
+        let read = file.read(&mut buffer[len..])?;
+        len += read;
+        let data = &buffer[..len];
+        for i in memchr::memmem::find_iter(data, SEPARATOR) {
+            let start = position + (i + 1) as u64;
+            if start < from || !is_record_start(&data[..=i], &data[i + 1..]) {
+                continue;
+            }
+            return Ok(Some(start));
+        }
+        if read == 0 {
+            return Ok(None);
+        }
+        // Хвост может содержать неполный разделитель и начало записи
+        let keep = len.min(LOOKBEHIND + RECORD_START);
+        buffer.copy_within(len - keep..len, 0);
+        position += (len - keep) as u64;
+        len = keep;
+    }
+}
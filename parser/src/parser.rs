@@ -1,20 +1,42 @@
-use std::{borrow::Cow, cmp::min, marker::PhantomData, str::FromStr};
+use crate::error::{Field, ParseError, ParseResult};
+use alloc::borrow::Cow;
+#[cfg(not(feature = "safe-parser"))]
+use core::marker::PhantomData;
+use core::str::FromStr;
 use uuid::Uuid;
 
-pub struct LogStr<'a> {
+pub(crate) struct LogStr<'a> {
     str: &'a [u8],
     need_replace_quotes: bool,
+    #[cfg(feature = "encoding")]
+    encoding: Option<&'static crate::encoding::Encoding>,
 }
 
 impl<'a> LogStr<'a> {
-    pub fn new(str: &'a [u8], need_replace_quotes: bool) -> LogStr {
+    pub fn new(str: &'a [u8], need_replace_quotes: bool) -> LogStr<'a> {
         LogStr {
             str,
             need_replace_quotes,
+            #[cfg(feature = "encoding")]
+            encoding: None,
         }
     }
+
+    #[cfg(feature = "encoding")]
+    pub fn with_encoding(self, encoding: Option<&'static crate::encoding::Encoding>) -> LogStr<'a> {
+        LogStr { encoding, ..self }
+    }
+
+    #[cfg(feature = "regex")]
+    pub fn raw(&self) -> &'a [u8] {
+        self.str
+    }
+
     pub fn str(&self) -> Cow<'a, str> {
-        let str = String::from_utf8_lossy(self.str);
+        #[cfg(feature = "encoding")]
+        let str = crate::encoding::decode(self.str, self.encoding);
+        #[cfg(not(feature = "encoding"))]
+        let str = alloc::string::String::from_utf8_lossy(self.str);
         match self.need_replace_quotes {
             true => Cow::Owned(str.replace(r#""""#, r#"""#)),
             _ => str,
@@ -22,15 +44,62 @@ impl<'a> LogStr<'a> {
     }
 }
 
-pub struct Parser<'a> {
+// Строка, декодируемая при первом обращении. В ячейке только результат
+// с заменой символов: корректный UTF-8 берется из исходных байт
+pub(crate) struct LazyStr<'a> {
+    str: LogStr<'a>,
+    decoded: core::cell::OnceCell<Option<alloc::string::String>>,
+}
+
+impl<'a> LazyStr<'a> {
+    pub fn new(str: LogStr<'a>) -> LazyStr<'a> {
+        LazyStr {
+            str,
+            decoded: core::cell::OnceCell::new(),
+        }
+    }
+
+    pub fn decoded(str: &'a str) -> LazyStr<'a> {
+        LazyStr {
+            str: LogStr::new(str.as_bytes(), false),
+            decoded: core::cell::OnceCell::from(None),
+        }
+    }
+
+    pub fn get(&self) -> &str {
+        let decoded = self.decoded.get_or_init(|| match self.str.str() {
+            Cow::Borrowed(_) => None,
+            Cow::Owned(str) => Some(str),
+        });
+        match decoded {
+            Some(str) => str,
+            None => borrowed(self.str.str),
+        }
+    }
+}
+
+#[cfg(not(feature = "safe-parser"))]
+fn borrowed(bytes: &[u8]) -> &str {
+    // Cow::Borrowed получен только для корректного UTF-8
+    unsafe { core::str::from_utf8_unchecked(bytes) }
+}
+
+#[cfg(feature = "safe-parser")]
+fn borrowed(bytes: &[u8]) -> &str {
+    core::str::from_utf8(bytes).unwrap_or_default()
+}
+
+#[cfg(not(feature = "safe-parser"))]
+pub(crate) struct Parser<'a> {
     source: *const u8,
     ptr: *const u8,
     end: *const u8,
     _marker: PhantomData<&'a u8>,
 }
 
+#[cfg(not(feature = "safe-parser"))]
 impl<'a> Parser<'a> {
-    pub fn new(buffer: &[u8]) -> Parser {
+    pub fn new(buffer: &[u8]) -> Parser<'_> {
         let ptr = buffer.as_ptr();
         let end = unsafe { ptr.add(buffer.len()) };
         Parser {
@@ -45,149 +114,368 @@ impl<'a> Parser<'a> {
         unsafe { self.ptr.offset_from(self.source) as usize }
     }
 
-    pub fn next(&mut self) -> Option<u8> {
+    pub fn seek(&mut self, position: usize) {
+        let len = unsafe { self.end.offset_from(self.source) } as usize;
+        if position > len {
+            panic!("position out of bounds")
+        }
+        self.ptr = unsafe { self.source.add(position) };
+    }
+
+    pub fn next(&mut self) -> ParseResult<u8> {
         if self.ptr == self.end {
-            None
+            Err(ParseError::Incomplete)
         } else {
             let v = unsafe { *self.ptr };
             self.ptr = unsafe { self.ptr.add(1) };
-            Some(v)
+            Ok(v)
         }
     }
 
-    pub fn skip(&mut self, count: usize) -> Option<()> {
-        let new_ptr = unsafe { self.ptr.add(count) };
-        if new_ptr > self.end {
-            None
+    pub fn skip(&mut self, count: usize) -> ParseResult<()> {
+        let len = unsafe { self.end.offset_from(self.ptr) } as usize;
+        if count > len {
+            Err(ParseError::Incomplete)
         } else {
-            self.ptr = new_ptr;
-            Some(())
+            self.ptr = unsafe { self.ptr.add(count) };
+            Ok(())
         }
     }
 
-    pub fn skip_to(&mut self, ch: u8) -> Option<()> {
+    pub fn remaining(&self) -> &'a [u8] {
         let len = unsafe { self.end.offset_from(self.ptr) } as usize;
-        let haystack = unsafe { std::slice::from_raw_parts(self.ptr, len) };
-        let i = memchr::memchr(ch, haystack)?;
-        self.skip(i + 1)
+        unsafe { core::slice::from_raw_parts(self.ptr, len) }
     }
 
-    pub fn skip_to2(&mut self, ch1: u8, ch2: u8) -> Option<()> {
-        let len = unsafe { self.end.offset_from(self.ptr) } as usize;
-        let haystack = unsafe { std::slice::from_raw_parts(self.ptr, len) };
-        let i = memchr::memchr2(ch1, ch2, haystack)?;
-        self.skip(i + 1)
+    pub fn peek(&self) -> ParseResult<u8> {
+        if self.ptr == self.end {
+            Err(ParseError::Incomplete)
+        } else {
+            let v = unsafe { *self.ptr };
+            Ok(v)
+        }
+    }
+
+    fn slice(&self, start: usize, end: usize) -> &'a [u8] {
+        unsafe { core::slice::from_raw_parts(self.source.add(start), end - start) }
+    }
+}
+
+// Реализация на индексах среза, без unsafe
+#[cfg(feature = "safe-parser")]
+pub(crate) struct Parser<'a> {
+    buffer: &'a [u8],
+    pos: usize,
+}
+
+#[cfg(feature = "safe-parser")]
+impl<'a> Parser<'a> {
+    pub fn new(buffer: &[u8]) -> Parser<'_> {
+        Parser { buffer, pos: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
     }
 
-    pub fn current(&self) -> u8 {
-        if self.ptr == self.source {
-            panic!("before need to call next()")
+    pub fn seek(&mut self, position: usize) {
+        if position > self.buffer.len() {
+            panic!("position out of bounds")
         }
-        unsafe { *self.ptr.sub(1) }
+        self.pos = position;
     }
 
-    pub fn peek(&self) -> Option<u8> {
-        if self.ptr == self.end {
-            None
+    pub fn next(&mut self) -> ParseResult<u8> {
+        let v = *self.buffer.get(self.pos).ok_or(ParseError::Incomplete)?;
+        self.pos += 1;
+        Ok(v)
+    }
+
+    pub fn skip(&mut self, count: usize) -> ParseResult<()> {
+        if count > self.buffer.len() - self.pos {
+            Err(ParseError::Incomplete)
         } else {
-            let v = unsafe { *self.ptr };
-            Some(v)
+            self.pos += count;
+            Ok(())
         }
     }
 
-    pub fn parse_usize(&mut self) -> Option<usize> {
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.buffer[self.pos..]
+    }
+
+    pub fn peek(&self) -> ParseResult<u8> {
+        self.buffer
+            .get(self.pos)
+            .copied()
+            .ok_or(ParseError::Incomplete)
+    }
+
+    fn slice(&self, start: usize, end: usize) -> &'a [u8] {
+        &self.buffer[start..end]
+    }
+}
+
+impl<'a> Parser<'a> {
+    // Байты от start до текущей позиции
+    pub fn slice_from(&self, start: usize) -> &'a [u8] {
+        self.slice(start, self.position())
+    }
+
+    pub fn skip_to(&mut self, ch: u8) -> ParseResult<()> {
+        let i = memchr::memchr(ch, self.remaining()).ok_or(ParseError::Incomplete)?;
+        self.skip(i + 1)
+    }
+
+    pub fn skip_to2(&mut self, ch1: u8, ch2: u8) -> ParseResult<()> {
+        let i = memchr::memchr2(ch1, ch2, self.remaining()).ok_or(ParseError::Incomplete)?;
+        self.skip(i + 1)
+    }
+
+    // Пропустить испорченную запись, начинающуюся с position, до следующей строки
+    pub fn recover(&mut self, position: usize) -> ParseResult<()> {
+        self.seek(position);
+        self.skip_to(b'{')?;
+        self.skip_to(b'\n')
+    }
+
+    pub fn parse_usize(&mut self) -> ParseResult<usize> {
+        let position = self.position();
         let mut number: usize = 0;
         loop {
             let next = self.next()?;
             if next == b',' || next == b'}' {
                 break;
             }
-            number = number * 10 + (next - b'0') as usize;
+            let digit = next.wrapping_sub(b'0');
+            if digit > 9 {
+                return Err(ParseError::invalid(
+                    self.position() - 1,
+                    Field::Number,
+                    "digit",
+                ));
+            }
+            number = number
+                .checked_mul(10)
+                .and_then(|number| number.checked_add(digit as usize))
+                .ok_or(ParseError::invalid(position, Field::Number, "usize"))?;
         }
-        Some(number)
+        Ok(number)
     }
 
-    pub fn parse_raw(&mut self) -> Option<&'a [u8]> {
-        let ptr = self.ptr;
+    pub fn parse_raw(&mut self) -> ParseResult<&'a [u8]> {
+        let start = self.position();
         self.skip_to2(b',', b'}')?;
-        Some(unsafe { std::slice::from_raw_parts(ptr, self.ptr.offset_from(ptr) as usize - 1) })
+        Ok(self.slice(start, self.position() - 1))
     }
 
-    pub fn parse_uuid(&mut self) -> Option<Uuid> {
-        let raw = self.parse_raw()?;
-        let s = std::str::from_utf8(raw).expect("Invalid file format");
-        Some(Uuid::from_str(s).expect("Invalid file format"))
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub fn parse_uuid(&mut self) -> ParseResult<Uuid> {
+        let position = self.position();
+        let mut raw = self.parse_raw()?;
+        // В старых журналах встречается форма в фигурных скобках: '}' уже прочитана,
+        // дальше идет разделитель
+        if raw.first() == Some(&b'{') {
+            let delimiter = self.next()?;
+            if delimiter != b',' && delimiter != b'}' {
+                return Err(ParseError::invalid(
+                    self.position() - 1,
+                    Field::Uuid,
+                    "',' or '}'",
+                ));
+            }
+            raw = &raw[1..];
+        }
+        // Без проверки UTF-8; допускаются любой регистр и форма без дефисов
+        Uuid::try_parse_ascii(raw.trim_ascii())
+            .map_err(|_| ParseError::invalid(position, Field::Uuid, "uuid"))
     }
 
-    pub fn parse_str(&mut self) -> Option<LogStr<'a>> {
+    pub fn parse_str(&mut self) -> ParseResult<LogStr<'a>> {
         let ch = self.next()?;
         if ch != b'"' {
-            let len = min(20, unsafe { self.end.offset_from(self.ptr) as usize });
-            let s = unsafe { std::slice::from_raw_parts(self.ptr, len) };
-            panic!("Invalid data 1: {}", String::from_utf8_lossy(s));
-        }
-        let ptr = self.ptr;
-        let mut need_replace_quotes = false;
-
-        loop {
-            self.skip_to(b'"')?;
-            let next = self.next()?;
-            if next == b',' || next == b'}' {
-                break;
-            } else if next == b'"' {
-                need_replace_quotes = true;
-            }
+            return Err(ParseError::invalid(
+                self.position() - 1,
+                Field::String,
+                "'\"'",
+            ));
         }
+        let start = self.position();
+        let (len, need_replace_quotes) = str_len(self.remaining())?;
+        // Закрывающая кавычка и разделитель ',' или '}'
+        self.skip(len + 2)?;
+        Ok(LogStr::new(
+            self.slice(start, start + len),
+            need_replace_quotes,
+        ))
+    }
 
-        let s = unsafe { std::slice::from_raw_parts(ptr, self.ptr.offset_from(ptr) as usize - 2) };
-        Some(LogStr::new(s, need_replace_quotes))
+    pub fn parse_object(&mut self) -> ParseResult<&'a str> {
+        let raw = self.parse_object_raw()?;
+        let start = self.position() - raw.len() - 1;
+        core::str::from_utf8(raw).map_err(|_| ParseError::invalid(start, Field::Object, "utf-8"))
     }
 
-    pub fn parse_object(&mut self) -> Option<&'a str> {
+    pub fn parse_object_raw(&mut self) -> ParseResult<&'a [u8]> {
         // Перейти к '{'
-        while self.next()? != b'{' {}
+        self.skip_to(b'{')?;
 
         // Запомнить начало строки
-        let ptr = unsafe { self.ptr.sub(1) };
-        let mut end_of_record = false;
+        let start = self.position() - 1;
+        self.skip_object()?;
+
+        // Перевод строки может быть как "\r\n", так и "\n"
+        let mut last = self.next()?;
+        if last == b'\r' && self.peek()? == b'\n' {
+            self.skip(1)?;
+            last = self.next()?;
+        } else if last == b'\n' {
+            last = self.next()?;
+        }
+        if last != b',' && last != b'}' {
+            return Err(ParseError::invalid(
+                self.position() - 1,
+                Field::Object,
+                "',' or '}'",
+            ));
+        }
+
+        Ok(self.slice(start, self.position() - 1))
+    }
 
-        while !end_of_record {
-            let peek = self.peek()?;
-            match peek {
+    // Пропустить остаток объекта после '{' вместе с закрывающей '}':
+    // переход сразу к следующей кавычке или скобке, строки пропускаются целиком
+    pub fn skip_object(&mut self) -> ParseResult<()> {
+        let mut depth = 1;
+        loop {
+            let remaining = self.remaining();
+            let i = memchr::memchr3(b'"', b'{', b'}', remaining).ok_or(ParseError::Incomplete)?;
+            match remaining[i] {
                 b'"' => {
-                    self.parse_str()?;
+                    let (len, _) = str_len(&remaining[i + 1..])?;
+                    self.skip(i + len + 2)?;
                 }
                 b'{' => {
-                    self.parse_object()?;
+                    depth += 1;
+                    self.skip(i + 1)?;
                 }
-                b'\r' => self.skip(2)?,
                 _ => {
-                    self.parse_raw()?;
+                    depth -= 1;
+                    self.skip(i + 1)?;
+                    if depth == 0 {
+                        return Ok(());
+                    }
                 }
             }
-            end_of_record = self.current() == b'}';
         }
-        let mut last = self.next()?;
-        if last == b'\r' {
-            self.skip(1)?;
-            last = self.next()?;
+    }
+}
+
+// Длина строки до закрывающей кавычки, за которой идет ',' или '}'; "" - экранированная кавычка
+fn str_len(str: &[u8]) -> ParseResult<(usize, bool)> {
+    let mut need_replace_quotes = false;
+    let mut position = 0;
+    loop {
+        let i = position + memchr::memchr(b'"', &str[position..]).ok_or(ParseError::Incomplete)?;
+        match str.get(i + 1) {
+            Some(b',' | b'}') => return Ok((i, need_replace_quotes)),
+            Some(b'"') => {
+                need_replace_quotes = true;
+                position = i + 2;
+            }
+            Some(_) => position = i + 1,
+            None => return Err(ParseError::Incomplete),
         }
-        if last != b',' && last != b'}' {
-            unsafe {
-                let len = min(20, self.ptr.offset_from(self.end) as usize);
-                let s = std::slice::from_raw_parts(self.ptr, len);
-                panic!("Invalid data 2: {}", String::from_utf8_lossy(s));
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token<'a> {
+    OpenBrace,
+    CloseBrace,
+    Number(&'a str),
+    Str(Cow<'a, str>),
+    Uuid(Uuid),
+    Raw(&'a str),
+}
+
+pub struct Tokenizer<'a> {
+    parser: Parser<'a>,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(buffer: &'a [u8]) -> Tokenizer<'a> {
+        Tokenizer {
+            parser: Parser::new(buffer),
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.parser.position()
+    }
+
+    pub fn next_token(&mut self) -> ParseResult<Option<Token<'a>>> {
+        loop {
+            let ch = match self.parser.peek() {
+                Ok(ch) => ch,
+                Err(_) => return Ok(None),
+            };
+            match ch {
+                b',' | b' ' | b'\t' | b'\r' | b'\n' => self.parser.skip(1)?,
+                b'{' => {
+                    self.parser.skip(1)?;
+                    return Ok(Some(Token::OpenBrace));
+                }
+                b'}' => {
+                    self.parser.skip(1)?;
+                    return Ok(Some(Token::CloseBrace));
+                }
+                b'"' => {
+                    let str = self.parser.parse_str()?;
+                    self.unread();
+                    return Ok(Some(Token::Str(str.str())));
+                }
+                _ => {
+                    let position = self.parser.position();
+                    let raw = self.parser.parse_raw()?;
+                    self.unread();
+                    let raw = core::str::from_utf8(raw)
+                        .map_err(|_| ParseError::invalid(position, Field::String, "utf-8"))?;
+                    return Ok(Some(classify(raw.trim())));
+                }
             }
         }
+    }
+
+    // Вернуть разделитель ',' или '}', прочитанный вместе со значением
+    fn unread(&mut self) {
+        self.parser.seek(self.parser.position() - 1);
+    }
+}
 
-        let s = unsafe { std::slice::from_raw_parts(ptr, self.ptr.offset_from(ptr) as usize - 1) };
-        Some(std::str::from_utf8(s).expect("Invalid file format"))
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = ParseResult<Token<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token().transpose()
+    }
+}
+
+fn classify(token: &str) -> Token<'_> {
+    let digits = token.strip_prefix('-').unwrap_or(token);
+    if !digits.is_empty() && digits.bytes().all(|ch| ch.is_ascii_digit()) {
+        return Token::Number(token);
+    }
+    match Uuid::from_str(token) {
+        Ok(id) if token.len() == 36 => Token::Uuid(id),
+        _ => Token::Raw(token),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::{string::ToString, vec, vec::Vec};
 
     #[test]
     fn test_parse_u32() {
@@ -197,6 +485,21 @@ mod tests {
         assert_eq!(n, 12345);
     }
 
+    #[test]
+    fn test_parse_usize_invalid() {
+        let mut parser = Parser::new(b"12a4,");
+        assert_eq!(
+            parser.parse_usize(),
+            Err(ParseError::invalid(2, Field::Number, "digit"))
+        );
+
+        let mut parser = Parser::new(b"99999999999999999999999,");
+        assert_eq!(
+            parser.parse_usize(),
+            Err(ParseError::invalid(0, Field::Number, "usize"))
+        );
+    }
+
     #[test]
     fn test_parse_raw() {
         let buf = b"12345,";
@@ -209,9 +512,9 @@ mod tests {
     fn test_parse_none() {
         let buf = b"1111,12345";
         let mut parser = Parser::new(buf);
-        parser.skip(5);
+        parser.skip(5).unwrap();
         let r = parser.parse_raw();
-        assert_eq!(r, None)
+        assert_eq!(r, Err(ParseError::Incomplete))
     }
 
     #[test]
@@ -225,6 +528,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_uuid_variants() {
+        let expected = Uuid::from_str("71ada582-5c75-466a-b17c-7b9a48af5f0b").unwrap();
+        let buf = b"71ADA582-5C75-466A-B17C-7B9A48AF5F0B,71ada5825c75466ab17c7b9a48af5f0b,\
+                    {71ada582-5c75-466a-b17c-7b9a48af5f0b},{71ADA582-5C75-466A-B17C-7B9A48AF5F0B}}";
+        let mut parser = Parser::new(buf);
+        for _ in 0..4 {
+            assert_eq!(parser.parse_uuid(), Ok(expected));
+        }
+        assert!(parser.remaining().is_empty());
+
+        let mut parser = Parser::new(b"{71ada582-5c75-466a-b17c-7b9a48af5f0b}x");
+        assert_eq!(
+            parser.parse_uuid(),
+            Err(ParseError::invalid(38, Field::Uuid, "',' or '}'"))
+        );
+        let mut parser = Parser::new(b"71ada582-5c75-466a-b17c-7b9a48af5fzz,");
+        assert_eq!(
+            parser.parse_uuid(),
+            Err(ParseError::invalid(0, Field::Uuid, "uuid"))
+        );
+    }
+
     #[test]
     fn test_parse_str_1() {
         let buf = b"\"12345\"}";
@@ -242,6 +568,29 @@ mod tests {
         assert_eq!(str.str(), r#"123"45"#);
     }
 
+    #[test]
+    fn test_parse_str_3() {
+        // Экранированная кавычка перед разделителем внутри строки
+        let buf = br#""a"",b",""""}"#;
+        let mut parser = Parser::new(buf);
+        assert_eq!(parser.parse_str().unwrap().str(), r#"a",b"#);
+        assert_eq!(parser.parse_str().unwrap().str(), r#"""#);
+        assert!(parser.remaining().is_empty());
+
+        let mut parser = Parser::new(br#""abc"""#);
+        assert_eq!(parser.parse_str().err(), Some(ParseError::Incomplete));
+    }
+
+    #[test]
+    fn test_parse_str_invalid() {
+        let buf = b"12,\"N\"}";
+        let mut parser = Parser::new(buf);
+        parser.skip(4).unwrap();
+        let error = parser.parse_str().err().unwrap();
+        assert_eq!(error, ParseError::invalid(4, Field::String, "'\"'"));
+        assert_eq!(error.offset(), Some(4));
+    }
+
     #[test]
     fn test_parse_object_1() {
         let buf = br#"   {1,"N"}, 321"#;
@@ -250,6 +599,56 @@ mod tests {
         assert_eq!(res, r#"{1,"N"}"#);
     }
 
+    #[test]
+    fn test_skip_object() {
+        let buf = br#"1,"}",{2,"N"}}, 321"#;
+        let mut parser = Parser::new(buf);
+        parser.skip_object().unwrap();
+        assert_eq!(parser.next(), Ok(b','));
+    }
+
+    #[test]
+    fn test_recover() {
+        let buf = b"{1,X,\r\n{2}},\n{3}";
+        let mut parser = Parser::new(buf);
+        parser.recover(0).unwrap();
+        assert_eq!(parser.peek(), Ok(b'{'));
+        assert_eq!(parser.recover(parser.position()), Ok(()));
+        assert_eq!(parser.next(), Ok(b'{'));
+        assert_eq!(
+            parser.recover(parser.position()),
+            Err(ParseError::Incomplete)
+        );
+    }
+
+    #[test]
+    fn test_tokenizer() {
+        let buf = b"{1,\r\n{\"S\",\"a\"\"b\"},71ada582-5c75-466a-b17c-7b9a48af5f0b,-2,\r\n{}}";
+        let tokens = Tokenizer::new(buf)
+            .collect::<ParseResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::OpenBrace,
+                Token::Number("1"),
+                Token::OpenBrace,
+                Token::Str(Cow::Borrowed("S")),
+                Token::Str(Cow::Owned(r#"a"b"#.to_string())),
+                Token::CloseBrace,
+                Token::Uuid(Uuid::from_str("71ada582-5c75-466a-b17c-7b9a48af5f0b").unwrap()),
+                Token::Number("-2"),
+                Token::OpenBrace,
+                Token::CloseBrace,
+                Token::CloseBrace,
+            ]
+        );
+
+        let mut tokenizer = Tokenizer::new(b"{\"abc");
+        assert_eq!(tokenizer.next(), Some(Ok(Token::OpenBrace)));
+        assert_eq!(tokenizer.next(), Some(Err(ParseError::Incomplete)));
+    }
+
     #[test]
     fn test_parse_object_2() {
         let buf = br#"   {1,2,3,"123",{1,"N"}}, 321"#;
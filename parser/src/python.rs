@@ -0,0 +1,239 @@
+// Модуль Python: maturin build --features python,pyo3/extension-module
+use crate::{
+    directory::LogDirectory,
+    events::{self, Event},
+    export::{Cell, Column},
+    query::{CompiledQuery, Query},
+    references::References,
+    source::EventSource,
+};
+use pyo3::{
+    exceptions::{PyIOError, PyValueError},
+    prelude::*,
+    types::PyDict,
+};
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    io,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+const BATCH: usize = 1024;
+
+type Row = Vec<Cell<'static>>;
+
+#[pymodule]
+#[pyo3(name = "event_log_parser")]
+pub fn register(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(parse_events, module)?)?;
+    module.add_class::<PyReferences>()?;
+    module.add_class::<EventIterator>()?;
+    Ok(())
+}
+
+// Каталог журнала или отдельный файл *.lgp рядом с 1Cv8.lgf
+enum Source {
+    Directory(LogDirectory),
+    File(PathBuf, Arc<References>),
+}
+
+impl Source {
+    fn open(path: &Path) -> io::Result<Source> {
+        if path.is_dir() {
+            return LogDirectory::open(path).map(Source::Directory);
+        }
+        let mut refs = References::default();
+        let dir = path.parent().unwrap_or(Path::new("."));
+        refs.parse(dir.join("1Cv8.lgf"))?;
+        Ok(Source::File(path.to_path_buf(), Arc::new(refs)))
+    }
+
+    fn references(&self) -> &References {
+        match self {
+            Source::Directory(dir) => dir.references(),
+            Source::File(_, refs) => refs,
+        }
+    }
+
+    fn parse_until(&self, action: &mut dyn FnMut(Event) -> ControlFlow<()>) -> io::Result<()> {
+        match self {
+            Source::Directory(dir) => dir.parse_until(action),
+            Source::File(path, _) => events::parse_until(path, &mut |event| action(event)),
+        }
+    }
+}
+
+/// Итератор событий журнала в виде словарей, разбор идет в отдельном потоке без GIL
+#[pyfunction]
+#[pyo3(signature = (path, query = None))]
+fn parse_events(path: PathBuf, query: Option<&str>) -> PyResult<EventIterator> {
+    let source = Source::open(&path).map_err(|error| PyIOError::new_err(error.to_string()))?;
+    let query: Option<CompiledQuery> = match query {
+        Some(query) => Some(
+            Query::parse(query)
+                .map_err(|error| PyValueError::new_err(error.to_string()))?
+                .compile(source.references()),
+        ),
+        None => None,
+    };
+
+    let (sender, receiver) = mpsc::sync_channel(4);
+    thread::spawn(move || {
+        let refs = source.references();
+        let mut batch = Vec::with_capacity(BATCH);
+        let result = source.parse_until(&mut |event| {
+            if query.as_ref().is_none_or(|query| query.matches(&event)) {
+                let event = event.resolve(refs);
+                batch.push(
+                    Column::ALL
+                        .iter()
+                        .map(|column| match column.value(&event) {
+                            Cell::Str(s) => Cell::Str(Cow::Owned(s.into_owned())),
+                            Cell::Number(n) => Cell::Number(n),
+                            Cell::Date(date) => Cell::Date(date),
+                        })
+                        .collect(),
+                );
+            }
+            if batch.len() < BATCH {
+                return ControlFlow::Continue(());
+            }
+            let full = std::mem::replace(&mut batch, Vec::with_capacity(BATCH));
+            match sender.send(Ok(full)) {
+                Ok(()) => ControlFlow::Continue(()),
+                Err(_) => ControlFlow::Break(()),
+            }
+        });
+        let _ = match result {
+            Ok(()) if !batch.is_empty() => sender.send(Ok(batch)),
+            Ok(()) => Ok(()),
+            Err(error) => sender.send(Err(error)),
+        };
+    });
+    Ok(EventIterator {
+        receiver: Mutex::new(receiver),
+        batch: VecDeque::new(),
+    })
+}
+
+#[pyclass]
+pub struct EventIterator {
+    receiver: Mutex<Receiver<io::Result<Vec<Row>>>>,
+    batch: VecDeque<Row>,
+}
+
+#[pymethods]
+impl EventIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__<'py>(&mut self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        if self.batch.is_empty() {
+            let receiver = &self.receiver;
+            let batch = py.detach(|| receiver.lock().expect("receiver lock").recv());
+            match batch {
+                Ok(Ok(batch)) => self.batch = batch.into(),
+                Ok(Err(error)) => return Err(PyIOError::new_err(error.to_string())),
+                Err(_) => return Ok(None),
+            }
+        }
+        let Some(row) = self.batch.pop_front() else {
+            return Ok(None);
+        };
+        let dict = PyDict::new(py);
+        for (column, cell) in Column::ALL.iter().zip(row) {
+            match cell {
+                Cell::Str(s) => dict.set_item(column.name(), s.as_ref())?,
+                Cell::Number(n) => dict.set_item(column.name(), n)?,
+                Cell::Date(date) => dict.set_item(column.name(), date)?,
+            }
+        }
+        Ok(Some(dict))
+    }
+}
+
+/// Справочники 1Cv8.lgf; путь к файлу или каталогу журнала
+#[pyclass(name = "References", frozen)]
+pub struct PyReferences {
+    refs: References,
+}
+
+#[pymethods]
+impl PyReferences {
+    #[new]
+    fn new(path: PathBuf) -> PyResult<PyReferences> {
+        let path = match path.is_dir() {
+            true => path.join("1Cv8.lgf"),
+            false => path,
+        };
+        let mut refs = References::default();
+        refs.parse(path)
+            .map_err(|error| PyIOError::new_err(error.to_string()))?;
+        Ok(PyReferences { refs })
+    }
+
+    /// Пары (uuid, имя), индекс в списке - номер в журнале
+    #[getter]
+    fn users(&self) -> Vec<(String, String)> {
+        self.refs
+            .users()
+            .iter()
+            .map(|user| (user.id().to_string(), user.name().to_string()))
+            .collect()
+    }
+
+    #[getter]
+    fn computers(&self) -> Vec<String> {
+        self.refs.computers().to_vec()
+    }
+
+    #[getter]
+    fn applications(&self) -> Vec<String> {
+        self.refs.applications().to_vec()
+    }
+
+    #[getter]
+    fn events(&self) -> Vec<String> {
+        self.refs.events().to_vec()
+    }
+
+    #[getter]
+    fn metadata(&self) -> Vec<(String, String)> {
+        self.refs
+            .metadata()
+            .iter()
+            .map(|metadata| (metadata.id().to_string(), metadata.name().to_string()))
+            .collect()
+    }
+
+    #[getter]
+    fn worker_servers(&self) -> Vec<String> {
+        self.refs.worker_servers().to_vec()
+    }
+
+    #[getter]
+    fn ports(&self) -> Vec<u32> {
+        self.refs.ports().to_vec()
+    }
+
+    fn user_id(&self, name: &str) -> Option<usize> {
+        self.refs.user_id_by_name(name)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "References(users={}, events={}, metadata={})",
+            self.refs.users().len(),
+            self.refs.events().len(),
+            self.refs.metadata().len()
+        )
+    }
+}
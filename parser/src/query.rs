@@ -0,0 +1,667 @@
+use crate::{
+    events::{Event, EventLogLevel, TransactionStatus},
+    references::References,
+};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use std::{error, fmt, str::FromStr};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryError {
+    position: usize,
+    message: String,
+}
+
+impl QueryError {
+    fn new<S: Into<String>>(position: usize, message: S) -> QueryError {
+        QueryError {
+            position,
+            message: message.into(),
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl error::Error for QueryError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    Date,
+    Level,
+    TransactionStatus,
+    User,
+    Computer,
+    Application,
+    Event,
+    Metadata,
+    Comment,
+    Data,
+    DataPresentation,
+    WorkerServer,
+    Port,
+    SyncPort,
+    Connection,
+    Session,
+}
+
+impl Field {
+    const ALL: [Field; 16] = [
+        Field::Date,
+        Field::Level,
+        Field::TransactionStatus,
+        Field::User,
+        Field::Computer,
+        Field::Application,
+        Field::Event,
+        Field::Metadata,
+        Field::Comment,
+        Field::Data,
+        Field::DataPresentation,
+        Field::WorkerServer,
+        Field::Port,
+        Field::SyncPort,
+        Field::Connection,
+        Field::Session,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Field::Date => "date",
+            Field::Level => "level",
+            Field::TransactionStatus => "transaction_status",
+            Field::User => "user",
+            Field::Computer => "computer",
+            Field::Application => "application",
+            Field::Event => "event",
+            Field::Metadata => "metadata",
+            Field::Comment => "comment",
+            Field::Data => "data",
+            Field::DataPresentation => "data_presentation",
+            Field::WorkerServer => "server",
+            Field::Port => "port",
+            Field::SyncPort => "sync_port",
+            Field::Connection => "connection",
+            Field::Session => "session",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    NotContains,
+}
+
+impl Op {
+    fn is_ordering(&self) -> bool {
+        matches!(self, Op::Lt | Op::Le | Op::Gt | Op::Ge)
+    }
+
+    fn compare<T: PartialOrd>(&self, left: T, right: T) -> bool {
+        match self {
+            Op::Eq => left == right,
+            Op::Ne => left != right,
+            Op::Lt => left < right,
+            Op::Le => left <= right,
+            Op::Gt => left > right,
+            Op::Ge => left >= right,
+            Op::Contains | Op::NotContains => false,
+        }
+    }
+
+    // Поиск подстроки без учета регистра
+    fn text(&self, text: &str, value: &str) -> bool {
+        match self {
+            Op::Contains => text.to_lowercase().contains(value),
+            Op::NotContains => !text.to_lowercase().contains(value),
+            op => op.compare(text, value),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Text(String),
+    Number(u64),
+    Level(EventLogLevel),
+    Status(TransactionStatus),
+    // Дата без времени означает весь день
+    Date(NaiveDateTime, NaiveDateTime),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Cond(Field, Op, Value),
+}
+
+// Выражение вида: level >= Warning and user = "Иванов" and comment ~ "deadlock"
+//     and date between 2024-01-01 and 2024-01-31
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    expr: Expr,
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Query, QueryError> {
+        let tokens = tokenize(text)?;
+        let mut parser = QueryParser {
+            tokens,
+            position: 0,
+            len: text.len(),
+        };
+        let expr = parser.or()?;
+        match parser.peek() {
+            None => Ok(Query { expr }),
+            Some((position, token)) => Err(QueryError::new(
+                *position,
+                format!("unexpected {}", token.describe()),
+            )),
+        }
+    }
+
+    pub fn compile(&self, refs: &References) -> CompiledQuery {
+        CompiledQuery {
+            expr: compile(&self.expr, refs),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Query::parse(s)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Compiled {
+    And(Box<Compiled>, Box<Compiled>),
+    Or(Box<Compiled>, Box<Compiled>),
+    Not(Box<Compiled>),
+    Ids(Field, Vec<bool>),
+    Text(Field, Op, String),
+    Number(Field, Op, u64),
+    Level(Op, u8),
+    Status(Op, TransactionStatus),
+    Date(Op, NaiveDateTime, NaiveDateTime),
+}
+
+#[derive(Clone, Debug)]
+pub struct CompiledQuery {
+    expr: Compiled,
+}
+
+impl CompiledQuery {
+    pub fn matches(&self, event: &Event) -> bool {
+        matches(&self.expr, event)
+    }
+}
+
+fn compile(expr: &Expr, refs: &References) -> Compiled {
+    let ids = |field, values: Vec<bool>| Compiled::Ids(field, values);
+    match expr {
+        Expr::And(a, b) => Compiled::And(Box::new(compile(a, refs)), Box::new(compile(b, refs))),
+        Expr::Or(a, b) => Compiled::Or(Box::new(compile(a, refs)), Box::new(compile(b, refs))),
+        Expr::Not(a) => Compiled::Not(Box::new(compile(a, refs))),
+        Expr::Cond(field, op, value) => match (field, value) {
+            (Field::User, Value::Text(s)) => ids(
+                *field,
+                refs.users().iter().map(|x| op.text(x.name(), s)).collect(),
+            ),
+            (Field::Metadata, Value::Text(s)) => ids(
+                *field,
+                refs.metadata()
+                    .iter()
+                    .map(|x| op.text(x.name(), s) || op.text(&x.id().to_string(), s))
+                    .collect(),
+            ),
+            (
+                Field::Computer | Field::Application | Field::Event | Field::WorkerServer,
+                Value::Text(s),
+            ) => {
+                let names = match field {
+                    Field::Computer => refs.computers(),
+                    Field::Application => refs.applications(),
+                    Field::Event => refs.events(),
+                    _ => refs.worker_servers(),
+                };
+                ids(*field, names.iter().map(|x| op.text(x, s)).collect())
+            }
+            (Field::Port | Field::SyncPort, Value::Number(n)) => {
+                let ports = match field {
+                    Field::Port => refs.ports(),
+                    _ => refs.sync_ports(),
+                };
+                ids(
+                    *field,
+                    ports.iter().map(|x| op.compare(*x as u64, *n)).collect(),
+                )
+            }
+            (_, Value::Text(s)) => Compiled::Text(*field, *op, s.clone()),
+            (_, Value::Number(n)) => Compiled::Number(*field, *op, *n),
+            (_, Value::Level(level)) => Compiled::Level(*op, rank(level)),
+            (_, Value::Status(status)) => Compiled::Status(*op, *status),
+            (_, Value::Date(start, end)) => Compiled::Date(*op, *start, *end),
+        },
+    }
+}
+
+fn matches(expr: &Compiled, event: &Event) -> bool {
+    match expr {
+        Compiled::And(a, b) => matches(a, event) && matches(b, event),
+        Compiled::Or(a, b) => matches(a, event) || matches(b, event),
+        Compiled::Not(a) => !matches(a, event),
+        Compiled::Ids(field, ids) => {
+            let id = match field {
+                Field::User => event.user_id(),
+                Field::Computer => event.computer_id(),
+                Field::Application => event.application_id(),
+                Field::Event => event.event_id(),
+                Field::Metadata => event.metadata_id(),
+                Field::WorkerServer => event.worker_server_id(),
+                Field::Port => event.port_id(),
+                _ => event.sync_port_id(),
+            };
+            ids.get(id).copied().unwrap_or(false)
+        }
+        Compiled::Text(field, op, value) => match field {
+            Field::Comment => op.text(&event.comment(), value),
+            Field::Data => op.text(event.data(), value),
+            _ => op.text(&event.data_presentation(), value),
+        },
+        Compiled::Number(field, op, value) => {
+            let number = match field {
+                Field::Connection => event.connection(),
+                _ => event.session(),
+            };
+            op.compare(number as u64, *value)
+        }
+        Compiled::Level(op, value) => op.compare(rank(event.log_level()), *value),
+        Compiled::Status(op, status) => (event.transaction_status() == status) == (*op == Op::Eq),
+        Compiled::Date(op, start, end) => {
+            let date = event.date();
+            match op {
+                Op::Eq => date >= *start && date <= *end,
+                Op::Ne => date < *start || date > *end,
+                Op::Lt => date < *start,
+                Op::Le => date <= *end,
+                Op::Gt => date > *end,
+                _ => date >= *start,
+            }
+        }
+    }
+}
+
+fn rank(level: &EventLogLevel) -> u8 {
+    match level {
+        EventLogLevel::Note => 0,
+        EventLogLevel::Information => 1,
+        EventLogLevel::Warning => 2,
+        EventLogLevel::Error => 3,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Op(Op),
+    Open,
+    Close,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Word(word) => format!("'{word}'"),
+            Token::Str(s) => format!("\"{s}\""),
+            Token::Op(op) => format!("operator {op:?}"),
+            Token::Open => "'('".to_string(),
+            Token::Close => "')'".to_string(),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((position, ch)) = chars.next() {
+        let mut next_is = |expected: char| chars.next_if(|(_, ch)| *ch == expected).is_some();
+        let token = match ch {
+            ch if ch.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '=' => Token::Op(Op::Eq),
+            '~' => Token::Op(Op::Contains),
+            '<' if next_is('=') => Token::Op(Op::Le),
+            '<' if next_is('>') => Token::Op(Op::Ne),
+            '<' => Token::Op(Op::Lt),
+            '>' if next_is('=') => Token::Op(Op::Ge),
+            '>' => Token::Op(Op::Gt),
+            '!' if next_is('=') => Token::Op(Op::Ne),
+            '!' if next_is('~') => Token::Op(Op::NotContains),
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, ch)) => s.push(ch),
+                            None => return Err(QueryError::new(position, "unterminated string")),
+                        },
+                        Some((_, '"')) => break,
+                        Some((_, ch)) => s.push(ch),
+                        None => return Err(QueryError::new(position, "unterminated string")),
+                    }
+                }
+                Token::Str(s)
+            }
+            ch if is_word_char(ch) => {
+                let mut word = ch.to_string();
+                while let Some((_, ch)) = chars.next_if(|(_, ch)| is_word_char(*ch)) {
+                    word.push(ch);
+                }
+                Token::Word(word)
+            }
+            ch => return Err(QueryError::new(position, format!("unexpected '{ch}'"))),
+        };
+        tokens.push((position, token));
+    }
+    Ok(tokens)
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || matches!(ch, '_' | '-' | ':' | '.' | '$')
+}
+
+struct QueryParser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    len: usize,
+}
+
+impl QueryParser {
+    fn peek(&self) -> Option<&(usize, Token)> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self, expected: &str) -> Result<(usize, Token), QueryError> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| QueryError::new(self.len, format!("expected {expected}")))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = self
+            .peek()
+            .is_some_and(|(_, token)| token.is_keyword(keyword));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.unary()?;
+        while self.keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, QueryError> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.peek().is_some_and(|(_, token)| *token == Token::Open) {
+            self.position += 1;
+            let expr = self.or()?;
+            return match self.next("')'")? {
+                (_, Token::Close) => Ok(expr),
+                (position, token) => Err(QueryError::new(
+                    position,
+                    format!("expected ')', found {}", token.describe()),
+                )),
+            };
+        }
+        self.condition()
+    }
+
+    fn condition(&mut self) -> Result<Expr, QueryError> {
+        let (position, token) = self.next("field name")?;
+        let field = match &token {
+            Token::Word(word) => Field::ALL
+                .into_iter()
+                .find(|field| field.name().eq_ignore_ascii_case(word)),
+            _ => None,
+        }
+        .ok_or_else(|| QueryError::new(position, format!("unknown field {}", token.describe())))?;
+
+        if self.keyword("between") {
+            let from = self.value(field, Op::Ge)?;
+            if !self.keyword("and") {
+                let position = self.peek().map_or(self.len, |(position, _)| *position);
+                return Err(QueryError::new(position, "expected 'and'"));
+            }
+            let to = self.value(field, Op::Le)?;
+            return Ok(Expr::And(
+                Box::new(Expr::Cond(field, Op::Ge, from)),
+                Box::new(Expr::Cond(field, Op::Le, to)),
+            ));
+        }
+
+        let op = match self.next("operator")? {
+            (_, Token::Op(op)) => op,
+            (position, token) => {
+                return Err(QueryError::new(
+                    position,
+                    format!("expected operator, found {}", token.describe()),
+                ))
+            }
+        };
+        let value = self.value(field, op)?;
+        Ok(Expr::Cond(field, op, value))
+    }
+
+    fn value(&mut self, field: Field, op: Op) -> Result<Value, QueryError> {
+        let (position, token) = self.next("value")?;
+        let text = match token {
+            Token::Word(s) | Token::Str(s) => s,
+            token => {
+                return Err(QueryError::new(
+                    position,
+                    format!("expected value, found {}", token.describe()),
+                ))
+            }
+        };
+        let error = |message: &str| Err(QueryError::new(position, message));
+        let text_field = matches!(
+            field,
+            Field::User
+                | Field::Computer
+                | Field::Application
+                | Field::Event
+                | Field::Metadata
+                | Field::Comment
+                | Field::Data
+                | Field::DataPresentation
+                | Field::WorkerServer
+        );
+        if matches!(op, Op::Contains | Op::NotContains) && !text_field {
+            return error("'~' is supported only for text fields");
+        }
+        if op.is_ordering() && matches!(field, Field::TransactionStatus) {
+            return error("transaction status can only be compared for equality");
+        }
+        match field {
+            _ if text_field => match op {
+                Op::Contains | Op::NotContains => Ok(Value::Text(text.to_lowercase())),
+                _ => Ok(Value::Text(text)),
+            },
+            Field::Date => match parse_date(&text) {
+                Some((start, end)) => Ok(Value::Date(start, end)),
+                None => error("expected date like 2024-01-31 or 2024-01-31T10:00:00"),
+            },
+            Field::Level => match parse_level(&text) {
+                Some(level) => Ok(Value::Level(level)),
+                None => error("expected Error, Warning, Information or Note"),
+            },
+            Field::TransactionStatus => match parse_status(&text) {
+                Some(status) => Ok(Value::Status(status)),
+                None => error("expected Committed, RolledBack, Unfinished or NotApplicable"),
+            },
+            _ => match text.parse() {
+                Ok(number) => Ok(Value::Number(number)),
+                Err(_) => error("expected number"),
+            },
+        }
+    }
+}
+
+// Дата без времени задает весь день
+pub fn parse_date(text: &str) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        let end = NaiveTime::from_hms_opt(23, 59, 59)?;
+        return Some((date.and_time(NaiveTime::MIN), date.and_time(end)));
+    }
+    [
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ]
+    .into_iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+    .map(|date| (date, date))
+}
+
+fn parse_level(text: &str) -> Option<EventLogLevel> {
+    match text.to_lowercase().as_str() {
+        "error" | "e" => Some(EventLogLevel::Error),
+        "warning" | "w" => Some(EventLogLevel::Warning),
+        "information" | "info" | "i" => Some(EventLogLevel::Information),
+        "note" | "n" => Some(EventLogLevel::Note),
+        _ => None,
+    }
+}
+
+fn parse_status(text: &str) -> Option<TransactionStatus> {
+    match text.to_lowercase().as_str() {
+        "committed" | "c" => Some(TransactionStatus::Committed),
+        "rolledback" | "r" => Some(TransactionStatus::RolledBack),
+        "unfinished" | "u" => Some(TransactionStatus::Unfinished),
+        "notapplicable" | "n" => Some(TransactionStatus::NotApplicable),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let query = Query::parse(
+            "level >= Warning and user = \"Иванов\" and comment ~ \"Deadlock\" \
+             and date between 2024-01-01 and 2024-01-31",
+        )
+        .unwrap();
+        let Expr::And(left, date) = query.expr else {
+            panic!("expected and");
+        };
+        assert_eq!(
+            *date,
+            Expr::And(
+                Box::new(Expr::Cond(
+                    Field::Date,
+                    Op::Ge,
+                    Value::Date(
+                        NaiveDate::from_ymd_opt(2024, 1, 1)
+                            .unwrap()
+                            .and_hms_opt(0, 0, 0)
+                            .unwrap(),
+                        NaiveDate::from_ymd_opt(2024, 1, 1)
+                            .unwrap()
+                            .and_hms_opt(23, 59, 59)
+                            .unwrap(),
+                    )
+                )),
+                Box::new(Expr::Cond(
+                    Field::Date,
+                    Op::Le,
+                    Value::Date(
+                        NaiveDate::from_ymd_opt(2024, 1, 31)
+                            .unwrap()
+                            .and_hms_opt(0, 0, 0)
+                            .unwrap(),
+                        NaiveDate::from_ymd_opt(2024, 1, 31)
+                            .unwrap()
+                            .and_hms_opt(23, 59, 59)
+                            .unwrap(),
+                    )
+                )),
+            )
+        );
+        let Expr::And(left, comment) = *left else {
+            panic!("expected and");
+        };
+        assert_eq!(
+            *comment,
+            Expr::Cond(Field::Comment, Op::Contains, Value::Text("deadlock".into()))
+        );
+        let Expr::And(level, _) = *left else {
+            panic!("expected and");
+        };
+        assert_eq!(
+            *level,
+            Expr::Cond(Field::Level, Op::Ge, Value::Level(EventLogLevel::Warning))
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(Query::parse("levle = Error").unwrap_err().position(), 0);
+        assert_eq!(Query::parse("level = Fatal").unwrap_err().position(), 8);
+        assert_eq!(Query::parse("session ~ 5").unwrap_err().position(), 10);
+        assert_eq!(Query::parse("(user = \"a\"").unwrap_err().position(), 11);
+        assert!(Query::parse("user = \"a").is_err());
+        assert!(Query::parse("not (session = 1 or session = 2)").is_ok());
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    io::{self, Read},
+    ops::ControlFlow,
+};
+
+pub(crate) const BUFFER_SIZE: usize = 1024 * 1024;
+// Предел роста буфера под одну запись
+const MAX_BUFFER_SIZE: usize = 256 * 1024 * 1024;
+
+// Данные читаются в конец буфера, разобранная часть [..start] освобождается сдвигом
+// остатка в начало только когда свободное место в конце заканчивается
+pub(crate) struct Buffer {
+    data: Box<[u8]>,
+    start: usize,
+    end: usize,
+}
+
+impl Buffer {
+    pub fn with_capacity(capacity: usize) -> Buffer {
+        Buffer {
+            data: vec![0u8; capacity.max(64)].into_boxed_slice(),
+            start: 0,
+            end: 0,
+        }
+    }
+
+    // Еще не разобранные байты
+    pub fn data(&self) -> &[u8] {
+        &self.data[self.start..self.end]
+    }
+
+    pub fn consume(&mut self, len: usize) {
+        self.start += len;
+    }
+
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.end = 0;
+    }
+
+    // Дочитать данные, 0 - конец файла
+    pub fn fill<R: Read>(&mut self, reader: &mut R) -> io::Result<usize> {
+        self.make_room()?;
+        loop {
+            match reader.read(&mut self.data[self.end..]) {
+                Ok(len) => {
+                    self.end += len;
+                    return Ok(len);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn make_room(&mut self) -> io::Result<()> {
+        let free = self.data.len() - self.end;
+        if free >= self.data.len() / 8 || (free > 0 && self.start == 0) {
+            return Ok(());
+        }
+        if self.start > 0 {
+            self.data.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+            return Ok(());
+        }
+
+        // Запись не помещается в буфер
+        let size = self.data.len() * 2;
+        if size > MAX_BUFFER_SIZE.max(self.data.len()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("record is larger than {MAX_BUFFER_SIZE} bytes"),
+            ));
+        }
+        let mut data = vec![0u8; size].into_boxed_slice();
+        data[..self.end].copy_from_slice(&self.data[..self.end]);
+        self.data = data;
+        Ok(())
+    }
+}
+
+pub struct ChunkReader<R> {
+    reader: R,
+    buffer: Buffer,
+    total: u64,
+}
+
+impl<R: Read> ChunkReader<R> {
+    pub fn new(reader: R) -> ChunkReader<R> {
+        ChunkReader::with_capacity(reader, BUFFER_SIZE)
+    }
+
+    pub fn with_capacity(reader: R, capacity: usize) -> ChunkReader<R> {
+        ChunkReader {
+            reader,
+            buffer: Buffer::with_capacity(capacity),
+            total: 0,
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    // Байты, оставшиеся необработанными после достижения конца файла
+    pub fn leftover(&self) -> &[u8] {
+        self.buffer.data()
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn read<F>(&mut self, parse_buffer: &mut F) -> io::Result<()>
+    where
+        F: FnMut(&[u8]) -> ControlFlow<(), usize>,
+    {
+        self.buffer.clear();
+        loop {
+            let len = self.buffer.fill(&mut self.reader)?;
+            if len == 0 {
+                break;
+            }
+            self.total += len as u64;
+            match parse_buffer(self.buffer.data()) {
+                ControlFlow::Continue(read) => self.buffer.consume(read),
+                ControlFlow::Break(()) => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct SlowReader<'a>(&'a [u8]);
+
+    impl Read for SlowReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(self.0.len()).min(3);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn test_short_reads() {
+        let mut reader = ChunkReader::new(SlowReader(b"{1},{22},{333},"));
+        let mut records = Vec::new();
+        reader
+            .read(&mut |buffer| {
+                let mut position = 0;
+                while let Some(i) = buffer[position..].iter().position(|&b| b == b',') {
+                    records.push(buffer[position..position + i].to_vec());
+                    position += i + 1;
+                }
+                ControlFlow::Continue(position)
+            })
+            .unwrap();
+        assert_eq!(
+            records,
+            vec![b"{1}".to_vec(), b"{22}".to_vec(), b"{333}".to_vec()]
+        );
+        assert_eq!(reader.total(), 15);
+        assert!(reader.leftover().is_empty());
+    }
+
+    #[test]
+    fn test_buffer_growth() {
+        let data = [b"{".as_slice(), &[b'x'; 1000], b"},{2},{3".as_slice()].concat();
+        let mut reader = ChunkReader::with_capacity(SlowReader(&data), 16);
+        let mut records = Vec::new();
+        reader
+            .read(&mut |buffer| {
+                let mut position = 0;
+                while let Some(i) = buffer[position..].iter().position(|&b| b == b',') {
+                    records.push(i);
+                    position += i + 1;
+                }
+                ControlFlow::Continue(position)
+            })
+            .unwrap();
+        assert_eq!(records, vec![1002, 3]);
+        assert_eq!(reader.leftover(), b"{3");
+        assert_eq!(reader.total(), data.len() as u64);
+    }
+}
@@ -0,0 +1,586 @@
+// Разбор записей *.lgp из буфера в памяти, без std: файлы и потоки - в модуле events.
+use crate::{
+    data::Value,
+    error::{Field, ParseError, ParseResult},
+    parser::{LazyStr, LogStr, Parser},
+};
+use alloc::borrow::Cow;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use core::ops::{BitOr, ControlFlow};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TransactionStatus {
+    Unfinished,
+    NotApplicable,
+    Committed,
+    RolledBack,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum EventLogLevel {
+    Error,
+    Information,
+    Note,
+    Warning,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TransactionInfo {
+    start: NaiveDateTime,
+    number: u64,
+}
+
+impl TransactionInfo {
+    pub fn parse(transaction_data: &str) -> Option<TransactionInfo> {
+        let s = transaction_data.strip_prefix('{')?.strip_suffix('}')?;
+        let (start, number) = s.split_once(',')?;
+        let start = u64::from_str_radix(start.trim(), 16).ok()?;
+        let number = u64::from_str_radix(number.trim(), 16).ok()?;
+        if start == 0 && number == 0 {
+            return None;
+        }
+
+        let start = ticks_to_datetime(start as i64)?;
+        Some(TransactionInfo { start, number })
+    }
+
+    pub fn start(&self) -> NaiveDateTime {
+        self.start
+    }
+
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+}
+
+/// Поля, которые разбираются из записи. Остальные пропускаются без декодирования
+/// и в событии имеют пустое значение: 0 или пустая строка.
+/// Дата, статус транзакции и уровень читаются всегда.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Projection(u32);
+
+impl Projection {
+    /// Только поля, которые читаются всегда
+    pub const NONE: Projection = Projection(0);
+    pub const TRANSACTION_DATA: Projection = Projection(1);
+    pub const USER: Projection = Projection(1 << 1);
+    pub const COMPUTER: Projection = Projection(1 << 2);
+    pub const APPLICATION: Projection = Projection(1 << 3);
+    pub const CONNECTION: Projection = Projection(1 << 4);
+    pub const EVENT: Projection = Projection(1 << 5);
+    pub const COMMENT: Projection = Projection(1 << 6);
+    pub const METADATA: Projection = Projection(1 << 7);
+    pub const DATA: Projection = Projection(1 << 8);
+    pub const DATA_PRESENTATION: Projection = Projection(1 << 9);
+    pub const WORKER_SERVER: Projection = Projection(1 << 10);
+    pub const PORT: Projection = Projection(1 << 11);
+    pub const SYNC_PORT: Projection = Projection(1 << 12);
+    pub const SESSION: Projection = Projection(1 << 13);
+    pub const UNKNOWN: Projection = Projection(1 << 14);
+    pub const ALL: Projection = Projection((1 << 15) - 1);
+
+    pub fn contains(self, other: Projection) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl Default for Projection {
+    fn default() -> Self {
+        Projection::ALL
+    }
+}
+
+impl BitOr for Projection {
+    type Output = Projection;
+
+    fn bitor(self, rhs: Projection) -> Projection {
+        Projection(self.0 | rhs.0)
+    }
+}
+
+pub struct Event<'a> {
+    pub(crate) date: NaiveDateTime,
+    pub(crate) transaction_status: TransactionStatus,
+    pub(crate) transaction_data: &'a str,
+    pub(crate) user_id: usize,
+    pub(crate) computer_id: usize,
+    pub(crate) application_id: usize,
+    pub(crate) connection: usize,
+    pub(crate) event_id: usize,
+    pub(crate) log_level: EventLogLevel,
+    pub(crate) comment: LogStr<'a>,
+    pub(crate) metadata_id: usize,
+    pub(crate) data: LazyStr<'a>,
+    pub(crate) data_presentation: LogStr<'a>,
+    pub(crate) worker_server_id: usize,
+    pub(crate) port_id: usize,
+    pub(crate) sync_port_id: usize,
+    pub(crate) session: usize,
+    pub(crate) unknown1: usize,
+    pub(crate) unknown2: &'a str,
+    pub(crate) offset: u64,
+    pub(crate) end_offset: u64,
+    pub(crate) raw: &'a [u8],
+}
+
+// Поля записи; имена по справочникам - в events
+impl<'a> Event<'a> {
+    pub fn date(&self) -> NaiveDateTime {
+        self.date
+    }
+
+    pub fn transaction_status(&self) -> &TransactionStatus {
+        &self.transaction_status
+    }
+
+    pub fn transaction_data(&self) -> &str {
+        self.transaction_data
+    }
+
+    pub fn transaction(&self) -> Option<TransactionInfo> {
+        TransactionInfo::parse(self.transaction_data)
+    }
+
+    pub fn user_id(&self) -> usize {
+        self.user_id
+    }
+
+    pub fn computer_id(&self) -> usize {
+        self.computer_id
+    }
+
+    pub fn application_id(&self) -> usize {
+        self.application_id
+    }
+
+    pub fn connection(&self) -> usize {
+        self.connection
+    }
+
+    pub fn event_id(&self) -> usize {
+        self.event_id
+    }
+
+    pub fn log_level(&self) -> &EventLogLevel {
+        &self.log_level
+    }
+
+    pub fn comment(&self) -> Cow<'a, str> {
+        self.comment.str()
+    }
+
+    pub fn metadata_id(&self) -> usize {
+        self.metadata_id
+    }
+
+    pub fn data(&self) -> &str {
+        self.data.get()
+    }
+
+    pub fn data_value(&self) -> Option<Value<'_>> {
+        Value::parse(self.data.get())
+    }
+
+    pub fn data_presentation(&self) -> Cow<'a, str> {
+        self.data_presentation.str()
+    }
+
+    pub fn worker_server_id(&self) -> usize {
+        self.worker_server_id
+    }
+
+    pub fn port_id(&self) -> usize {
+        self.port_id
+    }
+
+    pub fn sync_port_id(&self) -> usize {
+        self.sync_port_id
+    }
+
+    pub fn session(&self) -> usize {
+        self.session
+    }
+
+    pub fn unknown1(&self) -> usize {
+        self.unknown1
+    }
+
+    pub fn unknown2(&self) -> &str {
+        self.unknown2
+    }
+
+    /// Разделители данных сеанса: пары (номер разделителя, номер значения)
+    pub fn data_separation_ids(&self) -> impl Iterator<Item = (usize, usize)> + 'a {
+        separation_ids(self.unknown2)
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn end_offset(&self) -> u64 {
+        self.end_offset
+    }
+
+    /// Исходные байты записи от '{' до '}'. Пусто, если событие получено
+    /// не из *.lgp: из кэша, EventArena, EventOwned, *.lgd или xml.
+    pub fn raw(&self) -> &'a [u8] {
+        self.raw
+    }
+}
+
+// Последнее поле записи: {количество,разделитель,значение,...}
+pub(crate) fn separation_ids(s: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let mut numbers = s
+        .trim_matches(|c: char| c == '{' || c == '}' || c.is_ascii_whitespace())
+        .split(',')
+        .skip(1)
+        .map(|x| x.trim().parse().unwrap_or_default());
+    core::iter::from_fn(move || Some((numbers.next()?, numbers.next()?)))
+}
+
+// Отбор и декодирование во время разбора записи, реализуется events::ParseOptions
+pub(crate) trait RecordHooks {
+    fn matches_date(&self, _date: NaiveDateTime) -> bool {
+        true
+    }
+
+    fn log_str<'b>(&self, str: LogStr<'b>) -> LogStr<'b> {
+        str
+    }
+
+    fn matches_comment(&self, _comment: &LogStr) -> bool {
+        true
+    }
+
+    fn matches_data_presentation(&self, _data_presentation: &LogStr) -> bool {
+        true
+    }
+
+    fn matches_fields(&self, _event: &Event) -> bool {
+        true
+    }
+
+    fn projection(&self) -> Projection {
+        Projection::ALL
+    }
+}
+
+pub(crate) struct NoHooks;
+
+impl RecordHooks for NoHooks {}
+
+/// Разбор записей из буфера, `offset` - смещение буфера в файле.
+/// Возвращает число разобранных байт: остаток - начало неполной записи.
+/// Испорченные записи пропускаются.
+pub fn parse_buffer<F>(buffer: &[u8], offset: u64, action: &mut F) -> ControlFlow<(), usize>
+where
+    F: FnMut(Event) -> ControlFlow<()>,
+{
+    let mut parser = Parser::new(buffer);
+    loop {
+        let position = parser.position();
+        match parse_record_in_range(&mut parser, offset, &NoHooks) {
+            Ok(Some(event)) => action(event)?,
+            Ok(None) => {}
+            Err(ParseError::Incomplete) => return ControlFlow::Continue(position),
+            Err(_) => {
+                if skip_invalid(&mut parser, position).is_err() {
+                    return ControlFlow::Continue(position);
+                }
+            }
+        }
+    }
+}
+
+/// Одна запись с начала буфера.
+pub fn parse_event(buffer: &[u8], offset: u64) -> ParseResult<Event<'_>> {
+    parse_record(&mut Parser::new(buffer), offset).map_err(|error| error.shift(offset))
+}
+
+// Пропустить испорченную запись до строки вида "{YYYYMMDDhhmmss,"
+pub(crate) fn skip_invalid(parser: &mut Parser, position: usize) -> ParseResult<()> {
+    parser.recover(position)?;
+    loop {
+        let line = parser.remaining();
+        if line.len() < 16 {
+            return Err(ParseError::Incomplete);
+        }
+        if line[0] == b'{' && line[1..15].iter().all(u8::is_ascii_digit) && line[15] == b',' {
+            return Ok(());
+        }
+        parser.skip_to(b'\n')?;
+    }
+}
+
+pub(crate) fn parse_record<'a>(parser: &mut Parser<'a>, offset: u64) -> ParseResult<Event<'a>> {
+    let (start, date) = parse_record_start(parser)?;
+    let event = parse_record_body(parser, offset, start, date, &NoHooks)?;
+    Ok(event.expect("record without filter"))
+}
+
+pub(crate) fn parse_record_in_range<'a, H: RecordHooks>(
+    parser: &mut Parser<'a>,
+    offset: u64,
+    hooks: &H,
+) -> ParseResult<Option<Event<'a>>> {
+    let (start, date) = parse_record_start(parser)?;
+    if !hooks.matches_date(date) {
+        parser.skip_object()?;
+        return Ok(None);
+    }
+    parse_record_body(parser, offset, start, date, hooks)
+}
+
+// Запись целиком без разбора полей, проверяется только дата
+#[cfg(feature = "std")]
+pub(crate) fn parse_raw_record<'a>(parser: &mut Parser<'a>) -> ParseResult<&'a [u8]> {
+    let (start, _) = parse_record_start(parser)?;
+    parser.skip_object()?;
+    Ok(parser.slice_from(start))
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn parse_record_date(parser: &mut Parser) -> ParseResult<NaiveDateTime> {
+    parse_record_start(parser).map(|(_, date)| date)
+}
+
+fn parse_record_start(parser: &mut Parser) -> ParseResult<(usize, NaiveDateTime)> {
+    parser.skip_to(b'{')?;
+    let start = parser.position() - 1;
+    Ok((start, parse_datetime(parser)?))
+}
+
+fn parse_record_body<'a, H: RecordHooks>(
+    parser: &mut Parser<'a>,
+    offset: u64,
+    start: usize,
+    date: NaiveDateTime,
+    hooks: &H,
+) -> ParseResult<Option<Event<'a>>> {
+    let projection = hooks.projection();
+    let number = |parser: &mut Parser, field| match projection.contains(field) {
+        true => parser.parse_usize(),
+        false => parser.skip_to2(b',', b'}').map(|_| 0),
+    };
+    let object = |parser: &mut Parser<'a>, field| match projection.contains(field) {
+        true => parser.parse_object(),
+        false => parser.parse_object_raw().map(|_| ""),
+    };
+
+    let transaction_status = parse_transaction_status(parser)?;
+    let transaction_data = object(parser, Projection::TRANSACTION_DATA)?;
+    let user_id = number(parser, Projection::USER)?;
+    let computer_id = number(parser, Projection::COMPUTER)?;
+    let application_id = number(parser, Projection::APPLICATION)?;
+    let connection = number(parser, Projection::CONNECTION)?;
+    let event_id = number(parser, Projection::EVENT)?;
+    let log_level = parse_log_level(parser)?;
+    let comment = match projection.contains(Projection::COMMENT) {
+        true => hooks.log_str(parser.parse_str()?),
+        false => parser.parse_str().map(|_| LogStr::new(b"", false))?,
+    };
+    if !hooks.matches_comment(&comment) {
+        parser.skip_object()?;
+        return Ok(None);
+    }
+    let metadata_id = number(parser, Projection::METADATA)?;
+    let data = match projection.contains(Projection::DATA) {
+        true => LazyStr::new(hooks.log_str(LogStr::new(parser.parse_object_raw()?, false))),
+        false => parser.parse_object_raw().map(|_| LazyStr::decoded(""))?,
+    };
+    let data_presentation = match projection.contains(Projection::DATA_PRESENTATION) {
+        true => hooks.log_str(parser.parse_str()?),
+        false => parser.parse_str().map(|_| LogStr::new(b"", false))?,
+    };
+    if !hooks.matches_data_presentation(&data_presentation) {
+        parser.skip_object()?;
+        return Ok(None);
+    }
+    let worker_server_id = number(parser, Projection::WORKER_SERVER)?;
+    let port_id = number(parser, Projection::PORT)?;
+    let sync_port_id = number(parser, Projection::SYNC_PORT)?;
+    let session = number(parser, Projection::SESSION)?;
+    let unknown1 = number(parser, Projection::UNKNOWN)?;
+    let unknown2 = object(parser, Projection::UNKNOWN)?;
+    let raw = parser.slice_from(start);
+    let end_offset = offset + parser.position() as u64;
+    let offset = offset + start as u64;
+
+    let event = Event {
+        date,
+        transaction_status,
+        transaction_data,
+        user_id,
+        computer_id,
+        application_id,
+        connection,
+        event_id,
+        log_level,
+        comment,
+        metadata_id,
+        data,
+        data_presentation,
+        worker_server_id,
+        port_id,
+        sync_port_id,
+        session,
+        unknown1,
+        unknown2,
+        offset,
+        end_offset,
+        raw,
+    };
+    match hooks.matches_fields(&event) {
+        true => Ok(Some(event)),
+        false => Ok(None),
+    }
+}
+
+// Время в десятитысячных долях секунды от 0001-01-01
+pub(crate) fn ticks_to_datetime(ticks: i64) -> Option<NaiveDateTime> {
+    NaiveDate::from_ymd_opt(1, 1, 1)?
+        .and_hms_opt(0, 0, 0)?
+        .checked_add_signed(Duration::microseconds(ticks.checked_mul(100)?))
+}
+
+#[cfg(any(feature = "lgd", feature = "xml"))]
+pub(crate) fn datetime_to_ticks(date: NaiveDateTime) -> i64 {
+    let epoch = NaiveDate::from_ymd_opt(1, 1, 1)
+        .and_then(|x| x.and_hms_opt(0, 0, 0))
+        .expect("valid date");
+    (date - epoch).num_microseconds().unwrap_or_default() / 100
+}
+
+fn parse_datetime(parser: &mut Parser) -> ParseResult<NaiveDateTime> {
+    fn next2(parser: &mut Parser) -> ParseResult<u32> {
+        let mut value = 0;
+        for _ in 0..2 {
+            let ch = parser.next()?;
+            if !ch.is_ascii_digit() {
+                return Err(ParseError::invalid(
+                    parser.position() - 1,
+                    Field::Date,
+                    "digit",
+                ));
+            }
+            value = value * 10 + (ch - b'0') as u32;
+        }
+        Ok(value)
+    }
+
+    let position = parser.position();
+    let year = next2(parser)? * 100 + next2(parser)?;
+    let month = next2(parser)?;
+    let day = next2(parser)?;
+    let hour = next2(parser)?;
+    let min = next2(parser)?;
+    let sec = next2(parser)?;
+    parser.skip(1)?;
+
+    NaiveDate::from_ymd_opt(year as i32, month, day)
+        .and_then(|date| date.and_hms_opt(hour, min, sec))
+        .ok_or(ParseError::invalid(position, Field::Date, "valid date"))
+}
+
+fn parse_transaction_status(parser: &mut Parser) -> ParseResult<TransactionStatus> {
+    let ch = parser.next()?;
+    parser.skip(1)?;
+    Ok(match ch {
+        b'R' => TransactionStatus::RolledBack,
+        b'N' => TransactionStatus::NotApplicable,
+        b'U' => TransactionStatus::Unfinished,
+        b'C' => TransactionStatus::Committed,
+        _ => {
+            let position = parser.position() - 2;
+            return Err(ParseError::invalid(
+                position,
+                Field::TransactionStatus,
+                "R, N, U or C",
+            ));
+        }
+    })
+}
+
+fn parse_log_level(parser: &mut Parser) -> ParseResult<EventLogLevel> {
+    let ch = parser.next()?;
+    parser.skip(1)?;
+    Ok(match ch {
+        b'E' => EventLogLevel::Error,
+        b'I' => EventLogLevel::Information,
+        b'N' => EventLogLevel::Note,
+        b'W' => EventLogLevel::Warning,
+        _ => {
+            let position = parser.position() - 2;
+            return Err(ParseError::invalid(
+                position,
+                Field::LogLevel,
+                "E, I, N or W",
+            ));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, vec::Vec};
+
+    #[test]
+    fn test_transaction_info() {
+        let info = TransactionInfo::parse("{244520c4b9bb0,3a5d0}").unwrap();
+        assert_eq!(
+            info.start(),
+            NaiveDate::from_ymd_opt(2022, 12, 17)
+                .unwrap()
+                .and_hms_opt(22, 42, 51)
+                .unwrap()
+        );
+        assert_eq!(info.number(), 0x3a5d0);
+
+        assert_eq!(TransactionInfo::parse("{0,0}"), None);
+    }
+
+    #[test]
+    fn test_parse_buffer() {
+        let buffer = b"{20221217224304,N,\r\n{0,0},2,1,1,1,3,I,\"\",0,\r\n{\"U\"},\"\",0,0,0,2,0,\r\n{0}\r\n},\r\n{2022121722";
+        let mut events = Vec::new();
+        let read = parse_buffer(buffer, 100, &mut |event| {
+            events.push((event.offset(), event.user_id(), event.data().to_string()));
+            ControlFlow::Continue(())
+        });
+        assert_eq!(read, ControlFlow::Continue(buffer.len() - 14));
+        assert_eq!(events, [(100, 2, r#"{"U"}"#.to_string())]);
+
+        let event = parse_event(buffer, 0).unwrap();
+        assert_eq!(*event.log_level(), EventLogLevel::Information);
+        assert_eq!(event.session(), 2);
+        assert!(parse_event(b"{2022", 0).is_err_and(|error| error.is_incomplete()));
+    }
+
+    #[test]
+    fn test_separation_ids() {
+        let ids: Vec<_> = separation_ids("{2,1,1,2,3}").collect();
+        assert_eq!(ids, [(1, 1), (2, 3)]);
+        assert_eq!(separation_ids("{0}").count(), 0);
+        assert_eq!(separation_ids("").count(), 0);
+    }
+
+    #[test]
+    fn test_lazy_data() {
+        let buffer = b"{20221217224304,N,\r\n{0,0},2,1,1,1,3,I,\"\",0,\r\n{\"S\",\"\xff\"},\"\",0,0,0,2,0,\r\n{0}\r\n}";
+        let event = parse_event(buffer, 0).unwrap();
+        #[cfg(not(feature = "encoding"))]
+        assert_eq!(event.data(), "{\"S\",\"\u{fffd}\"}");
+        #[cfg(feature = "encoding")]
+        assert_eq!(event.data(), "{\"S\",\"я\"}");
+        assert!(core::ptr::eq(event.data(), event.data()));
+
+        let buffer = b"{20221217224304,N,\r\n{0,0},2,1,1,1,3,I,\"\",0,\r\n{\"U\"},\"\",0,0,0,2,0,\r\n{0}\r\n}";
+        let event = parse_event(buffer, 0).unwrap();
+        assert!(core::ptr::eq(event.data().as_bytes(), &buffer[45..50]));
+    }
+}
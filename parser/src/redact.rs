@@ -0,0 +1,202 @@
+use crate::{
+    events::{self, Event, EventOwned},
+    references::References,
+};
+use std::{
+    io::{self, Write},
+    path::Path,
+    sync::OnceLock,
+};
+
+#[cfg(feature = "regex")]
+pub use regex::bytes::Regex;
+
+#[derive(Clone, Debug)]
+pub struct Redactor {
+    users: bool,
+    computers: bool,
+    substrings: Vec<String>,
+    #[cfg(feature = "regex")]
+    patterns: Vec<Regex>,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Redactor {
+            users: true,
+            computers: true,
+            substrings: Vec::new(),
+            #[cfg(feature = "regex")]
+            patterns: Vec::new(),
+        }
+    }
+}
+
+impl Redactor {
+    pub fn new() -> Redactor {
+        Redactor::default()
+    }
+
+    pub fn users(mut self, users: bool) -> Redactor {
+        self.users = users;
+        self
+    }
+
+    pub fn computers(mut self, computers: bool) -> Redactor {
+        self.computers = computers;
+        self
+    }
+
+    pub fn comment_contains<S: Into<String>>(mut self, text: S) -> Redactor {
+        self.substrings.push(text.into());
+        self
+    }
+
+    #[cfg(feature = "regex")]
+    pub fn comment_regex(mut self, regex: Regex) -> Redactor {
+        self.patterns.push(regex);
+        self
+    }
+
+    // Имя получает псевдоним по номеру в справочнике, поэтому замены
+    // согласованы во всех файлах каталога с общим 1Cv8.lgf
+    pub fn compile(&self, refs: &References) -> Redaction {
+        let mut refs = refs.clone();
+        refs.lookup = OnceLock::new();
+        let mut names = Vec::new();
+        if self.users {
+            for (num, user) in refs.users.iter_mut().enumerate() {
+                if !user.name.is_empty() {
+                    let pseudonym = format!("user{num}");
+                    names.push((
+                        std::mem::replace(&mut user.name, pseudonym.clone()),
+                        pseudonym,
+                    ));
+                }
+            }
+        }
+        if self.computers {
+            for (num, computer) in refs.computers.iter_mut().enumerate() {
+                if !computer.is_empty() {
+                    let pseudonym = format!("computer{num}");
+                    names.push((std::mem::replace(computer, pseudonym.clone()), pseudonym));
+                }
+            }
+        }
+        // Длинные имена заменяются раньше, чтобы не задеть их части
+        names.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+
+        Redaction {
+            refs,
+            names,
+            substrings: self.substrings.clone(),
+            #[cfg(feature = "regex")]
+            patterns: self.patterns.clone(),
+        }
+    }
+}
+
+pub struct Redaction {
+    refs: References,
+    names: Vec<(String, String)>,
+    substrings: Vec<String>,
+    #[cfg(feature = "regex")]
+    patterns: Vec<Regex>,
+}
+
+impl Redaction {
+    pub fn references(&self) -> &References {
+        &self.refs
+    }
+
+    pub fn text(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for substring in &self.substrings {
+            if !substring.is_empty() && text.contains(substring.as_str()) {
+                text = text.replace(substring.as_str(), &pseudonym(substring));
+            }
+        }
+        #[cfg(feature = "regex")]
+        for pattern in &self.patterns {
+            let replaced = pattern.replace_all(text.as_bytes(), |caps: &regex::bytes::Captures| {
+                pseudonym(&String::from_utf8_lossy(&caps[0])).into_bytes()
+            });
+            if let std::borrow::Cow::Owned(replaced) = replaced {
+                text = String::from_utf8_lossy(&replaced).into_owned();
+            }
+        }
+        for (name, pseudonym) in &self.names {
+            if text.contains(name.as_str()) {
+                text = text.replace(name.as_str(), pseudonym);
+            }
+        }
+        text
+    }
+
+    pub fn event(&self, event: &Event) -> EventOwned {
+        let mut event = event.to_owned();
+        event.comment = self.text(&event.comment).into();
+        event.data = self.text(&event.data).into();
+        event.data_presentation = self.text(&event.data_presentation).into();
+        event
+    }
+
+    pub fn parse<F, P>(&self, path: P, action: &mut F) -> io::Result<()>
+    where
+        F: FnMut(Event),
+        P: AsRef<Path>,
+    {
+        events::parse(path, &mut |event| action(self.event(&event).as_event()))
+    }
+
+    pub fn write_lgp<P, W>(&self, path: P, writer: &mut events::Writer<W>) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+        W: Write,
+    {
+        let mut result = Ok(());
+        events::parse_until(path, &mut |event| match writer.write(&self.event(&event)) {
+            Ok(()) => std::ops::ControlFlow::Continue(()),
+            Err(error) => {
+                result = Err(error);
+                std::ops::ControlFlow::Break(())
+            }
+        })?;
+        result
+    }
+}
+
+// FNV-1a: псевдоним не зависит от порядка событий и версии компилятора
+fn pseudonym(value: &str) -> String {
+    let mut hash: u32 = 0x811c9dc5;
+    for b in value.bytes() {
+        hash ^= b as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    format!("[redacted:{hash:08x}]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text() {
+        let mut refs = References {
+            users: vec![Default::default(), Default::default()],
+            ..Default::default()
+        };
+        refs.users[1].name = "Иванов".to_string();
+        let redaction = Redactor::new()
+            .comment_contains("ООО Ромашка")
+            .compile(&refs);
+        assert_eq!(redaction.references().users()[1].name(), "user1");
+        let text = redaction.text("Иванов: счет для ООО Ромашка");
+        assert_eq!(
+            text,
+            format!("user1: счет для {}", pseudonym("ООО Ромашка"))
+        );
+        assert_eq!(pseudonym("ООО Ромашка"), pseudonym("ООО Ромашка"));
+        assert_ne!(pseudonym("ООО Ромашка"), pseudonym("ООО Лютик"));
+    }
+}
@@ -1,16 +1,41 @@
-use crate::parser::Parser;
+use crate::{
+    cache::{invalid, write_bytes, write_varint, Input},
+    error::{Field, ParseError, ParseResult},
+    parser::Parser,
+    reader::ChunkReader,
+};
 use std::cmp::Ordering;
-use std::{fs::File, io::Read};
-use std::{io, path::Path};
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::Hash;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
+use std::{
+    fmt, io,
+    ops::{BitOr, ControlFlow},
+    path::Path,
+};
 use uuid::Uuid;
 
-#[derive(Default, Debug)]
+const MAGIC: &[u8; 8] = b"LGFCACHE";
+const VERSION: u64 = 2;
+
+#[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct User {
-    id: Uuid,
-    name: String,
+    pub(crate) id: Uuid,
+    pub(crate) name: String,
 }
 
 impl User {
+    pub fn new<S: Into<String>>(id: Uuid, name: S) -> User {
+        User {
+            id,
+            name: name.into(),
+        }
+    }
+
     pub fn name(&self) -> &str {
         self.name.as_ref()
     }
@@ -20,13 +45,28 @@ impl User {
     }
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Metadata {
-    id: Uuid,
-    name: String,
+    pub(crate) id: Uuid,
+    pub(crate) name: String,
+    // Заполняются по выгрузке конфигурации, см. config::Configuration::enrich
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "String::is_empty"))]
+    pub(crate) kind: String,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "String::is_empty"))]
+    pub(crate) synonym: String,
 }
 
 impl Metadata {
+    pub fn new<S: Into<String>>(id: Uuid, name: S) -> Metadata {
+        Metadata {
+            id,
+            name: name.into(),
+            kind: String::new(),
+            synonym: String::new(),
+        }
+    }
+
     pub fn name(&self) -> &str {
         self.name.as_ref()
     }
@@ -34,9 +74,27 @@ impl Metadata {
     pub fn id(&self) -> Uuid {
         self.id
     }
+
+    /// Вид объекта конфигурации как в выгрузке: Catalog, Document, InformationRegister...
+    pub fn kind(&self) -> &str {
+        self.kind.as_ref()
+    }
+
+    pub fn synonym(&self) -> &str {
+        self.synonym.as_ref()
+    }
+
+    /// Синоним, если он известен, иначе имя
+    pub fn presentation(&self) -> &str {
+        match self.synonym.is_empty() {
+            true => &self.name,
+            false => &self.synonym,
+        }
+    }
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DataSeparation {
     id: Uuid,
     name: String,
@@ -57,76 +115,645 @@ impl DataSeparation {
     }
 }
 
-#[derive(Default)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct UnknownRecord {
+    kind: usize,
+    payload: String,
+}
+
+impl UnknownRecord {
+    pub fn kind(&self) -> usize {
+        self.kind
+    }
+
+    pub fn payload(&self) -> &str {
+        self.payload.as_ref()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Header {
+    version: String,
+    id: Uuid,
+}
+
+impl Header {
+    pub fn version(&self) -> &str {
+        self.version.as_ref()
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    // BOM, строка "1CV8LOG(ver 2.0)" и идентификатор журнала
+    fn parse(buffer: &[u8]) -> ParseResult<Option<(Header, usize)>> {
+        let start = if buffer.starts_with(b"\xEF\xBB\xBF") {
+            3
+        } else {
+            0
+        };
+        let mut parser = Parser::new(buffer);
+        parser.skip(start)?;
+        let rest = parser.remaining();
+        if !rest.starts_with(b"1CV8LOG(ver ") && !b"1CV8LOG(ver ".starts_with(rest) {
+            return Ok(None);
+        }
+
+        parser.skip_to(b'\n')?;
+        let line = &buffer[start..parser.position()];
+        let version = line
+            .trim_ascii_end()
+            .strip_prefix(b"1CV8LOG(ver ")
+            .and_then(|line| line.strip_suffix(b")"))
+            .and_then(|version| std::str::from_utf8(version).ok())
+            .ok_or(ParseError::invalid(
+                start,
+                Field::Header,
+                "1CV8LOG(ver X.Y)",
+            ))?
+            .to_string();
+
+        let position = parser.position();
+        parser.skip_to(b'\n')?;
+        let id = std::str::from_utf8(&buffer[position..parser.position()])
+            .ok()
+            .and_then(|id| Uuid::from_str(id.trim()).ok())
+            .ok_or(ParseError::invalid(position, Field::Header, "uuid"))?;
+
+        Ok(Some((Header { version, id }, parser.position())))
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Lookup {
+    users_by_name: HashMap<String, usize>,
+    users_by_uuid: HashMap<Uuid, usize>,
+    computers: HashMap<String, usize>,
+    applications: HashMap<String, usize>,
+    events: HashMap<String, usize>,
+    metadata_by_name: HashMap<String, usize>,
+    metadata_by_uuid: HashMap<Uuid, usize>,
+}
+
+impl Lookup {
+    fn new(refs: &References) -> Lookup {
+        Lookup {
+            users_by_name: index(refs.users.iter().map(|x| x.name.clone())),
+            users_by_uuid: index(refs.users.iter().map(|x| x.id)),
+            computers: index(refs.computers.iter().cloned()),
+            applications: index(refs.applications.iter().cloned()),
+            events: index(refs.events.iter().cloned()),
+            metadata_by_name: index(refs.metadata.iter().map(|x| x.name.clone())),
+            metadata_by_uuid: index(refs.metadata.iter().map(|x| x.id)),
+        }
+    }
+}
+
+impl Lookup {
+    fn heap_size(&self) -> usize {
+        fn map<K>(map: &HashMap<K, usize>, key: impl Fn(&K) -> usize) -> usize {
+            map.capacity() * (size_of::<(K, usize)>() + 1) + map.keys().map(key).sum::<usize>()
+        }
+        map(&self.users_by_name, String::capacity)
+            + map(&self.users_by_uuid, |_| 0)
+            + map(&self.computers, String::capacity)
+            + map(&self.applications, String::capacity)
+            + map(&self.events, String::capacity)
+            + map(&self.metadata_by_name, String::capacity)
+            + map(&self.metadata_by_uuid, |_| 0)
+    }
+}
+
+// При повторах остается первый (наименьший) номер
+fn index<K: Hash + Eq>(keys: impl Iterator<Item = K>) -> HashMap<K, usize> {
+    let mut map = HashMap::new();
+    for (num, key) in keys.enumerate() {
+        map.entry(key).or_insert(num);
+    }
+    map
+}
+
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct References {
-    users: Vec<User>,
-    computers: Vec<String>,
-    applications: Vec<String>,
-    events: Vec<String>,
-    metadata: Vec<Metadata>,
-    worker_servers: Vec<String>,
-    ports: Vec<u32>,
-    sync_ports: Vec<u32>,
-    data_separation: Vec<DataSeparation>,
+    pub(crate) header: Option<Header>,
+    pub(crate) users: Vec<User>,
+    pub(crate) computers: Vec<String>,
+    pub(crate) applications: Vec<String>,
+    pub(crate) events: Vec<String>,
+    pub(crate) metadata: Vec<Metadata>,
+    pub(crate) worker_servers: Vec<String>,
+    pub(crate) ports: Vec<u32>,
+    pub(crate) sync_ports: Vec<u32>,
+    pub(crate) data_separation: Vec<DataSeparation>,
+    pub(crate) record11: Vec<String>,
+    pub(crate) record12: Vec<String>,
+    pub(crate) record13: Vec<usize>,
+    pub(crate) unknown_records: Vec<UnknownRecord>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) lookup: OnceLock<Lookup>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) offset: u64,
+    // Число прочитанных записей по справочникам в порядке Tables, для refresh:
+    // запись на месте пустого номера меняет справочник, не меняя его размер
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) generations: [u64; 10],
+}
+
+/// Набор справочников, например изменившихся при `References::refresh`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tables(u16);
+
+impl Tables {
+    pub const USERS: Tables = Tables(1);
+    pub const COMPUTERS: Tables = Tables(1 << 1);
+    pub const APPLICATIONS: Tables = Tables(1 << 2);
+    pub const EVENTS: Tables = Tables(1 << 3);
+    pub const METADATA: Tables = Tables(1 << 4);
+    pub const WORKER_SERVERS: Tables = Tables(1 << 5);
+    pub const PORTS: Tables = Tables(1 << 6);
+    pub const SYNC_PORTS: Tables = Tables(1 << 7);
+    pub const DATA_SEPARATION: Tables = Tables(1 << 8);
+    /// Записи 11-13 и записи неизвестных типов
+    pub const OTHER: Tables = Tables(1 << 9);
+
+    pub fn contains(self, tables: Tables) -> bool {
+        self.0 & tables.0 == tables.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    fn changed(before: [u64; 10], after: [u64; 10]) -> Tables {
+        let bits = (0..10)
+            .filter(|&i| before[i] != after[i])
+            .fold(0, |bits, i| bits | 1 << i);
+        Tables(bits)
+    }
+}
+
+impl BitOr for Tables {
+    type Output = Tables;
+
+    fn bitor(self, rhs: Tables) -> Tables {
+        Tables(self.0 | rhs.0)
+    }
 }
 
 impl References {
     pub fn parse<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
-        let mut reader = File::open(path)?;
+        self.parse_reader(File::open(path)?)
+    }
 
-        let mut buffer = Box::new([0u8; 1024 * 1024]);
-        let mut offset = 0usize;
+    /// Справочники из произвольного источника, например полученные по сети
+    pub fn parse_reader<R: Read>(&mut self, reader: R) -> io::Result<()> {
+        self.read_from(reader, 0)
+    }
 
-        // let mut ver = String::new();
-        // let _ = reader.read_line(&mut ver).unwrap();
-        // let mut id = String::new();
-        // let _ = reader.read_line(&mut id).unwrap();
-        // let id = Uuid::parse_str(&id).unwrap();
+    /// Дочитывает записи, дописанные в конец 1Cv8.lgf после предыдущего разбора,
+    /// и возвращает изменившиеся справочники. Если файл стал короче, он читается заново.
+    pub fn refresh<P: AsRef<Path>>(&mut self, path: P) -> io::Result<Tables> {
+        let before = self.generations;
+        let mut file = File::open(path)?;
+        if file.metadata()?.len() < self.offset {
+            *self = References {
+                generations: before,
+                ..Default::default()
+            };
+        }
+        let offset = self.offset;
+        file.seek(SeekFrom::Start(offset))?;
+        self.read_from(file, offset)?;
+        Ok(Tables::changed(before, self.generations))
+    }
 
-        loop {
-            let len = reader.read(&mut buffer[offset..])?;
-            if len == 0 {
-                break;
+    /// Сохраняет справочники в двоичном виде вместе с позицией в 1Cv8.lgf:
+    /// после `load` новые записи дочитываются через `refresh`
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut out = MAGIC.to_vec();
+        write_varint(&mut out, VERSION);
+        write_varint(&mut out, self.offset);
+        match &self.header {
+            Some(header) => {
+                out.push(1);
+                write_bytes(&mut out, header.version.as_bytes());
+                out.extend_from_slice(header.id.as_bytes());
             }
-            let len = len + offset;
-            let read = self.parse_buffer(&buffer[0..len]);
+            None => out.push(0),
+        }
+        write_varint(&mut out, self.users.len() as u64);
+        for user in &self.users {
+            out.extend_from_slice(user.id.as_bytes());
+            write_bytes(&mut out, user.name.as_bytes());
+        }
+        write_strings(&mut out, &self.computers);
+        write_strings(&mut out, &self.applications);
+        write_strings(&mut out, &self.events);
+        write_varint(&mut out, self.metadata.len() as u64);
+        for metadata in &self.metadata {
+            out.extend_from_slice(metadata.id.as_bytes());
+            write_bytes(&mut out, metadata.name.as_bytes());
+            write_bytes(&mut out, metadata.kind.as_bytes());
+            write_bytes(&mut out, metadata.synonym.as_bytes());
+        }
+        write_strings(&mut out, &self.worker_servers);
+        write_numbers(&mut out, self.ports.iter().map(|&x| x as u64));
+        write_numbers(&mut out, self.sync_ports.iter().map(|&x| x as u64));
+        write_varint(&mut out, self.data_separation.len() as u64);
+        for data_separation in &self.data_separation {
+            out.extend_from_slice(data_separation.id.as_bytes());
+            write_bytes(&mut out, data_separation.name.as_bytes());
+            write_strings(&mut out, &data_separation.values);
+        }
+        write_strings(&mut out, &self.record11);
+        write_strings(&mut out, &self.record12);
+        write_numbers(&mut out, self.record13.iter().map(|&x| x as u64));
+        write_varint(&mut out, self.unknown_records.len() as u64);
+        for record in &self.unknown_records {
+            write_varint(&mut out, record.kind as u64);
+            write_bytes(&mut out, record.payload.as_bytes());
+        }
+        std::fs::write(path, out)
+    }
 
-            for i in read..len {
-                buffer[i - read] = buffer[i];
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<References> {
+        let data = std::fs::read(path)?;
+        let mut input = Input {
+            data: data.strip_prefix(MAGIC).ok_or_else(invalid)?,
+        };
+        if input.varint()? != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unsupported references file version",
+            ));
+        }
+        let mut refs = References {
+            offset: input.varint()?,
+            ..Default::default()
+        };
+        if input.byte()? == 1 {
+            refs.header = Some(Header {
+                version: input.string()?,
+                id: input.uuid()?,
+            });
+        }
+        for _ in 0..input.varint()? {
+            let id = input.uuid()?;
+            let name = input.string()?;
+            refs.users.push(User { name, id });
+        }
+        refs.computers = read_strings(&mut input)?;
+        refs.applications = read_strings(&mut input)?;
+        refs.events = read_strings(&mut input)?;
+        for _ in 0..input.varint()? {
+            let mut metadata = Metadata::new(input.uuid()?, input.string()?);
+            metadata.kind = input.string()?;
+            metadata.synonym = input.string()?;
+            refs.metadata.push(metadata);
+        }
+        refs.worker_servers = read_strings(&mut input)?;
+        refs.ports = read_numbers(&mut input)?;
+        refs.sync_ports = read_numbers(&mut input)?;
+        for _ in 0..input.varint()? {
+            let id = input.uuid()?;
+            let name = input.string()?;
+            let values = read_strings(&mut input)?;
+            refs.data_separation
+                .push(DataSeparation { id, name, values });
+        }
+        refs.record11 = read_strings(&mut input)?;
+        refs.record12 = read_strings(&mut input)?;
+        refs.record13 = read_numbers(&mut input)?;
+        for _ in 0..input.varint()? {
+            let kind = input.varint()? as usize;
+            let payload = input.string()?;
+            refs.unknown_records.push(UnknownRecord { kind, payload });
+        }
+        Ok(refs)
+    }
+
+    /// Дописывает записи `other`, которых нет в справочниках, и возвращает
+    /// номера записей `other` в объединенных справочниках. Пользователи, метаданные
+    /// и разделители сопоставляются по идентификатору, остальное - по значению;
+    /// записи 11-13 и неизвестных типов не объединяются.
+    pub fn merge(&mut self, other: &References) -> Merge {
+        self.lookup = OnceLock::new();
+        let mut conflicts = Vec::new();
+        let mut conflict = |table, id, ours: &str, theirs: &str| {
+            if ours != theirs {
+                conflicts.push(Conflict {
+                    table,
+                    id,
+                    ours: ours.to_string(),
+                    theirs: theirs.to_string(),
+                });
             }
-            offset = len - read;
+        };
+        let users = merge_table(
+            &mut self.users,
+            &other.users,
+            |x| x.id,
+            |ours, theirs| conflict(Tables::USERS, ours.id, &ours.name, &theirs.name),
+        );
+        let metadata = merge_table(
+            &mut self.metadata,
+            &other.metadata,
+            |x| x.id,
+            |ours, theirs| conflict(Tables::METADATA, ours.id, &ours.name, &theirs.name),
+        );
+        let separation = merge_table(
+            &mut self.data_separation,
+            &other.data_separation,
+            |x| x.id,
+            |ours, theirs| conflict(Tables::DATA_SEPARATION, ours.id, &ours.name, &theirs.name),
+        );
+        let data_separation = other
+            .data_separation
+            .iter()
+            .zip(separation)
+            .map(|(theirs, num)| {
+                let ours = &mut self.data_separation[num].values;
+                (
+                    num,
+                    merge_table(ours, &theirs.values, String::clone, |_, _| {}),
+                )
+            })
+            .collect();
+        let same = |_: &String, _: &String| {};
+        Merge {
+            users,
+            computers: merge_table(&mut self.computers, &other.computers, String::clone, same),
+            applications: merge_table(
+                &mut self.applications,
+                &other.applications,
+                String::clone,
+                same,
+            ),
+            events: merge_table(&mut self.events, &other.events, String::clone, same),
+            metadata,
+            worker_servers: merge_table(
+                &mut self.worker_servers,
+                &other.worker_servers,
+                String::clone,
+                same,
+            ),
+            ports: merge_table(&mut self.ports, &other.ports, |&x| x, |_, _| {}),
+            sync_ports: merge_table(&mut self.sync_ports, &other.sync_ports, |&x| x, |_, _| {}),
+            data_separation,
+            conflicts,
         }
+    }
 
-        Ok(())
+    /// Записи, которые есть только в `other` (added) или только в `self` (removed),
+    /// и записи с одним идентификатором, но разными именами
+    pub fn diff(&self, other: &References) -> Diff {
+        let mut added = self.clone();
+        let conflicts = added.merge(other).conflicts;
+        let mut removed = other.clone();
+        removed.merge(self);
+        Diff {
+            added: added.entries_after(self),
+            removed: removed.entries_after(other),
+            conflicts,
+        }
     }
 
-    fn parse_buffer(&mut self, buffer: &[u8]) -> usize {
-        let mut parser = Parser::new(buffer);
-        loop {
-            let position = parser.position();
-            if self.parser_record(&mut parser).is_none() {
-                return position;
+    // Записи, дописанные после before; пустые записи-заполнители пропускаются
+    fn entries_after(&self, before: &References) -> Vec<Entry> {
+        let mut entries = Vec::new();
+        let mut push = |table, id: Option<Uuid>, name: String| {
+            if !name.is_empty() && name != "0" {
+                entries.push(Entry { table, id, name });
+            }
+        };
+        for user in &self.users[before.users.len()..] {
+            push(Tables::USERS, Some(user.id), user.name.clone());
+        }
+        let strings = [
+            (Tables::COMPUTERS, &self.computers, &before.computers),
+            (
+                Tables::APPLICATIONS,
+                &self.applications,
+                &before.applications,
+            ),
+            (Tables::EVENTS, &self.events, &before.events),
+            (
+                Tables::WORKER_SERVERS,
+                &self.worker_servers,
+                &before.worker_servers,
+            ),
+        ];
+        for (table, values, before) in strings {
+            for value in &values[before.len()..] {
+                push(table, None, value.clone());
             }
         }
+        for metadata in &self.metadata[before.metadata.len()..] {
+            push(Tables::METADATA, Some(metadata.id), metadata.name.clone());
+        }
+        for port in &self.ports[before.ports.len()..] {
+            push(Tables::PORTS, None, port.to_string());
+        }
+        for port in &self.sync_ports[before.sync_ports.len()..] {
+            push(Tables::SYNC_PORTS, None, port.to_string());
+        }
+        for separation in &self.data_separation[before.data_separation.len()..] {
+            push(
+                Tables::DATA_SEPARATION,
+                Some(separation.id),
+                separation.name.clone(),
+            );
+        }
+        entries
+    }
+
+    /// Размеры справочников, число пустых записей-заполнителей на месте пропущенных
+    /// номеров и примерный объем занятой памяти
+    pub fn stats(&self) -> ReferencesStats {
+        let string = |x: &String| x.capacity();
+        let user = |x: &User| x.name.capacity();
+        let metadata = |x: &Metadata| x.name.capacity();
+        let tables = vec![
+            TableStats::new(Tables::USERS, "users", &self.users, user),
+            TableStats::new(Tables::COMPUTERS, "computers", &self.computers, string),
+            TableStats::new(
+                Tables::APPLICATIONS,
+                "applications",
+                &self.applications,
+                string,
+            ),
+            TableStats::new(Tables::EVENTS, "events", &self.events, string),
+            TableStats::new(Tables::METADATA, "metadata", &self.metadata, metadata),
+            TableStats::new(
+                Tables::WORKER_SERVERS,
+                "worker_servers",
+                &self.worker_servers,
+                string,
+            ),
+            TableStats::new(Tables::PORTS, "ports", &self.ports, |_| 0),
+            TableStats::new(Tables::SYNC_PORTS, "sync_ports", &self.sync_ports, |_| 0),
+            TableStats::new(
+                Tables::DATA_SEPARATION,
+                "data_separation",
+                &self.data_separation,
+                |x| {
+                    x.name.capacity()
+                        + x.values.capacity() * size_of::<String>()
+                        + x.values.iter().map(String::capacity).sum::<usize>()
+                },
+            ),
+            TableStats::new(Tables::OTHER, "record11", &self.record11, string),
+            TableStats::new(Tables::OTHER, "record12", &self.record12, string),
+            TableStats::new(Tables::OTHER, "record13", &self.record13, |_| 0),
+            TableStats::new(
+                Tables::OTHER,
+                "unknown_records",
+                &self.unknown_records,
+                |x| x.payload.capacity(),
+            ),
+        ];
+        let lookup = self.lookup.get().map_or(0, Lookup::heap_size);
+        ReferencesStats { tables, lookup }
     }
 
-    fn parser_record(&mut self, parser: &mut Parser) -> Option<()> {
-        fn add_ref<T: Default>(vec: &mut Vec<T>, value: T, num: usize) {
-            match num.cmp(&vec.len()) {
-                Ordering::Less => vec[num] = value,
-                Ordering::Equal => vec.push(value),
-                Ordering::Greater => {
-                    for _ in 0..num - vec.len() {
-                        vec.push(T::default());
+    // offset - позиция в файле, с которой читает reader; 0 - с заголовка
+    fn read_from<R: Read>(&mut self, reader: R, offset: u64) -> io::Result<()> {
+        self.lookup = OnceLock::new();
+        self.offset = offset;
+        let mut reader = ChunkReader::new(reader);
+        let mut header = offset == 0;
+        reader.read(&mut |buffer| {
+            let mut position = 0;
+            if header {
+                match Header::parse(buffer) {
+                    Ok(Some((value, len))) => {
+                        self.header = Some(value);
+                        position = len;
+                    }
+                    Err(ParseError::Incomplete) => return ControlFlow::Continue(0),
+                    _ => {}
+                }
+                header = false;
+            }
+            let read = position + self.parse_buffer(&buffer[position..]);
+            self.offset += read as u64;
+            ControlFlow::Continue(read)
+        })
+    }
+
+    pub fn from_slice(buffer: &[u8]) -> io::Result<References> {
+        let mut refs = References::default();
+        refs.parse_reader(buffer)?;
+        Ok(refs)
+    }
+
+    /// Дописывает пользователя в конец справочника и возвращает его номер;
+    /// номера, как и в 1Cv8.lgf, начинаются с 1
+    pub fn push_user(&mut self, user: User) -> usize {
+        self.lookup = OnceLock::new();
+        push_ref(&mut self.users, user)
+    }
+
+    /// Пользователь с номером `num`; пропущенные номера заполняются пустыми записями
+    pub fn insert_user(&mut self, num: usize, user: User) {
+        self.lookup = OnceLock::new();
+        add_ref(&mut self.users, user, num);
+    }
+
+    pub fn push_computer<S: Into<String>>(&mut self, name: S) -> usize {
+        self.lookup = OnceLock::new();
+        push_ref(&mut self.computers, name.into())
+    }
+
+    pub fn insert_computer<S: Into<String>>(&mut self, num: usize, name: S) {
+        self.lookup = OnceLock::new();
+        add_ref(&mut self.computers, name.into(), num);
+    }
+
+    pub fn push_application<S: Into<String>>(&mut self, name: S) -> usize {
+        self.lookup = OnceLock::new();
+        push_ref(&mut self.applications, name.into())
+    }
+
+    pub fn insert_application<S: Into<String>>(&mut self, num: usize, name: S) {
+        self.lookup = OnceLock::new();
+        add_ref(&mut self.applications, name.into(), num);
+    }
+
+    pub fn push_event<S: Into<String>>(&mut self, name: S) -> usize {
+        self.lookup = OnceLock::new();
+        push_ref(&mut self.events, name.into())
+    }
+
+    pub fn insert_event<S: Into<String>>(&mut self, num: usize, name: S) {
+        self.lookup = OnceLock::new();
+        add_ref(&mut self.events, name.into(), num);
+    }
+
+    pub fn push_metadata(&mut self, metadata: Metadata) -> usize {
+        self.lookup = OnceLock::new();
+        push_ref(&mut self.metadata, metadata)
+    }
+
+    pub fn insert_metadata(&mut self, num: usize, metadata: Metadata) {
+        self.lookup = OnceLock::new();
+        add_ref(&mut self.metadata, metadata, num);
+    }
+
+    pub fn push_worker_server<S: Into<String>>(&mut self, name: S) -> usize {
+        push_ref(&mut self.worker_servers, name.into())
+    }
+
+    pub fn insert_worker_server<S: Into<String>>(&mut self, num: usize, name: S) {
+        add_ref(&mut self.worker_servers, name.into(), num);
+    }
+
+    pub fn push_port(&mut self, port: u32) -> usize {
+        push_ref(&mut self.ports, port)
+    }
+
+    pub fn insert_port(&mut self, num: usize, port: u32) {
+        add_ref(&mut self.ports, port, num);
+    }
+
+    pub fn push_sync_port(&mut self, port: u32) -> usize {
+        push_ref(&mut self.sync_ports, port)
+    }
+
+    pub fn insert_sync_port(&mut self, num: usize, port: u32) {
+        add_ref(&mut self.sync_ports, port, num);
+    }
+
+    pub fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
+    }
+
+    fn parse_buffer(&mut self, buffer: &[u8]) -> usize {
+        let mut parser = Parser::new(buffer);
+        loop {
+            let position = parser.position();
+            match self.parser_record(&mut parser) {
+                Ok(()) => {}
+                Err(ParseError::Incomplete) => return position,
+                Err(ParseError::InvalidFormat { .. }) => {
+                    if parser.recover(position).is_err() {
+                        return position;
                     }
-                    vec.push(value);
                 }
             }
         }
+    }
 
+    fn parser_record(&mut self, parser: &mut Parser) -> ParseResult<()> {
         while parser.next()? != b'{' {}
 
-        match parser.parse_usize()? {
+        let position = parser.position();
+        let kind = parser.parse_usize()?;
+        match kind {
             1 => {
                 let id = parser.parse_uuid()?;
                 let name = parser.parse_str()?.str().to_string();
@@ -153,7 +780,7 @@ impl References {
                 let id = parser.parse_uuid()?;
                 let name = parser.parse_str()?.str().to_string();
                 let num = parser.parse_usize()?;
-                let metadata = Metadata { name, id };
+                let metadata = Metadata::new(id, name);
                 add_ref(&mut self.metadata, metadata, num);
             }
             6 => {
@@ -186,20 +813,49 @@ impl References {
                 let obj = parser.parse_object()?.to_string();
                 let ind = parser.parse_usize()?;
                 let num = parser.parse_usize()?;
-                let vec = &mut self.data_separation[ind].values;
-                add_ref(vec, obj, num);
+                let data_separation =
+                    self.data_separation
+                        .get_mut(ind)
+                        .ok_or(ParseError::invalid(
+                            position,
+                            Field::DataSeparation,
+                            "known data separation",
+                        ))?;
+                add_ref(&mut data_separation.values, obj, num);
             }
-            11 | 12 => {
-                let _obj = parser.parse_object()?;
-                let _num = parser.parse_usize()?;
+            11 => {
+                let obj = parser.parse_object()?.to_string();
+                let num = parser.parse_usize()?;
+                add_ref(&mut self.record11, obj, num);
+            }
+            12 => {
+                let obj = parser.parse_object()?.to_string();
+                let num = parser.parse_usize()?;
+                add_ref(&mut self.record12, obj, num);
             }
             13 => {
-                let _num = parser.parse_usize()?;
-                let _num = parser.parse_usize()?;
+                let value = parser.parse_usize()?;
+                let num = parser.parse_usize()?;
+                add_ref(&mut self.record13, value, num);
+            }
+            _ => {
+                // Неизвестный тип записи сохраняется как есть
+                let rest = parser.remaining();
+                parser.skip_object()?;
+                let len = rest.len() - parser.remaining().len() - 1;
+                let payload = std::str::from_utf8(&rest[..len])
+                    .map_err(|_| ParseError::invalid(position, Field::ReferenceType, "utf-8"))?
+                    .to_string();
+                self.unknown_records.push(UnknownRecord { kind, payload });
             }
-            t => panic!("Unknown reference type: {t}"),
         }
-        Some(())
+        let table = match kind {
+            1..=9 => kind - 1,
+            10 => 8,
+            _ => 9,
+        };
+        self.generations[table] += 1;
+        Ok(())
     }
 
     pub fn users(&self) -> &[User] {
@@ -233,6 +889,632 @@ impl References {
     pub fn sync_ports(&self) -> &[u32] {
         self.sync_ports.as_ref()
     }
+
+    pub fn data_separation(&self) -> &[DataSeparation] {
+        self.data_separation.as_ref()
+    }
+
+    pub fn record11(&self) -> &[String] {
+        self.record11.as_ref()
+    }
+
+    pub fn record12(&self) -> &[String] {
+        self.record12.as_ref()
+    }
+
+    pub fn record13(&self) -> &[usize] {
+        self.record13.as_ref()
+    }
+
+    pub fn unknown_records(&self) -> &[UnknownRecord] {
+        self.unknown_records.as_ref()
+    }
+
+    fn lookup(&self) -> &Lookup {
+        self.lookup.get_or_init(|| Lookup::new(self))
+    }
+
+    pub fn user_id_by_name(&self, name: &str) -> Option<usize> {
+        self.lookup().users_by_name.get(name).copied()
+    }
+
+    pub fn user_id_by_uuid(&self, id: Uuid) -> Option<usize> {
+        self.lookup().users_by_uuid.get(&id).copied()
+    }
+
+    pub fn user_by_uuid(&self, id: Uuid) -> Option<&User> {
+        self.user_id_by_uuid(id).map(|num| &self.users[num])
+    }
+
+    pub fn computer_id_by_name(&self, name: &str) -> Option<usize> {
+        self.lookup().computers.get(name).copied()
+    }
+
+    pub fn application_id_by_name(&self, name: &str) -> Option<usize> {
+        self.lookup().applications.get(name).copied()
+    }
+
+    pub fn event_id_by_name(&self, name: &str) -> Option<usize> {
+        self.lookup().events.get(name).copied()
+    }
+
+    pub fn metadata_id_by_name(&self, name: &str) -> Option<usize> {
+        self.lookup().metadata_by_name.get(name).copied()
+    }
+
+    pub fn metadata_id_by_uuid(&self, id: Uuid) -> Option<usize> {
+        self.lookup().metadata_by_uuid.get(&id).copied()
+    }
+
+    pub fn metadata_by_uuid(&self, id: Uuid) -> Option<&Metadata> {
+        self.metadata_id_by_uuid(id).map(|num| &self.metadata[num])
+    }
+}
+
+/// Разрешение номеров из событий в значения справочников. Реализуется `References`;
+/// свои реализации могут, например, читать справочники из базы данных по мере надобности.
+/// Номера, которых нет в справочнике, события разрешают в пустые значения.
+pub trait ResolveRefs {
+    fn user(&self, id: usize) -> Option<&User>;
+    fn computer(&self, id: usize) -> Option<&str>;
+    fn application(&self, id: usize) -> Option<&str>;
+    fn event(&self, id: usize) -> Option<&str>;
+    fn metadata(&self, id: usize) -> Option<&Metadata>;
+    fn worker_server(&self, id: usize) -> Option<&str>;
+    fn port(&self, id: usize) -> Option<u32>;
+    fn sync_port(&self, id: usize) -> Option<u32>;
+}
+
+impl ResolveRefs for References {
+    fn user(&self, id: usize) -> Option<&User> {
+        self.users.get(id)
+    }
+
+    fn computer(&self, id: usize) -> Option<&str> {
+        self.computers.get(id).map(String::as_str)
+    }
+
+    fn application(&self, id: usize) -> Option<&str> {
+        self.applications.get(id).map(String::as_str)
+    }
+
+    fn event(&self, id: usize) -> Option<&str> {
+        self.events.get(id).map(String::as_str)
+    }
+
+    fn metadata(&self, id: usize) -> Option<&Metadata> {
+        self.metadata.get(id)
+    }
+
+    fn worker_server(&self, id: usize) -> Option<&str> {
+        self.worker_servers.get(id).map(String::as_str)
+    }
+
+    fn port(&self, id: usize) -> Option<u32> {
+        self.ports.get(id).copied()
+    }
+
+    fn sync_port(&self, id: usize) -> Option<u32> {
+        self.sync_ports.get(id).copied()
+    }
+}
+
+// Вызов с &refs, когда refs - ссылка или снимок Arc
+impl<T: ResolveRefs + ?Sized> ResolveRefs for &T {
+    fn user(&self, id: usize) -> Option<&User> {
+        (**self).user(id)
+    }
+
+    fn computer(&self, id: usize) -> Option<&str> {
+        (**self).computer(id)
+    }
+
+    fn application(&self, id: usize) -> Option<&str> {
+        (**self).application(id)
+    }
+
+    fn event(&self, id: usize) -> Option<&str> {
+        (**self).event(id)
+    }
+
+    fn metadata(&self, id: usize) -> Option<&Metadata> {
+        (**self).metadata(id)
+    }
+
+    fn worker_server(&self, id: usize) -> Option<&str> {
+        (**self).worker_server(id)
+    }
+
+    fn port(&self, id: usize) -> Option<u32> {
+        (**self).port(id)
+    }
+
+    fn sync_port(&self, id: usize) -> Option<u32> {
+        (**self).sync_port(id)
+    }
+}
+
+impl<T: ResolveRefs + ?Sized> ResolveRefs for Arc<T> {
+    fn user(&self, id: usize) -> Option<&User> {
+        (**self).user(id)
+    }
+
+    fn computer(&self, id: usize) -> Option<&str> {
+        (**self).computer(id)
+    }
+
+    fn application(&self, id: usize) -> Option<&str> {
+        (**self).application(id)
+    }
+
+    fn event(&self, id: usize) -> Option<&str> {
+        (**self).event(id)
+    }
+
+    fn metadata(&self, id: usize) -> Option<&Metadata> {
+        (**self).metadata(id)
+    }
+
+    fn worker_server(&self, id: usize) -> Option<&str> {
+        (**self).worker_server(id)
+    }
+
+    fn port(&self, id: usize) -> Option<u32> {
+        (**self).port(id)
+    }
+
+    fn sync_port(&self, id: usize) -> Option<u32> {
+        (**self).sync_port(id)
+    }
+}
+
+pub(crate) static NO_USER: User = User {
+    id: Uuid::nil(),
+    name: String::new(),
+};
+
+pub(crate) static NO_METADATA: Metadata = Metadata {
+    id: Uuid::nil(),
+    name: String::new(),
+    kind: String::new(),
+    synonym: String::new(),
+};
+
+// Пустая запись на месте пропущенного в 1Cv8.lgf номера
+pub(crate) trait Placeholder {
+    fn is_placeholder(&self) -> bool;
+}
+
+impl Placeholder for User {
+    fn is_placeholder(&self) -> bool {
+        self.id.is_nil() && self.name.is_empty()
+    }
+}
+
+impl Placeholder for Metadata {
+    fn is_placeholder(&self) -> bool {
+        self.id.is_nil() && self.name.is_empty()
+    }
+}
+
+impl Placeholder for DataSeparation {
+    fn is_placeholder(&self) -> bool {
+        self.id.is_nil() && self.name.is_empty()
+    }
+}
+
+impl Placeholder for String {
+    fn is_placeholder(&self) -> bool {
+        self.is_empty()
+    }
+}
+
+impl Placeholder for u32 {
+    fn is_placeholder(&self) -> bool {
+        *self == 0
+    }
+}
+
+impl Placeholder for usize {
+    fn is_placeholder(&self) -> bool {
+        *self == 0
+    }
+}
+
+impl Placeholder for UnknownRecord {
+    fn is_placeholder(&self) -> bool {
+        false
+    }
+}
+
+/// Справочники, общие для нескольких потоков. Читатели берут неизменяемый снимок
+/// и разрешают по нему события без блокировок; обновление подменяет снимок целиком.
+#[derive(Clone, Default)]
+pub struct SharedReferences(Arc<RwLock<Arc<References>>>);
+
+impl SharedReferences {
+    pub fn new(refs: References) -> SharedReferences {
+        SharedReferences(Arc::new(RwLock::new(Arc::new(refs))))
+    }
+
+    pub fn snapshot(&self) -> Arc<References> {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub fn replace(&self, refs: References) {
+        *self.0.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(refs);
+    }
+
+    /// `References::refresh` над копией текущего снимка; снимок подменяется,
+    /// только если справочники изменились
+    pub fn refresh<P: AsRef<Path>>(&self, path: P) -> io::Result<Tables> {
+        let mut refs = References::clone(&self.snapshot());
+        let changes = refs.refresh(path)?;
+        if !changes.is_empty() {
+            self.replace(refs);
+        }
+        Ok(changes)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TableStats {
+    #[cfg_attr(feature = "serde", serde(skip))]
+    table: Tables,
+    name: &'static str,
+    len: usize,
+    placeholders: usize,
+    heap_size: usize,
+}
+
+impl TableStats {
+    // Запись 0 не используется 1С и заполнителем не считается
+    fn new<T: Placeholder>(
+        table: Tables,
+        name: &'static str,
+        values: &Vec<T>,
+        heap_size: impl Fn(&T) -> usize,
+    ) -> TableStats {
+        TableStats {
+            table,
+            name,
+            len: values.len(),
+            placeholders: values.iter().skip(1).filter(|x| x.is_placeholder()).count(),
+            heap_size: values.capacity() * size_of::<T>()
+                + values.iter().map(heap_size).sum::<usize>(),
+        }
+    }
+
+    pub fn table(&self) -> Tables {
+        self.table
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn placeholders(&self) -> usize {
+        self.placeholders
+    }
+
+    /// Примерный объем памяти в байтах
+    pub fn heap_size(&self) -> usize {
+        self.heap_size
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ReferencesStats {
+    tables: Vec<TableStats>,
+    lookup: usize,
+}
+
+impl ReferencesStats {
+    pub fn tables(&self) -> &[TableStats] {
+        self.tables.as_ref()
+    }
+
+    pub fn table(&self, name: &str) -> Option<&TableStats> {
+        self.tables.iter().find(|x| x.name == name)
+    }
+
+    pub fn placeholders(&self) -> usize {
+        self.tables.iter().map(|x| x.placeholders).sum()
+    }
+
+    /// Примерный объем памяти в байтах вместе с индексами поиска по имени
+    pub fn heap_size(&self) -> usize {
+        self.lookup + self.tables.iter().map(|x| x.heap_size).sum::<usize>()
+    }
+}
+
+/// Номера записей присоединенных справочников в объединенных, см. `References::merge`
+#[derive(Clone, Debug, Default)]
+pub struct Merge {
+    pub(crate) users: Vec<usize>,
+    pub(crate) computers: Vec<usize>,
+    pub(crate) applications: Vec<usize>,
+    pub(crate) events: Vec<usize>,
+    pub(crate) metadata: Vec<usize>,
+    pub(crate) worker_servers: Vec<usize>,
+    pub(crate) ports: Vec<usize>,
+    pub(crate) sync_ports: Vec<usize>,
+    // Новый номер разделителя и номера его значений
+    pub(crate) data_separation: Vec<(usize, Vec<usize>)>,
+    conflicts: Vec<Conflict>,
+}
+
+impl Merge {
+    /// Новый номер записи `id` справочника `table`
+    pub fn map(&self, table: Tables, id: usize) -> Option<usize> {
+        let map = match table {
+            Tables::USERS => &self.users,
+            Tables::COMPUTERS => &self.computers,
+            Tables::APPLICATIONS => &self.applications,
+            Tables::EVENTS => &self.events,
+            Tables::METADATA => &self.metadata,
+            Tables::WORKER_SERVERS => &self.worker_servers,
+            Tables::PORTS => &self.ports,
+            Tables::SYNC_PORTS => &self.sync_ports,
+            Tables::DATA_SEPARATION => return self.data_separation.get(id).map(|x| x.0),
+            _ => return None,
+        };
+        map.get(id).copied()
+    }
+
+    /// Новые номера разделителя `separation` и его значения `value`
+    pub fn map_separation(&self, separation: usize, value: usize) -> Option<(usize, usize)> {
+        let (num, values) = self.data_separation.get(separation)?;
+        Some((*num, *values.get(value)?))
+    }
+
+    pub fn conflicts(&self) -> &[Conflict] {
+        self.conflicts.as_ref()
+    }
+}
+
+/// Запись с одним идентификатором и разными именами; в объединенных справочниках
+/// остается имя `ours`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    table: Tables,
+    id: Uuid,
+    ours: String,
+    theirs: String,
+}
+
+impl Conflict {
+    pub fn table(&self) -> Tables {
+        self.table
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn ours(&self) -> &str {
+        self.ours.as_ref()
+    }
+
+    pub fn theirs(&self) -> &str {
+        self.theirs.as_ref()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    table: Tables,
+    id: Option<Uuid>,
+    name: String,
+}
+
+impl Entry {
+    pub fn table(&self) -> Tables {
+        self.table
+    }
+
+    pub fn id(&self) -> Option<Uuid> {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Diff {
+    added: Vec<Entry>,
+    removed: Vec<Entry>,
+    conflicts: Vec<Conflict>,
+}
+
+impl Diff {
+    pub fn added(&self) -> &[Entry] {
+        self.added.as_ref()
+    }
+
+    pub fn removed(&self) -> &[Entry] {
+        self.removed.as_ref()
+    }
+
+    pub fn conflicts(&self) -> &[Conflict] {
+        self.conflicts.as_ref()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.conflicts.is_empty()
+    }
+}
+
+// Номера записей theirs в ours; отсутствующие в ours записи дописываются в конец
+fn merge_table<T: Clone, K: Hash + Eq>(
+    ours: &mut Vec<T>,
+    theirs: &[T],
+    key: impl Fn(&T) -> K,
+    mut conflict: impl FnMut(&T, &T),
+) -> Vec<usize> {
+    let mut nums = index(ours.iter().map(&key));
+    theirs
+        .iter()
+        .map(|value| match nums.get(&key(value)) {
+            Some(&num) => {
+                conflict(&ours[num], value);
+                num
+            }
+            None => {
+                nums.insert(key(value), ours.len());
+                ours.push(value.clone());
+                ours.len() - 1
+            }
+        })
+        .collect()
+}
+
+pub struct Writer<W: Write> {
+    out: W,
+    id: Option<Uuid>,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(out: W) -> Writer<W> {
+        Writer { out, id: None }
+    }
+
+    // По умолчанию берется идентификатор из заголовка исходного файла
+    pub fn id(mut self, id: Uuid) -> Writer<W> {
+        self.id = Some(id);
+        self
+    }
+
+    pub fn write(mut self, refs: &References) -> io::Result<W> {
+        let id = self.id.or(refs.header.as_ref().map(|x| x.id));
+        write_header(&mut self.out, id.unwrap_or_default())?;
+        let mut first = true;
+        let mut record = |out: &mut W, args: fmt::Arguments| {
+            if !std::mem::take(&mut first) {
+                out.write_all(b",\r\n")?;
+            }
+            write!(out, "{{{args}}}")
+        };
+        let out = &mut self.out;
+
+        for (num, user) in refs.users.iter().enumerate().skip(1) {
+            let name = quote(&user.name);
+            record(out, format_args!("1,{},{name},{num}", user.id))?;
+        }
+        for (kind, names) in [
+            (2, &refs.computers),
+            (3, &refs.applications),
+            (4, &refs.events),
+        ] {
+            for (num, name) in names.iter().enumerate().skip(1) {
+                record(out, format_args!("{kind},{},{num}", quote(name)))?;
+            }
+        }
+        for (num, metadata) in refs.metadata.iter().enumerate().skip(1) {
+            let name = quote(&metadata.name);
+            record(out, format_args!("5,{},{name},{num}", metadata.id))?;
+        }
+        for (num, name) in refs.worker_servers.iter().enumerate().skip(1) {
+            record(out, format_args!("6,{},{num}", quote(name)))?;
+        }
+        for (kind, ports) in [(7, &refs.ports), (8, &refs.sync_ports)] {
+            for (num, port) in ports.iter().enumerate().skip(1) {
+                record(out, format_args!("{kind},{port},{num}"))?;
+            }
+        }
+        for (ind, separation) in refs.data_separation.iter().enumerate().skip(1) {
+            let name = quote(&separation.name);
+            record(out, format_args!("9,{},{name},{ind}", separation.id))?;
+            for (num, value) in separation.values.iter().enumerate().skip(1) {
+                record(out, format_args!("10,\r\n{},{ind},{num}", value.trim_end()))?;
+            }
+        }
+        for (kind, objects) in [(11, &refs.record11), (12, &refs.record12)] {
+            for (num, object) in objects.iter().enumerate().skip(1) {
+                record(out, format_args!("{kind},\r\n{},{num}", object.trim_end()))?;
+            }
+        }
+        for (num, value) in refs.record13.iter().enumerate().skip(1) {
+            record(out, format_args!("13,{value},{num}"))?;
+        }
+        for unknown in &refs.unknown_records {
+            record(out, format_args!("{},{}", unknown.kind, unknown.payload))?;
+        }
+
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+pub(crate) fn write_header<W: Write>(out: &mut W, id: Uuid) -> io::Result<()> {
+    write!(out, "\u{feff}1CV8LOG(ver 2.0)\r\n{id}\r\n\r\n")
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+pub(crate) fn write_str<W: Write>(out: &mut W, s: &str) -> io::Result<()> {
+    out.write_all(quote(s).as_bytes())
+}
+
+fn write_strings(out: &mut Vec<u8>, strings: &[String]) {
+    write_varint(out, strings.len() as u64);
+    for s in strings {
+        write_bytes(out, s.as_bytes());
+    }
+}
+
+fn read_strings(input: &mut Input) -> io::Result<Vec<String>> {
+    (0..input.varint()?).map(|_| input.string()).collect()
+}
+
+fn write_numbers(out: &mut Vec<u8>, numbers: impl ExactSizeIterator<Item = u64>) {
+    write_varint(out, numbers.len() as u64);
+    for num in numbers {
+        write_varint(out, num);
+    }
+}
+
+fn read_numbers<T: TryFrom<u64>>(input: &mut Input) -> io::Result<Vec<T>> {
+    (0..input.varint()?)
+        .map(|_| T::try_from(input.varint()?).map_err(|_| invalid()))
+        .collect()
+}
+
+// Запись 0 не используется: в пустой справочник запись добавляется под номером 1
+fn push_ref<T: Default>(vec: &mut Vec<T>, value: T) -> usize {
+    let num = vec.len().max(1);
+    add_ref(vec, value, num);
+    num
+}
+
+pub(crate) fn add_ref<T: Default>(vec: &mut Vec<T>, value: T, num: usize) {
+    match num.cmp(&vec.len()) {
+        Ordering::Less => vec[num] = value,
+        Ordering::Equal => vec.push(value),
+        Ordering::Greater => {
+            for _ in 0..num - vec.len() {
+                vec.push(T::default());
+            }
+            vec.push(value);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -241,7 +1523,10 @@ mod tests {
 
     use uuid::Uuid;
 
-    use crate::{parser::Parser, references::References};
+    use crate::{
+        parser::Parser,
+        references::{Header, References},
+    };
 
     #[test]
     fn test_parse_record_1() {
@@ -258,4 +1543,45 @@ mod tests {
         );
         assert_eq!(user.name, "Executor")
     }
+
+    #[test]
+    fn test_parse_header() {
+        let buf =
+            b"\xEF\xBB\xBF1CV8LOG(ver 2.0)\r\na90f60dd-31e1-4e46-bc6e-f08a732c8fb9\r\n\r\n{1,";
+        let (header, position) = Header::parse(buf).unwrap().unwrap();
+        assert_eq!(header.version(), "2.0");
+        assert_eq!(
+            header.id(),
+            Uuid::from_str("a90f60dd-31e1-4e46-bc6e-f08a732c8fb9").unwrap()
+        );
+        assert_eq!(&buf[position..], b"\r\n{1,");
+
+        assert_eq!(Header::parse(b"{1,"), Ok(None));
+        assert!(Header::parse(b"1CV8LOG(ver 2.0)\r\na90f")
+            .unwrap_err()
+            .is_incomplete());
+    }
+
+    #[test]
+    fn test_skip_invalid_record() {
+        let mut references = References::default();
+        let buf = b"{1,x,\"User\",1},\r\n{2,\"COMPUTER1\",1},\r\n";
+        let position = references.parse_buffer(buf);
+
+        assert_eq!(position, buf.len() - 3);
+        assert!(references.users.is_empty());
+        assert_eq!(references.computers[1], "COMPUTER1");
+    }
+
+    #[test]
+    fn test_unknown_record() {
+        let mut references = References::default();
+        let buf = b"{99,\"x\",\r\n{1,2},1},\r\n{2,\"COMPUTER1\",1},\r\n";
+        references.parse_buffer(buf);
+
+        let record = &references.unknown_records()[0];
+        assert_eq!(record.kind(), 99);
+        assert_eq!(record.payload(), "\"x\",\r\n{1,2},1");
+        assert_eq!(references.computers[1], "COMPUTER1");
+    }
 }
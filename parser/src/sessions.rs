@@ -0,0 +1,126 @@
+use crate::{
+    events::Event,
+    references::{References, User},
+};
+use chrono::{Duration, NaiveDateTime};
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SessionRecord {
+    session: usize,
+    user_id: usize,
+    computer_id: usize,
+    application_id: usize,
+    start: NaiveDateTime,
+    end: Option<NaiveDateTime>,
+    event_count: usize,
+}
+
+impl SessionRecord {
+    pub fn session(&self) -> usize {
+        self.session
+    }
+
+    pub fn user_id(&self) -> usize {
+        self.user_id
+    }
+
+    pub fn user<'refs>(&self, refs: &'refs References) -> &'refs User {
+        &refs.users()[self.user_id]
+    }
+
+    pub fn computer_id(&self) -> usize {
+        self.computer_id
+    }
+
+    pub fn computer<'refs>(&self, refs: &'refs References) -> &'refs str {
+        &refs.computers()[self.computer_id]
+    }
+
+    pub fn application_id(&self) -> usize {
+        self.application_id
+    }
+
+    pub fn application<'refs>(&self, refs: &'refs References) -> &'refs str {
+        &refs.applications()[self.application_id]
+    }
+
+    pub fn start(&self) -> NaiveDateTime {
+        self.start
+    }
+
+    pub fn end(&self) -> Option<NaiveDateTime> {
+        self.end
+    }
+
+    pub fn duration(&self) -> Option<Duration> {
+        self.end.map(|end| end - self.start)
+    }
+
+    pub fn event_count(&self) -> usize {
+        self.event_count
+    }
+}
+
+pub struct SessionTracker {
+    start_id: Option<usize>,
+    finish_id: Option<usize>,
+    open: HashMap<(usize, usize), SessionRecord>,
+}
+
+impl SessionTracker {
+    pub fn new(refs: &References) -> SessionTracker {
+        SessionTracker {
+            start_id: refs.event_id_by_name("_$Session$_.Start"),
+            finish_id: refs.event_id_by_name("_$Session$_.Finish"),
+            open: HashMap::new(),
+        }
+    }
+
+    pub fn open_sessions(&self) -> usize {
+        self.open.len()
+    }
+
+    pub fn push<F>(&mut self, event: &Event, emit: &mut F)
+    where
+        F: FnMut(SessionRecord),
+    {
+        let key = (event.session(), event.user_id());
+        let event_id = Some(event.event_id());
+
+        if event_id == self.start_id {
+            // Номер сеанса переиспользуется после перезапуска: прежний сеанс не завершен
+            if let Some(record) = self.open.remove(&key) {
+                emit(record);
+            }
+            self.open.insert(
+                key,
+                SessionRecord {
+                    session: event.session(),
+                    user_id: event.user_id(),
+                    computer_id: event.computer_id(),
+                    application_id: event.application_id(),
+                    start: event.date(),
+                    end: None,
+                    event_count: 1,
+                },
+            );
+        } else if let Some(record) = self.open.get_mut(&key) {
+            record.event_count += 1;
+            if event_id == self.finish_id {
+                record.end = Some(event.date());
+                emit(self.open.remove(&key).unwrap());
+            }
+        }
+    }
+
+    pub fn flush<F>(&mut self, emit: &mut F)
+    where
+        F: FnMut(SessionRecord),
+    {
+        let mut records: Vec<_> = self.open.drain().map(|(_, record)| record).collect();
+        records.sort_by_key(|record| (record.start, record.session));
+        records.into_iter().for_each(emit);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{directory::LogDirectory, events::Event, references::References};
+use std::{io, ops::ControlFlow, path::Path};
+
+pub trait EventSource {
+    fn references(&self) -> &References;
+
+    fn parse_until(&self, action: &mut dyn FnMut(Event) -> ControlFlow<()>) -> io::Result<()>;
+
+    fn parse(&self, action: &mut dyn FnMut(Event)) -> io::Result<()> {
+        self.parse_until(&mut |event| {
+            action(event);
+            ControlFlow::Continue(())
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    Lgf,
+    Lgd,
+}
+
+pub struct LogReader {
+    format: LogFormat,
+    source: Box<dyn EventSource>,
+}
+
+impl LogReader {
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<LogReader> {
+        let dir = dir.as_ref();
+        if dir.join("1Cv8.lgf").is_file() {
+            let source = Box::new(LogDirectory::open(dir)?);
+            return Ok(LogReader {
+                format: LogFormat::Lgf,
+                source,
+            });
+        }
+        if dir.join("1Cv8.lgd").is_file() {
+            return open_lgd(&dir.join("1Cv8.lgd"));
+        }
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Event log not found in {}", dir.display()),
+        ))
+    }
+
+    pub fn format(&self) -> LogFormat {
+        self.format
+    }
+}
+
+impl EventSource for LogReader {
+    fn references(&self) -> &References {
+        self.source.references()
+    }
+
+    fn parse_until(&self, action: &mut dyn FnMut(Event) -> ControlFlow<()>) -> io::Result<()> {
+        self.source.parse_until(action)
+    }
+}
+
+#[cfg(feature = "lgd")]
+struct LgdLog {
+    reader: crate::lgd::LgdReader,
+    refs: References,
+}
+
+#[cfg(feature = "lgd")]
+impl EventSource for LgdLog {
+    fn references(&self) -> &References {
+        &self.refs
+    }
+
+    fn parse_until(&self, action: &mut dyn FnMut(Event) -> ControlFlow<()>) -> io::Result<()> {
+        self.reader.parse_until(&mut |event| action(event))
+    }
+}
+
+#[cfg(feature = "lgd")]
+fn open_lgd(path: &Path) -> io::Result<LogReader> {
+    let reader = crate::lgd::LgdReader::open(path)?;
+    let refs = reader.references()?;
+    Ok(LogReader {
+        format: LogFormat::Lgd,
+        source: Box::new(LgdLog { reader, refs }),
+    })
+}
+
+#[cfg(not(feature = "lgd"))]
+fn open_lgd(path: &Path) -> io::Result<LogReader> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!(
+            "{}: SQLite event log requires the lgd feature",
+            path.display()
+        ),
+    ))
+}
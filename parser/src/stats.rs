@@ -0,0 +1,283 @@
+use crate::{
+    events::{Event, EventLogLevel},
+    references::References,
+};
+use chrono::{Duration, NaiveDateTime};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LevelCounts {
+    error: usize,
+    warning: usize,
+    information: usize,
+    note: usize,
+}
+
+impl LevelCounts {
+    pub fn error(&self) -> usize {
+        self.error
+    }
+
+    pub fn warning(&self) -> usize {
+        self.warning
+    }
+
+    pub fn information(&self) -> usize {
+        self.information
+    }
+
+    pub fn note(&self) -> usize {
+        self.note
+    }
+
+    pub fn get(&self, level: EventLogLevel) -> usize {
+        match level {
+            EventLogLevel::Error => self.error,
+            EventLogLevel::Warning => self.warning,
+            EventLogLevel::Information => self.information,
+            EventLogLevel::Note => self.note,
+        }
+    }
+
+    pub(crate) fn add(&mut self, level: EventLogLevel) {
+        match level {
+            EventLogLevel::Error => self.error += 1,
+            EventLogLevel::Warning => self.warning += 1,
+            EventLogLevel::Information => self.information += 1,
+            EventLogLevel::Note => self.note += 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Count {
+    name: String,
+    count: usize,
+}
+
+impl Count {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Bucket {
+    start: NaiveDateTime,
+    count: usize,
+}
+
+impl Bucket {
+    pub fn start(&self) -> NaiveDateTime {
+        self.start
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Report {
+    total: usize,
+    first: Option<NaiveDateTime>,
+    last: Option<NaiveDateTime>,
+    levels: LevelCounts,
+    events: Vec<Count>,
+    users: Vec<Count>,
+    metadata: Vec<Count>,
+    top_errors: Vec<Count>,
+    histogram: Vec<Bucket>,
+}
+
+impl Report {
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    pub fn first(&self) -> Option<NaiveDateTime> {
+        self.first
+    }
+
+    pub fn last(&self) -> Option<NaiveDateTime> {
+        self.last
+    }
+
+    pub fn levels(&self) -> &LevelCounts {
+        &self.levels
+    }
+
+    pub fn events(&self) -> &[Count] {
+        &self.events
+    }
+
+    pub fn users(&self) -> &[Count] {
+        &self.users
+    }
+
+    pub fn metadata(&self) -> &[Count] {
+        &self.metadata
+    }
+
+    pub fn top_errors(&self) -> &[Count] {
+        &self.top_errors
+    }
+
+    pub fn histogram(&self) -> &[Bucket] {
+        &self.histogram
+    }
+}
+
+pub struct Collector {
+    top: usize,
+    bucket: Option<Duration>,
+    total: usize,
+    first: Option<NaiveDateTime>,
+    last: Option<NaiveDateTime>,
+    levels: LevelCounts,
+    events: HashMap<usize, usize>,
+    users: HashMap<usize, usize>,
+    metadata: HashMap<usize, usize>,
+    errors: HashMap<usize, usize>,
+    histogram: BTreeMap<NaiveDateTime, usize>,
+}
+
+impl Default for Collector {
+    fn default() -> Self {
+        Collector {
+            top: 10,
+            bucket: None,
+            total: 0,
+            first: None,
+            last: None,
+            levels: LevelCounts::default(),
+            events: HashMap::new(),
+            users: HashMap::new(),
+            metadata: HashMap::new(),
+            errors: HashMap::new(),
+            histogram: BTreeMap::new(),
+        }
+    }
+}
+
+impl Collector {
+    pub fn new() -> Collector {
+        Collector::default()
+    }
+
+    pub fn top(mut self, top: usize) -> Collector {
+        self.top = top;
+        self
+    }
+
+    pub fn bucket(mut self, bucket: Duration) -> Collector {
+        if bucket < Duration::milliseconds(1) {
+            panic!("Invalid bucket: {bucket}");
+        }
+        self.bucket = Some(bucket);
+        self
+    }
+
+    pub fn push(&mut self, event: &Event) {
+        let date = event.date();
+        self.total += 1;
+        self.first = Some(self.first.map_or(date, |first| first.min(date)));
+        self.last = Some(self.last.map_or(date, |last| last.max(date)));
+        self.levels.add(*event.log_level());
+        *self.events.entry(event.event_id()).or_default() += 1;
+        *self.users.entry(event.user_id()).or_default() += 1;
+        *self.metadata.entry(event.metadata_id()).or_default() += 1;
+        if *event.log_level() == EventLogLevel::Error {
+            *self.errors.entry(event.event_id()).or_default() += 1;
+        }
+        if let Some(bucket) = self.bucket {
+            *self.histogram.entry(align(date, bucket)).or_default() += 1;
+        }
+    }
+
+    // Объединение результатов, собранных по частям журнала
+    pub fn merge(&mut self, other: Collector) {
+        self.total += other.total;
+        self.first = self.first.into_iter().chain(other.first).min();
+        self.last = self.last.into_iter().chain(other.last).max();
+        self.levels.error += other.levels.error;
+        self.levels.warning += other.levels.warning;
+        self.levels.information += other.levels.information;
+        self.levels.note += other.levels.note;
+        for (target, source) in [
+            (&mut self.events, other.events),
+            (&mut self.users, other.users),
+            (&mut self.metadata, other.metadata),
+            (&mut self.errors, other.errors),
+        ] {
+            for (id, count) in source {
+                *target.entry(id).or_default() += count;
+            }
+        }
+        for (start, count) in other.histogram {
+            *self.histogram.entry(start).or_default() += count;
+        }
+    }
+
+    pub fn report(&self, refs: &References) -> Report {
+        let events = |id: usize| refs.events().get(id).cloned().unwrap_or_default();
+        let mut top_errors = counts(&self.errors, events);
+        top_errors.truncate(self.top);
+
+        Report {
+            total: self.total,
+            first: self.first,
+            last: self.last,
+            levels: self.levels,
+            events: counts(&self.events, events),
+            users: counts(&self.users, |id| {
+                refs.users()
+                    .get(id)
+                    .map(|user| user.name().to_string())
+                    .unwrap_or_default()
+            }),
+            metadata: counts(&self.metadata, |id| {
+                refs.metadata()
+                    .get(id)
+                    .map(|metadata| metadata.name().to_string())
+                    .unwrap_or_default()
+            }),
+            top_errors,
+            histogram: self
+                .histogram
+                .iter()
+                .map(|(&start, &count)| Bucket { start, count })
+                .collect(),
+        }
+    }
+}
+
+// По убыванию количества, при равенстве по имени
+fn counts<F>(counts: &HashMap<usize, usize>, name: F) -> Vec<Count>
+where
+    F: Fn(usize) -> String,
+{
+    let mut counts: Vec<Count> = counts
+        .iter()
+        .map(|(&id, &count)| Count {
+            name: name(id),
+            count,
+        })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    counts
+}
+
+fn align(date: NaiveDateTime, bucket: Duration) -> NaiveDateTime {
+    let bucket = bucket.num_milliseconds();
+    let millis = date.and_utc().timestamp_millis();
+    date - Duration::milliseconds(millis.rem_euclid(bucket))
+}
@@ -0,0 +1,34 @@
+// Чтение событий из асинхронного кода: файл разбирается в потоке tokio::task::spawn_blocking,
+// события передаются через ограниченный канал, поэтому медленный потребитель тормозит разбор.
+use crate::events::{self, EventOwned, ParseOptions};
+use std::{io, ops::ControlFlow, path::Path};
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, Stream};
+
+const CHANNEL_SIZE: usize = 256;
+
+/// Должна вызываться внутри среды выполнения tokio.
+/// Нераспознанные записи приходят как ошибки `InvalidData` с `ParseError` внутри,
+/// после них чтение продолжается; ошибка ввода-вывода завершает поток.
+/// Разбор прекращается, когда поток событий удален.
+pub fn stream<P: AsRef<Path>>(file_name: P) -> impl Stream<Item = io::Result<EventOwned>> {
+    let file_name = file_name.as_ref().to_path_buf();
+    let (sender, receiver) = mpsc::channel(CHANNEL_SIZE);
+    tokio::task::spawn_blocking(move || {
+        let mut options = ParseOptions::new().on_invalid(|_, _, error| {
+            let error = io::Error::new(io::ErrorKind::InvalidData, *error);
+            let _ = sender.blocking_send(Err(error));
+        });
+        let result =
+            events::parse_with_options(&file_name, &mut options, &mut |event| match sender
+                .blocking_send(Ok(event.to_owned()))
+            {
+                Ok(()) => ControlFlow::Continue(()),
+                Err(_) => ControlFlow::Break(()),
+            });
+        if let Err(error) = result {
+            let _ = sender.blocking_send(Err(error));
+        }
+    });
+    ReceiverStream::new(receiver)
+}
@@ -0,0 +1,125 @@
+use crate::{
+    events::{Event, TransactionInfo, TransactionStatus},
+    references::{Metadata, References},
+};
+use chrono::{Duration, NaiveDateTime};
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TransactionGroup {
+    info: TransactionInfo,
+    status: TransactionStatus,
+    connection: usize,
+    session: usize,
+    first: NaiveDateTime,
+    last: NaiveDateTime,
+    event_count: usize,
+    metadata_ids: Vec<usize>,
+}
+
+impl TransactionGroup {
+    pub fn info(&self) -> &TransactionInfo {
+        &self.info
+    }
+
+    pub fn status(&self) -> &TransactionStatus {
+        &self.status
+    }
+
+    pub fn connection(&self) -> usize {
+        self.connection
+    }
+
+    pub fn session(&self) -> usize {
+        self.session
+    }
+
+    pub fn first(&self) -> NaiveDateTime {
+        self.first
+    }
+
+    pub fn last(&self) -> NaiveDateTime {
+        self.last
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.last - self.first
+    }
+
+    pub fn event_count(&self) -> usize {
+        self.event_count
+    }
+
+    pub fn is_rolled_back(&self) -> bool {
+        self.status == TransactionStatus::RolledBack
+    }
+
+    pub fn metadata_ids(&self) -> &[usize] {
+        &self.metadata_ids
+    }
+
+    pub fn metadata<'a>(&'a self, refs: &'a References) -> impl Iterator<Item = &'a Metadata> {
+        self.metadata_ids.iter().map(|&id| &refs.metadata()[id])
+    }
+}
+
+#[derive(Default)]
+pub struct Transactions {
+    groups: HashMap<TransactionInfo, TransactionGroup>,
+}
+
+impl Transactions {
+    pub fn new() -> Transactions {
+        Transactions::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    pub fn push(&mut self, event: &Event) {
+        let Some(info) = event.transaction() else {
+            return;
+        };
+        let date = event.date();
+        let group = self.groups.entry(info).or_insert_with(|| TransactionGroup {
+            info,
+            status: *event.transaction_status(),
+            connection: event.connection(),
+            session: event.session(),
+            first: date,
+            last: date,
+            event_count: 0,
+            metadata_ids: Vec::new(),
+        });
+
+        group.event_count += 1;
+        group.first = group.first.min(date);
+        group.last = group.last.max(date);
+        // Отмена транзакции важнее статуса остальных записей
+        if group.status != TransactionStatus::RolledBack {
+            group.status = *event.transaction_status();
+        }
+        let metadata_id = event.metadata_id();
+        if metadata_id != 0 {
+            if let Err(index) = group.metadata_ids.binary_search(&metadata_id) {
+                group.metadata_ids.insert(index, metadata_id);
+            }
+        }
+    }
+
+    pub fn get(&self, info: &TransactionInfo) -> Option<&TransactionGroup> {
+        self.groups.get(info)
+    }
+
+    pub fn into_groups(self) -> Vec<TransactionGroup> {
+        let mut groups: Vec<_> = self.groups.into_values().collect();
+        groups.sort_by_key(|group| (group.first, group.info.number()));
+        groups
+    }
+}
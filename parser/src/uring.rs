@@ -0,0 +1,201 @@
+// Чтение файла через io_uring: несколько запросов чтения следующих блоков стоят в очереди ядра,
+// пока разбирается текущий блок. Если io_uring недоступен (старое ядро, seccomp),
+// используется обычное чтение файла.
+use io_uring::{opcode, types, IoUring};
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    os::unix::{fs::FileExt, io::AsRawFd},
+    path::Path,
+};
+
+const QUEUE_DEPTH: usize = 4;
+const READ_SIZE: usize = 256 * 1024;
+
+pub(crate) enum FileReader {
+    Uring(Box<UringReader>),
+    File(File),
+}
+
+impl Read for FileReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            FileReader::Uring(reader) => reader.read(buf),
+            FileReader::File(file) => file.read(buf),
+        }
+    }
+}
+
+pub(crate) fn open_file<P: AsRef<Path>>(file_name: P, offset: u64) -> io::Result<FileReader> {
+    let mut file = File::open(file_name)?;
+    match IoUring::new(QUEUE_DEPTH as u32) {
+        Ok(ring) => Ok(FileReader::Uring(Box::new(UringReader::new(
+            file, ring, offset,
+        )?))),
+        Err(_) => {
+            file.seek(SeekFrom::Start(offset))?;
+            Ok(FileReader::File(file))
+        }
+    }
+}
+
+pub(crate) struct UringReader {
+    file: File,
+    ring: IoUring,
+    buffers: Vec<Box<[u8]>>,
+    // Результаты завершенных запросов по номеру буфера
+    results: Vec<Option<i32>>,
+    // Отправленные запросы в порядке смещений: номер буфера и смещение в файле
+    queue: VecDeque<(usize, u64)>,
+    pending: usize,
+    next_offset: u64,
+    current: Option<usize>,
+    position: usize,
+    len: usize,
+    eof: bool,
+}
+
+impl UringReader {
+    fn new(file: File, ring: IoUring, offset: u64) -> io::Result<UringReader> {
+        let mut reader = UringReader {
+            file,
+            ring,
+            buffers: (0..QUEUE_DEPTH)
+                .map(|_| vec![0u8; READ_SIZE].into_boxed_slice())
+                .collect(),
+            results: vec![None; QUEUE_DEPTH],
+            queue: VecDeque::with_capacity(QUEUE_DEPTH),
+            pending: 0,
+            next_offset: offset,
+            current: None,
+            position: 0,
+            len: 0,
+            eof: false,
+        };
+        for index in 0..QUEUE_DEPTH {
+            reader.push(index)?;
+        }
+        reader.ring.submit()?;
+        Ok(reader)
+    }
+
+    #[allow(unsafe_code)]
+    fn push(&mut self, index: usize) -> io::Result<()> {
+        let buffer = &mut self.buffers[index];
+        let entry = opcode::Read::new(
+            types::Fd(self.file.as_raw_fd()),
+            buffer.as_mut_ptr(),
+            buffer.len() as u32,
+        )
+        .offset(self.next_offset)
+        .build()
+        .user_data(index as u64);
+        // Буфер не перемещается и не используется, пока запрос не завершен (см. Drop)
+        unsafe { self.ring.submission().push(&entry) }
+            .map_err(|_| io::Error::other("io_uring submission queue is full"))?;
+        self.queue.push_back((index, self.next_offset));
+        self.pending += 1;
+        self.next_offset += READ_SIZE as u64;
+        Ok(())
+    }
+
+    fn wait(&mut self, index: usize) -> io::Result<i32> {
+        loop {
+            if let Some(result) = self.results[index].take() {
+                return Ok(result);
+            }
+            match self.ring.submit_and_wait(1) {
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+            self.complete();
+        }
+    }
+
+    fn complete(&mut self) {
+        for entry in self.ring.completion() {
+            self.results[entry.user_data() as usize] = Some(entry.result());
+            self.pending -= 1;
+        }
+    }
+
+    // Следующий по порядку блок файла
+    fn next_block(&mut self) -> io::Result<bool> {
+        let Some((index, offset)) = self.queue.pop_front() else {
+            return Ok(false);
+        };
+        let result = self.wait(index)?;
+        if result < 0 {
+            return Err(io::Error::from_raw_os_error(-result));
+        }
+        // После конца файла дописанные позже данные не читаются, чтобы не было пропусков
+        let mut len = match self.eof {
+            true => 0,
+            false => result as usize,
+        };
+        if len == 0 {
+            self.eof = true;
+        }
+        // Короткое чтение дочитывается синхронно, чтобы следующие запросы остались на своих смещениях
+        while len > 0 && len < READ_SIZE {
+            match self
+                .file
+                .read_at(&mut self.buffers[index][len..], offset + len as u64)
+            {
+                Ok(0) => {
+                    self.eof = true;
+                    break;
+                }
+                Ok(read) => len += read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.current = Some(index);
+        self.position = 0;
+        self.len = len;
+        Ok(true)
+    }
+}
+
+impl Read for UringReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(index) = self.current {
+                if self.position < self.len {
+                    let len = buf.len().min(self.len - self.position);
+                    buf[..len]
+                        .copy_from_slice(&self.buffers[index][self.position..self.position + len]);
+                    self.position += len;
+                    return Ok(len);
+                }
+                self.current = None;
+                if !self.eof {
+                    self.push(index)?;
+                    self.ring.submit()?;
+                }
+            }
+            if !self.next_block()? {
+                return Ok(0);
+            }
+        }
+    }
+}
+
+impl Drop for UringReader {
+    fn drop(&mut self) {
+        while self.pending > 0 {
+            match self.ring.submit_and_wait(self.pending) {
+                Ok(_) => self.complete(),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) => {
+                    // Ядро еще может писать в буферы
+                    std::mem::forget(std::mem::take(&mut self.buffers));
+                    return;
+                }
+            }
+        }
+    }
+}
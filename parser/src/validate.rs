@@ -0,0 +1,238 @@
+use crate::{
+    archive,
+    error::ParseError,
+    events::{self, Event, EventOwned, ParseOptions},
+    references::{Placeholder, References},
+};
+use chrono::NaiveDateTime;
+use std::{
+    fmt,
+    io::{self, Write},
+    ops::ControlFlow,
+    path::Path,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Problem {
+    InvalidRecord {
+        offset: u64,
+        error: String,
+    },
+    TruncatedTail {
+        offset: u64,
+    },
+    OutOfOrder {
+        offset: u64,
+        date: NaiveDateTime,
+        previous: NaiveDateTime,
+    },
+    DanglingId {
+        offset: u64,
+        field: &'static str,
+        id: usize,
+    },
+}
+
+impl Problem {
+    pub fn offset(&self) -> u64 {
+        match self {
+            Problem::InvalidRecord { offset, .. }
+            | Problem::TruncatedTail { offset }
+            | Problem::OutOfOrder { offset, .. }
+            | Problem::DanglingId { offset, .. } => *offset,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::InvalidRecord { offset, error } => {
+                write!(f, "offset {offset}: invalid record: {error}")
+            }
+            Problem::TruncatedTail { offset } => write!(f, "offset {offset}: truncated record"),
+            Problem::OutOfOrder {
+                offset,
+                date,
+                previous,
+            } => write!(f, "offset {offset}: date {date} is before {previous}"),
+            Problem::DanglingId { offset, field, id } => {
+                write!(f, "offset {offset}: unknown {field} id {id}")
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Report {
+    records: usize,
+    problems: Vec<Problem>,
+}
+
+impl Report {
+    pub fn records(&self) -> usize {
+        self.records
+    }
+
+    pub fn problems(&self) -> &[Problem] {
+        &self.problems
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+// Ссылки на отсутствующие в 1Cv8.lgf записи, в том числе на пустые записи
+// на месте пропущенных номеров; 0 означает "не задано"
+pub fn dangling_ids(event: &Event, refs: &References) -> Vec<(&'static str, usize)> {
+    [
+        (
+            "user",
+            event.user_id(),
+            known(refs.users(), event.user_id()),
+        ),
+        (
+            "computer",
+            event.computer_id(),
+            known(refs.computers(), event.computer_id()),
+        ),
+        (
+            "application",
+            event.application_id(),
+            known(refs.applications(), event.application_id()),
+        ),
+        (
+            "event",
+            event.event_id(),
+            known(refs.events(), event.event_id()),
+        ),
+        (
+            "metadata",
+            event.metadata_id(),
+            known(refs.metadata(), event.metadata_id()),
+        ),
+        (
+            "worker server",
+            event.worker_server_id(),
+            known(refs.worker_servers(), event.worker_server_id()),
+        ),
+        (
+            "port",
+            event.port_id(),
+            known(refs.ports(), event.port_id()),
+        ),
+        (
+            "sync port",
+            event.sync_port_id(),
+            known(refs.sync_ports(), event.sync_port_id()),
+        ),
+    ]
+    .into_iter()
+    .filter(|&(_, id, known)| id != 0 && !known)
+    .map(|(field, id, _)| (field, id))
+    .collect()
+}
+
+/// Пропущенные номера справочников 1Cv8.lgf, заполненные пустыми записями.
+/// Обычно означают, что файл обрезан или скопирован не полностью
+pub fn holes(refs: &References) -> Vec<(&'static str, usize)> {
+    let mut holes = Vec::new();
+    holes.extend(table_holes("user", refs.users()));
+    holes.extend(table_holes("computer", refs.computers()));
+    holes.extend(table_holes("application", refs.applications()));
+    holes.extend(table_holes("event", refs.events()));
+    holes.extend(table_holes("metadata", refs.metadata()));
+    holes.extend(table_holes("worker server", refs.worker_servers()));
+    holes.extend(table_holes("port", refs.ports()));
+    holes.extend(table_holes("sync port", refs.sync_ports()));
+    holes
+}
+
+fn known<T: Placeholder>(values: &[T], id: usize) -> bool {
+    values.get(id).is_some_and(|value| !value.is_placeholder())
+}
+
+fn table_holes<'a, T: Placeholder>(
+    field: &'static str,
+    values: &'a [T],
+) -> impl Iterator<Item = (&'static str, usize)> + 'a {
+    (1..values.len())
+        .filter(move |&id| values[id].is_placeholder())
+        .map(move |id| (field, id))
+}
+
+pub fn validate_file<P: AsRef<Path>>(path: P, refs: &References) -> io::Result<Report> {
+    check(path.as_ref(), refs, &mut |_, _| {})
+}
+
+// Копия файла без испорченных записей и событий с неизвестными ссылками,
+// события упорядочены по дате. Файл журнала целиком держится в памяти
+pub fn repair_file<P, W>(path: P, refs: &References, out: W) -> io::Result<Report>
+where
+    P: AsRef<Path>,
+    W: Write,
+{
+    let mut events: Vec<EventOwned> = Vec::new();
+    let report = check(path.as_ref(), refs, &mut |event, valid| {
+        if valid {
+            events.push(event.to_owned());
+        }
+    })?;
+    events.sort_by_key(|event| event.date());
+    let mut writer = events::Writer::new(out);
+    if let Some(header) = refs.header() {
+        writer = writer.id(header.id());
+    }
+    for event in &events {
+        writer.write(event)?;
+    }
+    writer.finish()?;
+    Ok(report)
+}
+
+fn check<F>(path: &Path, refs: &References, action: &mut F) -> io::Result<Report>
+where
+    F: FnMut(&Event, bool),
+{
+    let mut invalid = Vec::new();
+    let mut problems = Vec::new();
+    let mut previous: Option<NaiveDateTime> = None;
+    let mut options = ParseOptions::new().on_invalid(|_, offset, error| {
+        invalid.push(match error {
+            ParseError::Incomplete => Problem::TruncatedTail { offset },
+            error => Problem::InvalidRecord {
+                offset,
+                error: error.to_string(),
+            },
+        })
+    });
+    let summary = events::read_events(archive::open(path)?, 0, &mut options, &mut |event| {
+        let offset = event.offset();
+        if let Some(previous) = previous.filter(|previous| event.date() < *previous) {
+            problems.push(Problem::OutOfOrder {
+                offset,
+                date: event.date(),
+                previous,
+            });
+        }
+        previous = previous.max(Some(event.date()));
+        let dangling = dangling_ids(&event, refs);
+        action(&event, dangling.is_empty());
+        problems.extend(dangling.into_iter().map(|(field, id)| Problem::DanglingId {
+            offset,
+            field,
+            id,
+        }));
+        ControlFlow::Continue(())
+    })?;
+    drop(options);
+    problems.append(&mut invalid);
+    problems.sort_by_key(Problem::offset);
+    Ok(Report {
+        records: summary.records(),
+        problems,
+    })
+}
@@ -0,0 +1,78 @@
+// Разбор в браузере без доступа к файлам: содержимое 1Cv8.lgf и *.lgp передается массивами байт.
+// Сборка: cargo rustc --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib,
+// затем wasm-bindgen --target web target/wasm32-unknown-unknown/release/event_log_parser.wasm
+use crate::{
+    events::{self, EventLogLevel},
+    query::Query,
+    references::References,
+};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub struct LogParser {
+    refs: References,
+}
+
+#[wasm_bindgen]
+impl LogParser {
+    /// `lgf` - содержимое 1Cv8.lgf
+    #[wasm_bindgen(constructor)]
+    pub fn new(lgf: &[u8]) -> Result<LogParser, JsError> {
+        Ok(LogParser {
+            refs: References::from_slice(lgf)?,
+        })
+    }
+
+    /// Справочники в виде объекта JS
+    pub fn references(&self) -> Result<JsValue, JsError> {
+        to_value(&self.refs)
+    }
+
+    /// События файла *.lgp массивом объектов, `query` - выражение отбора как в `query::Query`
+    pub fn parse(&self, lgp: &[u8], query: Option<String>) -> Result<js_sys::Array, JsError> {
+        let query = match query {
+            Some(query) => Some(Query::parse(&query)?.compile(&self.refs)),
+            None => None,
+        };
+        let result = js_sys::Array::new();
+        let mut error = None;
+        events::parse_reader(lgp, &mut |event| {
+            if error.is_some() || query.as_ref().is_some_and(|query| !query.matches(&event)) {
+                return;
+            }
+            match to_value(&event.resolve(&self.refs)) {
+                Ok(value) => {
+                    result.push(&value);
+                }
+                Err(value) => error = Some(value),
+            }
+        })?;
+        match error {
+            Some(error) => Err(error),
+            None => Ok(result),
+        }
+    }
+
+    /// Количество событий по уровням: [информация, примечание, предупреждение, ошибка]
+    pub fn count_levels(&self, lgp: &[u8]) -> Result<Vec<u32>, JsError> {
+        let mut counts = vec![0; 4];
+        events::parse_reader(lgp, &mut |event| {
+            let index = match event.log_level() {
+                EventLogLevel::Information => 0,
+                EventLogLevel::Note => 1,
+                EventLogLevel::Warning => 2,
+                EventLogLevel::Error => 3,
+            };
+            counts[index] += 1;
+        })?;
+        Ok(counts)
+    }
+}
+
+fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, JsError> {
+    // Словари становятся обычными объектами JS, а не Map
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|error| JsError::new(&error.to_string()))
+}
@@ -0,0 +1,234 @@
+use crate::{
+    events::{Event, EventResolved},
+    follow::Follower,
+    references::References,
+};
+use std::{
+    fs::{metadata, read_dir},
+    io,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+pub struct Watcher {
+    dir: PathBuf,
+    refs: Arc<References>,
+    refs_state: Option<(u64, SystemTime)>,
+    current: Option<Follower>,
+    // Файлы до ротации и время последней дописанной в них записи
+    previous: Vec<(Follower, Instant)>,
+    grace: Duration,
+}
+
+impl Watcher {
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<Watcher> {
+        let mut watcher = Watcher {
+            dir: dir.as_ref().to_path_buf(),
+            refs: Arc::default(),
+            refs_state: None,
+            current: None,
+            previous: Vec::new(),
+            grace: Duration::from_secs(5),
+        };
+        watcher.reload_references()?;
+        if let Some(last) = watcher.log_files()?.pop() {
+            watcher.current = Some(Follower::open(last)?);
+        }
+        Ok(watcher)
+    }
+
+    /// Сколько дочитывать предыдущий файл после появления нового:
+    /// запись может завершаться уже после ротации. Отсчет идет от последней
+    /// дописанной в него записи, по умолчанию 5 секунд
+    pub fn grace(mut self, grace: Duration) -> Watcher {
+        self.grace = grace;
+        self
+    }
+
+    pub fn references(&self) -> &References {
+        &self.refs
+    }
+
+    /// Справочники для других потоков; при изменении 1Cv8.lgf watcher
+    /// обновляет свою копию, выданные снимки остаются прежними
+    pub fn snapshot(&self) -> Arc<References> {
+        self.refs.clone()
+    }
+
+    pub fn current_file(&self) -> Option<&Path> {
+        self.current.as_ref().map(|follower| follower.path())
+    }
+
+    pub fn poll<F>(&mut self, action: &mut F) -> io::Result<ControlFlow<()>>
+    where
+        F: FnMut(EventResolved) -> ControlFlow<()>,
+    {
+        self.reload_references()?;
+
+        let refs = &self.refs;
+        for (follower, seen) in &mut self.previous {
+            let offset = follower.offset();
+            let flow = follower.poll(&mut |event: Event| action(event.resolve(refs)))?;
+            if follower.offset() != offset {
+                *seen = Instant::now();
+            }
+            if flow.is_break() {
+                return Ok(flow);
+            }
+        }
+        let grace = self.grace;
+        self.previous.retain(|(_, seen)| seen.elapsed() < grace);
+
+        loop {
+            if let Some(follower) = &mut self.current {
+                let refs = &self.refs;
+                let flow = follower.poll(&mut |event: Event| action(event.resolve(refs)))?;
+                if flow.is_break() {
+                    return Ok(flow);
+                }
+            }
+
+            // Переход на следующий файл после ротации
+            let current = self.current_file().map(Path::to_path_buf);
+            let next = self
+                .log_files()?
+                .into_iter()
+                .find(|file| current.as_ref().is_none_or(|current| file > current));
+            match next {
+                Some(next) => {
+                    if let Some(previous) = self.current.replace(Follower::open(next)?) {
+                        self.previous.push((previous, Instant::now()));
+                    }
+                }
+                None => return Ok(ControlFlow::Continue(())),
+            }
+        }
+    }
+
+    fn reload_references(&mut self) -> io::Result<()> {
+        let path = self.dir.join("1Cv8.lgf");
+        let meta = metadata(&path)?;
+        let state = Some((meta.len(), meta.modified()?));
+        if state != self.refs_state {
+            Arc::make_mut(&mut self.refs).refresh(&path)?;
+            self.refs_state = state;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn log_files(&self) -> io::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for entry in read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "lgp") {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+pub fn watch<F, P>(dir: P, interval: Duration, action: &mut F) -> io::Result<()>
+where
+    F: FnMut(EventResolved) -> ControlFlow<()>,
+    P: AsRef<Path>,
+{
+    let mut watcher = Watcher::open(dir)?;
+    while watcher.poll(action)?.is_continue() {
+        thread::sleep(interval);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, io::Write};
+
+    const RECORD: &[u8] =
+        b"{20221217221504,N,\r\n{0,0},1,1,1,1,1,I,\"\",0,\r\n{\"U\"},\"\",0,0,0,2,0,\r\n{0}\r\n}";
+
+    #[test]
+    fn test_watch_rotation() {
+        let dir = std::env::temp_dir().join("event-log-parser-test-watch");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        fs::copy("../test-log/1Cv8.lgf", dir.join("1Cv8.lgf")).unwrap();
+        fs::write(dir.join("20221217000000.lgp"), RECORD).unwrap();
+
+        let mut computers = Vec::new();
+        let mut action = |event: EventResolved| {
+            computers.push(event.computer().to_string());
+            ControlFlow::Continue(())
+        };
+
+        let mut watcher = Watcher::open(&dir).unwrap();
+        assert!(watcher.poll(&mut action).unwrap().is_continue());
+
+        fs::write(dir.join("20221218000000.lgp"), RECORD).unwrap();
+        assert!(watcher.poll(&mut action).unwrap().is_continue());
+        assert!(watcher.poll(&mut action).unwrap().is_continue());
+        assert!(watcher
+            .current_file()
+            .unwrap()
+            .ends_with("20221218000000.lgp"));
+
+        assert_eq!(computers, vec!["computer1", "computer1"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_watch_rotation_append() {
+        let dir = std::env::temp_dir().join("event-log-parser-test-watch-append");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        fs::copy("../test-log/1Cv8.lgf", dir.join("1Cv8.lgf")).unwrap();
+        let old = dir.join("20221217000000.lgp");
+        fs::write(&old, RECORD).unwrap();
+
+        let mut total_events = 0;
+        let mut action = |_: EventResolved| {
+            total_events += 1;
+            ControlFlow::Continue(())
+        };
+
+        let mut watcher = Watcher::open(&dir).unwrap();
+        assert!(watcher.poll(&mut action).unwrap().is_continue());
+
+        fs::write(dir.join("20221218000000.lgp"), RECORD).unwrap();
+        assert!(watcher.poll(&mut action).unwrap().is_continue());
+
+        // Запись в старый файл завершается после появления нового
+        let mut file = fs::OpenOptions::new().append(true).open(&old).unwrap();
+        file.write_all(b",\r\n").unwrap();
+        file.write_all(&RECORD[..20]).unwrap();
+        assert!(watcher.poll(&mut action).unwrap().is_continue());
+        file.write_all(&RECORD[20..]).unwrap();
+        assert!(watcher.poll(&mut action).unwrap().is_continue());
+
+        assert_eq!(total_events, 3);
+
+        // После периода ожидания старый файл больше не читается
+        let mut watcher = watcher.grace(Duration::ZERO);
+        assert!(watcher
+            .poll(&mut |_| ControlFlow::Continue(()))
+            .unwrap()
+            .is_continue());
+        file.write_all(b",\r\n").unwrap();
+        file.write_all(RECORD).unwrap();
+        let mut late = 0;
+        assert!(watcher
+            .poll(&mut |_| {
+                late += 1;
+                ControlFlow::Continue(())
+            })
+            .unwrap()
+            .is_continue());
+        assert_eq!(late, 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
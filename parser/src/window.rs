@@ -0,0 +1,333 @@
+use chrono::{Duration, NaiveDateTime};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    error, fmt,
+    hash::Hash,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowError {
+    // Шаг меньше миллисекунды
+    InvalidStep(Duration),
+    // Шаг больше размера окна
+    StepExceedsSize { size: Duration, step: Duration },
+}
+
+impl fmt::Display for WindowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WindowError::InvalidStep(step) => {
+                write!(f, "window step {step} is less than 1 ms")
+            }
+            WindowError::StepExceedsSize { size, step } => {
+                write!(f, "window step {step} exceeds window size {size}")
+            }
+        }
+    }
+}
+
+impl error::Error for WindowError {}
+
+pub struct WindowResult<K> {
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    key: K,
+    count: usize,
+    distinct: usize,
+}
+
+impl<K> WindowResult<K> {
+    pub fn start(&self) -> NaiveDateTime {
+        self.start
+    }
+
+    pub fn end(&self) -> NaiveDateTime {
+        self.end
+    }
+
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn distinct(&self) -> usize {
+        self.distinct
+    }
+
+    pub fn rate(&self) -> f64 {
+        let seconds = (self.end - self.start).num_milliseconds() as f64 / 1000f64;
+        self.count as f64 / seconds
+    }
+}
+
+// Ключи в порядке появления, чтобы результаты окна выдавались в одном порядке
+struct Pane<K, V> {
+    start: NaiveDateTime,
+    index: HashMap<K, usize>,
+    keys: Vec<(K, usize, HashSet<V>)>,
+}
+
+pub struct Window<K, V> {
+    size: Duration,
+    step: Duration,
+    panes: VecDeque<Pane<K, V>>,
+    next_end: Option<NaiveDateTime>,
+    late: usize,
+}
+
+impl<K, V> Window<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Hash + Eq + Clone,
+{
+    pub fn tumbling(size: Duration) -> Result<Window<K, V>, WindowError> {
+        Window::sliding(size, size)
+    }
+
+    /// Шаг не меньше миллисекунды и не больше размера окна
+    pub fn sliding(size: Duration, step: Duration) -> Result<Window<K, V>, WindowError> {
+        if step < Duration::milliseconds(1) {
+            return Err(WindowError::InvalidStep(step));
+        }
+        if size < step {
+            return Err(WindowError::StepExceedsSize { size, step });
+        }
+        Ok(Window {
+            size,
+            step,
+            panes: VecDeque::new(),
+            next_end: None,
+            late: 0,
+        })
+    }
+
+    pub fn late(&self) -> usize {
+        self.late
+    }
+
+    pub fn push<F>(&mut self, date: NaiveDateTime, key: K, value: V, emit: &mut F)
+    where
+        F: FnMut(WindowResult<K>),
+    {
+        let pane_start = self.align(date);
+        if let Some(next_end) = self.next_end {
+            if pane_start >= next_end {
+                self.advance(pane_start, emit);
+            }
+        }
+
+        let pane = match self.panes.iter_mut().find(|p| p.start == pane_start) {
+            Some(pane) => pane,
+            None => {
+                if self.panes.back().is_some_and(|p| p.start > pane_start) {
+                    self.late += 1;
+                    return;
+                }
+                self.panes.push_back(Pane {
+                    start: pane_start,
+                    index: HashMap::new(),
+                    keys: Vec::new(),
+                });
+                self.panes.back_mut().unwrap()
+            }
+        };
+        let num = *pane.index.entry(key.clone()).or_insert_with(|| {
+            pane.keys.push((key, 0, HashSet::new()));
+            pane.keys.len() - 1
+        });
+        let (_, count, values) = &mut pane.keys[num];
+        *count += 1;
+        values.insert(value);
+
+        if self.next_end.is_none() {
+            self.next_end = Some(pane_start + self.step);
+        }
+    }
+
+    pub fn flush<F>(&mut self, emit: &mut F)
+    where
+        F: FnMut(WindowResult<K>),
+    {
+        if let Some(last) = self.panes.back() {
+            let end = last.start + self.size;
+            self.advance(end, emit);
+        }
+        self.panes.clear();
+        self.next_end = None;
+    }
+
+    fn align(&self, date: NaiveDateTime) -> NaiveDateTime {
+        let step = self.step.num_milliseconds();
+        let millis = date.and_utc().timestamp_millis();
+        let aligned = millis - millis.rem_euclid(step);
+        date - Duration::milliseconds(millis - aligned)
+    }
+
+    fn advance<F>(&mut self, until: NaiveDateTime, emit: &mut F)
+    where
+        F: FnMut(WindowResult<K>),
+    {
+        let Some(mut end) = self.next_end else {
+            return;
+        };
+        while end <= until {
+            let start = end - self.size;
+            while self.panes.front().is_some_and(|p| p.start < start) {
+                self.panes.pop_front();
+            }
+            if self.panes.is_empty() {
+                end = until + self.step;
+                break;
+            }
+            self.emit_window(start, end, emit);
+            end += self.step;
+        }
+        while self
+            .panes
+            .front()
+            .is_some_and(|p| p.start < end - self.size)
+        {
+            self.panes.pop_front();
+        }
+        self.next_end = Some(end);
+    }
+
+    fn emit_window<F>(&self, start: NaiveDateTime, end: NaiveDateTime, emit: &mut F)
+    where
+        F: FnMut(WindowResult<K>),
+    {
+        let mut index = HashMap::<&K, usize>::new();
+        let mut totals = Vec::<(&K, usize, HashSet<&V>)>::new();
+        for pane in self.panes.iter().take_while(|p| p.start < end) {
+            for (key, count, values) in &pane.keys {
+                let num = *index.entry(key).or_insert_with(|| {
+                    totals.push((key, 0, HashSet::new()));
+                    totals.len() - 1
+                });
+                totals[num].1 += count;
+                totals[num].2.extend(values.iter());
+            }
+        }
+        for (key, count, values) in totals {
+            emit(WindowResult {
+                start,
+                end,
+                key: key.clone(),
+                count,
+                distinct: values.len(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn date(h: u32, m: u32, s: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2022, 12, 17)
+            .unwrap()
+            .and_hms_opt(h, m, s)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_tumbling() {
+        let mut window = Window::tumbling(Duration::minutes(1)).unwrap();
+        let mut results = Vec::new();
+        let mut emit =
+            |r: WindowResult<&'static str>| results.push((r.start(), r.key, r.count, r.distinct));
+
+        window.push(date(10, 0, 1), "Start", 1, &mut emit);
+        window.push(date(10, 0, 30), "Start", 2, &mut emit);
+        window.push(date(10, 0, 59), "Start", 1, &mut emit);
+        window.push(date(10, 3, 0), "Start", 3, &mut emit);
+        window.flush(&mut emit);
+
+        assert_eq!(
+            results,
+            vec![
+                (date(10, 0, 0), "Start", 3, 2),
+                (date(10, 3, 0), "Start", 1, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sliding() {
+        let mut window = Window::sliding(Duration::minutes(2), Duration::minutes(1)).unwrap();
+        let mut results = Vec::new();
+        let mut emit = |r: WindowResult<usize>| results.push((r.start(), r.count, r.rate()));
+
+        window.push(date(10, 0, 10), 1, (), &mut emit);
+        window.push(date(10, 1, 10), 1, (), &mut emit);
+        window.push(date(10, 1, 20), 1, (), &mut emit);
+        window.flush(&mut emit);
+
+        assert_eq!(
+            results,
+            vec![
+                (date(9, 59, 0), 1, 1f64 / 120f64),
+                (date(10, 0, 0), 3, 3f64 / 120f64),
+                (date(10, 1, 0), 2, 2f64 / 120f64)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_late() {
+        let mut window = Window::tumbling(Duration::minutes(1)).unwrap();
+        let mut emit = |_: WindowResult<usize>| {};
+
+        window.push(date(10, 5, 0), 1, 1, &mut emit);
+        window.push(date(10, 1, 0), 1, 1, &mut emit);
+        assert_eq!(window.late(), 1);
+    }
+
+    #[test]
+    fn test_invalid_window() {
+        assert_eq!(
+            Window::<usize, usize>::tumbling(Duration::zero()).err(),
+            Some(WindowError::InvalidStep(Duration::zero()))
+        );
+        assert!(
+            Window::<usize, usize>::sliding(Duration::minutes(1), -Duration::minutes(1)).is_err()
+        );
+        assert_eq!(
+            Window::<usize, usize>::sliding(Duration::minutes(1), Duration::minutes(2)).err(),
+            Some(WindowError::StepExceedsSize {
+                size: Duration::minutes(1),
+                step: Duration::minutes(2)
+            })
+        );
+        assert!(Window::<usize, usize>::tumbling(Duration::microseconds(10)).is_err());
+        assert!(Window::<usize, usize>::tumbling(Duration::milliseconds(1)).is_ok());
+    }
+
+    #[test]
+    fn test_window_order() {
+        let mut window = Window::sliding(Duration::minutes(2), Duration::minutes(1)).unwrap();
+        let mut results = Vec::new();
+        let mut emit = |r: WindowResult<usize>| results.push((r.start(), r.key, r.count));
+
+        for key in [5, 3, 9, 1, 7] {
+            window.push(date(10, 0, 10), key, (), &mut emit);
+        }
+        window.push(date(10, 1, 10), 2, (), &mut emit);
+        window.push(date(10, 1, 20), 3, (), &mut emit);
+        window.flush(&mut emit);
+
+        let starts: Vec<_> = results.iter().map(|r| r.0).collect();
+        assert!(starts.windows(2).all(|w| w[0] <= w[1]));
+        let keys: Vec<_> = results
+            .iter()
+            .filter(|r| r.0 == date(10, 0, 0))
+            .map(|r| (r.1, r.2))
+            .collect();
+        assert_eq!(keys, [(5, 1), (3, 2), (9, 1), (1, 1), (7, 1), (2, 1)]);
+    }
+}
@@ -0,0 +1,384 @@
+use crate::{
+    events::{Event, EventLogLevel, TransactionStatus},
+    parser::{LazyStr, LogStr},
+    record::datetime_to_ticks,
+    references::{Metadata, References, User},
+};
+use chrono::NaiveDateTime;
+use quick_xml::{escape::resolve_predefined_entity, events::Event as XmlEvent, Reader};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader},
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use uuid::Uuid;
+
+// Выгрузка журнала регистрации из 1С в XML (http://v8.1c.ru/eventLog)
+pub struct XmlReader {
+    path: PathBuf,
+    refs: References,
+    ids: Ids,
+}
+
+#[derive(Default)]
+struct Ids {
+    users: HashMap<(String, String), usize>,
+    computers: HashMap<String, usize>,
+    applications: HashMap<String, usize>,
+    events: HashMap<String, usize>,
+    metadata: HashMap<String, usize>,
+    worker_servers: HashMap<String, usize>,
+    ports: HashMap<u32, usize>,
+    sync_ports: HashMap<u32, usize>,
+}
+
+// Нулевой элемент каждого справочника - пустое значение
+fn intern<K, T>(ids: &mut HashMap<K, usize>, vec: &mut Vec<T>, key: K, value: T, empty: bool)
+where
+    K: std::hash::Hash + Eq,
+    T: Default,
+{
+    if vec.is_empty() {
+        vec.push(T::default());
+    }
+    if empty {
+        return;
+    }
+    ids.entry(key).or_insert_with(|| {
+        vec.push(value);
+        vec.len() - 1
+    });
+}
+
+impl XmlReader {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<XmlReader> {
+        let path = path.as_ref().to_path_buf();
+        let mut reader = XmlReader {
+            path: PathBuf::new(),
+            refs: References::default(),
+            ids: Ids::default(),
+        };
+        read_records(&path, &mut |record| {
+            reader.add_references(&record);
+            Ok(ControlFlow::Continue(()))
+        })?;
+        reader.path = path;
+        Ok(reader)
+    }
+
+    pub fn references(&self) -> &References {
+        &self.refs
+    }
+
+    fn add_references(&mut self, record: &Record) {
+        let (refs, ids) = (&mut self.refs, &mut self.ids);
+        let user = User::new(
+            Uuid::from_str(&record.user).unwrap_or_default(),
+            record.user_name.as_str(),
+        );
+        let key = (record.user.clone(), record.user_name.clone());
+        let empty = record.user.is_empty() && record.user_name.is_empty();
+        intern(&mut ids.users, &mut refs.users, key, user, empty);
+        let metadata = Metadata::new(Uuid::default(), record.metadata.as_str());
+        intern(
+            &mut ids.metadata,
+            &mut refs.metadata,
+            record.metadata.clone(),
+            metadata,
+            record.metadata.is_empty(),
+        );
+        for (ids, vec, value) in [
+            (&mut ids.computers, &mut refs.computers, &record.computer),
+            (
+                &mut ids.applications,
+                &mut refs.applications,
+                &record.application,
+            ),
+            (&mut ids.events, &mut refs.events, &record.event),
+            (
+                &mut ids.worker_servers,
+                &mut refs.worker_servers,
+                &record.server,
+            ),
+        ] {
+            intern(ids, vec, value.clone(), value.clone(), value.is_empty());
+        }
+        for (ids, vec, value) in [
+            (&mut ids.ports, &mut refs.ports, &record.port),
+            (&mut ids.sync_ports, &mut refs.sync_ports, &record.sync_port),
+        ] {
+            let port = value.parse().unwrap_or_default();
+            intern(ids, vec, port, port, port == 0);
+        }
+    }
+
+    pub fn parse<F>(&self, action: &mut F) -> io::Result<()>
+    where
+        F: FnMut(Event),
+    {
+        self.parse_until(&mut |event| {
+            action(event);
+            ControlFlow::Continue(())
+        })
+    }
+
+    pub fn parse_until<F>(&self, action: &mut F) -> io::Result<()>
+    where
+        F: FnMut(Event) -> ControlFlow<()>,
+    {
+        read_records(&self.path, &mut |record| {
+            let owned = self.resolve(&record)?;
+            Ok(action(owned.event()))
+        })
+    }
+
+    fn resolve(&self, record: &Record) -> io::Result<Resolved> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let ids = &self.ids;
+        let id = |ids: &HashMap<String, usize>, key: &String| ids.get(key).copied().unwrap_or(0);
+        let port = |ids: &HashMap<u32, usize>, value: &str| {
+            let port = value.parse().unwrap_or_default();
+            ids.get(&port).copied().unwrap_or(0)
+        };
+
+        let date = NaiveDateTime::parse_from_str(&record.date, "%Y-%m-%dT%H:%M:%S")
+            .map_err(|_| invalid(format!("Invalid date: {}", record.date)))?;
+        let transaction_status = match record.transaction_status.as_str() {
+            "Committed" => TransactionStatus::Committed,
+            "Unfinished" => TransactionStatus::Unfinished,
+            "NotApplicable" | "" => TransactionStatus::NotApplicable,
+            "RolledBack" => TransactionStatus::RolledBack,
+            s => return Err(invalid(format!("Unknown transaction status: {s}"))),
+        };
+        let log_level = match record.level.as_str() {
+            "Information" => EventLogLevel::Information,
+            "Warning" => EventLogLevel::Warning,
+            "Error" => EventLogLevel::Error,
+            "Note" => EventLogLevel::Note,
+            s => return Err(invalid(format!("Unknown log level: {s}"))),
+        };
+        let user = (record.user.clone(), record.user_name.clone());
+
+        Ok(Resolved {
+            date,
+            transaction_status,
+            transaction_data: transaction_data(&record.transaction_id),
+            user_id: ids.users.get(&user).copied().unwrap_or(0),
+            computer_id: id(&ids.computers, &record.computer),
+            application_id: id(&ids.applications, &record.application),
+            connection: record.connection.parse().unwrap_or_default(),
+            event_id: id(&ids.events, &record.event),
+            log_level,
+            comment: record.comment.clone(),
+            metadata_id: id(&ids.metadata, &record.metadata),
+            data: data(&record.data_type, &record.data),
+            data_presentation: record.data_presentation.clone(),
+            worker_server_id: id(&ids.worker_servers, &record.server),
+            port_id: port(&ids.ports, &record.port),
+            sync_port_id: port(&ids.sync_ports, &record.sync_port),
+            session: record.session.parse().unwrap_or_default(),
+        })
+    }
+}
+
+#[derive(Default)]
+struct Record {
+    level: String,
+    date: String,
+    application: String,
+    event: String,
+    user: String,
+    user_name: String,
+    computer: String,
+    metadata: String,
+    comment: String,
+    data: String,
+    data_type: Option<String>,
+    data_presentation: String,
+    transaction_status: String,
+    transaction_id: String,
+    connection: String,
+    session: String,
+    server: String,
+    port: String,
+    sync_port: String,
+}
+
+impl Record {
+    fn field(&mut self, name: &[u8]) -> Option<&mut String> {
+        Some(match name {
+            b"Level" => &mut self.level,
+            b"Date" => &mut self.date,
+            b"ApplicationName" => &mut self.application,
+            b"Event" => &mut self.event,
+            b"User" => &mut self.user,
+            b"UserName" => &mut self.user_name,
+            b"Computer" => &mut self.computer,
+            b"Metadata" => &mut self.metadata,
+            b"Comment" => &mut self.comment,
+            b"Data" => &mut self.data,
+            b"DataPresentation" => &mut self.data_presentation,
+            b"TransactionStatus" => &mut self.transaction_status,
+            b"TransactionID" => &mut self.transaction_id,
+            b"Connection" => &mut self.connection,
+            b"Session" => &mut self.session,
+            b"ServerName" => &mut self.server,
+            b"Port" => &mut self.port,
+            b"SyncPort" => &mut self.sync_port,
+            _ => return None,
+        })
+    }
+}
+
+struct Resolved {
+    date: NaiveDateTime,
+    transaction_status: TransactionStatus,
+    transaction_data: String,
+    user_id: usize,
+    computer_id: usize,
+    application_id: usize,
+    connection: usize,
+    event_id: usize,
+    log_level: EventLogLevel,
+    comment: String,
+    metadata_id: usize,
+    data: String,
+    data_presentation: String,
+    worker_server_id: usize,
+    port_id: usize,
+    sync_port_id: usize,
+    session: usize,
+}
+
+impl Resolved {
+    fn event(&self) -> Event<'_> {
+        Event {
+            date: self.date,
+            transaction_status: self.transaction_status,
+            transaction_data: &self.transaction_data,
+            user_id: self.user_id,
+            computer_id: self.computer_id,
+            application_id: self.application_id,
+            connection: self.connection,
+            event_id: self.event_id,
+            log_level: self.log_level,
+            comment: LogStr::new(self.comment.as_bytes(), false),
+            metadata_id: self.metadata_id,
+            data: LazyStr::decoded(&self.data),
+            data_presentation: LogStr::new(self.data_presentation.as_bytes(), false),
+            worker_server_id: self.worker_server_id,
+            port_id: self.port_id,
+            sync_port_id: self.sync_port_id,
+            session: self.session,
+            unknown1: 0,
+            unknown2: "{0}",
+            offset: 0,
+            end_offset: 0,
+            raw: &[],
+        }
+    }
+}
+
+// Уровни вложенности: EventLog / Event / поле / содержимое сложного значения
+fn read_records<F>(path: &Path, action: &mut F) -> io::Result<()>
+where
+    F: FnMut(Record) -> io::Result<ControlFlow<()>>,
+{
+    let mut reader = Reader::from_reader(BufReader::new(File::open(path)?));
+    let mut buf = Vec::new();
+    let mut depth = 0;
+    let mut record = Record::default();
+    let mut field: Option<Vec<u8>> = None;
+
+    loop {
+        let event = reader.read_event_into(&mut buf).map_err(io::Error::other)?;
+        match event {
+            XmlEvent::Start(start) => {
+                depth += 1;
+                if depth == 3 {
+                    let name = start.local_name().as_ref().to_vec();
+                    if name == b"Data" {
+                        record.data_type = data_type(&start);
+                    }
+                    field = Some(name);
+                }
+            }
+            XmlEvent::Empty(start) if depth == 2 && start.local_name().as_ref() == b"Data" => {
+                record.data_type = data_type(&start);
+            }
+            XmlEvent::End(_) => {
+                if depth == 3 {
+                    field = None;
+                }
+                if depth == 2 && action(std::mem::take(&mut record))?.is_break() {
+                    break;
+                }
+                depth -= 1;
+            }
+            XmlEvent::Text(text) => {
+                if let Some(value) = field.as_deref().and_then(|x| record.field(x)) {
+                    value.push_str(&text.xml_content().map_err(io::Error::other)?);
+                }
+            }
+            XmlEvent::CData(text) => {
+                if let Some(value) = field.as_deref().and_then(|x| record.field(x)) {
+                    value.push_str(&text.xml_content().map_err(io::Error::other)?);
+                }
+            }
+            XmlEvent::GeneralRef(reference) => {
+                if let Some(value) = field.as_deref().and_then(|x| record.field(x)) {
+                    match reference.resolve_char_ref().map_err(io::Error::other)? {
+                        Some(ch) => value.push(ch),
+                        None => {
+                            let name = reference.decode().map_err(io::Error::other)?;
+                            value.push_str(resolve_predefined_entity(&name).unwrap_or_default());
+                        }
+                    }
+                }
+            }
+            XmlEvent::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(())
+}
+
+fn data_type(start: &quick_xml::events::BytesStart) -> Option<String> {
+    for attr in start.attributes().flatten() {
+        let value = attr.unescape_value().ok()?;
+        match attr.key.local_name().as_ref() {
+            b"nil" if value == "true" => return None,
+            b"type" => return Some(value.rsplit(':').next().unwrap_or_default().to_string()),
+            _ => {}
+        }
+    }
+    Some("string".to_string())
+}
+
+// Значение данных в формате lgp
+fn data(data_type: &Option<String>, value: &str) -> String {
+    match data_type.as_deref() {
+        None => "{\"U\"}".to_string(),
+        Some("decimal") => format!("{{\"N\",{value}}}"),
+        Some("boolean") => format!("{{\"B\",{}}}", (value == "true") as u8),
+        Some("dateTime") => format!("{{\"D\",{}}}", value.replace(['-', 'T', ':'], "")),
+        Some(_) => format!("{{\"S\",\"{}\"}}", value.replace('"', "\"\"")),
+    }
+}
+
+// TransactionID вида "2022-12-17T22:15:04 (2345)" в формат lgp
+fn transaction_data(value: &str) -> String {
+    let parsed = value.split_once('(').and_then(|(start, number)| {
+        let start = NaiveDateTime::parse_from_str(start.trim(), "%Y-%m-%dT%H:%M:%S").ok()?;
+        let number: u64 = number.trim_end_matches(')').trim().parse().ok()?;
+        Some((start, number))
+    });
+    match parsed {
+        Some((start, number)) => format!("{{{:x},{number:x}}}", datetime_to_ticks(start)),
+        None => "{0,0}".to_string(),
+    }
+}
@@ -1,4 +1,21 @@
-use event_log_parser::{events, references::References};
+use std::{ops::ControlFlow, thread};
+
+use chrono::NaiveDate;
+
+use event_log_parser::{
+    audit::{Auditor, Finding},
+    directory::LogDirectory,
+    error::{Field, ParseError},
+    events::{self, EventLogLevel, EventOwned, ParseOptions},
+    filter::EventFilter,
+    known_events::KnownEvent,
+    merge::{self, RefMapper},
+    references::{References, SharedReferences, Tables},
+    sessions::SessionTracker,
+    source::{EventSource, LogFormat, LogReader},
+    stats::Collector,
+    transactions::Transactions,
+};
 
 #[test]
 fn test_files() {
@@ -36,3 +53,3237 @@ fn test_files() {
 
     assert_eq!(total_events, 1274);
 }
+
+#[test]
+fn test_event_resolved() {
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let mut total_events = 0;
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        if total_events == 11 {
+            let resolved = event.resolve(&refs);
+            assert_eq!(resolved.user_name(), "Андрей Кудрявцев");
+            assert_eq!(resolved.computer(), "computer1");
+            assert_eq!(resolved.event_name(), "_$Data$_.Update");
+            assert_eq!(
+                resolved.metadata_name(),
+                "Константа.ИдентификаторИнформационнойБазы"
+            );
+        }
+        total_events += 1;
+    })
+    .unwrap();
+    assert_eq!(total_events, 1274);
+}
+
+#[test]
+fn test_transaction_info() {
+    let mut total_transactions = 0;
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        if let Some(transaction) = event.transaction() {
+            assert!(transaction.start() <= event.date());
+            total_transactions += 1;
+        }
+    })
+    .unwrap();
+    assert_eq!(total_transactions, 1274 - 321);
+}
+
+#[test]
+fn test_data_value() {
+    let mut total_events = 0;
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        assert!(event.data_value().is_some());
+        total_events += 1;
+    })
+    .unwrap();
+    assert_eq!(total_events, 1274);
+}
+
+#[test]
+fn test_references_header() {
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let header = refs.header().unwrap();
+    assert_eq!(header.version(), "2.0");
+    assert_eq!(
+        header.id().to_string(),
+        "a90f60dd-31e1-4e46-bc6e-f08a732c8fb9"
+    );
+}
+
+#[test]
+fn test_references_records() {
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    assert_eq!(refs.record11()[1], "{2,1,1,2,1}");
+    assert_eq!(refs.record12()[1], "{2,1,1,2,1}");
+    assert_eq!(refs.record13()[1..], [1, 1]);
+    assert!(refs.unknown_records().is_empty());
+}
+
+#[test]
+fn test_references_lookup() {
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let id = refs.events().iter().position(|x| x == "_$Session$_.Start");
+    assert_eq!(refs.event_id_by_name("_$Session$_.Start"), id);
+    assert_eq!(refs.user_id_by_name("Андрей Кудрявцев"), Some(2));
+    let id = refs.users()[2].id();
+    assert_eq!(refs.user_by_uuid(id).unwrap().name(), "Андрей Кудрявцев");
+    let id = refs.metadata()[6].id();
+    assert_eq!(refs.metadata_id_by_uuid(id), Some(6));
+    assert_eq!(refs.event_id_by_name("unknown"), None);
+}
+
+#[test]
+fn test_owned_events() {
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let mut events = Vec::<EventOwned>::new();
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        events.push(event.to_owned());
+    })
+    .unwrap();
+    assert_eq!(events[11].user(&refs).name(), "Андрей Кудрявцев");
+
+    let errors = thread::spawn(move || {
+        events
+            .iter()
+            .filter(|event| event.comment().contains("полнотекстового поиска"))
+            .count()
+    })
+    .join()
+    .unwrap();
+
+    assert!(errors > 0);
+}
+
+#[test]
+fn test_parse_until() {
+    let mut total_events = 0;
+    events::parse_until("../test-log/20221212000000.lgp", &mut |_| {
+        total_events += 1;
+        if total_events == 100 {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    })
+    .unwrap();
+
+    assert_eq!(total_events, 100);
+}
+
+#[test]
+fn test_parse_range() {
+    let date = |h, m, s| {
+        NaiveDate::from_ymd_opt(2022, 12, 17)
+            .unwrap()
+            .and_hms_opt(h, m, s)
+            .unwrap()
+    };
+    let from = date(22, 16, 0);
+    let to = date(22, 20, 0);
+
+    let mut expected = 0;
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        if event.date() >= from && event.date() <= to {
+            expected += 1;
+        }
+    })
+    .unwrap();
+
+    let mut total_events = 0;
+    events::parse_range("../test-log/20221212000000.lgp", from, to, &mut |event| {
+        assert!(event.date() >= from && event.date() <= to);
+        total_events += 1;
+    })
+    .unwrap();
+
+    assert!(total_events > 0);
+    assert_eq!(total_events, expected);
+}
+
+#[test]
+fn test_parse_reader() {
+    let buffer = std::fs::read("../test-log/20221212000000.lgp").unwrap();
+
+    let mut total_events = 0;
+    events::parse_reader(buffer.as_slice(), &mut |_| total_events += 1).unwrap();
+
+    assert_eq!(total_events, 1274);
+}
+
+#[test]
+fn test_references_from_slice() {
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    let buffer = std::fs::read("../test-log/1Cv8.lgf").unwrap();
+
+    let copy = References::from_slice(&buffer).unwrap();
+    assert_eq!(copy.header().unwrap().id(), refs.header().unwrap().id());
+    assert_eq!(copy.users().len(), refs.users().len());
+    assert_eq!(copy.metadata()[5].name(), refs.metadata()[5].name());
+    assert_eq!(copy.ports(), refs.ports());
+
+    let mut copy = References::default();
+    copy.parse_reader(std::io::Cursor::new(buffer)).unwrap();
+    assert_eq!(copy.events(), refs.events());
+}
+
+#[test]
+fn test_references_refresh() {
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    let buffer = std::fs::read("../test-log/1Cv8.lgf").unwrap();
+    let path = std::env::temp_dir().join("event-log-parser-test-refresh.lgf");
+    let half = buffer.len() / 2 + 7;
+    std::fs::write(&path, &buffer[..half]).unwrap();
+
+    let mut copy = References::default();
+    let changes = copy.refresh(&path).unwrap();
+    assert!(changes.contains(Tables::USERS | Tables::EVENTS));
+    assert!(copy.header().is_some());
+    assert!(copy.refresh(&path).unwrap().is_empty());
+
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap();
+    std::io::Write::write_all(&mut file, &buffer[half..]).unwrap();
+    drop(file);
+    assert!(!copy.refresh(&path).unwrap().is_empty());
+    assert_eq!(copy.users().len(), refs.users().len());
+    assert_eq!(copy.metadata().len(), refs.metadata().len());
+    assert_eq!(copy.events(), refs.events());
+    assert_eq!(copy.ports(), refs.ports());
+    assert_eq!(copy.header().unwrap().id(), refs.header().unwrap().id());
+
+    std::fs::write(&path, &buffer[..half]).unwrap();
+    assert!(!copy.refresh(&path).unwrap().is_empty());
+    assert!(copy.events().len() < refs.events().len());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_references_refresh_hole() {
+    let path = std::env::temp_dir().join("event-log-parser-test-refresh-hole.lgf");
+    std::fs::copy("../test-log/1Cv8.lgf", &path).unwrap();
+    let append = |record: &str| {
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        std::io::Write::write_all(&mut file, record.as_bytes()).unwrap();
+    };
+
+    let mut refs = References::default();
+    refs.refresh(&path).unwrap();
+    assert_eq!(refs.users().len(), 3);
+
+    append(",\r\n{1,8f1c2d3e-0000-4000-8000-000000000004,\"Четвертый\",4}");
+    assert!(refs.refresh(&path).unwrap().contains(Tables::USERS));
+    assert_eq!(refs.users().len(), 5);
+    assert_eq!(refs.users()[3].name(), "");
+
+    // Запись на месте пустого номера не меняет размер справочника
+    append(",\r\n{1,8f1c2d3e-0000-4000-8000-000000000003,\"Третий\",3}");
+    let changes = refs.refresh(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(changes, Tables::USERS);
+    assert_eq!(refs.users().len(), 5);
+    assert_eq!(refs.users()[3].name(), "Третий");
+}
+
+#[test]
+fn test_references_save_load() {
+    let path = std::env::temp_dir().join("event-log-parser-test-refs.bin");
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    refs.save(&path).unwrap();
+
+    let mut copy = References::load(&path).unwrap();
+    assert_eq!(
+        copy.header().unwrap().version(),
+        refs.header().unwrap().version()
+    );
+    assert_eq!(copy.users()[2].name(), "Андрей Кудрявцев");
+    assert_eq!(copy.users()[2].id(), refs.users()[2].id());
+    assert_eq!(copy.metadata().len(), refs.metadata().len());
+    assert_eq!(copy.events(), refs.events());
+    assert_eq!(copy.ports(), refs.ports());
+    assert_eq!(copy.record13(), refs.record13());
+    assert_eq!(copy.user_id_by_name("Андрей Кудрявцев"), Some(2));
+    assert!(copy.refresh("../test-log/1Cv8.lgf").unwrap().is_empty());
+
+    std::fs::write(&path, b"LGFCACHE\x01\x05").unwrap();
+    assert!(References::load(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_references_merge() {
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    let lgf = format!(
+        "1CV8LOG(ver 2.0)\r\n{}\r\n\r\n\
+        {{1,{},\"Кудрявцев А.\",1}},\r\n\
+        {{1,5d21ec0b-1a39-4a84-8ef2-0a6a4d2c1f01,\"Новый\",2}},\r\n\
+        {{2,\"server2\",1}},\r\n\
+        {{2,\"computer1\",2}},\r\n\
+        {{7,1541,1}}",
+        refs.header().unwrap().id(),
+        refs.users()[2].id()
+    );
+    let other = References::from_slice(lgf.as_bytes()).unwrap();
+
+    let diff = refs.diff(&other);
+    let added = diff.added().iter().map(|x| x.name()).collect::<Vec<_>>();
+    assert_eq!(added, ["Новый", "server2", "1541"]);
+    assert!(diff.removed().iter().any(|x| x.name() == "Designer"));
+    assert_eq!(diff.conflicts().len(), 1);
+    assert!(refs.diff(&refs).is_empty());
+
+    let mut merged = refs.clone();
+    let merge = merged.merge(&other);
+    assert_eq!(merge.map(Tables::USERS, 1), Some(2));
+    assert_eq!(merge.map(Tables::USERS, 2), Some(refs.users().len()));
+    assert_eq!(merge.map(Tables::COMPUTERS, 2), Some(1));
+    assert_eq!(merge.map(Tables::COMPUTERS, 3), None);
+    let server = merge.map(Tables::COMPUTERS, 1).unwrap();
+    assert_eq!(merged.computers()[server], "server2");
+    assert_eq!(merged.users()[2].name(), "Андрей Кудрявцев");
+    assert_eq!(merged.user_id_by_name("Новый"), Some(refs.users().len()));
+    let conflict = &merge.conflicts()[0];
+    assert_eq!(conflict.table(), Tables::USERS);
+    assert_eq!(conflict.ours(), "Андрей Кудрявцев");
+    assert_eq!(conflict.theirs(), "Кудрявцев А.");
+    assert!(merged.merge(&other).conflicts().len() == 1);
+    assert_eq!(merged.users().len(), refs.users().len() + 1);
+}
+
+#[test]
+fn test_ref_mapper() {
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    let lgf = "1CV8LOG(ver 2.0)\r\n5d21ec0b-1a39-4a84-8ef2-0a6a4d2c1f01\r\n\r\n\
+        {2,\"server2\",1},\r\n\
+        {4,\"_$Session$_.Start\",1},\r\n\
+        {4,\"_$Session$_.Finish\",2},\r\n\
+        {9,0b1c4f6e-7d2a-4c59-9a3e-2f5d8e6a1b70,\"ОбластьДанныхДругая\",1},\r\n\
+        {10,\r\n{\"N\",5},1,1}";
+    let mut target = References::from_slice(lgf.as_bytes()).unwrap();
+    let mapper = RefMapper::new(&refs, &mut target);
+    assert!(mapper.conflicts().is_empty());
+
+    let mut count = 0;
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        let mut owned = event.to_owned();
+        let user = event.user(&refs).name().to_string();
+        let name = event.event(&refs).to_string();
+        let metadata = event.metadata(&refs).name().to_string();
+        let computer = event.computer(&refs).to_string();
+        let separation: Vec<_> = event
+            .data_separation(&refs)
+            .into_iter()
+            .map(|(separation, value)| (separation.name().to_string(), value.to_string()))
+            .collect();
+        let ids: Vec<_> = event.data_separation_ids().collect();
+        let event = mapper.map(event);
+        // Значения разделителей без копирования записи не перенумеровываются
+        assert_eq!(event.data_separation_ids().collect::<Vec<_>>(), ids);
+        assert_eq!(event.user(&target).name(), user);
+        assert_eq!(event.event(&target), name);
+        assert_eq!(event.metadata(&target).name(), metadata);
+        assert_eq!(event.computer(&target), computer);
+        mapper.map_owned(&mut owned);
+        assert_eq!(owned.event_id(), event.event_id());
+        let mapped: Vec<_> = owned
+            .data_separation(&target)
+            .into_iter()
+            .map(|(separation, value)| (separation.name().to_string(), value.to_string()))
+            .collect();
+        assert_eq!(mapped, separation);
+        if !ids.is_empty() {
+            assert_eq!(
+                owned.data_separation_ids().collect::<Vec<_>>(),
+                [(2, 1), (3, 1)]
+            );
+        }
+        count += 1;
+    })
+    .unwrap();
+    assert_eq!(count, 1274);
+    assert_eq!(target.events()[2], "_$Session$_.Finish");
+}
+
+#[test]
+fn test_data_separation() {
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    let names: Vec<_> = refs.data_separation()[1..]
+        .iter()
+        .map(|x| x.name())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "ОбластьДанныхВспомогательныеДанные",
+            "ОбластьДанныхОсновныеДанные"
+        ]
+    );
+
+    let mut separated = 0;
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        let values = event.data_separation(&refs);
+        if values.is_empty() {
+            return;
+        }
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0].0.name(), "ОбластьДанныхВспомогательныеДанные");
+        assert_eq!(values[1].1, r#"{"N",0}"#);
+        assert_eq!(
+            event.to_owned().data_separation_ids().collect::<Vec<_>>(),
+            [(1, 1), (2, 1)]
+        );
+        separated += 1;
+    })
+    .unwrap();
+    assert_eq!(separated, 1079);
+}
+
+#[test]
+fn test_references_stats() {
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    let stats = refs.stats();
+    let users = stats.table("users").unwrap();
+    assert_eq!(users.table(), Tables::USERS);
+    assert_eq!(users.len(), refs.users().len());
+    assert!(users.heap_size() > 0);
+    assert_eq!(stats.table("events").unwrap().len(), refs.events().len());
+    assert_eq!(stats.placeholders(), 0);
+    refs.user_id_by_name("Андрей Кудрявцев").unwrap();
+    assert!(refs.stats().heap_size() > stats.heap_size());
+
+    let lgf = "{2,\"computer1\",1},\r\n{2,\"computer4\",4},\r\n{7,1541,2}";
+    let stats = References::from_slice(lgf.as_bytes()).unwrap().stats();
+    assert_eq!(stats.table("computers").unwrap().len(), 5);
+    assert_eq!(stats.table("computers").unwrap().placeholders(), 2);
+    assert_eq!(stats.table("ports").unwrap().placeholders(), 1);
+    assert_eq!(stats.placeholders(), 3);
+}
+
+#[test]
+fn test_references_construction() {
+    use event_log_parser::references::{Metadata, User, Writer};
+
+    let id = uuid::Uuid::from_u128(0x0715_23a4_516f_4fce_ba4b_0d11_ab7a_1893);
+    let mut refs = References::default();
+    assert_eq!(refs.push_user(User::new(id, "Executor")), 1);
+    refs.insert_user(3, User::new(uuid::Uuid::from_u128(3), "Третий"));
+    assert_eq!(refs.push_computer("computer1"), 1);
+    assert_eq!(refs.push_event("_$Session$_.Start"), 1);
+    assert_eq!(refs.push_event("_$Session$_.Finish"), 2);
+    refs.insert_metadata(
+        2,
+        Metadata::new(uuid::Uuid::from_u128(5), "Справочник.Банки"),
+    );
+    assert_eq!(refs.push_port(1541), 1);
+    assert_eq!(refs.user_id_by_name("Третий"), Some(3));
+    assert_eq!(
+        refs.push_user(User::new(uuid::Uuid::from_u128(4), "Четвертый")),
+        4
+    );
+    assert_eq!(refs.user_id_by_name("Четвертый"), Some(4));
+    assert_eq!(refs.users()[2].name(), "");
+
+    let lgf = Writer::new(Vec::new()).write(&refs).unwrap();
+    let copy = References::from_slice(&lgf).unwrap();
+    assert_eq!(copy.users()[1].id(), id);
+    assert_eq!(copy.users()[4].name(), "Четвертый");
+    assert_eq!(copy.events(), refs.events());
+    assert_eq!(copy.metadata()[2].name(), "Справочник.Банки");
+    assert_eq!(copy.ports(), [0, 1541]);
+}
+
+#[test]
+fn test_resolve_refs() {
+    use event_log_parser::references::{Metadata, ResolveRefs, User};
+    use std::collections::HashMap;
+
+    // Только события и компьютеры, например загруженные из базы данных
+    struct Names {
+        events: HashMap<usize, String>,
+        computers: HashMap<usize, String>,
+    }
+
+    impl ResolveRefs for Names {
+        fn user(&self, _id: usize) -> Option<&User> {
+            None
+        }
+        fn computer(&self, id: usize) -> Option<&str> {
+            self.computers.get(&id).map(String::as_str)
+        }
+        fn application(&self, _id: usize) -> Option<&str> {
+            None
+        }
+        fn event(&self, id: usize) -> Option<&str> {
+            self.events.get(&id).map(String::as_str)
+        }
+        fn metadata(&self, _id: usize) -> Option<&Metadata> {
+            None
+        }
+        fn worker_server(&self, _id: usize) -> Option<&str> {
+            None
+        }
+        fn port(&self, _id: usize) -> Option<u32> {
+            None
+        }
+        fn sync_port(&self, _id: usize) -> Option<u32> {
+            None
+        }
+    }
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    let names = Names {
+        events: refs.events().iter().cloned().enumerate().collect(),
+        computers: HashMap::from([(1, "computer1".to_string())]),
+    };
+    let dyn_refs: &dyn ResolveRefs = &names;
+
+    let mut count = 0;
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        assert_eq!(event.event(&names), event.event(&refs));
+        assert_eq!(event.computer(dyn_refs), event.computer(&refs));
+        assert_eq!(event.user(&names).name(), "");
+        assert_eq!(event.metadata(dyn_refs).id(), uuid::Uuid::nil());
+        assert_eq!(event.port(&names), 0);
+        let resolved = event.resolve_with(&names);
+        assert_eq!(resolved.event_name(), event.event(&refs));
+        assert_eq!(resolved.user_name(), "");
+        assert_eq!(
+            event.resolve_with(dyn_refs).computer(),
+            event.computer(&refs)
+        );
+        count += 1;
+    })
+    .unwrap();
+    assert_eq!(count, 1274);
+}
+
+#[test]
+fn test_shared_references() {
+    let shared = SharedReferences::new(References::default());
+    let empty = shared.snapshot();
+    let changes = shared.refresh("../test-log/1Cv8.lgf").unwrap();
+    assert!(changes.contains(Tables::USERS | Tables::METADATA));
+    assert!(empty.users().is_empty());
+    let snapshot = shared.snapshot();
+    assert!(shared.refresh("../test-log/1Cv8.lgf").unwrap().is_empty());
+    assert!(std::sync::Arc::ptr_eq(&snapshot, &shared.snapshot()));
+
+    let warnings = thread::scope(|scope| {
+        let threads = (0..2)
+            .map(|_| {
+                let shared = shared.clone();
+                scope.spawn(move || {
+                    let refs = shared.snapshot();
+                    let mut warnings = Vec::new();
+                    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+                        if *event.log_level() == EventLogLevel::Warning {
+                            warnings.push(event.user(&refs).name().to_string());
+                        }
+                    })
+                    .unwrap();
+                    warnings
+                })
+            })
+            .collect::<Vec<_>>();
+        threads
+            .into_iter()
+            .flat_map(|thread| thread.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(warnings, ["Андрей Кудрявцев", "Андрей Кудрявцев"]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serialize() {
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let json = serde_json::to_value(&refs).unwrap();
+    assert_eq!(json["users"][2]["name"], "Андрей Кудрявцев");
+
+    let mut total_events = 0;
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        if total_events == 11 {
+            let json = serde_json::to_value(&event).unwrap();
+            assert_eq!(json, serde_json::to_value(event.to_owned()).unwrap());
+            assert_eq!(json["user_id"], event.user_id());
+
+            let json = serde_json::to_value(event.resolve(&refs)).unwrap();
+            assert_eq!(json["user"], "Андрей Кудрявцев");
+            assert_eq!(json["event"], "_$Data$_.Update");
+        }
+        total_events += 1;
+    })
+    .unwrap();
+}
+
+#[cfg(feature = "jsonl")]
+#[test]
+fn test_jsonl() {
+    use event_log_parser::export::{jsonl, Column};
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let mut writer = jsonl::Writer::new(Vec::new(), &refs);
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        writer.write(&event).unwrap();
+    })
+    .unwrap();
+    let out = String::from_utf8(writer.into_inner()).unwrap();
+    let lines: Vec<_> = out.lines().collect();
+    assert_eq!(lines.len(), 1274);
+    let json: serde_json::Value = serde_json::from_str(lines[11]).unwrap();
+    assert_eq!(json["user"], "Андрей Кудрявцев");
+    assert_eq!(json["event"], "_$Data$_.Update");
+    assert_eq!(json.as_object().unwrap().len(), Column::ALL.len());
+
+    let columns = [Column::Date, Column::Event];
+    let mut writer = jsonl::Writer::new(Vec::new(), &refs).columns(&columns);
+    events::parse_until("../test-log/20221212000000.lgp", &mut |event| {
+        writer.write(&event).unwrap();
+        std::ops::ControlFlow::Break(())
+    })
+    .unwrap();
+    let out = String::from_utf8(writer.into_inner()).unwrap();
+    assert_eq!(
+        out,
+        "{\"date\":\"2022-12-17T22:15:04\",\"event\":\"_$Session$_.Authentication\"}\n"
+    );
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_csv() {
+    use event_log_parser::export::{csv, Column};
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let columns = [Column::Date, Column::Event, Column::Comment];
+    let mut writer = csv::Writer::new(Vec::new(), &refs)
+        .columns(&columns)
+        .header(csv::Header::Localized)
+        .delimiter(b';')
+        .bom(true);
+    let mut comments = Vec::new();
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        comments.push(event.comment().into_owned());
+        writer.write(&event).unwrap();
+    })
+    .unwrap();
+    let out = writer.into_inner().unwrap();
+    let text = std::str::from_utf8(&out).unwrap();
+    assert!(text.starts_with("\u{feff}Дата;Событие;Комментарий\n"));
+    assert!(comments.iter().any(|x| x.contains('\n')));
+
+    let mut reader = ::csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .from_reader(&out[3..]);
+    let records: Vec<_> = reader.records().map(Result::unwrap).collect();
+    assert_eq!(records.len(), comments.len());
+    for (record, comment) in records.iter().zip(&comments) {
+        assert_eq!(&record[2], comment);
+    }
+    assert_eq!(&records[0][1], "_$Session$_.Authentication");
+}
+
+#[cfg(all(feature = "hashing", feature = "csv"))]
+#[test]
+fn test_hash_identities() {
+    use event_log_parser::{
+        export::{csv, Column},
+        hashing::{hash, HashedIdentities, UserKey},
+    };
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let identities = HashedIdentities::new(&refs, b"secret");
+    let user = &identities.users()[2];
+    assert_eq!(
+        user.name(),
+        hash(b"secret", &refs.users()[2].id().to_string())
+    );
+    assert_ne!(user.id(), refs.users()[2].id());
+    assert_eq!(identities.users()[0].name(), "");
+    assert_eq!(identities.computers()[0], "");
+    let by_name = HashedIdentities::with_key(&refs, b"secret", UserKey::Name);
+    assert_eq!(
+        by_name.users()[2].name(),
+        hash(b"secret", "Андрей Кудрявцев")
+    );
+
+    let empty = HashedIdentities::new(&References::default(), b"secret");
+    let mut writer = csv::Writer::new(Vec::new(), &refs)
+        .columns(&[Column::User, Column::Computer])
+        .header(csv::Header::None)
+        .hash_identities(&identities);
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        assert_eq!(empty.user(&event), "");
+        assert_eq!(empty.computer(&event), "");
+        writer.write(&event).unwrap();
+    })
+    .unwrap();
+    let out = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    assert!(!out.contains("Андрей Кудрявцев"));
+    assert!(!out.contains("computer1"));
+    let line = format!("{},{}", user.name(), identities.computers()[1]);
+    assert!(out.lines().any(|x| x == line));
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_parquet() {
+    use event_log_parser::export::parquet::{Writer, WriterOptions};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let path = std::env::temp_dir().join("event-log-parser-test.parquet");
+    let file = std::fs::File::create(&path).unwrap();
+    let options = WriterOptions::new().batch_size(100).row_group_size(500);
+    let mut writer = Writer::new(file, &refs, options).unwrap();
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        writer.write(&event).unwrap();
+    })
+    .unwrap();
+    writer.close().unwrap();
+
+    let file = std::fs::File::open(&path).unwrap();
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+    assert_eq!(builder.metadata().num_row_groups(), 3);
+    let rows: usize = builder
+        .build()
+        .unwrap()
+        .map(|batch| batch.unwrap().num_rows())
+        .sum();
+    assert_eq!(rows, 1274);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "gelf")]
+#[test]
+fn test_gelf() {
+    use event_log_parser::export::gelf;
+    use std::{
+        io::Read,
+        net::{TcpListener, UdpSocket},
+    };
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let mut writer = gelf::Writer::udp(socket.local_addr().unwrap(), &refs)
+        .unwrap()
+        .host("server1");
+    events::parse_until("../test-log/20221212000000.lgp", &mut |event| {
+        writer.write(&event).unwrap();
+        std::ops::ControlFlow::Break(())
+    })
+    .unwrap();
+    let mut buffer = [0u8; 8192];
+    let len = socket.recv(&mut buffer).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&buffer[..len]).unwrap();
+    assert_eq!(json["version"], "1.1");
+    assert_eq!(json["host"], "server1");
+    assert_eq!(json["short_message"], "_$Session$_.Authentication");
+    assert_eq!(json["level"], 6);
+    assert_eq!(json["_computer"], "computer1");
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let receiver = thread::spawn(move || {
+        let mut out = Vec::new();
+        listener.accept().unwrap().0.read_to_end(&mut out).unwrap();
+        out
+    });
+    let mut writer = gelf::Writer::tcp(addr, &refs).unwrap();
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        writer.write(&event).unwrap();
+    })
+    .unwrap();
+    drop(writer);
+    let out = receiver.join().unwrap();
+    let messages: Vec<_> = out.split(|ch| *ch == 0).filter(|x| !x.is_empty()).collect();
+    assert_eq!(messages.len(), 1274);
+    let json: serde_json::Value = serde_json::from_slice(messages[11]).unwrap();
+    assert_eq!(json["_user"], "Андрей Кудрявцев");
+}
+
+#[cfg(feature = "loki")]
+#[test]
+fn test_loki() {
+    use event_log_parser::export::loki;
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+    };
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    // Первый запрос отклоняется с 429, второй принимается
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let mut bodies = Vec::new();
+        for status in ["429 Too Many Requests", "204 No Content"] {
+            let mut stream = BufReader::new(listener.accept().unwrap().0);
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                stream.read_line(&mut line).unwrap();
+                if let Some((name, value)) = line.trim_end().split_once(": ") {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.parse().unwrap();
+                    }
+                } else if line.trim_end().is_empty() {
+                    break;
+                }
+            }
+            let mut body = vec![0; length];
+            stream.read_exact(&mut body).unwrap();
+            bodies.push(body);
+            let response =
+                format!("HTTP/1.1 {status}\r\nRetry-After: 0\r\nContent-Length: 0\r\n\r\n");
+            stream.get_mut().write_all(response.as_bytes()).unwrap();
+        }
+        bodies
+    });
+
+    let mut writer = loki::Writer::new(format!("http://{addr}"), &refs)
+        .infobase("test")
+        .batch_size(10_000);
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        writer.write(&event).unwrap();
+    })
+    .unwrap();
+    writer.flush().unwrap();
+
+    let bodies = server.join().unwrap();
+    assert_eq!(bodies[0], bodies[1]);
+    let json: serde_json::Value = serde_json::from_slice(&bodies[1]).unwrap();
+    let streams = json["streams"].as_array().unwrap();
+    let mut total = 0;
+    for stream in streams {
+        assert_eq!(stream["stream"]["infobase"], "test");
+        let values = stream["values"].as_array().unwrap();
+        let timestamps: Vec<i64> = values
+            .iter()
+            .map(|x| x[0].as_str().unwrap().parse().unwrap())
+            .collect();
+        assert!(timestamps.windows(2).all(|x| x[0] <= x[1]));
+        total += values.len();
+    }
+    assert_eq!(total, 1274);
+}
+
+#[cfg(feature = "kafka")]
+#[test]
+fn test_kafka_delivery_failure() {
+    use event_log_parser::export::kafka::{Key, Producer};
+    use rdkafka::ClientConfig;
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let mut config = ClientConfig::new();
+    config
+        .set("bootstrap.servers", "127.0.0.1:1")
+        .set("message.timeout.ms", "500");
+    let mut producer = Producer::from_config(&config, "events", &refs)
+        .unwrap()
+        .key(Key::User);
+    let mut sent = 0;
+    events::parse_until("../test-log/20221212000000.lgp", &mut |event| {
+        producer.send(&event).unwrap();
+        sent += 1;
+        if sent == 10 {
+            std::ops::ControlFlow::Break(())
+        } else {
+            std::ops::ControlFlow::Continue(())
+        }
+    })
+    .unwrap();
+    producer.flush(std::time::Duration::from_secs(10)).unwrap();
+
+    let delivery = producer.delivery();
+    assert_eq!(delivery.delivered(), 0);
+    assert_eq!(delivery.failed(), 10);
+    assert!(delivery.last_error().is_some());
+}
+
+#[test]
+fn test_log_reader() {
+    let reader = LogReader::open("../test-log").unwrap();
+    assert_eq!(reader.format(), LogFormat::Lgf);
+    assert_eq!(reader.references().computers()[1], "computer1");
+
+    let mut total_events = 0;
+    reader.parse(&mut |_| total_events += 1).unwrap();
+    assert_eq!(total_events, 1274);
+}
+
+#[test]
+fn test_log_directory() {
+    let dir = LogDirectory::open("../test-log").unwrap();
+    assert_eq!(dir.files().len(), 1);
+    assert_eq!(
+        dir.files()[0].start(),
+        NaiveDate::from_ymd_opt(2022, 12, 12)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+    );
+
+    let mut total_events = 0;
+    dir.events(&mut |_| total_events += 1).unwrap();
+    assert_eq!(total_events, 1274);
+
+    let from = NaiveDate::from_ymd_opt(2022, 12, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    let to = NaiveDate::from_ymd_opt(2022, 12, 11)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    let mut total_events = 0;
+    dir.events_between(from, to, &mut |_| total_events += 1)
+        .unwrap();
+    assert_eq!(total_events, 0);
+}
+
+#[test]
+fn test_merge_files() {
+    let file = "../test-log/20221212000000.lgp";
+
+    let mut total_events = 0;
+    let mut last_date = None;
+    merge::merge_files(&[file, file], &mut |path, event| {
+        assert_eq!(path.to_str(), Some(file));
+        assert!(last_date <= Some(event.date()));
+        last_date = Some(event.date());
+        total_events += 1;
+    })
+    .unwrap();
+
+    assert_eq!(total_events, 1274 * 2);
+}
+
+#[test]
+fn test_merge_truncated() {
+    let data = std::fs::read("../test-log/20221212000000.lgp").unwrap();
+    let truncated = &data[..data.len() - 20];
+
+    let mut counts = [0; 2];
+    merge::merge(vec![&data[..], truncated], &mut |i, _| counts[i] += 1).unwrap();
+    assert_eq!(counts, [1274, 1273]);
+}
+
+#[test]
+fn test_parse_from_offset() {
+    let file = "../test-log/20221212000000.lgp";
+
+    let mut offsets = Vec::new();
+    events::parse(file, &mut |event| {
+        offsets.push((event.offset(), event.end_offset()))
+    })
+    .unwrap();
+    assert_eq!(offsets.len(), 1274);
+
+    let buffer = std::fs::read(file).unwrap();
+    let (offset, end_offset) = offsets[100];
+    assert_eq!(buffer[offset as usize], b'{');
+    assert_eq!(buffer[end_offset as usize - 1], b'}');
+
+    let mut resumed = Vec::new();
+    events::parse_from_offset(file, end_offset, &mut |event| {
+        resumed.push((event.offset(), event.end_offset()));
+        ControlFlow::Continue(())
+    })
+    .unwrap();
+    assert_eq!(resumed, offsets[101..]);
+}
+
+#[test]
+fn test_event_filter() {
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let mut expected = Vec::new();
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        let resolved = event.resolve(&refs);
+        if resolved.user_name() == "Андрей Кудрявцев"
+            && resolved.event_name() == "_$Data$_.Update"
+            && *event.log_level() == EventLogLevel::Information
+        {
+            expected.push(event.offset());
+        }
+    })
+    .unwrap();
+    assert!(!expected.is_empty());
+
+    let filter = EventFilter::new()
+        .users(["Андрей Кудрявцев"])
+        .events(["_$Data$_.Update", "unknown"])
+        .levels([EventLogLevel::Information])
+        .compile(&refs);
+    let mut options = ParseOptions::new().filter(filter);
+    let mut actual = Vec::new();
+    let summary = events::parse_with_options(
+        "../test-log/20221212000000.lgp",
+        &mut options,
+        &mut |event| {
+            actual.push(event.offset());
+            ControlFlow::Continue(())
+        },
+    )
+    .unwrap();
+    assert_eq!(actual, expected);
+    assert_eq!(summary.records(), expected.len());
+    assert_eq!(summary.skipped(), 0);
+
+    let filter = EventFilter::new().computers(["unknown"]).compile(&refs);
+    let mut options = ParseOptions::new().filter(filter);
+    let summary =
+        events::parse_with_options("../test-log/20221212000000.lgp", &mut options, &mut |_| {
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(summary.records(), 0);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_regex_filter() {
+    use event_log_parser::filter::Regex;
+
+    let regex = Regex::new("^Запуск процедуры").unwrap();
+    let mut expected = Vec::new();
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        if event.comment().starts_with("Запуск процедуры") {
+            expected.push(event.offset());
+        }
+    })
+    .unwrap();
+    assert_eq!(expected.len(), 26);
+
+    let filter = EventFilter::new()
+        .comment_regex(regex)
+        .compile(&References::default());
+    let mut options = ParseOptions::new().filter(filter);
+    let mut actual = Vec::new();
+    events::parse_with_options(
+        "../test-log/20221212000000.lgp",
+        &mut options,
+        &mut |event| {
+            actual.push(event.offset());
+            ControlFlow::Continue(())
+        },
+    )
+    .unwrap();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_stats() {
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let mut updates = 0;
+    let mut errors = 0;
+    let mut collector = Collector::new().top(3).bucket(chrono::Duration::hours(1));
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        if event.resolve(&refs).event_name() == "_$Data$_.Update" {
+            updates += 1;
+        }
+        if *event.log_level() == EventLogLevel::Error {
+            errors += 1;
+        }
+        collector.push(&event);
+    })
+    .unwrap();
+
+    let report = collector.report(&refs);
+    assert_eq!(report.total(), 1274);
+    let levels = report.levels();
+    assert_eq!(
+        levels.error() + levels.warning() + levels.information() + levels.note(),
+        1274
+    );
+    assert_eq!(levels.get(EventLogLevel::Error), errors);
+    let update = report
+        .events()
+        .iter()
+        .find(|x| x.name() == "_$Data$_.Update");
+    assert_eq!(update.unwrap().count(), updates);
+    assert!(report
+        .events()
+        .windows(2)
+        .all(|x| x[0].count() >= x[1].count()));
+    assert!(report.top_errors().len() <= 3);
+    let total: usize = report.histogram().iter().map(|x| x.count()).sum();
+    assert_eq!(total, 1274);
+    let first = report.histogram()[0].start();
+    assert_eq!(first.and_utc().timestamp() % 3600, 0);
+    assert!(first <= report.first().unwrap());
+}
+
+#[test]
+fn test_sessions() {
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let mut starts = 0;
+    let mut finishes = 0;
+    let mut records = Vec::new();
+    let mut tracker = SessionTracker::new(&refs);
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        match event.resolve(&refs).event_name() {
+            "_$Session$_.Start" => starts += 1,
+            "_$Session$_.Finish" => finishes += 1,
+            _ => {}
+        }
+        tracker.push(&event, &mut |record| records.push(record));
+    })
+    .unwrap();
+    tracker.flush(&mut |record| records.push(record));
+    assert_eq!(tracker.open_sessions(), 0);
+
+    assert!(starts > 0);
+    assert_eq!(records.len(), starts);
+    let finished: Vec<_> = records.iter().filter(|x| x.end().is_some()).collect();
+    assert!(!finished.is_empty() && finished.len() <= finishes);
+    for record in finished {
+        assert!(record.duration().unwrap() >= chrono::Duration::zero());
+        assert!(record.event_count() >= 2);
+        assert!(!record.application(&refs).is_empty());
+    }
+}
+
+#[test]
+fn test_transactions() {
+    let mut total = 0;
+    let mut transactions = Transactions::new();
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        if event.transaction().is_some() {
+            total += 1;
+        }
+        transactions.push(&event);
+    })
+    .unwrap();
+
+    assert!(!transactions.is_empty());
+    let groups = transactions.into_groups();
+    assert_eq!(groups.iter().map(|x| x.event_count()).sum::<usize>(), total);
+    assert!(groups.windows(2).all(|x| x[0].first() <= x[1].first()));
+    for group in &groups {
+        assert!(group.first() >= group.info().start());
+        assert!(group.duration() >= chrono::Duration::zero());
+        assert!(!group.metadata_ids().contains(&0));
+    }
+    assert!(groups.iter().any(|x| x.metadata_ids().len() > 1));
+}
+
+#[test]
+fn test_known_events() {
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let mut authentications = 0;
+    let mut jobs = 0;
+    let mut unknown = 0;
+    events::parse(
+        "../test-log/20221212000000.lgp",
+        &mut |event| match KnownEvent::decode(&event, &refs) {
+            Some(KnownEvent::Authentication(credentials)) => {
+                assert!(credentials.os_user().is_some());
+                authentications += 1;
+            }
+            Some(KnownEvent::Job { name, .. }) => {
+                assert!(name.is_some());
+                jobs += 1;
+            }
+            Some(_) => {}
+            None => {
+                assert!(!event.event(&refs).starts_with("_$"));
+                unknown += 1;
+            }
+        },
+    )
+    .unwrap();
+    assert!(authentications > 0 && jobs > 0 && unknown > 0);
+}
+
+#[test]
+fn test_audit() {
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let mut failures = 0;
+    let mut auditor = Auditor::new(&refs)
+        .failed_authentication(1, chrono::Duration::minutes(1))
+        .external_events(["_$Job$_.Start"]);
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        if event.event(&refs) == "_$Session$_.AuthenticationError" {
+            failures += 1;
+        }
+        auditor.push(&event);
+    })
+    .unwrap();
+    let report = auditor.finish();
+
+    let attempts: usize = report
+        .failed_authentication_bursts()
+        .map(|x| match x {
+            Finding::FailedAuthenticationBurst {
+                user,
+                start,
+                end,
+                attempts,
+                ..
+            } => {
+                assert_eq!(user, "COMPUTER1\\user1");
+                assert!(start <= end);
+                attempts
+            }
+            _ => unreachable!(),
+        })
+        .sum();
+    assert!(failures > 0);
+    assert_eq!(attempts, failures);
+    assert!(report.external_processors().count() > 0);
+    assert!(report
+        .findings()
+        .windows(2)
+        .all(|x| x[0].date() <= x[1].date()));
+}
+
+#[test]
+fn test_parse_with_report() {
+    let file = "../test-log/20221212000000.lgp";
+
+    let mut offsets = Vec::new();
+    events::parse(file, &mut |event| {
+        offsets.push((event.offset(), event.end_offset()))
+    })
+    .unwrap();
+
+    // Испортить статус транзакции одной записи и оборвать последнюю
+    let mut buffer = std::fs::read(file).unwrap();
+    buffer[offsets[100].0 as usize + 16] = b'X';
+    buffer.truncate(buffer.len() - 10);
+    let path = std::env::temp_dir().join("event-log-parser-test-report.lgp");
+    std::fs::write(&path, &buffer).unwrap();
+
+    let mut parsed = Vec::new();
+    let summary = events::parse_with_report(&path, &mut |event| {
+        parsed.push((event.offset(), event.end_offset()))
+    })
+    .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut expected = offsets[..1273].to_vec();
+    expected.remove(100);
+    assert_eq!(parsed, expected);
+    assert_eq!(summary.records(), 1272);
+    assert_eq!(summary.skipped(), 2);
+    assert_eq!(summary.bytes(), buffer.len() as u64);
+    assert_eq!(summary.last_offset(), offsets[1272].1);
+}
+
+#[test]
+fn test_on_invalid() {
+    let file = "../test-log/20221212000000.lgp";
+
+    let mut offsets = Vec::new();
+    events::parse(file, &mut |event| {
+        offsets.push((event.offset(), event.end_offset()))
+    })
+    .unwrap();
+
+    let mut buffer = std::fs::read(file).unwrap();
+    buffer[offsets[10].0 as usize + 16] = b'X';
+    buffer.truncate(buffer.len() - 10);
+    let path = std::env::temp_dir().join("event-log-parser-test-invalid.lgp");
+    std::fs::write(&path, &buffer).unwrap();
+
+    let mut invalid = Vec::new();
+    let mut options = ParseOptions::new().on_invalid(|record, offset, error| {
+        invalid.push((record.to_vec(), offset, *error));
+    });
+    let summary =
+        events::parse_with_options(&path, &mut options, &mut |_| ControlFlow::Continue(()))
+            .unwrap();
+    drop(options);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(summary.skipped(), 2);
+    assert_eq!(invalid.len(), 2);
+
+    let (start, end) = offsets[10];
+    assert_eq!(invalid[0].1, start);
+    assert_eq!(invalid[0].0, buffer[start as usize..end as usize]);
+    assert_eq!(
+        invalid[0].2,
+        ParseError::InvalidFormat {
+            offset: start + 16,
+            field: Field::TransactionStatus,
+            expected: "R, N, U or C"
+        }
+    );
+    assert_eq!(
+        invalid[0].2.to_string(),
+        format!(
+            "invalid transaction status at offset {}: expected R, N, U or C",
+            start + 16
+        )
+    );
+
+    let start = offsets[1273].0;
+    assert_eq!(invalid[1].1, start);
+    assert_eq!(invalid[1].0, buffer[start as usize..buffer.len() - 1]);
+    assert_eq!(invalid[1].2, ParseError::Incomplete);
+}
+
+#[test]
+fn test_line_endings() {
+    let file = "../test-log/20221212000000.lgp";
+
+    let mut expected = Vec::new();
+    events::parse(file, &mut |event| {
+        expected.push((
+            event.date(),
+            event.user_id(),
+            event.comment().replace("\r\n", "\n"),
+        ))
+    })
+    .unwrap();
+
+    let buffer = std::fs::read(file).unwrap();
+    let lf = String::from_utf8(buffer).unwrap().replace("\r\n", "\n");
+    let mixed = lf
+        .split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            if i % 2 == 0 {
+                format!("{line}\r")
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    for (name, content) in [("lf", lf), ("mixed", mixed)] {
+        let path = std::env::temp_dir().join(format!("event-log-parser-test-{name}.lgp"));
+        std::fs::write(&path, content).unwrap();
+
+        let mut events = Vec::new();
+        let summary = events::parse_with_report(&path, &mut |event| {
+            events.push((
+                event.date(),
+                event.user_id(),
+                event.comment().replace("\r\n", "\n"),
+            ))
+        })
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(summary.skipped(), 0, "{name}");
+        assert_eq!(events, expected, "{name}");
+    }
+
+    let lgf = std::fs::read("../test-log/1Cv8.lgf").unwrap();
+    let lgf = String::from_utf8(lgf).unwrap().replace("\r\n", "\n");
+    let path = std::env::temp_dir().join("event-log-parser-test-lf.lgf");
+    std::fs::write(&path, lgf).unwrap();
+    let mut refs = References::default();
+    refs.parse(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(refs.users()[2].name(), "Андрей Кудрявцев");
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+fn test_windows_1251() {
+    let mut record = b"{20221217221504,N,\r\n{0,0},1,1,1,1,1,I,\"".to_vec();
+    record.extend_from_slice(b"\xce\xf8\xe8\xe1\xea\xe0");
+    record.extend_from_slice(b"\",0,\r\n{\"S\",\"");
+    record.extend_from_slice(b"\xc4\xe0\xed\xed\xfb\xe5");
+    record.extend_from_slice(b"\"},\"\",0,0,0,2,0,\r\n{0}\r\n}");
+    let path = std::env::temp_dir().join("event-log-parser-test-1251.lgp");
+    std::fs::write(&path, &record).unwrap();
+
+    let mut events = Vec::new();
+    events::parse(&path, &mut |event| {
+        events.push((event.comment().to_string(), event.data().to_string()))
+    })
+    .unwrap();
+
+    let mut options = ParseOptions::new().encoding(event_log_parser::encoding::UTF_8);
+    let mut lossy = Vec::new();
+    events::parse_with_options(&path, &mut options, &mut |event| {
+        lossy.push(event.comment().to_string());
+        ControlFlow::Continue(())
+    })
+    .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        events,
+        vec![("Ошибка".to_string(), "{\"S\",\"Данные\"}".to_string())]
+    );
+    assert_eq!(lossy.len(), 1);
+    assert_ne!(lossy[0], "Ошибка");
+}
+
+#[cfg(feature = "config")]
+#[test]
+fn test_configuration() {
+    use event_log_parser::{config::Configuration, references::Metadata};
+
+    let register = r#"<?xml version="1.0" encoding="UTF-8"?>
+<MetaDataObject xmlns="http://v8.1c.ru/8.3/MDClasses" xmlns:v8="http://v8.1c.ru/8.1/data/core" version="2.17">
+	<InformationRegister uuid="fc67b510-3fb4-4305-92d2-c252bc718f03">
+		<Properties>
+			<Name>СведенияОПользователях</Name>
+			<Synonym>
+				<v8:item>
+					<v8:lang>en</v8:lang>
+					<v8:content>User details</v8:content>
+				</v8:item>
+				<v8:item>
+					<v8:lang>ru</v8:lang>
+					<v8:content>Сведения о пользователях</v8:content>
+				</v8:item>
+			</Synonym>
+		</Properties>
+	</InformationRegister>
+</MetaDataObject>"#;
+    let document = r#"<?xml version="1.0" encoding="UTF-8"?>
+<MetaDataObject xmlns="http://v8.1c.ru/8.3/MDClasses" xmlns:v8="http://v8.1c.ru/8.1/data/core" version="2.17">
+	<Document uuid="8f1c2d3e-0000-4000-8000-000000000001">
+		<Properties>
+			<Name>ЗаказКлиента</Name>
+			<Synonym>
+				<v8:item>
+					<v8:lang>ru</v8:lang>
+					<v8:content>Заказ &quot;клиента&quot;</v8:content>
+				</v8:item>
+			</Synonym>
+		</Properties>
+		<ChildObjects>
+			<Attribute uuid="8f1c2d3e-0000-4000-8000-000000000002">
+				<Properties>
+					<Name>Контрагент</Name>
+				</Properties>
+			</Attribute>
+		</ChildObjects>
+	</Document>
+</MetaDataObject>"#;
+    let dir = std::env::temp_dir().join("event-log-parser-test-config");
+    std::fs::create_dir_all(dir.join("InformationRegisters")).unwrap();
+    std::fs::create_dir_all(dir.join("Documents")).unwrap();
+    std::fs::write(
+        dir.join("InformationRegisters/СведенияОПользователях.xml"),
+        register,
+    )
+    .unwrap();
+    std::fs::write(dir.join("Documents/ЗаказКлиента.xml"), document).unwrap();
+    std::fs::write(dir.join("Configuration.xml"), "<Configuration/>").unwrap();
+    let config = Configuration::load(&dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(config.objects().len(), 2);
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    let document_id = uuid::Uuid::from_u128(0x8f1c_2d3e_0000_4000_8000_0000_0000_0001);
+    let bare = refs.push_metadata(Metadata::new(document_id, ""));
+    let by_name = refs.push_metadata(Metadata::new(uuid::Uuid::nil(), "Документ.ЗаказКлиента"));
+    assert_eq!(config.enrich(&mut refs), 3);
+
+    let register = &refs.metadata()[3];
+    assert_eq!(register.kind(), "InformationRegister");
+    assert_eq!(register.synonym(), "Сведения о пользователях");
+    assert_eq!(register.presentation(), "Сведения о пользователях");
+    assert_eq!(
+        config.object(register).unwrap().full_name(),
+        "РегистрСведений.СведенияОПользователях"
+    );
+    assert_eq!(refs.metadata()[bare].name(), "Документ.ЗаказКлиента");
+    assert_eq!(refs.metadata()[by_name].synonym(), "Заказ \"клиента\"");
+    assert_eq!(refs.metadata()[1].kind(), "");
+    assert_eq!(refs.metadata()[1].presentation(), refs.metadata()[1].name());
+}
+
+#[cfg(feature = "config")]
+#[test]
+fn test_configuration_save_load() {
+    use event_log_parser::config::Configuration;
+
+    let register = r#"<?xml version="1.0" encoding="UTF-8"?>
+<MetaDataObject xmlns="http://v8.1c.ru/8.3/MDClasses" xmlns:v8="http://v8.1c.ru/8.1/data/core" version="2.17">
+	<InformationRegister uuid="fc67b510-3fb4-4305-92d2-c252bc718f03">
+		<Properties>
+			<Name>СведенияОПользователях</Name>
+			<Synonym>
+				<v8:item>
+					<v8:lang>ru</v8:lang>
+					<v8:content>Сведения о пользователях</v8:content>
+				</v8:item>
+			</Synonym>
+		</Properties>
+	</InformationRegister>
+</MetaDataObject>"#;
+    let mut config = Configuration::default();
+    assert!(config.add_object(register.as_bytes()).unwrap());
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    assert_eq!(config.enrich(&mut refs), 1);
+
+    let path = std::env::temp_dir().join("event-log-parser-test-config-refs.bin");
+    refs.save(&path).unwrap();
+    let loaded = References::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let metadata = &loaded.metadata()[3];
+    assert_eq!(metadata.kind(), "InformationRegister");
+    assert_eq!(metadata.synonym(), "Сведения о пользователях");
+    assert_eq!(metadata.presentation(), "Сведения о пользователях");
+    assert_eq!(loaded.metadata()[1].kind(), "");
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn test_metrics() {
+    use event_log_parser::metrics::{self, Metrics};
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+        sync::Arc,
+    };
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let metrics = Arc::new(Metrics::new());
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        metrics.record(&event.resolve(&refs));
+    })
+    .unwrap();
+    let text = metrics.render();
+    assert!(text.contains("# TYPE eventlog_events_total counter\n"));
+    assert!(text.contains("eventlog_events_total{level=\"information\"} "));
+    assert!(text.contains("eventlog_events_total{level=\"error\"} 0\n"));
+    assert!(!text.contains("eventlog_sessions_started_total 0\n"));
+
+    let addr = metrics::serve("127.0.0.1:0", metrics.clone()).unwrap();
+    let get = |path: &str| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    };
+    let response = get("/metrics");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.ends_with(&text));
+    assert!(get("/").starts_with("HTTP/1.1 404"));
+}
+
+#[cfg(feature = "otlp")]
+#[test]
+fn test_otlp() {
+    use event_log_parser::export::otlp;
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+    };
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let mut stream = BufReader::new(listener.accept().unwrap().0);
+        let mut path = String::new();
+        stream.read_line(&mut path).unwrap();
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            stream.read_line(&mut line).unwrap();
+            if let Some((name, value)) = line.trim_end().split_once(": ") {
+                if name.eq_ignore_ascii_case("content-length") {
+                    length = value.parse().unwrap();
+                }
+            } else if line.trim_end().is_empty() {
+                break;
+            }
+        }
+        let mut body = vec![0; length];
+        stream.read_exact(&mut body).unwrap();
+        let response = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n";
+        stream.get_mut().write_all(response.as_bytes()).unwrap();
+        (path, body)
+    });
+
+    let mut writer = otlp::Writer::new(format!("http://{addr}"), &refs)
+        .infobase("test")
+        .server("server1")
+        .batch_size(10_000);
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        writer.write(&event).unwrap();
+    })
+    .unwrap();
+    writer.flush().unwrap();
+
+    let (path, body) = server.join().unwrap();
+    assert!(path.starts_with("POST /v1/logs "));
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let logs = &json["resourceLogs"][0];
+    let resource = logs["resource"]["attributes"].as_array().unwrap();
+    assert!(resource.contains(&serde_json::json!({
+        "key": "infobase.name",
+        "value": { "stringValue": "test" }
+    })));
+    assert!(resource.contains(&serde_json::json!({
+        "key": "host.name",
+        "value": { "stringValue": "server1" }
+    })));
+    let records = logs["scopeLogs"][0]["logRecords"].as_array().unwrap();
+    assert_eq!(records.len(), 1274);
+    assert_eq!(records[0]["severityNumber"], 9);
+    assert_eq!(records[0]["severityText"], "INFO");
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_sqlite() {
+    use event_log_parser::export::sqlite;
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let connection = rusqlite::Connection::open_in_memory().unwrap();
+    let mut writer = sqlite::Writer::from_connection(connection, &refs).unwrap();
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        writer.write(&event).unwrap();
+    })
+    .unwrap();
+    let connection = writer.finish().unwrap();
+
+    let count: usize = connection
+        .query_row("SELECT count(*) FROM event_log", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(count, 1274);
+    let (date, event, computer): (String, String, String) = connection
+        .query_row(
+            "SELECT l.date, e.name, c.name FROM event_log l \
+             JOIN events e ON e.id = l.event_id \
+             JOIN computers c ON c.id = l.computer_id \
+             ORDER BY l.id LIMIT 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .unwrap();
+    assert_eq!(date, "2022-12-17 22:15:04");
+    assert_eq!(event, "_$Session$_.Authentication");
+    assert_eq!(computer, "computer1");
+    let plan: String = connection
+        .query_row(
+            "EXPLAIN QUERY PLAN SELECT * FROM event_log WHERE user_id = 1",
+            [],
+            |row| row.get(3),
+        )
+        .unwrap();
+    assert!(plan.contains("event_log_user"));
+}
+
+#[cfg(all(feature = "hashing", feature = "sqlite"))]
+#[test]
+fn test_sqlite_hash_identities() {
+    use event_log_parser::{export::sqlite, hashing::HashedIdentities};
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let identities = HashedIdentities::new(&refs, b"secret");
+    let connection = rusqlite::Connection::open_in_memory().unwrap();
+    let writer = sqlite::Writer::from_connection(connection, &refs)
+        .unwrap()
+        .hash_identities(&identities)
+        .unwrap();
+    let connection = writer.finish().unwrap();
+
+    let (name, uuid): (String, String) = connection
+        .query_row("SELECT name, uuid FROM users WHERE id = 2", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .unwrap();
+    assert_eq!(name, identities.users()[2].name());
+    assert_eq!(uuid, identities.users()[2].id().to_string());
+    let computer: String = connection
+        .query_row("SELECT name FROM computers WHERE id = 1", [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+    assert_eq!(computer, identities.computers()[1]);
+}
+
+#[cfg(feature = "postgres")]
+#[test]
+fn test_postgres_copy() {
+    use event_log_parser::export::{
+        postgres::{self, Format},
+        Column,
+    };
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let columns = [Column::Date, Column::Event, Column::Session];
+    assert_eq!(
+        postgres::create_table("log", &columns),
+        "CREATE TABLE log (date timestamp NOT NULL, event text NOT NULL, session bigint NOT NULL)"
+    );
+    assert_eq!(
+        postgres::copy_statement("log", &columns, Format::Binary),
+        "COPY log (date, event, session) FROM STDIN (FORMAT binary)"
+    );
+
+    let mut writer = postgres::Writer::new(Vec::new(), &refs).columns(&columns);
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        writer.write(&event).unwrap();
+    })
+    .unwrap();
+    let text = String::from_utf8(writer.finish().unwrap()).unwrap();
+    assert_eq!(text.lines().count(), 1274);
+    let first: Vec<_> = text.lines().next().unwrap().split('\t').collect();
+    assert_eq!(first[0], "2022-12-17 22:15:04");
+    assert_eq!(first[1], "_$Session$_.Authentication");
+
+    let mut writer = postgres::Writer::new(Vec::new(), &refs)
+        .columns(&columns)
+        .format(Format::Binary);
+    events::parse_until("../test-log/20221212000000.lgp", &mut |event| {
+        writer.write(&event).unwrap();
+        std::ops::ControlFlow::Break(())
+    })
+    .unwrap();
+    let binary = writer.finish().unwrap();
+    assert!(binary.starts_with(b"PGCOPY\n\xff\r\n\0"));
+    assert_eq!(binary[19..21], [0, 3]);
+    assert!(binary.ends_with(&[0xff, 0xff]));
+}
+
+#[cfg(feature = "xml")]
+#[test]
+fn test_xml() {
+    use event_log_parser::xml::XmlReader;
+
+    let reader = XmlReader::open("../test-log/export.xml").unwrap();
+    let refs = reader.references();
+    assert_eq!(refs.users().len(), 2);
+    assert_eq!(refs.users()[1].name(), "Администратор");
+    assert_eq!(refs.events().len(), 3);
+    assert_eq!(refs.ports(), [0, 1560]);
+
+    let mut count = 0;
+    reader
+        .parse(&mut |event| {
+            count += 1;
+            let event = event.resolve(refs);
+            if count == 2 {
+                assert_eq!(*event.log_level(), EventLogLevel::Error);
+                assert_eq!(event.event_name(), "_$Data$_.Update");
+                assert_eq!(event.metadata_name(), "Справочник.Номенклатура");
+                assert_eq!(event.comment(), "Ошибка <записи> \"товара\"\nвторая строка");
+                assert_eq!(event.data(), r#"{"S","Товар ""1"""}"#);
+                assert_eq!(event.sync_port(), 1561);
+                let transaction = event.event().transaction().unwrap();
+                assert_eq!(transaction.number(), 4660);
+                assert_eq!(transaction.start().to_string(), "2022-12-17 22:16:09");
+            } else {
+                assert_eq!(event.data(), r#"{"U"}"#);
+                assert_eq!(event.event().transaction(), None);
+            }
+        })
+        .unwrap();
+    assert_eq!(count, 2);
+}
+
+#[cfg(feature = "xml")]
+#[test]
+fn test_xml_export() {
+    use event_log_parser::{export::xml, xml::XmlReader};
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+
+    let fields = |event: &events::EventResolved| {
+        (
+            event.date(),
+            event.event_name().to_string(),
+            event.user_name().to_string(),
+            event.comment().to_string(),
+            event.event().transaction(),
+            event.session(),
+        )
+    };
+
+    let mut expected = Vec::new();
+    let mut writer = xml::Writer::new(Vec::new(), &refs);
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        if event.user_id() == 1 {
+            writer.write(&event).unwrap();
+            expected.push(fields(&event.resolve(&refs)));
+        }
+    })
+    .unwrap();
+    let out = writer.finish().unwrap();
+    assert!(!expected.is_empty());
+
+    let path = std::env::temp_dir().join("event-log-parser-export.xml");
+    std::fs::write(&path, out).unwrap();
+    let reader = XmlReader::open(&path).unwrap();
+    let mut actual = Vec::new();
+    reader
+        .parse(&mut |event| actual.push(fields(&event.resolve(reader.references()))))
+        .unwrap();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_write_round_trip() {
+    use event_log_parser::references;
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    let dir = std::env::temp_dir().join("event-log-parser-round-trip");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let lgf = references::Writer::new(Vec::new()).write(&refs).unwrap();
+    std::fs::write(dir.join("1Cv8.lgf"), lgf).unwrap();
+    let mut copy = References::default();
+    copy.parse(dir.join("1Cv8.lgf")).unwrap();
+    assert_eq!(copy.header(), refs.header());
+    let users = |refs: &References| {
+        let users = refs.users().iter();
+        users
+            .map(|x| (x.id(), x.name().to_string()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(users(&copy), users(&refs));
+    let metadata = |refs: &References| {
+        let metadata = refs.metadata().iter();
+        metadata
+            .map(|x| (x.id(), x.name().to_string()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(metadata(&copy), metadata(&refs));
+    assert_eq!(copy.computers(), refs.computers());
+    assert_eq!(copy.applications(), refs.applications());
+    assert_eq!(copy.events(), refs.events());
+    assert_eq!(copy.worker_servers(), refs.worker_servers());
+    assert_eq!(copy.ports(), refs.ports());
+    assert_eq!(copy.sync_ports(), refs.sync_ports());
+    assert_eq!(copy.record11(), refs.record11());
+    assert_eq!(copy.record12(), refs.record12());
+    assert_eq!(copy.record13(), refs.record13());
+
+    let mut expected = Vec::new();
+    let mut writer = events::Writer::new(Vec::new());
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        let event = event.to_owned();
+        writer.write(&event).unwrap();
+        expected.push(event);
+    })
+    .unwrap();
+    let written = writer.finish().unwrap();
+    let original = std::fs::read("../test-log/20221212000000.lgp").unwrap();
+    let body = |x: &[u8]| x[x.windows(4).position(|x| x == b"\r\n\r\n").unwrap()..].to_vec();
+    assert_eq!(body(&written), body(&original));
+    std::fs::write(dir.join("20221212000000.lgp"), written).unwrap();
+
+    let mut actual = Vec::new();
+    events::parse(dir.join("20221212000000.lgp"), &mut |event| {
+        actual.push(event.to_owned());
+    })
+    .unwrap();
+    assert_eq!(actual.len(), expected.len());
+    for (actual, expected) in actual.iter().zip(&expected) {
+        assert_eq!(actual.date(), expected.date());
+        assert_eq!(actual.transaction_status(), expected.transaction_status());
+        assert_eq!(actual.transaction_data(), expected.transaction_data());
+        assert_eq!(actual.user_id(), expected.user_id());
+        assert_eq!(actual.event_id(), expected.event_id());
+        assert_eq!(actual.log_level(), expected.log_level());
+        assert_eq!(actual.comment(), expected.comment());
+        assert_eq!(actual.metadata_id(), expected.metadata_id());
+        assert_eq!(actual.data(), expected.data());
+        assert_eq!(actual.data_presentation(), expected.data_presentation());
+        assert_eq!(actual.session(), expected.session());
+        assert_eq!(actual.unknown2(), expected.unknown2());
+    }
+}
+
+#[test]
+fn test_redact() {
+    use event_log_parser::{redact::Redactor, references};
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    let redaction = Redactor::new()
+        .comment_contains("1СПАРК Риски")
+        .compile(&refs);
+    let redacted = redaction.references();
+    assert_eq!(redacted.users()[2].name(), "user2");
+    assert_eq!(redacted.users()[2].id(), refs.users()[2].id());
+    assert_eq!(redacted.user_id_by_name("user2"), Some(2));
+    assert_eq!(redacted.user_id_by_name(refs.users()[2].name()), None);
+
+    let dir = std::env::temp_dir().join("event-log-parser-redact");
+    std::fs::create_dir_all(&dir).unwrap();
+    let lgf = references::Writer::new(Vec::new()).write(redacted).unwrap();
+    std::fs::write(dir.join("1Cv8.lgf"), lgf).unwrap();
+    let mut writer = events::Writer::new(Vec::new());
+    redaction
+        .write_lgp("../test-log/20221212000000.lgp", &mut writer)
+        .unwrap();
+    std::fs::write(dir.join("20221212000000.lgp"), writer.finish().unwrap()).unwrap();
+
+    let mut copy = References::default();
+    copy.parse(dir.join("1Cv8.lgf")).unwrap();
+    let name = refs.users()[2].name().to_string();
+    let mut count = 0;
+    events::parse(dir.join("20221212000000.lgp"), &mut |event| {
+        count += 1;
+        let event = event.resolve(&copy);
+        assert!(!event.comment().contains("1СПАРК Риски"));
+        assert!(!event.comment().contains(&name));
+        assert!(!event.user_name().contains(&name));
+    })
+    .unwrap();
+    assert_eq!(count, 1274);
+
+    let mut comments = Vec::new();
+    redaction
+        .parse("../test-log/20221212000000.lgp", &mut |event| {
+            if event.event(&copy).starts_with("1СПАРК") {
+                comments.push(event.comment().to_string());
+            }
+        })
+        .unwrap();
+    assert!(comments[0]
+        .starts_with("Не удалось поставить контрагентов на мониторинг в сервисе [redacted:"));
+}
+
+#[cfg(feature = "lgd")]
+#[test]
+fn test_lgd_convert() {
+    use event_log_parser::lgd::{self, LgdReader};
+
+    let dir = std::env::temp_dir().join("event-log-parser-convert");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("1Cv8.lgd");
+    lgd::lgp_to_lgd("../test-log", &path).unwrap();
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    let reader = LgdReader::open(&path).unwrap();
+    let copy = reader.references().unwrap();
+    assert_eq!(copy.users()[2].name(), refs.users()[2].name());
+    assert_eq!(copy.users()[2].id(), refs.users()[2].id());
+    assert_eq!(copy.events(), refs.events());
+    assert_eq!(copy.computers(), refs.computers());
+
+    let mut expected = Vec::new();
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        expected.push(event.to_owned());
+    })
+    .unwrap();
+    let mut count = 0;
+    reader
+        .parse(&mut |event| {
+            let expected = &expected[count];
+            assert_eq!(event.date(), expected.date());
+            assert_eq!(event.transaction_data(), expected.transaction_data());
+            assert_eq!(event.user_id(), expected.user_id());
+            assert_eq!(event.event_id(), expected.event_id());
+            assert_eq!(event.comment(), expected.comment());
+            assert_eq!(event.data(), expected.data());
+            count += 1;
+        })
+        .unwrap();
+    assert_eq!(count, expected.len());
+
+    let lgp = dir.join("lgp");
+    std::fs::create_dir_all(&lgp).unwrap();
+    lgd::lgd_to_lgp(&path, &lgp).unwrap();
+    let mut copy = References::default();
+    copy.parse(lgp.join("1Cv8.lgf")).unwrap();
+    assert_eq!(copy.users()[2].name(), refs.users()[2].name());
+    assert_eq!(copy.metadata().len(), refs.metadata().len());
+    let mut actual = Vec::new();
+    for file in std::fs::read_dir(&lgp).unwrap() {
+        let file = file.unwrap().path();
+        if file.extension().is_some_and(|x| x == "lgp") {
+            events::parse(file, &mut |event| actual.push(event.to_owned())).unwrap();
+        }
+    }
+    actual.sort_by_key(|x| x.date());
+    assert_eq!(actual.len(), expected.len());
+    assert_eq!(actual[0].date(), expected[0].date());
+    assert_eq!(actual[0].comment(), expected[0].comment());
+}
+
+#[cfg(all(feature = "gzip", feature = "zstd"))]
+#[test]
+fn test_compressed_directory() {
+    use event_log_parser::archive;
+    use std::io::Write;
+
+    let dir = std::env::temp_dir().join("event-log-parser-compressed");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let lgf = std::fs::read("../test-log/1Cv8.lgf").unwrap();
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&lgf).unwrap();
+    std::fs::write(dir.join("1Cv8.lgf.gz"), encoder.finish().unwrap()).unwrap();
+    let lgp = std::fs::read("../test-log/20221212000000.lgp").unwrap();
+    let lgp = zstd::encode_all(lgp.as_slice(), 0).unwrap();
+    std::fs::write(dir.join("20221212000000.lgp.zst"), lgp).unwrap();
+
+    let mut count = 0;
+    events::parse_reader(
+        archive::open(dir.join("20221212000000.lgp.zst")).unwrap(),
+        &mut |_| count += 1,
+    )
+    .unwrap();
+    assert_eq!(count, 1274);
+
+    let log = LogDirectory::open(&dir).unwrap();
+    assert_eq!(log.files().len(), 1);
+    assert_eq!(
+        log.files()[0].start(),
+        NaiveDate::from_ymd_opt(2022, 12, 12)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+    );
+    assert!(log.references().users().len() > 2);
+    let mut count = 0;
+    log.events(&mut |_| count += 1).unwrap();
+    assert_eq!(count, 1274);
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn test_zip_directory() {
+    use std::io::Write;
+
+    let path = std::env::temp_dir().join("event-log-parser-test.zip");
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    let options = zip::write::SimpleFileOptions::default();
+    for name in ["1Cv8.lgf", "20221212000000.lgp"] {
+        zip.start_file(format!("1Cv8Log/{name}"), options).unwrap();
+        zip.write_all(&std::fs::read(format!("../test-log/{name}")).unwrap())
+            .unwrap();
+    }
+    zip.finish().unwrap();
+
+    let log = LogDirectory::open(&path).unwrap();
+    assert_eq!(log.files().len(), 1);
+    let mut count = 0;
+    log.events(&mut |_| count += 1).unwrap();
+    assert_eq!(count, 1274);
+
+    let from = NaiveDate::from_ymd_opt(2022, 12, 17)
+        .unwrap()
+        .and_hms_opt(22, 16, 0)
+        .unwrap();
+    let to = NaiveDate::from_ymd_opt(2022, 12, 17)
+        .unwrap()
+        .and_hms_opt(22, 17, 0)
+        .unwrap();
+    let mut count = 0;
+    log.events_between(from, to, &mut |event| {
+        assert!(event.date() >= from && event.date() < to);
+        count += 1;
+    })
+    .unwrap();
+    assert_eq!(count, 113);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_container() {
+    use event_log_parser::container;
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    let mut writer = container::Writer::new(Vec::new(), &refs)
+        .unwrap()
+        .chunk_size(100);
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        writer.write(&event).unwrap();
+    })
+    .unwrap();
+    let data = writer.finish().unwrap();
+    let raw = std::fs::metadata("../test-log/20221212000000.lgp")
+        .unwrap()
+        .len();
+    assert!((data.len() as u64) * 5 < raw);
+
+    let reader = container::Reader::new(std::io::Cursor::new(data)).unwrap();
+    assert_eq!(reader.chunks().len(), 13);
+    assert_eq!(reader.references().users().len(), refs.users().len());
+    assert_eq!(reader.references().events(), refs.events());
+    let mut count = 0;
+    reader.events(&mut |_| count += 1).unwrap();
+    assert_eq!(count, 1274);
+
+    let from = NaiveDate::from_ymd_opt(2022, 12, 17)
+        .unwrap()
+        .and_hms_opt(22, 16, 0)
+        .unwrap();
+    let to = NaiveDate::from_ymd_opt(2022, 12, 17)
+        .unwrap()
+        .and_hms_opt(22, 17, 0)
+        .unwrap();
+    let skipped = reader
+        .chunks()
+        .iter()
+        .filter(|chunk| !chunk.overlaps(from, to))
+        .count();
+    assert!(skipped > 0);
+    let mut count = 0;
+    reader
+        .events_between(from, to, &mut |event| {
+            assert!(event.date() >= from && event.date() <= to);
+            count += 1;
+        })
+        .unwrap();
+    assert_eq!(count, 113);
+}
+
+#[test]
+fn test_sidecar_index() {
+    use event_log_parser::index::{self, Index, Step};
+
+    let dir = std::env::temp_dir().join("event-log-parser-index");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("20221212000000.lgp");
+    std::fs::copy("../test-log/20221212000000.lgp", &path).unwrap();
+    let _ = std::fs::remove_file(index::sidecar(&path));
+
+    let from = NaiveDate::from_ymd_opt(2022, 12, 17)
+        .unwrap()
+        .and_hms_opt(22, 22, 0)
+        .unwrap();
+    let to = NaiveDate::from_ymd_opt(2022, 12, 17)
+        .unwrap()
+        .and_hms_opt(22, 22, 59)
+        .unwrap();
+    let mut expected = Vec::new();
+    events::parse_range(&path, from, to, &mut |event| expected.push(event.offset())).unwrap();
+    assert_eq!(expected.len(), 193);
+
+    let index = Index::create(&path).unwrap();
+    assert!(index::sidecar(&path).exists());
+    assert_eq!(Index::for_file(&path).unwrap(), Some(index.clone()));
+    assert!(index.offset(from) > 0);
+    assert!(index.offset(from) <= expected[0]);
+
+    let mut actual = Vec::new();
+    events::parse_range(&path, from, to, &mut |event| actual.push(event.offset())).unwrap();
+    assert_eq!(actual, expected);
+
+    let index = Index::build_with(&path, Step::Records(100)).unwrap();
+    assert_eq!(index.entries().len(), 12);
+}
+
+#[test]
+fn test_bloom_index() {
+    use event_log_parser::index::{Index, IndexBuilder, Key};
+
+    let dir = std::env::temp_dir().join("event-log-parser-bloom");
+    std::fs::create_dir_all(&dir).unwrap();
+    for name in ["1Cv8.lgf", "20221212000000.lgp"] {
+        std::fs::copy(format!("../test-log/{name}"), dir.join(name)).unwrap();
+    }
+    let log = LogDirectory::open(&dir).unwrap();
+    log.create_indexes(&IndexBuilder::new().filters(true))
+        .unwrap();
+    let index = Index::for_file(dir.join("20221212000000.lgp"))
+        .unwrap()
+        .unwrap();
+    assert!(index.filters().is_some());
+    assert!(index.may_contain(Key::User(2)));
+    assert!(!index.may_contain(Key::User(100_000)));
+
+    let from = NaiveDate::from_ymd_opt(2022, 12, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    let to = NaiveDate::from_ymd_opt(2022, 12, 31)
+        .unwrap()
+        .and_hms_opt(23, 59, 59)
+        .unwrap();
+    let mut expected = 0;
+    log.events(&mut |event| expected += (event.user_id() == 2) as usize)
+        .unwrap();
+    let mut count = 0;
+    log.events_matching(from, to, &[Key::User(2)], &mut |event| {
+        assert_eq!(event.user_id(), 2);
+        count += 1;
+    })
+    .unwrap();
+    assert!(count > 0);
+    assert_eq!(count, expected);
+
+    let mut count = 0;
+    log.events_matching(from, to, &[Key::User(100_000)], &mut |_| count += 1)
+        .unwrap();
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn test_cache() {
+    use event_log_parser::cache::Cache;
+
+    let dir = std::env::temp_dir().join("event-log-parser-cache");
+    std::fs::create_dir_all(&dir).unwrap();
+    for name in ["1Cv8.lgf", "20221212000000.lgp"] {
+        std::fs::copy(format!("../test-log/{name}"), dir.join(name)).unwrap();
+    }
+    let path = dir.join("events.cache");
+    let _ = std::fs::remove_file(&path);
+
+    let log = LogDirectory::open(&dir).unwrap();
+    let cache = Cache::open(&log, &path).unwrap();
+    assert!(path.exists());
+    assert_eq!(cache.len(), 1274);
+
+    let loaded = Cache::load(&path).unwrap();
+    assert!(loaded.is_valid(&log));
+    let mut expected = Vec::new();
+    log.events(&mut |event| expected.push(event.to_owned()))
+        .unwrap();
+    for (actual, expected) in loaded.events().zip(&expected) {
+        assert_eq!(actual.date(), expected.date());
+        assert_eq!(actual.transaction_data(), expected.transaction_data());
+        assert_eq!(actual.user_id(), expected.user_id());
+        assert_eq!(actual.event_id(), expected.event_id());
+        assert_eq!(actual.log_level(), expected.log_level());
+        assert_eq!(actual.comment(), expected.comment());
+        assert_eq!(actual.data(), expected.data());
+        assert_eq!(actual.data_presentation(), expected.data_presentation());
+        assert_eq!(actual.session(), expected.session());
+        assert_eq!(actual.offset(), expected.offset());
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(dir.join("20221212000000.lgp"))
+        .unwrap();
+    std::io::Write::write_all(&mut file, b"\r\n").unwrap();
+    let log = LogDirectory::open(&dir).unwrap();
+    assert!(!loaded.is_valid(&log));
+}
+
+#[test]
+fn test_query() {
+    use event_log_parser::query::Query;
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    let user = refs.users()[2].name().to_string();
+    let query = Query::parse(&format!(
+        "(event = \"_$Session$_.Authentication\" or level >= Warning) \
+         and user = \"{user}\" and date between 2022-12-17 and 2022-12-17 \
+         and not comment ~ \"NOT IN LOG\""
+    ))
+    .unwrap()
+    .compile(&refs);
+
+    let mut expected = 0;
+    let mut count = 0;
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        let level = event.log_level();
+        if (event.event(&refs) == "_$Session$_.Authentication"
+            || matches!(level, EventLogLevel::Warning | EventLogLevel::Error))
+            && event.user(&refs).name() == user
+        {
+            expected += 1;
+        }
+        if query.matches(&event) {
+            count += 1;
+        }
+    })
+    .unwrap();
+    assert!(expected > 0);
+    assert_eq!(count, expected);
+
+    let query = Query::parse("date < 2022-12-17").unwrap().compile(&refs);
+    let mut count = 0;
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        count += query.matches(&event) as usize
+    })
+    .unwrap();
+    assert_eq!(count, 0);
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_arrow_scan() {
+    use event_log_parser::{export::parquet::Scan, query::Query};
+
+    let log = LogDirectory::open("../test-log").unwrap();
+    let filter = Query::parse("level = Information")
+        .unwrap()
+        .compile(log.references());
+    let scan = Scan::new(&log)
+        .projection(vec![0, 7])
+        .filter(filter)
+        .limit(250)
+        .batch_size(100);
+    assert_eq!(scan.schema().fields().len(), 2);
+    assert_eq!(scan.schema().field(1).name(), "event");
+    let mut batches = Vec::new();
+    scan.run(&mut |batch| {
+        batches.push(batch);
+        ControlFlow::Continue(())
+    })
+    .unwrap();
+    assert_eq!(
+        batches.iter().map(|x| x.num_rows()).collect::<Vec<_>>(),
+        [100, 100, 50]
+    );
+    assert!(batches.iter().all(|x| x.schema() == scan.schema()));
+
+    let mut total_batches = 0;
+    scan.run(&mut |_| {
+        total_batches += 1;
+        ControlFlow::Break(())
+    })
+    .unwrap();
+    assert_eq!(total_batches, 1);
+}
+
+#[cfg(feature = "datafusion")]
+#[test]
+fn test_datafusion() {
+    use arrow_array::{cast::AsArray, types::Int64Type};
+    use datafusion::{physical_plan::displayable, prelude::SessionContext};
+    use event_log_parser::export::datafusion::LogTable;
+
+    let log = LogDirectory::open("../test-log").unwrap();
+    let from = NaiveDate::from_ymd_opt(2022, 12, 17)
+        .unwrap()
+        .and_hms_opt(22, 16, 0)
+        .unwrap();
+    let to = NaiveDate::from_ymd_opt(2022, 12, 17)
+        .unwrap()
+        .and_hms_opt(22, 17, 0)
+        .unwrap();
+    let mut between = 0;
+    log.events_between(from, to, &mut |_| between += 1).unwrap();
+    let mut user = 0;
+    log.events(&mut |event| {
+        if event.resolve(log.references()).user_name() == "Андрей Кудрявцев" {
+            user += 1;
+        }
+    })
+    .unwrap();
+
+    let ctx = SessionContext::new();
+    ctx.register_table("log", std::sync::Arc::new(LogTable::new(log)))
+        .unwrap();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let query = |sql: &str| {
+        runtime
+            .block_on(async { ctx.sql(sql).await?.collect().await })
+            .unwrap()
+    };
+    let count = |sql: &str| query(sql)[0].column(0).as_primitive::<Int64Type>().value(0);
+
+    assert_eq!(count("SELECT count(*) FROM log"), 1274);
+    let sql = "SELECT count(*) FROM log \
+        WHERE date BETWEEN '2022-12-17 22:16:00' AND '2022-12-17 22:17:00'";
+    assert_eq!(count(sql), between);
+    assert_eq!(
+        count("SELECT count(*) FROM log WHERE \"user\" = 'Андрей Кудрявцев'"),
+        user
+    );
+
+    let batches = query("SELECT date, event FROM log LIMIT 10");
+    assert_eq!(batches.iter().map(|x| x.num_rows()).sum::<usize>(), 10);
+    assert!(batches.iter().all(|x| x.num_columns() == 2));
+
+    // Отбор по дате выполняется при чтении журнала
+    let plan = runtime
+        .block_on(async { ctx.sql(sql).await?.create_physical_plan().await })
+        .unwrap();
+    let plan = displayable(plan.as_ref()).indent(true).to_string();
+    assert!(plan.contains("range=2022-12-17 22:16:00..=2022-12-17 22:17:00"));
+}
+
+#[test]
+fn test_validate() {
+    use event_log_parser::validate::{self, Problem};
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    let report = validate::validate_file("../test-log/20221212000000.lgp", &refs).unwrap();
+    assert!(report.is_valid());
+    assert_eq!(report.records(), 1274);
+
+    // Событие раньше предыдущего с неизвестным пользователем и оборванная запись
+    let mut data = std::fs::read("../test-log/20221212000000.lgp").unwrap();
+    let offset = data.len() as u64 + 3;
+    data.extend_from_slice(
+        b",\r\n{20221217221000,N,\r\n{0,0},999,1,1,1,3,I,\"\",0,\r\n{\"U\"},\"\",0,0,0,2,0,\r\n{0}\r\n}",
+    );
+    let truncated = data.len() as u64 + 3;
+    data.extend_from_slice(b",\r\n{20221217224400,N,\r\n{0,0},2,1");
+    let dir = std::env::temp_dir().join("event-log-parser-validate");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("20221212000000.lgp");
+    std::fs::write(&path, data).unwrap();
+
+    let report = validate::validate_file(&path, &refs).unwrap();
+    assert_eq!(report.records(), 1275);
+    assert_eq!(
+        report.problems(),
+        [
+            Problem::OutOfOrder {
+                offset,
+                date: NaiveDate::from_ymd_opt(2022, 12, 17)
+                    .unwrap()
+                    .and_hms_opt(22, 10, 0)
+                    .unwrap(),
+                previous: NaiveDate::from_ymd_opt(2022, 12, 17)
+                    .unwrap()
+                    .and_hms_opt(22, 43, 4)
+                    .unwrap(),
+            },
+            Problem::DanglingId {
+                offset,
+                field: "user",
+                id: 999
+            },
+            Problem::TruncatedTail { offset: truncated },
+        ]
+    );
+
+    let repaired = dir.join("repaired.lgp");
+    let out = std::fs::File::create(&repaired).unwrap();
+    validate::repair_file(&path, &refs, out).unwrap();
+    let report = validate::validate_file(&repaired, &refs).unwrap();
+    assert!(report.is_valid());
+    assert_eq!(report.records(), 1274);
+}
+
+#[test]
+fn test_validate_holes() {
+    use event_log_parser::validate;
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    assert!(validate::holes(&refs).is_empty());
+
+    let lgf = "{1,071523a4-516f-4fce-ba4b-0d11ab7a1893,\"Executor\",3},\r\n\
+        {2,\"computer1\",1},\r\n{2,\"computer4\",4}";
+    let refs = References::from_slice(lgf.as_bytes()).unwrap();
+    assert_eq!(
+        validate::holes(&refs),
+        [("user", 1), ("user", 2), ("computer", 2), ("computer", 3)]
+    );
+
+    let record =
+        b"{20221217221000,N,\r\n{0,0},2,3,1,1,3,I,\"\",0,\r\n{\"U\"},\"\",0,0,0,2,0,\r\n{0}\r\n}";
+    let mut dangling = Vec::new();
+    events::parse_reader(record.as_slice(), &mut |event| {
+        dangling = validate::dangling_ids(&event, &refs);
+    })
+    .unwrap();
+    assert_eq!(
+        dangling,
+        [
+            ("user", 2),
+            ("computer", 3),
+            ("application", 1),
+            ("event", 3)
+        ]
+    );
+}
+
+#[cfg(feature = "grpc")]
+#[test]
+fn test_grpc() {
+    use event_log_parser::grpc::{
+        proto::{event_log_server::EventLog, GetReferencesRequest, Level, ListEventsRequest},
+        EventLogService,
+    };
+    use tokio_stream::StreamExt;
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let service = EventLogService::new("../test-log");
+    runtime.block_on(async {
+        let request = ListEventsRequest {
+            from: "2022-12-17 22:16:00".to_string(),
+            to: "2022-12-17 22:16:59".to_string(),
+            query: "level >= Warning".to_string(),
+            limit: 0,
+        };
+        let stream = service
+            .list_events(tonic::Request::new(request))
+            .await
+            .unwrap()
+            .into_inner();
+        let events: Vec<_> = stream.map(Result::unwrap).collect().await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].level(), Level::Warning);
+        assert_eq!(events[0].user, "Андрей Кудрявцев");
+
+        let request = ListEventsRequest {
+            limit: 5,
+            ..Default::default()
+        };
+        let stream = service
+            .list_events(tonic::Request::new(request))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(stream.collect::<Vec<_>>().await.len(), 5);
+
+        let request = ListEventsRequest {
+            query: "level >".to_string(),
+            ..Default::default()
+        };
+        let status = service
+            .list_events(tonic::Request::new(request))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        let refs = service
+            .get_references(tonic::Request::new(GetReferencesRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(refs.users[2].name, "Андрей Кудрявцев");
+    });
+}
+
+#[cfg(feature = "ffi")]
+#[test]
+fn test_ffi() {
+    use event_log_parser::ffi::*;
+    use std::ffi::{CStr, CString};
+
+    let path = CString::new("../test-log").unwrap();
+    let mut log = std::ptr::null_mut();
+    unsafe {
+        assert_eq!(elp_open_dir(path.as_ptr(), &mut log), ELP_OK);
+        assert_eq!(elp_event_level(log), ELP_NO_EVENT);
+        assert!(elp_event_field(log, ELP_FIELD_USER).is_null());
+
+        let mut count = 0;
+        let mut warnings = 0;
+        while elp_next_event(log) == ELP_OK {
+            count += 1;
+            if elp_event_level(log) == 2 {
+                warnings += 1;
+                let user = CStr::from_ptr(elp_event_field(log, ELP_FIELD_USER));
+                assert_eq!(user.to_str().unwrap(), "Андрей Кудрявцев");
+            }
+        }
+        assert_eq!(count, 1274);
+        assert_eq!(warnings, 1);
+        assert_eq!(elp_next_event(log), ELP_END);
+        elp_close(log);
+
+        assert_eq!(elp_open_dir(path.as_ptr(), &mut log), ELP_OK);
+        let mut event = std::mem::MaybeUninit::<ElpEvent>::uninit();
+        let mut count = 0;
+        while elp_read_event(log, event.as_mut_ptr()) == ELP_OK {
+            let event = event.assume_init_ref();
+            count += 1;
+            if event.level == 2 {
+                assert_eq!(
+                    CStr::from_ptr(event.user).to_str().unwrap(),
+                    "Андрей Кудрявцев"
+                );
+                assert_eq!(CStr::from_ptr(event.user_id).to_bytes().len(), 36);
+                assert!(event.date > 0);
+            }
+        }
+        assert_eq!(count, 1274);
+        assert_eq!(
+            elp_read_event(log, std::ptr::null_mut()),
+            ELP_INVALID_ARGUMENT
+        );
+        elp_close(log);
+
+        let missing = CString::new("../missing-log").unwrap();
+        assert_eq!(elp_open_dir(missing.as_ptr(), &mut log), ELP_IO_ERROR);
+        assert!(log.is_null());
+    }
+}
+
+#[cfg(feature = "python")]
+#[test]
+fn test_python() {
+    use pyo3::{prelude::*, types::PyDict};
+    use std::ffi::CString;
+
+    Python::initialize();
+    Python::attach(|py| {
+        let module = PyModule::new(py, "event_log_parser").unwrap();
+        event_log_parser::python::register(&module).unwrap();
+        let globals = PyDict::new(py);
+        globals.set_item("elp", module).unwrap();
+        let code = CString::new(
+            r#"
+events = list(elp.parse_events("../test-log"))
+assert len(events) == 1274, len(events)
+assert events[0]["date"].year == 2022
+warnings = list(elp.parse_events("../test-log", 'level >= Warning'))
+assert len(warnings) == 1
+assert warnings[0]["user"] == "Андрей Кудрявцев"
+refs = elp.References("../test-log")
+assert refs.users[2][1] == "Андрей Кудрявцев"
+assert refs.user_id("Андрей Кудрявцев") == 2
+try:
+    elp.parse_events("../missing-log")
+    assert False
+except OSError:
+    pass
+"#,
+        )
+        .unwrap();
+        py.run(&code, Some(&globals), None).unwrap();
+    });
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn test_parse_mmap() {
+    let file = "../test-log/20221212000000.lgp";
+    let mut expected = Vec::new();
+    events::parse(file, &mut |event| expected.push(event.end_offset())).unwrap();
+    let mut offsets = Vec::new();
+    events::parse_mmap(file, &mut |event| offsets.push(event.end_offset())).unwrap();
+    assert_eq!(offsets.len(), 1274);
+    assert_eq!(offsets, expected);
+
+    let dir = std::env::temp_dir().join("event-log-parser-mmap");
+    std::fs::create_dir_all(&dir).unwrap();
+    let truncated = dir.join("truncated.lgp");
+    let data = std::fs::read(file).unwrap();
+    std::fs::write(&truncated, &data[..data.len() - 10]).unwrap();
+    let mut invalid = 0;
+    let mut options = ParseOptions::new().on_invalid(|_, _, _| invalid += 1);
+    let summary = events::parse_mmap_with_options(&truncated, &mut options, &mut |_| {
+        ControlFlow::Continue(())
+    })
+    .unwrap();
+    drop(options);
+    assert_eq!(summary.records(), 1273);
+    assert_eq!(summary.bytes(), data.len() as u64 - 10);
+    assert_eq!(summary.skipped(), 1);
+    assert_eq!(invalid, 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_buffer_size() {
+    let mut expected = Vec::new();
+    events::parse("../test-log/20221212000000.lgp", &mut |event| {
+        expected.push(event.end_offset())
+    })
+    .unwrap();
+
+    // Буфер меньше одной записи
+    let mut offsets = Vec::new();
+    let mut options = ParseOptions::new().buffer_size(100);
+    let summary = events::parse_with_options(
+        "../test-log/20221212000000.lgp",
+        &mut options,
+        &mut |event| {
+            offsets.push(event.end_offset());
+            ControlFlow::Continue(())
+        },
+    )
+    .unwrap();
+    assert_eq!(offsets, expected);
+    assert_eq!(summary.skipped(), 0);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_parse_parallel() {
+    use event_log_parser::parallel::ParallelParser;
+    use std::sync::Mutex;
+
+    let file = "../test-log/20221212000000.lgp";
+    let mut expected = Vec::new();
+    events::parse(file, &mut |event| expected.push(event.offset())).unwrap();
+
+    let offsets = Mutex::new(Vec::new());
+    events::parse_parallel(file, &|event| offsets.lock().unwrap().push(event.offset())).unwrap();
+    let mut offsets = offsets.into_inner().unwrap();
+    offsets.sort();
+    assert_eq!(offsets, expected);
+
+    // Части меньше записи и границы внутри записей
+    for chunk_size in [100, 4096, 100_000] {
+        let mut offsets = Vec::new();
+        ParallelParser::new(file)
+            .chunk_size(chunk_size)
+            .map_ordered(&|event| Some(event.offset()), &mut |offset| {
+                offsets.push(offset)
+            })
+            .unwrap();
+        assert_eq!(offsets, expected, "chunk size {chunk_size}");
+    }
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_parse_parallel_lf() {
+    use event_log_parser::parallel::ParallelParser;
+    use std::sync::Mutex;
+
+    let buffer = std::fs::read("../test-log/20221212000000.lgp").unwrap();
+    let lf = String::from_utf8(buffer).unwrap().replace("\r\n", "\n");
+    let path = std::env::temp_dir().join("event-log-parser-test-parallel-lf.lgp");
+    std::fs::write(&path, lf).unwrap();
+
+    let mut expected = Vec::new();
+    events::parse(&path, &mut |event| expected.push(event.offset())).unwrap();
+    assert_eq!(expected.len(), 1274);
+
+    for chunk_size in [100, 64 * 1024] {
+        let offsets = Mutex::new(Vec::new());
+        ParallelParser::new(&path)
+            .chunk_size(chunk_size)
+            .for_each(&|event| offsets.lock().unwrap().push(event.offset()))
+            .unwrap();
+        let mut offsets = offsets.into_inner().unwrap();
+        offsets.sort();
+        assert_eq!(offsets, expected, "chunk size {chunk_size}");
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_backfill() {
+    use event_log_parser::backfill::Backfill;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+        time::Duration,
+    };
+
+    let file = "../test-log/20221212000000.lgp";
+    let data = std::fs::read(file).unwrap();
+    let mut ends = Vec::new();
+    events::parse(file, &mut |event| ends.push(event.end_offset())).unwrap();
+    let boundary = ends[600] as usize;
+
+    let dir = std::env::temp_dir().join("event-log-parser-backfill");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy("../test-log/1Cv8.lgf", dir.join("1Cv8.lgf")).unwrap();
+    std::fs::copy(file, dir.join("20221211000000.lgp")).unwrap();
+    let live_file = dir.join("20221212000000.lgp");
+    std::fs::write(&live_file, &data[..boundary]).unwrap();
+
+    let history = Mutex::new(Vec::new());
+    let live = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(Duration::from_millis(200));
+            let mut file = std::fs::OpenOptions::new()
+                .append(true)
+                .open(&live_file)
+                .unwrap();
+            std::io::Write::write_all(&mut file, &data[boundary..]).unwrap();
+        });
+        Backfill::new(&dir)
+            .interval(Duration::from_millis(10))
+            .run(
+                &|event| history.lock().unwrap().push(event.event().offset()),
+                &mut |event| {
+                    assert!(event.event().offset() >= boundary as u64);
+                    match live.fetch_add(1, Ordering::Relaxed) + 1 < 1274 - 601 {
+                        true => ControlFlow::Continue(()),
+                        false => ControlFlow::Break(()),
+                    }
+                },
+            )
+            .unwrap();
+    });
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let history = history.into_inner().unwrap();
+    assert_eq!(history.len(), 1274 + 601);
+    assert_eq!(live.into_inner(), 1274 - 601);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_par_events() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let dir = std::env::temp_dir().join("event-log-parser-parallel");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy("../test-log/1Cv8.lgf", dir.join("1Cv8.lgf")).unwrap();
+    for name in [
+        "20221212000000.lgp",
+        "20221213000000.lgp",
+        "20221214000000.lgp",
+    ] {
+        std::fs::copy("../test-log/20221212000000.lgp", dir.join(name)).unwrap();
+    }
+
+    let log = LogDirectory::open(&dir).unwrap();
+    let count = AtomicUsize::new(0);
+    log.par_events(&|_| {
+        count.fetch_add(1, Ordering::Relaxed);
+    })
+    .unwrap();
+    assert_eq!(count.into_inner(), 1274 * 3);
+
+    let counts = log
+        .par_fold(|| 0, |count: &mut usize, _| *count += 1)
+        .unwrap();
+    assert_eq!(counts, vec![1274; 3]);
+
+    let mut expected = Collector::new();
+    log.events(&mut |event| expected.push(&event)).unwrap();
+    let collector = log
+        .par_fold(Collector::new, |collector, event| collector.push(&event))
+        .unwrap()
+        .into_iter()
+        .reduce(|mut a, b| {
+            a.merge(b);
+            a
+        })
+        .unwrap();
+    let expected = expected.report(log.references());
+    let report = collector.report(log.references());
+    assert_eq!(report.total(), expected.total());
+    assert_eq!(report.first(), expected.first());
+    assert_eq!(report.last(), expected.last());
+    assert_eq!(report.levels().warning(), 3);
+    assert_eq!(report.users()[0].name(), expected.users()[0].name());
+    assert_eq!(report.users()[0].count(), expected.users()[0].count());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn test_stream() {
+    use tokio_stream::StreamExt;
+
+    let path = std::env::temp_dir().join("event-log-parser-stream.lgp");
+    let mut data = std::fs::read("../test-log/20221212000000.lgp").unwrap();
+    data.extend_from_slice(b"{20221218000000,X,");
+    std::fs::write(&path, data).unwrap();
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let results: Vec<_> = events::stream(&path).collect().await;
+        assert_eq!(results.len(), 1275);
+        let warnings = results
+            .iter()
+            .filter_map(|result| result.as_ref().ok())
+            .filter(|event| *event.log_level() == EventLogLevel::Warning)
+            .count();
+        assert_eq!(warnings, 1);
+        let error = results.last().unwrap().as_ref().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(error.get_ref().unwrap().is::<ParseError>());
+
+        // Поток можно бросить, не дочитав
+        let first: Vec<_> = events::stream(&path).take(3).collect().await;
+        assert_eq!(first.len(), 3);
+
+        let mut missing = events::stream("../test-log/missing.lgp");
+        let error = missing.next().await.unwrap().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    });
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(all(feature = "uring", target_os = "linux"))]
+#[test]
+fn test_uring() {
+    // Несколько блоков чтения по 256 КБ
+    let path = std::env::temp_dir().join("event-log-parser-uring.lgp");
+    let data = std::fs::read("../test-log/20221212000000.lgp").unwrap();
+    let start = data.windows(3).position(|w| w == b"\n{2").unwrap() + 1;
+    let mut file = data.clone();
+    for _ in 0..4 {
+        file.extend_from_slice(b",\r\n");
+        file.extend_from_slice(&data[start..]);
+    }
+    std::fs::write(&path, &file).unwrap();
+
+    let mut expected = Vec::new();
+    events::parse_reader(std::fs::File::open(&path).unwrap(), &mut |event| {
+        expected.push((event.offset(), event.date()))
+    })
+    .unwrap();
+    assert_eq!(expected.len(), 1274 * 5);
+
+    let mut offsets = Vec::new();
+    events::parse(&path, &mut |event| {
+        offsets.push((event.offset(), event.date()))
+    })
+    .unwrap();
+    assert_eq!(offsets, expected);
+
+    let (offset, _) = expected[3000];
+    let mut offsets = Vec::new();
+    events::parse_from_offset(&path, offset, &mut |event| {
+        offsets.push((event.offset(), event.date()));
+        ControlFlow::Continue(())
+    })
+    .unwrap();
+    assert_eq!(offsets, expected[3000..]);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_projection() {
+    use event_log_parser::events::Projection;
+
+    let file = "../test-log/20221212000000.lgp";
+    let mut expected = Vec::new();
+    events::parse(file, &mut |event| {
+        expected.push((
+            event.date(),
+            *event.log_level(),
+            event.event_id(),
+            event.comment().to_string(),
+        ))
+    })
+    .unwrap();
+
+    let mut options = ParseOptions::new().projection(Projection::EVENT | Projection::COMMENT);
+    let mut events = Vec::new();
+    let summary = events::parse_with_options(file, &mut options, &mut |event| {
+        assert_eq!(event.user_id(), 0);
+        assert_eq!(event.metadata_id(), 0);
+        assert_eq!(event.data(), "");
+        assert_eq!(event.data_presentation(), "");
+        assert_eq!(event.transaction_data(), "");
+        events.push((
+            event.date(),
+            *event.log_level(),
+            event.event_id(),
+            event.comment().to_string(),
+        ));
+        ControlFlow::Continue(())
+    })
+    .unwrap();
+    assert_eq!(events, expected);
+    assert_eq!(summary.skipped(), 0);
+
+    // Отбор по пользователю читает все поля
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    let filter = EventFilter::new()
+        .users(["Андрей Кудрявцев"])
+        .compile(&refs);
+    let mut options = ParseOptions::new()
+        .projection(Projection::NONE)
+        .filter(filter);
+    let mut count = 0;
+    events::parse_with_options(file, &mut options, &mut |event| {
+        assert_eq!(event.user_id(), 2);
+        count += 1;
+        ControlFlow::Continue(())
+    })
+    .unwrap();
+    assert!(count > 0);
+}
+
+#[test]
+fn test_count() {
+    let file = "../test-log/20221212000000.lgp";
+    assert_eq!(events::count(file).unwrap(), 1274);
+
+    let mut expected = Collector::new();
+    events::parse(file, &mut |event| expected.push(&event)).unwrap();
+    let expected = *expected.report(&References::default()).levels();
+    let counts = events::count_by_level(file).unwrap();
+    assert_eq!(counts, expected);
+    assert_eq!(counts.warning(), 1);
+}
+
+#[test]
+fn test_parse_batches() {
+    let file = "../test-log/20221212000000.lgp";
+    let mut expected = Vec::new();
+    events::parse(file, &mut |event| expected.push(event.offset())).unwrap();
+
+    let mut offsets = Vec::new();
+    let mut batches = 0;
+    events::parse_batches(file, &mut |batch| {
+        assert!(!batch.is_empty());
+        batches += 1;
+        offsets.extend(batch.iter().map(|event| event.offset()));
+    })
+    .unwrap();
+    assert_eq!(offsets, expected);
+    assert_eq!(batches, 1);
+
+    // Буфер меньше файла - несколько пачек, остановка после второй
+    let mut options = ParseOptions::new().buffer_size(16 * 1024);
+    let mut offsets = Vec::new();
+    let mut batches = 0;
+    let summary = events::parse_batches_with_options(file, &mut options, &mut |batch| {
+        batches += 1;
+        offsets.extend(batch.iter().map(|event| event.offset()));
+        match batches {
+            2 => ControlFlow::Break(()),
+            _ => ControlFlow::Continue(()),
+        }
+    })
+    .unwrap();
+    assert_eq!(batches, 2);
+    assert_eq!(offsets, expected[..offsets.len()]);
+    assert_eq!(summary.records(), offsets.len());
+}
+
+#[test]
+fn test_interner() {
+    use event_log_parser::intern::Interner;
+
+    let file = "../test-log/20221212000000.lgp";
+    let mut interner = Interner::new();
+    let mut interned = Vec::new();
+    let mut owned = Vec::new();
+    events::parse(file, &mut |event| {
+        interned.push(interner.to_owned(&event));
+        owned.push(event.to_owned());
+    })
+    .unwrap();
+
+    for (a, b) in interned.iter().zip(&owned) {
+        assert_eq!(a.comment(), b.comment());
+        assert_eq!(a.data(), b.data());
+        assert_eq!(a.data_presentation(), b.data_presentation());
+        assert_eq!(a.transaction_data(), b.transaction_data());
+        assert_eq!(a.offset(), b.offset());
+    }
+
+    let stats = *interner.stats();
+    assert_eq!(stats.lookups(), 1274 * 5);
+    assert_eq!(stats.hits() + stats.strings(), stats.lookups());
+    assert!(stats.dedup_ratio() > 0.5, "{stats:?}");
+    assert!(stats.saved_bytes() > 0);
+
+    drop(interned);
+    interner.shrink();
+    assert_eq!(interner.stats().strings(), 0);
+    assert_eq!(interner.stats().bytes(), 0);
+}
+
+#[test]
+fn test_event_arena() {
+    use event_log_parser::arena::EventArena;
+
+    let file = "../test-log/20221212000000.lgp";
+    let mut arena = EventArena::new();
+    let mut owned = Vec::new();
+    events::parse(file, &mut |event| {
+        arena.push(&event);
+        owned.push(event.to_owned());
+    })
+    .unwrap();
+    assert_eq!(arena.len(), 1274);
+    for (event, expected) in arena.events().zip(&owned) {
+        assert_eq!(event.date(), expected.date());
+        assert_eq!(event.user_id(), expected.user_id());
+        assert_eq!(event.comment(), expected.comment());
+        assert_eq!(event.data(), expected.data());
+        assert_eq!(event.data_presentation(), expected.data_presentation());
+        assert_eq!(event.end_offset(), expected.end_offset());
+    }
+
+    // Обратный порядок, затем объединение двух половин и сортировка по дате
+    arena.sort_by(|a, b| b.offset().cmp(&a.offset()));
+    assert_eq!(arena.get(0).unwrap().offset(), owned[1273].offset());
+    let mut first = EventArena::with_capacity(1274, arena.bytes());
+    let mut second = EventArena::new();
+    for (i, event) in arena.events().enumerate() {
+        match i % 2 {
+            0 => first.push(&event),
+            _ => second.push(&event),
+        }
+    }
+    first.append(&mut second);
+    assert!(second.is_empty());
+    assert_eq!(first.len(), 1274);
+    first.sort_by(|a, b| a.offset().cmp(&b.offset()));
+    for (event, expected) in first.events().zip(&owned) {
+        assert_eq!(event.offset(), expected.offset());
+        assert_eq!(event.comment(), expected.comment());
+        assert_eq!(event.data(), expected.data());
+    }
+    first.sort_by_date();
+    assert!(first
+        .events()
+        .zip(first.events().skip(1))
+        .all(|(a, b)| a.date() <= b.date()));
+
+    first.clear();
+    assert!(first.is_empty());
+    assert_eq!(first.bytes(), 0);
+    assert!(first.get(0).is_none());
+}
+
+#[test]
+fn test_partition_plan() {
+    use event_log_parser::export::partition::{Granularity, Planner, Volumes};
+
+    let file = "../test-log/20221212000000.lgp";
+    let mut volumes = Volumes::new();
+    volumes.scan(file).unwrap();
+    let total = volumes.total();
+    assert_eq!(total.events(), 1274);
+    assert!(total.bytes() < std::fs::metadata(file).unwrap().len());
+
+    let hours = volumes.partitions(Granularity::Hour);
+    assert_eq!(
+        hours
+            .iter()
+            .map(|(_, volume)| volume.events())
+            .sum::<usize>(),
+        1274
+    );
+    assert_eq!(Planner::new().plan(&volumes), Granularity::Month);
+    let largest = hours
+        .iter()
+        .map(|(_, volume)| volume.bytes())
+        .max()
+        .unwrap();
+    let planner = Planner::new().ratio(1.0).max_bytes(largest - 1);
+    assert_eq!(planner.plan(&volumes), Granularity::Hour);
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_parquet_partitioned() {
+    use event_log_parser::export::{
+        parquet::{PartitionedWriter, WriterOptions},
+        partition::{Granularity, Planner, Volumes},
+    };
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    let mut refs = References::default();
+    refs.parse("../test-log/1Cv8.lgf").unwrap();
+    let file = "../test-log/20221212000000.lgp";
+    let mut volumes = Volumes::new();
+    volumes.scan(file).unwrap();
+
+    let dir = std::env::temp_dir().join("event-log-parser-test-partitions");
+    let _ = std::fs::remove_dir_all(&dir);
+    let planner = Planner::new().ratio(1.0).max_bytes(1);
+    let options = WriterOptions::new();
+    let mut writer = PartitionedWriter::planned(&dir, &refs, &planner, &volumes, options);
+    assert_eq!(writer.granularity(), Granularity::Hour);
+    events::parse(file, &mut |event| writer.write(&event).unwrap()).unwrap();
+    let files = writer.close().unwrap();
+
+    let hours = volumes.partitions(Granularity::Hour);
+    assert_eq!(files.len(), hours.len());
+    for (path, (start, volume)) in files.iter().zip(&hours) {
+        let expected = dir
+            .join(Granularity::Hour.path(*start))
+            .join("part-0.parquet");
+        assert_eq!(path, &expected);
+        let reader = SerializedFileReader::new(std::fs::File::open(path).unwrap()).unwrap();
+        let rows = reader.metadata().file_metadata().num_rows();
+        assert_eq!(rows as usize, volume.events());
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_raw_records() {
+    let file = "../test-log/20221212000000.lgp";
+    let data = std::fs::read(file).unwrap();
+    let mut expected = Vec::new();
+    events::parse(file, &mut |event| {
+        let raw = event.raw();
+        assert_eq!(raw.len() as u64, event.end_offset() - event.offset());
+        assert_eq!(
+            raw,
+            &data[event.offset() as usize..event.end_offset() as usize]
+        );
+        assert!(raw.starts_with(b"{") && raw.ends_with(b"}"));
+        expected.push((event.offset(), raw.to_vec()));
+    })
+    .unwrap();
+
+    let mut records = Vec::new();
+    events::parse_raw_records(file, &mut |offset, raw| {
+        records.push((offset, raw.to_vec()));
+        ControlFlow::Continue(())
+    })
+    .unwrap();
+    assert_eq!(records, expected);
+
+    // Повторный разбор сохраненной записи
+    let (offset, raw) = &records[10];
+    let event = event_log_parser::record::parse_event(raw, *offset).unwrap();
+    assert_eq!(event.offset(), *offset);
+    assert_eq!(event.raw(), raw.as_slice());
+}